@@ -8,11 +8,14 @@ const DEFAULT_COLUMNS = 80;
 const MIN_COLUMNS = 10;
 const MAX_COLUMNS = 200;
 const SOCKET_TIMEOUT_MS = 10_000;
-const TEXT_SCREENSHOT_FORMATS = new Set(['braille', 'ascii', 'ansi', 'text']);
+const TEXT_SCREENSHOT_FORMATS = new Set(['braille', 'ascii', 'ansi', 'halfblock', 'sixel', 'kitty', 'text']);
+const ANSI_FORMATS = new Set(['ansi', 'halfblock']);
+const GRAPHICS_FORMATS = new Set(['sixel', 'kitty']);
+const KITTY_DELETE_ALL_IMAGES = '\x1b_Ga=d,q=2\x1b\\';
 
 function printHelp() {
   console.log(`Usage:
-  browser-braille-watch.js [--socket <path>] [--format <braille|ascii|ansi|text>] [--columns <n>] [--sender-tile-id <tile-id>] [--sender-agent-id <agent-id>] <browser-tile-id> <interval>
+  browser-braille-watch.js [--socket <path>] [--format <braille|ascii|ansi|halfblock|sixel|kitty|text>] [--columns <n>] [--sender-tile-id <tile-id>] [--sender-agent-id <agent-id>] <browser-tile-id> <interval>
 
 Watches a browser tile and redraws a text screenshot every interval.
 
@@ -24,8 +27,13 @@ Arguments:
 Options:
   --socket <path>     Herd socket path. Defaults to HERD_SOCK, then HERD_RUNTIME_ID,
                       then ${DEFAULT_SOCKET_PATH}
-  --format <name>     Screenshot format: braille, ascii, ansi, or text. Defaults to braille
-                      text uses the browser text renderer
+  --format <name>     Screenshot format: braille, ascii, ansi, halfblock, sixel, kitty, or text.
+                      Defaults to braille. text lays out the page's own text instead of pixels.
+                      ansi and halfblock need a 24-bit color terminal.
+                      sixel and kitty draw real images in a terminal that decodes them;
+                      Herd's own shell tiles do not, so run those in an external terminal.
+                      Inside tmux they are wrapped for passthrough, which needs
+                      \`set -g allow-passthrough on\`
   --columns <n>       Text screenshot width in columns (${MIN_COLUMNS}-${MAX_COLUMNS})
   --sender-tile-id    Sender shell tile id. Defaults to HERD_TILE_ID, then the current Herd tmux pane
   --sender-agent-id   Sender agent id. Defaults to HERD_AGENT_ID
@@ -36,6 +44,8 @@ Examples:
   ./extensions/shell/browser-braille-watch.js GGDCrI 500ms
   ./extensions/shell/browser-braille-watch.js --format ascii GGDCrI 1s
   ./extensions/shell/browser-braille-watch.js --format ansi --columns 120 GGDCrI 1s
+  ./extensions/shell/browser-braille-watch.js --format halfblock --columns 160 GGDCrI 500ms
+  ./extensions/shell/browser-braille-watch.js --format kitty GGDCrI 1s
   ./extensions/shell/browser-braille-watch.js --format text GGDCrI 1s
   ./extensions/shell/browser-braille-watch.js --columns 120 GGDCrI 1s
   ./extensions/shell/browser-braille-watch.js --sender-tile-id AbCdEf GGDCrI 1s`);
@@ -109,7 +119,7 @@ function parseColumns(rawValue) {
 function parseFormat(rawValue) {
   const value = rawValue.trim().toLowerCase();
   if (!TEXT_SCREENSHOT_FORMATS.has(value)) {
-    fail(`format must be one of braille, ascii, ansi, or text, got "${rawValue}"`);
+    fail(`format must be one of braille, ascii, ansi, halfblock, sixel, kitty, or text, got "${rawValue}"`);
   }
   return value;
 }
@@ -333,10 +343,25 @@ function redraw(text) {
   }
 }
 
+// tmux drops sixel and kitty sequences unless each one arrives inside a
+// `tmux;` passthrough DCS with its escape bytes doubled.
+function wrapTmuxPassthrough(text) {
+  return text.replace(
+    /\x1b[P_][\s\S]*?\x1b\\/g,
+    (sequence) => `\x1bPtmux;${sequence.replaceAll('\x1b', '\x1b\x1b')}\x1b\\`,
+  );
+}
+
 function renderFrame({ format, tileId, intervalLabel, columns, rows, text }) {
   const header = `${formatLabel(format)} watch | tile ${tileId} | every ${intervalLabel} | ${columns}x${rows} | ${timestamp()}`;
-  const body = format === 'ansi' ? `${text}\x1b[0m` : text;
-  redraw(`${header}\n\n${body}`);
+  let body = ANSI_FORMATS.has(format) ? `${text}\x1b[0m` : text;
+  // Clearing the screen leaves kitty images in place, so drop the previous frame first.
+  let prefix = format === 'kitty' ? KITTY_DELETE_ALL_IMAGES : '';
+  if (GRAPHICS_FORMATS.has(format) && process.env.TMUX) {
+    body = wrapTmuxPassthrough(body);
+    prefix = wrapTmuxPassthrough(prefix);
+  }
+  redraw(`${prefix}${header}\n\n${body}`);
 }
 
 function renderError({ format, tileId, intervalLabel, socketPath, error }) {
@@ -154,6 +154,49 @@ describe("nested screenshot unwrapping", () => {
     });
   });
 
+  it("unwraps drive screenshot diffs into a JSON summary and diff image", () => {
+    const payload = {
+      tile_id: "tile-1",
+      action: "drive",
+      result: {
+        captureId: "cap-2",
+        against: "cap-1",
+        changedRatio: 0.25,
+        boxes: [{ x: 0, y: 0, width: 4, height: 4 }],
+        diffImage: { mimeType: "image/png", dataBase64: "Zm9v" },
+      },
+    };
+    const result = unwrapNestedScreenshotResult(
+      "drive",
+      { action: "screenshot_diff", args: { against: "cap-1", diff_image: true } },
+      payload,
+      "invalid screenshot payload",
+    );
+    expect(result?.content).toHaveLength(2);
+    expect(JSON.parse((result?.content[0] as { text: string }).text)).toEqual({
+      captureId: "cap-2",
+      against: "cap-1",
+      changedRatio: 0.25,
+      boxes: [{ x: 0, y: 0, width: 4, height: 4 }],
+    });
+    expect(result?.content[1]).toEqual({ type: "image", data: "Zm9v", mimeType: "image/png" });
+  });
+
+  it("appends capture ids to pixel screenshot content", () => {
+    const result = unwrapNestedScreenshotResult(
+      "drive",
+      { action: "screenshot", args: { format: "image", selector: "#board" } },
+      { tile_id: "tile-1", action: "drive", result: { mimeType: "image/png", dataBase64: "Zm9v", captureId: "cap-4" } },
+      "invalid screenshot payload",
+    );
+    expect(result).toEqual({
+      content: [
+        { type: "image", data: "Zm9v", mimeType: "image/png" },
+        { type: "text", text: "captureId: cap-4" },
+      ],
+    });
+  });
+
   it("returns an error result for malformed nested screenshot payloads", () => {
     const result = unwrapNestedScreenshotResult(
       "extension_call",
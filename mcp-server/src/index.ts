@@ -110,12 +110,19 @@ const TILE_TYPE_SCHEMA = z.enum(["shell", "agent", "browser", "work"]).optional(
 type BrowserImageScreenshotPayload = {
   mimeType: string;
   dataBase64: string;
+  captureId?: string;
 };
 type BrowserTextScreenshotPayload = {
-  format: "braille" | "ascii" | "ansi" | "text";
+  format: "braille" | "ascii" | "ansi" | "halfblock" | "sixel" | "kitty" | "text" | "accessibility";
   text: string;
   columns: number;
   rows: number;
+  captureId?: string;
+};
+type BrowserScreenshotDiffPayload = {
+  captureId: string;
+  diffImage?: BrowserImageScreenshotPayload;
+  [key: string]: unknown;
 };
 type AgentStreamEnvelope = {
   type: "event";
@@ -293,34 +300,58 @@ function isBrowserTextScreenshotPayload(value: unknown): value is BrowserTextScr
     (payload.format === "braille"
       || payload.format === "ascii"
       || payload.format === "ansi"
-      || payload.format === "text") &&
+      || payload.format === "halfblock"
+      || payload.format === "sixel"
+      || payload.format === "kitty"
+      || payload.format === "text"
+      || payload.format === "accessibility") &&
     typeof payload.text === "string" &&
     typeof payload.columns === "number" &&
     typeof payload.rows === "number"
   );
 }
 
+function captureIdContent(payload: { captureId?: string }) {
+  return typeof payload.captureId === "string"
+    ? [{ type: "text" as const, text: `captureId: ${payload.captureId}` }]
+    : [];
+}
+
 function screenshotPayloadResult(payload: unknown, invalidMessage: string): CallToolResult {
   if (isBrowserImageScreenshotPayload(payload)) {
     return {
-      content: [{ type: "image", data: payload.dataBase64, mimeType: payload.mimeType }],
+      content: [{ type: "image", data: payload.dataBase64, mimeType: payload.mimeType }, ...captureIdContent(payload)],
     };
   }
   if (isBrowserTextScreenshotPayload(payload)) {
     return {
-      content: [{ type: "text", text: payload.text }],
+      content: [{ type: "text", text: payload.text }, ...captureIdContent(payload)],
     };
   }
   return errorResult(invalidMessage);
 }
 
+function screenshotDiffPayloadResult(payload: unknown, invalidMessage: string): CallToolResult {
+  if (!payload || typeof payload !== "object" || typeof (payload as { captureId?: unknown }).captureId !== "string") {
+    return errorResult(invalidMessage);
+  }
+  const { diffImage, ...summary } = payload as BrowserScreenshotDiffPayload;
+  const content: CallToolResult["content"] = [{ type: "text", text: jsonText(summary) }];
+  if (isBrowserImageScreenshotPayload(diffImage)) {
+    content.push({ type: "image", data: diffImage.dataBase64, mimeType: diffImage.mimeType });
+  }
+  return { content };
+}
+
 function nestedScreenshotPayload(
   action: string,
   args: Record<string, unknown> | undefined,
   data: unknown,
 ): unknown | null {
   const isExtensionScreenshot = action === "extension_call" && typeof args?.method === "string" && args.method === "screenshot";
-  const isDriveScreenshot = action === "drive" && typeof args?.action === "string" && args.action === "screenshot";
+  const isDriveScreenshot = action === "drive"
+    && typeof args?.action === "string"
+    && (args.action === "screenshot" || args.action === "screenshot_diff");
   if (!isExtensionScreenshot && !isDriveScreenshot) {
     return null;
   }
@@ -340,6 +371,9 @@ export function unwrapNestedScreenshotResult(
   if (payload === null) {
     return null;
   }
+  if (action === "drive" && args?.action === "screenshot_diff") {
+    return screenshotDiffPayloadResult(payload, invalidMessage);
+  }
   return screenshotPayloadResult(payload, invalidMessage);
 }
 
@@ -480,7 +514,7 @@ const server = new McpServer(
     },
     instructions:
       (IS_ROOT_MODE
-        ? 'Messages arrive as <channel source="herd" kind="..."> with metadata including from_agent_id, from_display_name, to_agent_id, to_display_name, channels, mentions, replay, timestamp_ms, and for tile_event also delivery_reason, subscription_scope, subscription_direction, action, subject_tile_id, peer_tile_id, caller_tile_id, caller_agent_id, target_tile_id, target_agent_id, rpc_channel, outcome, args_json, and result_json. kind="direct" is private coordination. kind="public" is session-wide chatter. kind="channel" is subscription-gated channel chatter. kind="network" is local network coordination. kind="root" is traffic for the session root agent. kind="tile_event" is a live tile-call event notification. kind="system" is Herd lifecycle information. Treat replay="true" as historical context rather than a fresh request, and treat replay="false" as live traffic. If you want Herd or other agents to see your reply, respond through the Herd messaging tools such as message_direct, message_public, message_channel, message_network, or message_root. Plain assistant text in the local session does not publish a reply back onto the Herd channels. Use self_info to inspect your own tile, self_display_draw for the drawer, self_led_control for the chrome LED strip, and self_display_status for the chrome status line. Use the LED strip and status line for concise user-visible status updates, and reserve self_display_draw for richer frame output. For local tool interaction, inspect local tiles with network_list or network_get, use network_call or tile_call with the tile-specific message names exposed in responds_to, and inspect message_api on the returned tile payload for the required args and browser drive subcommands. Use tile_subscribe, tile_unsubscribe, and tile_subscription_list to manage session-wide tile event subscriptions for agents. Root may also use browser_drive for click, select, type, dom_query, eval, or screenshot on browser tiles in the current session.'
+        ? 'Messages arrive as <channel source="herd" kind="..."> with metadata including from_agent_id, from_display_name, to_agent_id, to_display_name, channels, mentions, replay, timestamp_ms, and for tile_event also delivery_reason, subscription_scope, subscription_direction, action, subject_tile_id, peer_tile_id, caller_tile_id, caller_agent_id, target_tile_id, target_agent_id, rpc_channel, outcome, args_json, and result_json. kind="direct" is private coordination. kind="public" is session-wide chatter. kind="channel" is subscription-gated channel chatter. kind="network" is local network coordination. kind="root" is traffic for the session root agent. kind="tile_event" is a live tile-call event notification. kind="system" is Herd lifecycle information. Treat replay="true" as historical context rather than a fresh request, and treat replay="false" as live traffic. If you want Herd or other agents to see your reply, respond through the Herd messaging tools such as message_direct, message_public, message_channel, message_network, or message_root. Plain assistant text in the local session does not publish a reply back onto the Herd channels. Use self_info to inspect your own tile, self_display_draw for the drawer, self_led_control for the chrome LED strip, and self_display_status for the chrome status line. Use the LED strip and status line for concise user-visible status updates, and reserve self_display_draw for richer frame output. For local tool interaction, inspect local tiles with network_list or network_get, use network_call or tile_call with the tile-specific message names exposed in responds_to, and inspect message_api on the returned tile payload for the required args and browser drive subcommands. Use tile_subscribe, tile_unsubscribe, and tile_subscription_list to manage session-wide tile event subscriptions for agents. Root may also use browser_drive for click, select, type, dom_query, eval, screenshot, press, hover, scroll, wait_for, fill_form, or check on browser tiles in the current session.'
         : 'Messages arrive as <channel source="herd" kind="..."> with metadata including from_agent_id, from_display_name, to_agent_id, to_display_name, channels, mentions, replay, timestamp_ms, and for tile_event also delivery_reason, subscription_scope, subscription_direction, action, subject_tile_id, peer_tile_id, caller_tile_id, caller_agent_id, target_tile_id, target_agent_id, rpc_channel, outcome, args_json, and result_json. kind="direct" is private coordination. kind="public" is session-wide chatter. kind="channel" is subscription-gated channel chatter. kind="network" is local network coordination. kind="root" is traffic for the session root agent. kind="tile_event" is a live tile-call event notification. kind="system" is Herd lifecycle information. Treat replay="true" as historical context rather than a fresh request, and treat replay="false" as live traffic. If you want Herd or other agents to see your reply, respond through the Herd messaging tools such as message_direct, message_public, message_channel, message_network, or message_root. Plain assistant text in the local session does not publish a reply back onto the Herd channels. Use self_info to inspect your own tile, self_display_draw for the drawer, self_led_control for the chrome LED strip, and self_display_status for the chrome status line. Use the LED strip and status line for concise user-visible status updates, and reserve self_display_draw for richer frame output. For local tool interaction, inspect your connected component with network_list or network_get, use network_call with the tile-specific message names exposed in responds_to for local-network tiles, and inspect message_api on the returned tile payload for the required args and browser drive subcommands. Use network_subscribe, network_unsubscribe, and network_subscription_list with selectors like in:exec, out:get, or both:extension_call to watch local-network tile activity.'),
   },
 );
@@ -922,10 +956,24 @@ function registerWorkerOnlyTools() {
 function registerRootTools() {
   registerTool(
     ROOT_TOOLS.browserDrive,
-    "Drive a browser tile in the current session. Supported actions: click, select, type, dom_query, eval, screenshot. `screenshot` accepts `{ format: \"image\" | \"braille\" | \"ascii\" | \"ansi\" | \"text\", columns?: number }`.",
+    "Drive a browser tile in the current session. Supported actions: click, select, type, dom_query, eval, screenshot, screenshot_diff, press, hover, scroll, wait_for, fill_form, check. `screenshot` accepts `{ format: \"image\" | \"braille\" | \"ascii\" | \"ansi\" | \"halfblock\" | \"sixel\" | \"kitty\" | \"text\" | \"accessibility\", columns?: number, selector?: string, clip?: { x, y, width, height } }`; the accessibility outline (alias \"outline\") tags interactive elements with refs such as `e3` that `click` and `type` accept as `{ ref }` in place of `selector`. Pixel screenshots return a `captureId`; `screenshot_diff` accepts `{ against?: captureId, baseline?: string, update_baseline?: boolean, tolerance?: number, diff_image?: boolean, selector?, clip? }` and returns the changed-pixel ratio and bounding boxes.",
     {
       tile_id: z.string(),
-      action: z.enum(["click", "select", "type", "dom_query", "eval", "screenshot"]),
+      action: z.enum([
+        "click",
+        "select",
+        "type",
+        "dom_query",
+        "eval",
+        "screenshot",
+        "screenshot_diff",
+        "press",
+        "hover",
+        "scroll",
+        "wait_for",
+        "fill_form",
+        "check",
+      ]),
       args: z.record(z.unknown()).optional(),
     },
     async ({ tile_id, action, args }) => {
@@ -945,6 +993,9 @@ function registerRootTools() {
         if (action === "screenshot") {
           return screenshotPayloadResult(resp.data, "browser_drive screenshot returned an invalid screenshot payload");
         }
+        if (action === "screenshot_diff") {
+          return screenshotDiffPayloadResult(resp.data, "browser_drive screenshot_diff returned an invalid diff payload");
+        }
         return { content: [{ type: "text", text: jsonText(resp.data) }] };
       } catch (err) {
         return errorResult(String(err));
@@ -1091,6 +1142,19 @@ function registerRootTools() {
       parent_session_id: z.string().optional(),
       parent_tile_id: z.string().optional(),
       browser_incognito: z.boolean().optional(),
+      worktree: z.boolean().optional(),
+      env: z.array(z.string()).optional(),
+      launch_policy: z
+        .object({
+          memory_limit_mb: z.number().int().positive().optional(),
+          cpu_time_limit_secs: z.number().int().positive().optional(),
+          max_processes: z.number().int().positive().optional(),
+          sandbox: z.enum(["none", "bubblewrap", "unshare"]).optional(),
+          read_only_paths: z.array(z.string()).optional(),
+          writable_paths: z.array(z.string()).optional(),
+          no_network: z.boolean().optional(),
+        })
+        .optional(),
     },
     async (params) => {
       if (params.tile_type === "work" && !params.title?.trim()) {
@@ -1113,6 +1177,9 @@ function registerRootTools() {
             parent_session_id: parentSessionId,
             parent_tile_id: parentTileId,
             browser_incognito: params.browser_incognito,
+            worktree: params.worktree,
+            env: params.env,
+            launch_policy: params.launch_policy,
             ...senderContext(),
           },
         );
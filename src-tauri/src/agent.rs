@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::network::{NetworkConnection, TilePortSetting};
 use crate::tile_message::TileMessageLogEntry;
+use crate::worktree::AgentWorktree;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
@@ -40,6 +41,8 @@ pub struct AgentInfo {
     pub channels: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<AgentWorktree>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -133,6 +136,8 @@ pub struct TileSubscriptionRecord {
     pub subject_tile_id: String,
     pub direction: TileSubscriptionDirection,
     pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
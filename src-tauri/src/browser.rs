@@ -13,9 +13,29 @@ use tauri::{
     Emitter, LogicalPosition, LogicalSize, Manager, Url, WebviewUrl,
 };
 
+use crate::browser_capture::{
+    capture_from_viewport_png, diff_captures, BrowserCapture, BrowserCaptureRegion, BrowserDiffBox,
+    DIFF_DEFAULT_TOLERANCE,
+};
+use crate::browser_console::{self, BrowserConsoleDrain, BrowserConsoleReadResult};
+use crate::browser_recording::{
+    browser_recordings_dir, write_frame, BrowserRecording, BrowserRecordingOptions, BrowserRecordingResult,
+    BrowserRecordingStatus,
+};
+
 const DEFAULT_BROWSER_URL: &str = "https://example.com/";
 const BROWSER_URL_EVENT: &str = "browser-url-changed";
 const BROWSER_DRIVE_TIMEOUT: Duration = Duration::from_secs(5);
+const BROWSER_EXTENSION_ASYNC_DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const BROWSER_EXTENSION_ASYNC_MAX_TIMEOUT_MS: u64 = 120_000;
+const BROWSER_EXTENSION_ASYNC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const BROWSER_WAIT_FOR_DEFAULT_TIMEOUT_MS: u64 = 5_000;
+const BROWSER_WAIT_FOR_MAX_TIMEOUT_MS: u64 = 60_000;
+const BROWSER_WAIT_FOR_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const BROWSER_DRIVE_PRESS_MODIFIERS: &[&str] = &["shift", "ctrl", "alt", "meta"];
+const BROWSER_DRIVE_SCROLL_TARGETS: &[&str] = &["top", "bottom", "left", "right"];
+const BROWSER_DRIVE_WAIT_STATES: &[&str] = &["visible", "hidden", "attached", "detached"];
+const BROWSER_ACCESSIBILITY_REF_ATTRIBUTE: &str = "data-herd-ref";
 const AGENT_BROWSER_VERSION: &str = "0.22.3";
 const AGENT_BROWSER_TARBALL_URL: &str = "https://registry.npmjs.org/agent-browser/-/agent-browser-0.22.3.tgz";
 const DEFAULT_BROWSER_PAGE_ZOOM: f64 = 1.0;
@@ -32,6 +52,13 @@ const ASCII_TEXT_ASPECT_RATIO: f64 = 0.5;
 const TEXT_GRID_MIN_ROW_HEIGHT_RATIO: f64 = 0.9;
 const TEXT_GRID_MAX_ROW_HEIGHT_RATIO: f64 = 2.2;
 const TEXT_GRID_FALLBACK_ROW_HEIGHT_RATIO: f64 = 1.4;
+/// Pixels per text column for the sixel and kitty encodings, which assume a
+/// terminal cell about twice as tall as it is wide.
+const TERMINAL_GRAPHICS_PIXELS_PER_COLUMN: u32 = 10;
+const KITTY_GRAPHICS_CHUNK_SIZE: usize = 4096;
+const SIXEL_RED_LEVELS: u32 = 6;
+const SIXEL_GREEN_LEVELS: u32 = 7;
+const SIXEL_BLUE_LEVELS: u32 = 6;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -366,6 +393,8 @@ struct BrowserUrlChangedEvent {
 struct BrowserImageScreenshotResult {
     mime_type: String,
     data_base64: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -375,6 +404,8 @@ pub struct BrowserTextScreenshotResult {
     text: String,
     columns: u32,
     rows: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -402,13 +433,27 @@ enum BrowserScreenshotResult {
     Text(BrowserTextScreenshotResult),
 }
 
+impl BrowserScreenshotResult {
+    fn with_capture_id(mut self, capture_id: String) -> Self {
+        match &mut self {
+            Self::Image(result) => result.capture_id = Some(capture_id),
+            Self::Text(result) => result.capture_id = Some(capture_id),
+        }
+        self
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BrowserScreenshotFormat {
     Image,
     Braille,
     Ascii,
     Ansi,
+    Halfblock,
+    Sixel,
+    Kitty,
     Text,
+    Accessibility,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -424,12 +469,16 @@ impl BrowserScreenshotFormat {
             Self::Braille => "braille",
             Self::Ascii => "ascii",
             Self::Ansi => "ansi",
+            Self::Halfblock => "halfblock",
+            Self::Sixel => "sixel",
+            Self::Kitty => "kitty",
             Self::Text => "text",
+            Self::Accessibility => "accessibility",
         }
     }
 
     fn uses_text_columns(self) -> bool {
-        !matches!(self, Self::Image)
+        !matches!(self, Self::Image | Self::Accessibility)
     }
 }
 
@@ -742,9 +791,21 @@ fn agent_browser_open_url(context: &AgentBrowserPaneContext, url: &str) -> Resul
         &["open".to_string(), url.to_string()],
         "agent-browser open",
     )?;
+    install_agent_browser_console_capture(context);
     agent_browser_current_url_for_context(context).or_else(|_| Ok(url.to_string()))
 }
 
+/// agent-browser has no per-page initialization script, so the console shim
+/// is installed right after Herd opens a page or clicks through one instead
+/// of on the first drain; output logged before the first `console_read` is
+/// then kept. The drain still installs it on pages that navigated on their
+/// own or were open before Herd started.
+fn install_agent_browser_console_capture(context: &AgentBrowserPaneContext) {
+    if let Err(error) = agent_browser_eval_value(context, browser_console::BROWSER_CONSOLE_CAPTURE_SCRIPT) {
+        log::debug!("agent-browser console capture install failed for {}: {error}", context.tile_id);
+    }
+}
+
 fn agent_browser_load_path(context: &AgentBrowserPaneContext, path: &str) -> Result<String, String> {
     let url = resolve_browser_file_url(path)?;
     agent_browser_open_url(context, url.as_str())
@@ -786,6 +847,7 @@ fn agent_browser_snapshot_text(
         rows: snapshot_text.lines().count() as u32,
         text: snapshot_text,
         columns,
+        capture_id: None,
     })
 }
 
@@ -981,14 +1043,58 @@ fn required_browser_drive_string_arg(args: &Value, field: &str, action: &str) ->
         .ok_or_else(|| format!("browser_drive {action} requires a non-empty string field `{field}`"))
 }
 
+/// Resolves the element an action targets. `ref` values come from the most
+/// recent `accessibility` screenshot, which tags each interactive element with
+/// a `data-herd-ref` attribute; `selector` is used when no ref is given.
+/// Scripts look the result up with `BROWSER_DEEP_QUERY_SCRIPT` because the
+/// outline also tags elements inside open shadow roots.
+fn browser_drive_target_selector(args: &Value, action: &str) -> Result<String, String> {
+    let Some(element_ref) = args.get("ref").and_then(Value::as_str).map(str::trim).filter(|value| !value.is_empty())
+    else {
+        return required_browser_drive_string_arg(args, "selector", action)
+            .map_err(|_| format!("browser_drive {action} requires a non-empty `selector` or `ref`"));
+    };
+    let valid = element_ref
+        .strip_prefix('e')
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit()));
+    if !valid {
+        return Err(format!(
+            "browser_drive {action} `ref` must look like e12, as returned by an accessibility screenshot"
+        ));
+    }
+    Ok(format!("[{BROWSER_ACCESSIBILITY_REF_ATTRIBUTE}=\"{element_ref}\"]"))
+}
+
+/// Defines `queryDeep`, which tries the document first and then each open
+/// shadow root breadth-first, since `document.querySelector` stops at shadow
+/// boundaries.
+const BROWSER_DEEP_QUERY_SCRIPT: &str = r#"
+const queryDeep = (selector) => {
+  const roots = [document];
+  while (roots.length > 0) {
+    const root = roots.shift();
+    const match = root.querySelector(selector);
+    if (match) {
+      return match;
+    }
+    for (const element of root.querySelectorAll('*')) {
+      if (element.shadowRoot) {
+        roots.push(element.shadowRoot);
+      }
+    }
+  }
+  return null;
+};
+"#;
+
 fn browser_drive_action_script(action: &str, args: &Value) -> Result<String, String> {
     match action {
         "click" => {
-            let selector = serde_json::to_string(&required_browser_drive_string_arg(args, "selector", action)?)
+            let selector = serde_json::to_string(&browser_drive_target_selector(args, action)?)
                 .map_err(|error| format!("failed to serialize browser selector: {error}"))?;
             Ok(format!(
-                r#"
-const element = document.querySelector({selector});
+                r#"{BROWSER_DEEP_QUERY_SCRIPT}
+const element = queryDeep({selector});
 if (!(element instanceof Element)) {{
   throw new Error(`No element matched selector: ${{{selector}}}`);
 }}
@@ -1031,14 +1137,14 @@ return {{ value: element.value }};
             ))
         }
         "type" => {
-            let selector = serde_json::to_string(&required_browser_drive_string_arg(args, "selector", action)?)
+            let selector = serde_json::to_string(&browser_drive_target_selector(args, action)?)
                 .map_err(|error| format!("failed to serialize browser selector: {error}"))?;
             let text = serde_json::to_string(&required_browser_drive_string_arg(args, "text", action)?)
                 .map_err(|error| format!("failed to serialize browser input text: {error}"))?;
             let clear = args.get("clear").and_then(Value::as_bool).unwrap_or(true);
             Ok(format!(
-                r#"
-const element = document.querySelector({selector});
+                r#"{BROWSER_DEEP_QUERY_SCRIPT}
+const element = queryDeep({selector});
 if (!(element instanceof Element)) {{
   throw new Error(`No element matched selector: ${{{selector}}}`);
 }}
@@ -1071,10 +1177,616 @@ throw new Error(`Element matched by ${{{selector}}} is not a supported text inpu
             Ok(format!("return (\n{js}\n);"))
         }
         "eval" => required_browser_drive_string_arg(args, "js", action),
+        "press" => {
+            required_browser_drive_string_arg(args, "key", action)?;
+            if let Some(modifiers) = args.get("modifiers") {
+                let modifiers = modifiers
+                    .as_array()
+                    .ok_or_else(|| "browser_drive press `modifiers` must be an array of strings".to_string())?;
+                for modifier in modifiers {
+                    let modifier = modifier.as_str().unwrap_or_default();
+                    if !BROWSER_DRIVE_PRESS_MODIFIERS.contains(&modifier) {
+                        return Err(format!(
+                            "browser_drive press modifier must be one of {}",
+                            BROWSER_DRIVE_PRESS_MODIFIERS.join(", ")
+                        ));
+                    }
+                }
+            }
+            Ok(BROWSER_DRIVE_PRESS_SCRIPT.to_string())
+        }
+        "hover" => {
+            required_browser_drive_string_arg(args, "selector", action)?;
+            Ok(BROWSER_DRIVE_HOVER_SCRIPT.to_string())
+        }
+        "scroll" => {
+            let has_selector = args.get("selector").and_then(Value::as_str).is_some_and(|value| !value.trim().is_empty());
+            let has_delta = ["x", "y"].iter().any(|field| args.get(*field).and_then(Value::as_f64).is_some());
+            let to = args.get("to").and_then(Value::as_str);
+            if let Some(to) = to {
+                if !BROWSER_DRIVE_SCROLL_TARGETS.contains(&to) {
+                    return Err(format!(
+                        "browser_drive scroll `to` must be one of {}",
+                        BROWSER_DRIVE_SCROLL_TARGETS.join(", ")
+                    ));
+                }
+            }
+            if !has_selector && !has_delta && to.is_none() {
+                return Err("browser_drive scroll requires `selector`, `x`/`y`, or `to`".to_string());
+            }
+            Ok(BROWSER_DRIVE_SCROLL_SCRIPT.to_string())
+        }
+        "wait_for" => {
+            if let Some(js) = args.get("js").and_then(Value::as_str).map(str::trim).filter(|js| !js.is_empty()) {
+                return browser_wait_for_predicate_script(js);
+            }
+            required_browser_drive_string_arg(args, "selector", action)
+                .map_err(|_| "browser_drive wait_for requires a non-empty `selector` or `js`".to_string())?;
+            if let Some(state) = args.get("state").and_then(Value::as_str) {
+                if !BROWSER_DRIVE_WAIT_STATES.contains(&state) {
+                    return Err(format!(
+                        "browser_drive wait_for `state` must be one of {}",
+                        BROWSER_DRIVE_WAIT_STATES.join(", ")
+                    ));
+                }
+            }
+            Ok(BROWSER_DRIVE_WAIT_FOR_SELECTOR_SCRIPT.to_string())
+        }
+        "fill_form" => {
+            let fields = args
+                .get("fields")
+                .and_then(Value::as_object)
+                .filter(|fields| !fields.is_empty())
+                .ok_or_else(|| "browser_drive fill_form requires a non-empty object field `fields`".to_string())?;
+            if let Some((selector, _)) = fields
+                .iter()
+                .find(|(_, value)| !(value.is_string() || value.is_number() || value.is_boolean()))
+            {
+                return Err(format!(
+                    "browser_drive fill_form value for {selector} must be a string, number, or boolean"
+                ));
+            }
+            Ok(BROWSER_DRIVE_FILL_FORM_SCRIPT.to_string())
+        }
+        "check" => {
+            required_browser_drive_string_arg(args, "selector", action)?;
+            if args.get("checked").is_some_and(|value| !value.is_boolean()) {
+                return Err("browser_drive check `checked` must be a boolean".to_string());
+            }
+            Ok(BROWSER_DRIVE_CHECK_SCRIPT.to_string())
+        }
         other => Err(format!("unsupported browser_drive action: {other}")),
     }
 }
 
+/// Walks the page DOM and renders landmarks, headings, links, buttons and
+/// form fields as an indented outline. Interactive entries are tagged with a
+/// fresh `data-herd-ref` so `click` and `type` can target them by `ref`.
+/// `args.selector` scopes the walk to one element and `args.clip` keeps only
+/// nodes that intersect that viewport rectangle.
+const BROWSER_ACCESSIBILITY_SNAPSHOT_SCRIPT: &str = r#"
+const refAttribute = 'data-herd-ref';
+const maxNodes = 500;
+const clip = args?.clip ?? null;
+let root = document.body ?? document.documentElement;
+if (args?.selector) {
+  root = document.querySelector(args.selector);
+  if (!root) {
+    throw new Error(`No element matched selector: ${args.selector}`);
+  }
+}
+const inClip = (element) => {
+  if (!clip) {
+    return true;
+  }
+  const rect = element.getBoundingClientRect();
+  return rect.right > clip.x && rect.left < clip.x + clip.width
+    && rect.bottom > clip.y && rect.top < clip.y + clip.height;
+};
+const clearRefs = (scope) => {
+  for (const element of scope.querySelectorAll('*')) {
+    element.removeAttribute(refAttribute);
+    if (element.shadowRoot) {
+      clearRefs(element.shadowRoot);
+    }
+  }
+};
+clearRefs(document);
+const landmarkTags = {
+  MAIN: 'main', NAV: 'navigation', ASIDE: 'complementary', FORM: 'form', DIALOG: 'dialog',
+};
+const interactiveRoles = new Set([
+  'link', 'button', 'textbox', 'searchbox', 'checkbox', 'radio', 'combobox', 'listbox',
+  'slider', 'spinbutton', 'switch', 'tab', 'menuitem', 'option',
+]);
+const landmarkRoles = new Set([
+  'main', 'navigation', 'complementary', 'banner', 'contentinfo', 'form', 'region', 'search', 'dialog',
+]);
+const collapse = (value) => String(value ?? '').replace(/\s+/g, ' ').trim();
+const truncate = (value, limit = 80) => value.length > limit ? `${value.slice(0, limit - 1)}…` : value;
+const isHidden = (element) => {
+  if (element.hidden || element.getAttribute('aria-hidden') === 'true') {
+    return true;
+  }
+  const style = window.getComputedStyle(element);
+  return style.display === 'none' || style.visibility === 'hidden';
+};
+const implicitRole = (element) => {
+  const tag = element.tagName;
+  if (/^H[1-6]$/.test(tag)) return 'heading';
+  if (tag === 'A') return element.hasAttribute('href') ? 'link' : null;
+  if (tag === 'BUTTON' || tag === 'SUMMARY') return 'button';
+  if (tag === 'TEXTAREA') return 'textbox';
+  if (tag === 'SELECT') return element.multiple ? 'listbox' : 'combobox';
+  if (tag === 'INPUT') {
+    const type = (element.getAttribute('type') ?? 'text').toLowerCase();
+    if (type === 'hidden') return null;
+    if (['button', 'submit', 'reset', 'image'].includes(type)) return 'button';
+    if (type === 'checkbox') return 'checkbox';
+    if (type === 'radio') return 'radio';
+    if (type === 'range') return 'slider';
+    if (type === 'number') return 'spinbutton';
+    if (type === 'search') return 'searchbox';
+    return 'textbox';
+  }
+  if (tag === 'HEADER') return element.closest('article, section, main') ? null : 'banner';
+  if (tag === 'FOOTER') return element.closest('article, section, main') ? null : 'contentinfo';
+  if (tag === 'SECTION') return element.hasAttribute('aria-label') || element.hasAttribute('aria-labelledby') ? 'region' : null;
+  if (element.isContentEditable && element.parentElement && !element.parentElement.isContentEditable) return 'textbox';
+  return landmarkTags[tag] ?? null;
+};
+const accessibleName = (element, role) => {
+  const labelledBy = element.getAttribute('aria-labelledby');
+  if (labelledBy) {
+    const text = labelledBy.split(/\s+/).map((id) => document.getElementById(id)?.textContent ?? '').join(' ');
+    if (collapse(text)) return collapse(text);
+  }
+  const ariaLabel = collapse(element.getAttribute('aria-label'));
+  if (ariaLabel) return ariaLabel;
+  if (element.labels && element.labels.length > 0) {
+    const text = collapse(Array.from(element.labels).map((label) => label.textContent).join(' '));
+    if (text) return text;
+  }
+  if (element.tagName === 'INPUT' && ['button', 'submit', 'reset'].includes(element.type)) {
+    return collapse(element.value) || element.type;
+  }
+  const alt = collapse(element.getAttribute('alt'));
+  if (alt) return alt;
+  if (['link', 'button', 'heading', 'tab', 'menuitem', 'option'].includes(role)) {
+    const text = collapse(element.textContent);
+    if (text) return text;
+  }
+  return collapse(element.getAttribute('title')) || collapse(element.getAttribute('placeholder'));
+};
+const describeState = (element, role) => {
+  const parts = [];
+  if (role === 'heading') parts.push(`[level=${element.tagName.slice(1)}]`);
+  if ('checked' in element && (role === 'checkbox' || role === 'radio' || role === 'switch')) {
+    parts.push(element.checked ? '[checked]' : '[unchecked]');
+  } else if (element.getAttribute('aria-checked')) {
+    parts.push(`[checked=${element.getAttribute('aria-checked')}]`);
+  }
+  if (element.disabled || element.getAttribute('aria-disabled') === 'true') parts.push('[disabled]');
+  if (element.required || element.getAttribute('aria-required') === 'true') parts.push('[required]');
+  if (element.getAttribute('aria-expanded')) parts.push(`[expanded=${element.getAttribute('aria-expanded')}]`);
+  if (element.getAttribute('aria-selected') === 'true') parts.push('[selected]');
+  if (role === 'link') parts.push(`href=${JSON.stringify(truncate(element.getAttribute('href') ?? ''))}`);
+  if (element instanceof HTMLSelectElement) {
+    const selected = Array.from(element.selectedOptions).map((option) => collapse(option.textContent));
+    parts.push(`value=${JSON.stringify(truncate(selected.join(', ')))}`);
+  } else if ((element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement)
+    && !['checkbox', 'radio', 'button', 'submit', 'reset', 'image'].includes(element.type)) {
+    parts.push(element.type === 'password'
+      ? `value=${element.value ? '"********"' : '""'}`
+      : `value=${JSON.stringify(truncate(element.value ?? ''))}`);
+  } else if (role === 'textbox' && element.isContentEditable) {
+    parts.push(`value=${JSON.stringify(truncate(collapse(element.textContent)))}`);
+  }
+  return parts;
+};
+const lines = [];
+let refCount = 0;
+let nodeCount = 0;
+let omitted = 0;
+const visit = (element, depth) => {
+  if (!(element instanceof Element) || isHidden(element)) {
+    return;
+  }
+  const explicitRole = collapse(element.getAttribute('role')).split(' ')[0] || null;
+  const role = explicitRole ?? implicitRole(element);
+  const included = role !== null
+    && (interactiveRoles.has(role) || landmarkRoles.has(role) || role === 'heading')
+    && inClip(element);
+  let childDepth = depth;
+  if (included) {
+    if (nodeCount >= maxNodes) {
+      omitted += 1;
+    } else {
+      nodeCount += 1;
+      const name = truncate(accessibleName(element, role));
+      const parts = [`${'  '.repeat(depth)}- ${role}`];
+      if (name) parts.push(JSON.stringify(name));
+      if (interactiveRoles.has(role)) {
+        refCount += 1;
+        const ref = `e${refCount}`;
+        element.setAttribute(refAttribute, ref);
+        parts.push(`[ref=${ref}]`);
+      }
+      parts.push(...describeState(element, role));
+      lines.push(parts.join(' '));
+      childDepth = depth + 1;
+    }
+    if (role === 'heading' || role === 'link' || role === 'button' || role === 'option') {
+      return;
+    }
+  }
+  if (element instanceof HTMLSelectElement || element instanceof HTMLTextAreaElement) {
+    return;
+  }
+  for (const child of element.children) {
+    visit(child, childDepth);
+  }
+  if (element.shadowRoot) {
+    for (const child of element.shadowRoot.children) {
+      visit(child, childDepth);
+    }
+  }
+};
+visit(root, 0);
+if (omitted > 0) {
+  lines.push(`- … ${omitted} more nodes omitted`);
+}
+const title = collapse(document.title);
+return {
+  text: [`- document ${JSON.stringify(title)} url=${JSON.stringify(location.href)}`, ...lines.map((line) => `  ${line}`)].join('\n'),
+  refs: refCount,
+};
+"#;
+
+#[derive(Debug, Deserialize)]
+struct BrowserAccessibilitySnapshot {
+    text: String,
+}
+
+fn accessibility_screenshot_result(data: Value) -> Result<BrowserScreenshotResult, String> {
+    let snapshot: BrowserAccessibilitySnapshot = serde_json::from_value(data)
+        .map_err(|error| format!("accessibility snapshot returned invalid JSON: {error}"))?;
+    let columns = snapshot
+        .text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u32;
+    Ok(text_screenshot_result(BrowserScreenshotFormat::Accessibility, snapshot.text, columns))
+}
+
+const BROWSER_DRIVE_PRESS_SCRIPT: &str = r#"
+const key = String(args.key);
+const modifiers = new Set(Array.isArray(args.modifiers) ? args.modifiers : []);
+let target = document.activeElement instanceof Element
+  ? document.activeElement
+  : (document.body ?? document.documentElement);
+if (typeof args.selector === 'string' && args.selector.trim()) {
+  const selected = document.querySelector(args.selector);
+  if (!(selected instanceof Element)) {
+    throw new Error(`No element matched selector: ${args.selector}`);
+  }
+  if (typeof selected.focus === 'function') {
+    selected.focus();
+  }
+  target = selected;
+}
+const namedKeyCodes = {
+  Backspace: 8, Tab: 9, Enter: 13, Shift: 16, Control: 17, Alt: 18, Escape: 27, ' ': 32,
+  PageUp: 33, PageDown: 34, End: 35, Home: 36, ArrowLeft: 37, ArrowUp: 38, ArrowRight: 39,
+  ArrowDown: 40, Delete: 46, Meta: 91,
+};
+const code = /^[a-z]$/i.test(key)
+  ? `Key${key.toUpperCase()}`
+  : /^[0-9]$/.test(key)
+    ? `Digit${key}`
+    : key === ' ' ? 'Space' : key;
+const keyCode = namedKeyCodes[key] ?? (key.length === 1 ? key.toUpperCase().charCodeAt(0) : 0);
+const init = {
+  key,
+  code,
+  bubbles: true,
+  cancelable: true,
+  composed: true,
+  shiftKey: modifiers.has('shift'),
+  ctrlKey: modifiers.has('ctrl'),
+  altKey: modifiers.has('alt'),
+  metaKey: modifiers.has('meta'),
+};
+const dispatchKey = (type) => {
+  const event = new KeyboardEvent(type, init);
+  Object.defineProperty(event, 'keyCode', { get: () => keyCode });
+  Object.defineProperty(event, 'which', { get: () => keyCode });
+  return target.dispatchEvent(event);
+};
+const accepted = dispatchKey('keydown');
+if (accepted && key.length === 1 && !init.ctrlKey && !init.metaKey) {
+  dispatchKey('keypress');
+}
+const holdMs = Number(args.hold_ms ?? 0);
+if (holdMs > 0) {
+  setTimeout(() => dispatchKey('keyup'), holdMs);
+} else {
+  dispatchKey('keyup');
+}
+return { key, code, modifiers: Array.from(modifiers), default_prevented: !accepted };
+"#;
+
+const BROWSER_DRIVE_HOVER_SCRIPT: &str = r#"
+const element = document.querySelector(args.selector);
+if (!(element instanceof Element)) {
+  throw new Error(`No element matched selector: ${args.selector}`);
+}
+element.scrollIntoView({ block: 'center', inline: 'center' });
+const rect = element.getBoundingClientRect();
+const clientX = rect.left + rect.width / 2;
+const clientY = rect.top + rect.height / 2;
+const init = { cancelable: true, composed: true, clientX, clientY, view: window };
+if (typeof PointerEvent === 'function') {
+  for (const type of ['pointerover', 'pointerenter', 'pointermove']) {
+    element.dispatchEvent(new PointerEvent(type, { ...init, bubbles: type !== 'pointerenter', pointerType: 'mouse' }));
+  }
+}
+for (const type of ['mouseover', 'mouseenter', 'mousemove']) {
+  element.dispatchEvent(new MouseEvent(type, { ...init, bubbles: type !== 'mouseenter' }));
+}
+return { hovered: true, x: Math.round(clientX), y: Math.round(clientY) };
+"#;
+
+const BROWSER_DRIVE_SCROLL_SCRIPT: &str = r#"
+let element = null;
+if (typeof args.selector === 'string' && args.selector.trim()) {
+  element = document.querySelector(args.selector);
+  if (!(element instanceof Element)) {
+    throw new Error(`No element matched selector: ${args.selector}`);
+  }
+}
+const scroller = element ?? document.scrollingElement ?? document.documentElement;
+const left = Number(args.x ?? 0) || 0;
+const top = Number(args.y ?? 0) || 0;
+if (typeof args.to === 'string') {
+  const positions = {
+    top: [scroller.scrollLeft, 0],
+    bottom: [scroller.scrollLeft, scroller.scrollHeight],
+    left: [0, scroller.scrollTop],
+    right: [scroller.scrollWidth, scroller.scrollTop],
+  };
+  const [x, y] = positions[args.to];
+  scroller.scrollTo({ left: x, top: y, behavior: 'instant' });
+} else if (element && left === 0 && top === 0) {
+  element.scrollIntoView({ block: 'center', inline: 'center', behavior: 'instant' });
+} else {
+  scroller.scrollBy({ left, top, behavior: 'instant' });
+}
+return {
+  scroll_x: Math.round(scroller.scrollLeft),
+  scroll_y: Math.round(scroller.scrollTop),
+  scroll_width: Math.round(scroller.scrollWidth),
+  scroll_height: Math.round(scroller.scrollHeight),
+};
+"#;
+
+const BROWSER_DRIVE_WAIT_FOR_SELECTOR_SCRIPT: &str = r#"
+const state = args.state ?? 'visible';
+const element = document.querySelector(args.selector);
+const isVisible = (candidate) => {
+  if (!(candidate instanceof Element)) {
+    return false;
+  }
+  const style = window.getComputedStyle(candidate);
+  const rect = candidate.getBoundingClientRect();
+  return style.display !== 'none'
+    && style.visibility !== 'hidden'
+    && Number(style.opacity) !== 0
+    && rect.width > 0
+    && rect.height > 0;
+};
+const visible = isVisible(element);
+const satisfied = state === 'attached'
+  ? element !== null
+  : state === 'detached'
+    ? element === null
+    : state === 'hidden' ? !visible : visible;
+return { satisfied };
+"#;
+
+const BROWSER_DRIVE_FILL_FORM_SCRIPT: &str = r#"
+const dispatchChange = (element) => {
+  element.dispatchEvent(new Event('input', { bubbles: true, cancelable: true }));
+  element.dispatchEvent(new Event('change', { bubbles: true, cancelable: true }));
+};
+const setNativeValue = (element, value) => {
+  const descriptor = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(element), 'value');
+  if (descriptor && typeof descriptor.set === 'function') {
+    descriptor.set.call(element, value);
+  } else {
+    element.value = value;
+  }
+};
+const filled = [];
+for (const [selector, value] of Object.entries(args.fields)) {
+  const element = document.querySelector(selector);
+  if (!(element instanceof Element)) {
+    throw new Error(`No element matched selector: ${selector}`);
+  }
+  if (element instanceof HTMLInputElement && (element.type === 'checkbox' || element.type === 'radio')) {
+    if (typeof value !== 'boolean') {
+      throw new Error(`Value for ${selector} must be a boolean for ${element.type} inputs`);
+    }
+    if (element.checked !== value) {
+      element.click();
+    }
+  } else if (element instanceof HTMLSelectElement) {
+    const next = String(value);
+    if (!Array.from(element.options).some((option) => option.value === next)) {
+      throw new Error(`No option matched value ${next} for selector ${selector}`);
+    }
+    setNativeValue(element, next);
+    dispatchChange(element);
+  } else if (element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement) {
+    if (typeof element.focus === 'function') {
+      element.focus();
+    }
+    setNativeValue(element, String(value));
+    dispatchChange(element);
+  } else if (element instanceof HTMLElement && element.isContentEditable) {
+    element.textContent = String(value);
+    dispatchChange(element);
+  } else {
+    throw new Error(`Element matched by ${selector} is not a supported form field`);
+  }
+  filled.push(selector);
+}
+return { filled };
+"#;
+
+const BROWSER_DRIVE_CHECK_SCRIPT: &str = r#"
+const element = document.querySelector(args.selector);
+if (!(element instanceof HTMLInputElement) || (element.type !== 'checkbox' && element.type !== 'radio')) {
+  throw new Error(`Element matched by ${args.selector} is not a checkbox or radio input`);
+}
+const desired = args.checked !== false;
+if (element.type === 'radio' && !desired) {
+  throw new Error('Radio inputs cannot be unchecked directly; check another option instead');
+}
+element.scrollIntoView({ block: 'center', inline: 'center' });
+if (element.checked !== desired) {
+  element.click();
+}
+if (element.checked !== desired) {
+  element.checked = desired;
+  element.dispatchEvent(new Event('input', { bubbles: true, cancelable: true }));
+  element.dispatchEvent(new Event('change', { bubbles: true, cancelable: true }));
+}
+return { checked: element.checked };
+"#;
+
+/// Evaluates a `wait_for` predicate. A promise result is kept on
+/// `__herdWaitFor` and read back by later polls, as pending extension calls
+/// are, so an async predicate is awaited instead of counting as truthy; the
+/// predicate runs again only after the previous promise settled falsy.
+fn browser_wait_for_predicate_script(js: &str) -> Result<String, String> {
+    let key = serde_json::to_string(&format!("wait-{}", uuid::Uuid::new_v4().simple()))
+        .map_err(|error| format!("failed to serialize browser wait key: {error}"))?;
+    Ok(format!(
+        r#"
+const waits = globalThis.__herdWaitFor || (globalThis.__herdWaitFor = {{}});
+const key = {key};
+const pending = waits[key];
+if (pending) {{
+  if (!pending.settled) {{
+    return {{ satisfied: false }};
+  }}
+  delete waits[key];
+  if ('error' in pending) {{
+    throw pending.error;
+  }}
+  if (pending.value) {{
+    return {{ satisfied: true }};
+  }}
+}}
+const value = (
+{js}
+);
+if (value && typeof value.then === 'function') {{
+  const entry = {{ settled: false }};
+  waits[key] = entry;
+  value.then(
+    (resolved) => {{
+      entry.settled = true;
+      entry.value = Boolean(resolved);
+    }},
+    (error) => {{
+      entry.settled = true;
+      entry.error = error;
+    }},
+  );
+  return {{ satisfied: false }};
+}}
+return {{ satisfied: Boolean(value) }};
+"#
+    ))
+}
+
+/// Polls a `wait_for` check script until it reports `satisfied` or the
+/// requested `timeout_ms` elapses. The drive wrapper evaluates synchronously,
+/// so the waiting happens here rather than inside the page.
+fn wait_for_browser_condition<F>(check_script: &str, args: &Value, mut check: F) -> Result<Value, String>
+where
+    F: FnMut(&str) -> Result<Value, String>,
+{
+    let timeout_ms = args
+        .get("timeout_ms")
+        .and_then(Value::as_u64)
+        .unwrap_or(BROWSER_WAIT_FOR_DEFAULT_TIMEOUT_MS)
+        .min(BROWSER_WAIT_FOR_MAX_TIMEOUT_MS);
+    let started = std::time::Instant::now();
+    loop {
+        let data = check(check_script)?;
+        if data.get("satisfied").and_then(Value::as_bool).unwrap_or(false) {
+            return Ok(serde_json::json!({
+                "satisfied": true,
+                "elapsed_ms": started.elapsed().as_millis() as u64,
+            }));
+        }
+        if started.elapsed() >= Duration::from_millis(timeout_ms) {
+            let condition = args
+                .get("js")
+                .and_then(Value::as_str)
+                .map(|js| format!("predicate {js}"))
+                .unwrap_or_else(|| {
+                    format!(
+                        "{} to be {}",
+                        args.get("selector").and_then(Value::as_str).unwrap_or_default(),
+                        args.get("state").and_then(Value::as_str).unwrap_or("visible"),
+                    )
+                });
+            return Err(format!("browser_drive wait_for timed out after {timeout_ms}ms waiting for {condition}"));
+        }
+        std::thread::sleep(BROWSER_WAIT_FOR_POLL_INTERVAL);
+    }
+}
+
+fn browser_drive_envelope_data(envelope: BrowserDriveEnvelope, action: &str) -> Result<Value, String> {
+    if envelope.ok {
+        Ok(envelope.data.unwrap_or(Value::Null))
+    } else {
+        Err(envelope
+            .error
+            .unwrap_or_else(|| format!("browser_drive {action} failed")))
+    }
+}
+
+fn evaluate_browser_drive_script(
+    webview: &tauri::Webview,
+    action_script: &str,
+    args: &Value,
+    action: &str,
+) -> Result<Value, String> {
+    let wrapped = browser_drive_wrapper_script(action_script, args)?;
+    let raw_result = evaluate_browser_script(webview, &wrapped)?;
+    let envelope: BrowserDriveEnvelope = serde_json::from_str(&raw_result)
+        .map_err(|error| format!("browser_drive {action} returned invalid JSON: {error}"))?;
+    browser_drive_envelope_data(envelope, action)
+}
+
+fn agent_browser_drive_script(
+    context: &AgentBrowserPaneContext,
+    action_script: &str,
+    args: &Value,
+    action: &str,
+) -> Result<Value, String> {
+    let wrapped = browser_drive_wrapper_script(action_script, args)?;
+    let raw_result = agent_browser_eval_value(context, &wrapped)?;
+    let envelope: BrowserDriveEnvelope = parse_browser_envelope_value(raw_result, &format!("browser_drive {action}"))?;
+    browser_drive_envelope_data(envelope, action)
+}
+
 fn browser_drive_wrapper_script(action_script: &str, args: &Value) -> Result<String, String> {
     let args_json = serde_json::to_string(args)
         .map_err(|error| format!("failed to serialize browser drive args: {error}"))?;
@@ -1373,6 +2085,9 @@ struct BrowserExtensionEnvelope {
     data: Option<Value>,
     #[serde(default)]
     error: Option<String>,
+    /// Set while an async method's promise is still outstanding.
+    #[serde(default)]
+    pending: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -1390,6 +2105,7 @@ fn browser_screenshot_options(args: &Value) -> Result<BrowserScreenshotOptions,
         args.get("format").and_then(Value::as_str),
         "browser_drive screenshot",
         true,
+        true,
     )?;
 
     let columns = if format.uses_text_columns() {
@@ -1406,6 +2122,7 @@ fn browser_extension_screenshot_options(args: &Value) -> Result<BrowserScreensho
         args.get("format").and_then(Value::as_str),
         "browser extension screenshot",
         true,
+        false,
     )?;
 
     let columns = if format.uses_text_columns() {
@@ -1457,9 +2174,15 @@ fn browser_extension_screenshot_result_from_value(
         BrowserScreenshotFormat::Braille
         | BrowserScreenshotFormat::Ascii
         | BrowserScreenshotFormat::Ansi
+        | BrowserScreenshotFormat::Halfblock
+        | BrowserScreenshotFormat::Sixel
+        | BrowserScreenshotFormat::Kitty
         | BrowserScreenshotFormat::Text => {
             text_screenshot_result_from_png(&png_bytes, options.format, options.columns)
         }
+        BrowserScreenshotFormat::Accessibility => {
+            Err("browser extension screenshots do not support the accessibility format".to_string())
+        }
     }
 }
 
@@ -1519,6 +2242,7 @@ fn browser_extension_call_script(
 const method = {method_json};
 const args = {args_json};
 const caller = {caller_json};
+{BROWSER_EXTENSION_PENDING_CALLS_SCRIPT}
 const envelope = (() => {{
   try {{
     const extension = globalThis.HerdBrowserExtension;
@@ -1527,7 +2251,7 @@ const envelope = (() => {{
     }}
     const result = extension.call(method, args, caller);
     if (result && typeof result === 'object' && typeof result.then === 'function') {{
-      throw new Error('browser extension methods must return synchronously');
+      return {{ ok: true, pending: __herdTrackExtensionCall(result) }};
     }}
     return {{
       ok: true,
@@ -1548,6 +2272,173 @@ return JSON.stringify(envelope);
     ))
 }
 
+/// Keeps promises returned by extension methods on `__herdExtensionCalls`
+/// until Herd polls for them, since neither webview evaluates scripts
+/// asynchronously.
+const BROWSER_EXTENSION_PENDING_CALLS_SCRIPT: &str = r#"const __herdExtensionCalls = globalThis.__herdExtensionCalls
+  || (globalThis.__herdExtensionCalls = { next: 0, calls: {} });
+const __herdTrackExtensionCall = (promise) => {
+  __herdExtensionCalls.next += 1;
+  const id = `call-${Date.now().toString(36)}-${__herdExtensionCalls.next}`;
+  const settle = (settled) => {
+    if (__herdExtensionCalls.calls[id]) {
+      __herdExtensionCalls.calls[id] = settled;
+    }
+  };
+  __herdExtensionCalls.calls[id] = { settled: false };
+  promise.then(
+    (value) => settle({ settled: true, ok: true, data: value === undefined ? null : value }),
+    (error) => settle({
+      settled: true,
+      ok: false,
+      error: error && typeof error === 'object' && 'message' in error ? String(error.message) : String(error),
+    }),
+  );
+  return id;
+};"#;
+
+/// Installed into every browser page so extensions can push events with
+/// `HerdBrowserExtension.emit(event, payload)`. `emit` is attached to whatever
+/// object the page assigns to `HerdBrowserExtension`, checks the event against
+/// `manifest.events`, and queues it on `__herdExtensionEvents` until Herd
+/// drains it. The script is idempotent so the agent-browser backend can re-run
+/// it on demand.
+const BROWSER_EXTENSION_EVENT_BRIDGE_SCRIPT: &str = r#"
+(function() {
+  const bridge = window.__herdExtensionEvents || (() => {
+    const created = { queue: [], dropped: 0, installed: false };
+    Object.defineProperty(window, '__herdExtensionEvents', { value: created, enumerable: false });
+    return created;
+  })();
+  const maxQueued = 200;
+  const emit = (event, payload) => {
+    const declared = Array.isArray(globalThis.HerdBrowserExtension?.manifest?.events)
+      ? globalThis.HerdBrowserExtension.manifest.events.map((entry) => entry?.name)
+      : [];
+    if (typeof event !== 'string' || !declared.includes(event)) {
+      throw new Error(`HerdBrowserExtension.emit: event ${String(event)} is not declared in manifest.events`);
+    }
+    let snapshot;
+    try {
+      snapshot = payload === undefined ? null : JSON.parse(JSON.stringify(payload));
+    } catch (_error) {
+      throw new Error(`HerdBrowserExtension.emit: payload for ${event} must be JSON-serializable`);
+    }
+    bridge.queue.push({ event, payload: snapshot, timestamp_ms: Date.now() });
+    if (bridge.queue.length > maxQueued) {
+      bridge.queue.shift();
+      bridge.dropped += 1;
+    }
+  };
+  const attach = (extension) => {
+    if (extension && typeof extension === 'object' && typeof extension.emit !== 'function') {
+      try {
+        extension.emit = emit;
+      } catch (_error) {}
+    }
+    return extension;
+  };
+  if (bridge.installed) {
+    attach(globalThis.HerdBrowserExtension);
+    return;
+  }
+  bridge.installed = true;
+  let current = attach(globalThis.HerdBrowserExtension);
+  try {
+    Object.defineProperty(globalThis, 'HerdBrowserExtension', {
+      configurable: true,
+      enumerable: true,
+      get() {
+        return current;
+      },
+      set(value) {
+        current = attach(value);
+      },
+    });
+  } catch (_error) {}
+})();
+"#;
+
+fn browser_extension_event_drain_script() -> String {
+    format!(
+        r#"
+{BROWSER_EXTENSION_EVENT_BRIDGE_SCRIPT}
+const bridge = window.__herdExtensionEvents;
+const events = bridge.queue.splice(0, bridge.queue.length);
+const dropped = bridge.dropped;
+bridge.dropped = 0;
+return {{ events, dropped }};
+"#
+    )
+}
+
+fn browser_extension_poll_script(call_id: &str, cancel: bool) -> Result<String, String> {
+    let call_id_json = serde_json::to_string(call_id)
+        .map_err(|error| format!("failed to serialize browser extension call id: {error}"))?;
+    Ok(format!(
+        r#"(function() {{
+const callId = {call_id_json};
+const calls = globalThis.__herdExtensionCalls?.calls ?? {{}};
+const call = calls[callId];
+let envelope;
+if ({cancel}) {{
+  delete calls[callId];
+  envelope = {{ ok: true, data: null }};
+}} else if (!call) {{
+  envelope = {{ ok: false, error: 'browser extension call was lost before it settled; the page may have reloaded' }};
+}} else if (!call.settled) {{
+  envelope = {{ ok: true, pending: callId }};
+}} else {{
+  delete calls[callId];
+  envelope = call.ok ? {{ ok: true, data: call.data }} : {{ ok: false, error: call.error }};
+}}
+return JSON.stringify(envelope);
+}})();"#,
+    ))
+}
+
+/// How long to wait for `method` to settle when it returns a promise. The
+/// caller's `timeout_ms` wins over the manifest's, and both are capped.
+fn browser_extension_call_timeout(
+    method: &crate::network::BrowserExtensionMethod,
+    requested_ms: Option<u64>,
+) -> Duration {
+    let timeout_ms = requested_ms
+        .or(method.timeout_ms)
+        .unwrap_or(BROWSER_EXTENSION_ASYNC_DEFAULT_TIMEOUT_MS)
+        .clamp(1, BROWSER_EXTENSION_ASYNC_MAX_TIMEOUT_MS);
+    Duration::from_millis(timeout_ms)
+}
+
+/// Polls a pending extension call until its promise settles. On timeout the
+/// page-side entry is dropped so a late result is discarded.
+fn await_browser_extension_envelope<F>(
+    mut envelope: BrowserExtensionEnvelope,
+    method: &str,
+    timeout: Duration,
+    mut evaluate: F,
+) -> Result<BrowserExtensionEnvelope, String>
+where
+    F: FnMut(&str) -> Result<BrowserExtensionEnvelope, String>,
+{
+    let started = std::time::Instant::now();
+    while envelope.ok {
+        let Some(call_id) = envelope.pending.take() else {
+            break;
+        };
+        if started.elapsed() >= timeout {
+            let _ = evaluate(&browser_extension_poll_script(&call_id, true)?);
+            return Err(format!(
+                "browser extension call {method} timed out after {}ms waiting for its promise",
+                timeout.as_millis()
+            ));
+        }
+        std::thread::sleep(BROWSER_EXTENSION_ASYNC_POLL_INTERVAL);
+        envelope = evaluate(&browser_extension_poll_script(&call_id, false)?)?;
+    }
+    Ok(envelope)
+}
+
 fn sanitize_browser_extension_info(
     value: Value,
     source_path: String,
@@ -1568,11 +2459,71 @@ fn sanitize_browser_extension_info(
         if !seen.insert(method.name.clone()) {
             return Err(format!("browser extension manifest method names must be unique: {}", method.name));
         }
+        for (field, schema) in [("args_schema", &method.args_schema), ("result_schema", &method.result_schema)] {
+            if let Some(schema) = schema {
+                jsonschema::validator_for(schema).map_err(|error| {
+                    format!("browser extension manifest method {} has an invalid {field}: {error}", method.name)
+                })?;
+            }
+        }
+    }
+    let reserved = browser_reserved_event_names();
+    let mut seen = std::collections::HashSet::new();
+    for event in &info.events {
+        if event.name.is_empty()
+            || !event
+                .name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.'))
+        {
+            return Err(format!(
+                "browser extension manifest event names must be non-empty and use only letters, digits, `_`, `-`, or `.`: {}",
+                event.name
+            ));
+        }
+        if reserved.contains(&event.name) {
+            return Err(format!(
+                "browser extension manifest event {} collides with a built-in browser tile action",
+                event.name
+            ));
+        }
+        if !seen.insert(event.name.clone()) {
+            return Err(format!("browser extension manifest event names must be unique: {}", event.name));
+        }
     }
+    crate::network::plan_named_ports("", crate::network::NetworkTileKind::Browser, &info.ports, &[])
+        .map_err(|error| format!("browser extension manifest ports are invalid: {error}"))?;
     info.source_path = Some(source_path);
     Ok(info)
 }
 
+/// Checks `value` against a manifest-declared schema and lists every violation
+/// with its JSON pointer, e.g. `/rom: 3 is not of type "string"`.
+fn validate_browser_extension_value(schema: &Value, value: &Value, subject: &str) -> Result<(), String> {
+    let validator = jsonschema::validator_for(schema).map_err(|error| format!("{subject} schema is invalid: {error}"))?;
+    let violations = validator
+        .iter_errors(value)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            format!("{}: {error}", if path.is_empty() { "/" } else { path.as_str() })
+        })
+        .collect::<Vec<_>>();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{subject} failed schema validation: {}", violations.join("; ")))
+    }
+}
+
+/// Actions browser tiles already publish as tile events, which extension
+/// events may not reuse.
+fn browser_reserved_event_names() -> Vec<String> {
+    let mut names = crate::network::responds_to(crate::network::NetworkTileKind::Browser);
+    names.push("extension_call".to_string());
+    names.push(browser_console::BROWSER_CONSOLE_EVENT.to_string());
+    names
+}
+
 pub fn browser_extension_info_for_pane(
     app: &tauri::AppHandle,
     pane_id: &str,
@@ -1603,6 +2554,7 @@ fn parse_browser_screenshot_format(
     raw: Option<&str>,
     context: &str,
     allow_image: bool,
+    allow_accessibility: bool,
 ) -> Result<BrowserScreenshotFormat, String> {
     match raw.map(str::trim).filter(|value| !value.is_empty()) {
         None if allow_image => Ok(BrowserScreenshotFormat::Image),
@@ -1611,9 +2563,16 @@ fn parse_browser_screenshot_format(
         Some("braille") => Ok(BrowserScreenshotFormat::Braille),
         Some("ascii") => Ok(BrowserScreenshotFormat::Ascii),
         Some("ansi") => Ok(BrowserScreenshotFormat::Ansi),
+        Some("halfblock") if allow_image => Ok(BrowserScreenshotFormat::Halfblock),
+        Some("sixel") if allow_image => Ok(BrowserScreenshotFormat::Sixel),
+        Some("kitty") if allow_image => Ok(BrowserScreenshotFormat::Kitty),
         Some("text") => Ok(BrowserScreenshotFormat::Text),
+        Some("accessibility" | "outline") if allow_accessibility => Ok(BrowserScreenshotFormat::Accessibility),
+        Some(other) if allow_accessibility => Err(format!(
+            "{context} requires `format` to be one of `image`, `braille`, `ascii`, `ansi`, `halfblock`, `sixel`, `kitty`, `text`, or `accessibility` (alias `outline`), got `{other}`"
+        )),
         Some(other) if allow_image => Err(format!(
-            "{context} requires `format` to be one of `image`, `braille`, `ascii`, `ansi`, or `text`, got `{other}`"
+            "{context} requires `format` to be one of `image`, `braille`, `ascii`, `ansi`, `halfblock`, `sixel`, `kitty`, or `text`, got `{other}`"
         )),
         Some(other) => Err(format!(
             "{context} requires `format` to be one of `text`, `braille`, `ansi`, or `ascii`, got `{other}`"
@@ -1626,7 +2585,7 @@ fn browser_preview_options(
     columns: Option<&Value>,
 ) -> Result<BrowserScreenshotOptions, String> {
     Ok(BrowserScreenshotOptions {
-        format: parse_browser_screenshot_format(format, "browser preview", false)?,
+        format: parse_browser_screenshot_format(format, "browser preview", false, false)?,
         columns: parse_text_columns_arg(columns, "browser preview")?,
     })
 }
@@ -1678,7 +2637,7 @@ fn resize_for_braille(image: &GrayImage, columns: u32) -> GrayImage {
         ((image.height() as f64 * target_width as f64) / image.width().max(1) as f64).round() as u32;
     let target_height = scaled_height.max(4);
     let padded_height = ((target_height + 3) / 4) * 4;
-    image::imageops::resize(image, target_width, padded_height, FilterType::Triangle)
+    image::imageops::resize(image, target_width, padded_height, filter)
 }
 
 fn resize_for_ascii(image: &GrayImage, columns: u32) -> GrayImage {
@@ -1690,7 +2649,7 @@ fn resize_for_ascii(image: &GrayImage, columns: u32) -> GrayImage {
     image::imageops::resize(image, target_width, target_height, FilterType::Triangle)
 }
 
-fn resize_for_ansi(image: &RgbImage, columns: u32) -> RgbImage {
+fn resize_for_ansi(image: &RgbImage, columns: u32, filter: FilterType) -> RgbImage {
     let target_width = columns.max(1);
     let scaled_height =
         ((image.height() as f64 * target_width as f64) / image.width().max(1) as f64).round() as u32;
@@ -1700,7 +2659,7 @@ fn resize_for_ansi(image: &RgbImage, columns: u32) -> RgbImage {
     } else {
         target_height + 1
     };
-    image::imageops::resize(image, target_width, padded_height, FilterType::Triangle)
+    image::imageops::resize(image, target_width, padded_height, filter)
 }
 
 fn braille_text_from_gray_image(image: &GrayImage, columns: u32) -> Result<String, String> {
@@ -1767,48 +2726,177 @@ fn ascii_text_from_gray_image(image: &GrayImage, columns: u32) -> Result<String,
     Ok(lines.join("\n"))
 }
 
+/// Draws one `▀` cell per two pixels stacked vertically, with a 24-bit
+/// foreground for the upper pixel and background for the lower one. Escapes
+/// are only emitted when a color changes and solid cells become plain spaces,
+/// which keeps frames small enough to redraw at wide column counts.
 fn ansi_text_from_rgb_image(image: &RgbImage, columns: u32) -> Result<String, String> {
+    halfblock_text_with_filter(image, columns, FilterType::Triangle)
+}
+
+/// `ansi` resampled with a sharper filter.
+fn halfblock_text_from_rgb_image(image: &RgbImage, columns: u32) -> Result<String, String> {
+    halfblock_text_with_filter(image, columns, FilterType::CatmullRom)
+}
+
+fn halfblock_text_with_filter(image: &RgbImage, columns: u32, filter: FilterType) -> Result<String, String> {
     if image.width() == 0 || image.height() == 0 {
         return Err("browser screenshot image was empty".to_string());
     }
 
-    let resized = resize_for_ansi(image, columns);
+    let resized = resize_for_ansi(image, columns, filter);
     let mut lines = Vec::new();
     for y in (0..resized.height()).step_by(2) {
         let mut line = String::new();
+        let mut foreground = None;
+        let mut background = None;
         for x in 0..resized.width() {
             let top = resized.get_pixel(x, y).0;
             let bottom = resized.get_pixel(x, y + 1).0;
-            line.push_str(&format!(
-                "\u{1b}[38;2;{};{};{}m\u{1b}[48;2;{};{};{}m▀",
-                top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
-            ));
+            if background != Some(bottom) {
+                line.push_str(&format!("\u{1b}[48;2;{};{};{}m", bottom[0], bottom[1], bottom[2]));
+                background = Some(bottom);
+            }
+            if top == bottom {
+                line.push(' ');
+                continue;
+            }
+            if foreground != Some(top) {
+                line.push_str(&format!("\u{1b}[38;2;{};{};{}m", top[0], top[1], top[2]));
+                foreground = Some(top);
+            }
+            line.push('▀');
         }
         line.push_str("\u{1b}[0m");
         lines.push(line);
     }
-
-    if lines.is_empty() {
-        return Ok("\u{1b}[0m".to_string());
-    }
     Ok(lines.join("\n"))
 }
 
-fn median_fragment_height(fragments: &[BrowserTextLayoutFragment]) -> Option<f64> {
-    let mut heights = fragments
-        .iter()
-        .map(|fragment| fragment.height)
-        .filter(|height| height.is_finite() && *height > 0.5)
-        .collect::<Vec<_>>();
-    if heights.is_empty() {
-        return None;
+/// Scales an image to `columns` terminal cells at
+/// `TERMINAL_GRAPHICS_PIXELS_PER_COLUMN` pixels each, returning the resized
+/// image and the number of terminal rows it covers.
+fn resize_for_terminal_graphics(image: &RgbImage, columns: u32) -> Result<(RgbImage, u32), String> {
+    if image.width() == 0 || image.height() == 0 {
+        return Err("browser screenshot image was empty".to_string());
     }
-    heights.sort_by(|left, right| left.partial_cmp(right).unwrap_or(std::cmp::Ordering::Equal));
-    Some(heights[heights.len() / 2])
+    let target_width = columns.max(1) * TERMINAL_GRAPHICS_PIXELS_PER_COLUMN;
+    let target_height =
+        (((image.height() as f64 * target_width as f64) / image.width() as f64).round() as u32).max(1);
+    let rows = target_height.div_ceil(TERMINAL_GRAPHICS_PIXELS_PER_COLUMN * 2);
+    Ok((
+        image::imageops::resize(image, target_width, target_height, FilterType::Triangle),
+        rows,
+    ))
 }
 
-fn text_grid_row_height(snapshot: &BrowserTextLayoutSnapshot, columns: u32) -> f64 {
-    let char_width = (snapshot.viewport_width / columns.max(1) as f64).max(1.0);
+/// Encodes the image as a DEC sixel sequence using a fixed 6x7x6 color cube,
+/// so the palette never has to be computed per frame.
+fn sixel_from_rgb_image(image: &RgbImage, columns: u32) -> Result<(String, u32), String> {
+    let (resized, rows) = resize_for_terminal_graphics(image, columns)?;
+    let (width, height) = resized.dimensions();
+    let level = |value: u8, levels: u32| (value as u32 * (levels - 1) + 127) / 255;
+    let palette_index = |pixel: &image::Rgb<u8>| {
+        let [red, green, blue] = pixel.0;
+        (level(red, SIXEL_RED_LEVELS) * SIXEL_GREEN_LEVELS + level(green, SIXEL_GREEN_LEVELS)) * SIXEL_BLUE_LEVELS
+            + level(blue, SIXEL_BLUE_LEVELS)
+    };
+    let palette_size = (SIXEL_RED_LEVELS * SIXEL_GREEN_LEVELS * SIXEL_BLUE_LEVELS) as usize;
+    let mut out = format!("\u{1b}P0;1;0q\"1;1;{width};{height}");
+    for index in 0..palette_size as u32 {
+        let percent = |value: u32, levels: u32| value * 100 / (levels - 1);
+        let red = index / (SIXEL_GREEN_LEVELS * SIXEL_BLUE_LEVELS);
+        let green = index / SIXEL_BLUE_LEVELS % SIXEL_GREEN_LEVELS;
+        let blue = index % SIXEL_BLUE_LEVELS;
+        out.push_str(&format!(
+            "#{index};2;{};{};{}",
+            percent(red, SIXEL_RED_LEVELS),
+            percent(green, SIXEL_GREEN_LEVELS),
+            percent(blue, SIXEL_BLUE_LEVELS)
+        ));
+    }
+    let mut band_masks: Vec<Option<Vec<u8>>> = vec![None; palette_size];
+    for band_top in (0..height).step_by(6) {
+        for dy in 0..6.min(height - band_top) {
+            for x in 0..width {
+                let index = palette_index(resized.get_pixel(x, band_top + dy)) as usize;
+                band_masks[index].get_or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << dy;
+            }
+        }
+        let mut first_color = true;
+        for (index, masks) in band_masks.iter_mut().enumerate() {
+            let Some(masks) = masks.take() else {
+                continue;
+            };
+            if !first_color {
+                out.push('$');
+            }
+            first_color = false;
+            out.push_str(&format!("#{index}"));
+            push_sixel_runs(&mut out, &masks);
+        }
+        out.push('-');
+    }
+    out.push_str("\u{1b}\\");
+    Ok((out, rows))
+}
+
+fn push_sixel_runs(out: &mut String, masks: &[u8]) {
+    let mut position = 0;
+    while position < masks.len() {
+        let mask = masks[position];
+        let run = masks[position..].iter().take_while(|candidate| **candidate == mask).count();
+        let sixel = char::from(63 + mask);
+        if run > 3 {
+            out.push_str(&format!("!{run}{sixel}"));
+        } else {
+            out.extend(std::iter::repeat(sixel).take(run));
+        }
+        position += run;
+    }
+}
+
+/// Encodes the image for the kitty graphics protocol as chunked PNG data,
+/// sized to `columns` cells so the terminal scales it to fit.
+fn kitty_graphics_from_rgb_image(image: &RgbImage, columns: u32) -> Result<(String, u32), String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+
+    let (resized, rows) = resize_for_terminal_graphics(image, columns)?;
+    let mut png_bytes = Vec::new();
+    resized
+        .write_to(&mut std::io::Cursor::new(&mut png_bytes), ImageFormat::Png)
+        .map_err(|error| format!("failed to encode kitty graphics PNG: {error}"))?;
+    let encoded = STANDARD.encode(png_bytes);
+    let chunks = encoded.as_bytes().chunks(KITTY_GRAPHICS_CHUNK_SIZE).collect::<Vec<_>>();
+    let mut out = String::with_capacity(encoded.len() + chunks.len() * 16);
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).map_err(|error| error.to_string())?;
+        if index == 0 {
+            out.push_str(&format!("\u{1b}_Ga=T,f=100,q=2,c={columns},r={rows},m={more};{chunk}\u{1b}\\"));
+        } else {
+            out.push_str(&format!("\u{1b}_Gm={more};{chunk}\u{1b}\\"));
+        }
+    }
+    Ok((out, rows))
+}
+
+fn median_fragment_height(fragments: &[BrowserTextLayoutFragment]) -> Option<f64> {
+    let mut heights = fragments
+        .iter()
+        .map(|fragment| fragment.height)
+        .filter(|height| height.is_finite() && *height > 0.5)
+        .collect::<Vec<_>>();
+    if heights.is_empty() {
+        return None;
+    }
+    heights.sort_by(|left, right| left.partial_cmp(right).unwrap_or(std::cmp::Ordering::Equal));
+    Some(heights[heights.len() / 2])
+}
+
+fn text_grid_row_height(snapshot: &BrowserTextLayoutSnapshot, columns: u32) -> f64 {
+    let char_width = (snapshot.viewport_width / columns.max(1) as f64).max(1.0);
     let min_row_height = char_width * TEXT_GRID_MIN_ROW_HEIGHT_RATIO;
     let max_row_height = char_width * TEXT_GRID_MAX_ROW_HEIGHT_RATIO;
     median_fragment_height(&snapshot.fragments)
@@ -1916,6 +3004,7 @@ fn text_screenshot_result(format: BrowserScreenshotFormat, text: String, columns
         rows: text.lines().count() as u32,
         text,
         columns,
+        capture_id: None,
     })
 }
 
@@ -1938,7 +3027,13 @@ fn browser_preview_result(
             let png_bytes = capture_browser_screenshot_png(&webview)?;
             text_screenshot_result_from_png(&png_bytes, format, columns)?
         }
-        BrowserScreenshotFormat::Image => unreachable!("browser preview should only request text formats"),
+        BrowserScreenshotFormat::Image
+        | BrowserScreenshotFormat::Halfblock
+        | BrowserScreenshotFormat::Sixel
+        | BrowserScreenshotFormat::Kitty
+        | BrowserScreenshotFormat::Accessibility => {
+            unreachable!("browser preview should only request text formats")
+        }
     }) else {
         unreachable!("browser preview should always return a text screenshot payload");
     };
@@ -1956,38 +3051,398 @@ fn text_screenshot_result_from_png(
         BrowserScreenshotFormat::Braille => braille_text_from_gray_image(&image.to_luma8(), columns)?,
         BrowserScreenshotFormat::Ascii => ascii_text_from_gray_image(&image.to_luma8(), columns)?,
         BrowserScreenshotFormat::Ansi => ansi_text_from_rgb_image(&image.to_rgb8(), columns)?,
+        BrowserScreenshotFormat::Halfblock => halfblock_text_from_rgb_image(&image.to_rgb8(), columns)?,
+        BrowserScreenshotFormat::Sixel | BrowserScreenshotFormat::Kitty => {
+            let (text, rows) = if format == BrowserScreenshotFormat::Sixel {
+                sixel_from_rgb_image(&image.to_rgb8(), columns)?
+            } else {
+                kitty_graphics_from_rgb_image(&image.to_rgb8(), columns)?
+            };
+            // Graphics escapes are a single line of text, so report the
+            // terminal rows the image covers instead of counting lines.
+            return Ok(BrowserScreenshotResult::Text(BrowserTextScreenshotResult {
+                format: format.as_str().to_string(),
+                text,
+                columns,
+                rows,
+                capture_id: None,
+            }));
+        }
         BrowserScreenshotFormat::Text => ascii_text_from_gray_image(&image.to_luma8(), columns)?,
-        BrowserScreenshotFormat::Image => unreachable!("non-image text screenshots should not use PNG rendering"),
+        BrowserScreenshotFormat::Image | BrowserScreenshotFormat::Accessibility => {
+            unreachable!("non-image text screenshots should not use PNG rendering")
+        }
     };
     Ok(text_screenshot_result(format, text, columns))
 }
 
 fn image_screenshot_result(png_bytes: &[u8]) -> BrowserScreenshotResult {
+    BrowserScreenshotResult::Image(image_screenshot_payload(png_bytes))
+}
+
+fn image_screenshot_payload(png_bytes: &[u8]) -> BrowserImageScreenshotResult {
     use base64::engine::general_purpose::STANDARD;
     use base64::Engine as _;
 
-    BrowserScreenshotResult::Image(BrowserImageScreenshotResult {
+    BrowserImageScreenshotResult {
         mime_type: "image/png".to_string(),
         data_base64: STANDARD.encode(png_bytes),
-    })
+        capture_id: None,
+    }
 }
 
-fn browser_screenshot_result_from_png(
-    png_bytes: &[u8],
+/// Resolves the screenshot region in viewport CSS pixels. A `selector` is
+/// scrolled into view first so the element is on screen when the PNG is taken.
+const BROWSER_SCREENSHOT_REGION_SCRIPT: &str = r#"
+const viewportWidth = window.innerWidth || document.documentElement?.clientWidth || 1;
+const viewportHeight = window.innerHeight || document.documentElement?.clientHeight || 1;
+let left = 0;
+let top = 0;
+let right = viewportWidth;
+let bottom = viewportHeight;
+if (args?.selector) {
+  const element = document.querySelector(args.selector);
+  if (!element) {
+    throw new Error(`No element matched selector: ${args.selector}`);
+  }
+  element.scrollIntoView({ block: 'nearest', inline: 'nearest' });
+  const rect = element.getBoundingClientRect();
+  ({ left, top, right, bottom } = rect);
+} else if (args?.clip) {
+  left = args.clip.x;
+  top = args.clip.y;
+  right = args.clip.x + args.clip.width;
+  bottom = args.clip.y + args.clip.height;
+}
+left = Math.max(0, left);
+top = Math.max(0, top);
+right = Math.min(viewportWidth, right);
+bottom = Math.min(viewportHeight, bottom);
+if (!(right > left && bottom > top)) {
+  throw new Error(args?.selector
+    ? `Element matched by ${args.selector} is not visible in the viewport`
+    : 'Screenshot clip does not overlap the viewport');
+}
+return { x: left, y: top, width: right - left, height: bottom - top, viewportWidth, viewportHeight };
+"#;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserScreenshotRegion {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    viewport_width: f64,
+}
+
+impl BrowserScreenshotRegion {
+    fn capture_region(self) -> BrowserCaptureRegion {
+        BrowserCaptureRegion {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+/// Reads the optional `selector` or `clip` screenshot arguments into the args
+/// passed to the region and accessibility scripts. Returns `None` for a
+/// full-viewport screenshot.
+fn browser_screenshot_region_args(args: &Value, action: &str) -> Result<Option<Value>, String> {
+    let selector = args
+        .get("selector")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let clip = args.get("clip").filter(|value| !value.is_null());
+    match (selector, clip) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(format!("browser_drive {action} accepts either `selector` or `clip`, not both")),
+        (Some(selector), None) => Ok(Some(serde_json::json!({ "selector": selector }))),
+        (None, Some(clip)) => {
+            let field = |names: &[&str]| {
+                names
+                    .iter()
+                    .find_map(|name| clip.get(*name))
+                    .and_then(Value::as_f64)
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| {
+                        format!("browser_drive {action} requires `clip` to be an object with numeric x, y, width and height")
+                    })
+            };
+            let (x, y, width, height) = (field(&["x"])?, field(&["y"])?, field(&["width", "w"])?, field(&["height", "h"])?);
+            if width <= 0.0 || height <= 0.0 {
+                return Err(format!("browser_drive {action} requires a `clip` with positive width and height"));
+            }
+            Ok(Some(serde_json::json!({
+                "clip": { "x": x, "y": y, "width": width, "height": height },
+            })))
+        }
+    }
+}
+
+/// Keeps the text fragments inside `region` and moves them so the region's
+/// top-left corner becomes the origin of the text grid.
+fn crop_text_layout_snapshot(
+    snapshot: BrowserTextLayoutSnapshot,
+    region: BrowserScreenshotRegion,
+) -> BrowserTextLayoutSnapshot {
+    let fragments = snapshot
+        .fragments
+        .into_iter()
+        .filter(|fragment| {
+            let middle = fragment.top + fragment.height / 2.0;
+            middle >= region.y
+                && middle < region.y + region.height
+                && fragment.left + fragment.width > region.x
+                && fragment.left < region.x + region.width
+        })
+        .map(|fragment| BrowserTextLayoutFragment {
+            left: fragment.left - region.x,
+            top: fragment.top - region.y,
+            ..fragment
+        })
+        .collect();
+    BrowserTextLayoutSnapshot {
+        viewport_width: region.width,
+        viewport_height: region.height,
+        fragments,
+    }
+}
+
+/// The browser a drive screenshot is taken from.
+enum BrowserScreenshotTarget<'a> {
+    LiveWebview(&'a tauri::Webview),
+    AgentBrowser(&'a AgentBrowserPaneContext),
+}
+
+impl BrowserScreenshotTarget<'_> {
+    fn drive_script(&self, script: &str, args: &Value, action: &str) -> Result<Value, String> {
+        match self {
+            Self::LiveWebview(webview) => evaluate_browser_drive_script(webview, script, args, action),
+            Self::AgentBrowser(context) => agent_browser_drive_script(context, script, args, action),
+        }
+    }
+
+    fn viewport_png(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Self::LiveWebview(webview) => capture_browser_screenshot_png(webview),
+            Self::AgentBrowser(context) => agent_browser_screenshot_png(context),
+        }
+    }
+
+    fn region(&self, region_args: Option<&Value>, action: &str) -> Result<BrowserScreenshotRegion, String> {
+        let data = self.drive_script(BROWSER_SCREENSHOT_REGION_SCRIPT, region_args.unwrap_or(&Value::Null), action)?;
+        serde_json::from_value(data).map_err(|error| format!("screenshot region returned invalid JSON: {error}"))
+    }
+
+    fn capture(&self, region_args: Option<&Value>, action: &str) -> Result<BrowserCapture, String> {
+        let region = self.region(region_args, action)?;
+        let png_bytes = self.viewport_png()?;
+        capture_from_viewport_png(
+            png_bytes,
+            region.viewport_width,
+            region_args.map(|_| region.capture_region()),
+        )
+    }
+}
+
+fn browser_screenshot(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+    target: &BrowserScreenshotTarget<'_>,
     args: &Value,
 ) -> Result<BrowserScreenshotResult, String> {
+    let action = "screenshot";
     let options = browser_screenshot_options(args)?;
+    let region_args = browser_screenshot_region_args(args, action)?;
     match options.format {
-        BrowserScreenshotFormat::Image => Ok(image_screenshot_result(png_bytes)),
-        BrowserScreenshotFormat::Braille
+        BrowserScreenshotFormat::Accessibility => target
+            .drive_script(BROWSER_ACCESSIBILITY_SNAPSHOT_SCRIPT, region_args.as_ref().unwrap_or(&Value::Null), action)
+            .and_then(accessibility_screenshot_result),
+        BrowserScreenshotFormat::Text => {
+            if let (BrowserScreenshotTarget::AgentBrowser(context), None) = (target, &region_args) {
+                return agent_browser_snapshot_text(context, options.columns).map(BrowserScreenshotResult::Text);
+            }
+            let region = region_args
+                .as_ref()
+                .map(|region_args| target.region(Some(region_args), action))
+                .transpose()?;
+            let snapshot: BrowserTextLayoutSnapshot =
+                serde_json::from_value(target.drive_script(browser_text_snapshot_script(), &Value::Null, action)?)
+                    .map_err(|error| format!("browser_drive text snapshot returned invalid snapshot JSON: {error}"))?;
+            let snapshot = match region {
+                Some(region) => crop_text_layout_snapshot(snapshot, region),
+                None => snapshot,
+            };
+            text_screenshot_result_from_dom_snapshot(snapshot, options.columns)
+        }
+        BrowserScreenshotFormat::Image
+        | BrowserScreenshotFormat::Braille
         | BrowserScreenshotFormat::Ascii
         | BrowserScreenshotFormat::Ansi
-        | BrowserScreenshotFormat::Text => {
-            text_screenshot_result_from_png(png_bytes, options.format, options.columns)
+        | BrowserScreenshotFormat::Halfblock
+        | BrowserScreenshotFormat::Sixel
+        | BrowserScreenshotFormat::Kitty => {
+            let capture = target.capture(region_args.as_ref(), action)?;
+            let result = if options.format == BrowserScreenshotFormat::Image {
+                image_screenshot_result(&capture.png)
+            } else {
+                text_screenshot_result_from_png(&capture.png, options.format, options.columns)?
+            };
+            let capture_id = app
+                .state::<crate::state::AppState>()
+                .with_browser_captures(pane_id, |store| store.record(capture))?;
+            Ok(result.with_capture_id(capture_id))
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserScreenshotDiffResult {
+    capture_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    against: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    baseline_created: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    baseline_updated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    changed_pixels: u64,
+    total_pixels: u64,
+    changed_ratio: f64,
+    changed: bool,
+    boxes: Vec<BrowserDiffBox>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff_image: Option<BrowserImageScreenshotResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BrowserScreenshotDiffSource {
+    Capture(String),
+    Baseline { name: String, update: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BrowserScreenshotDiffOptions {
+    source: BrowserScreenshotDiffSource,
+    tolerance: u8,
+    diff_image: bool,
+}
+
+fn browser_screenshot_diff_options(args: &Value) -> Result<BrowserScreenshotDiffOptions, String> {
+    let action = "screenshot_diff";
+    let string_arg = |field: &str| {
+        args.get(field)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let bool_arg = |field: &str| match args.get(field) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(_) => Err(format!("browser_drive {action} requires `{field}` to be a boolean")),
+    };
+    let source = match (string_arg("against"), string_arg("baseline")) {
+        (Some(capture_id), None) => BrowserScreenshotDiffSource::Capture(capture_id),
+        (None, Some(name)) => BrowserScreenshotDiffSource::Baseline {
+            name,
+            update: bool_arg("update_baseline")?,
+        },
+        _ => return Err(format!("browser_drive {action} requires exactly one of `against` or `baseline`")),
+    };
+    let tolerance = match args.get("tolerance") {
+        None | Some(Value::Null) => DIFF_DEFAULT_TOLERANCE,
+        Some(value) => value
+            .as_u64()
+            .and_then(|value| u8::try_from(value).ok())
+            .ok_or_else(|| format!("browser_drive {action} requires `tolerance` to be an integer from 0 to 255"))?,
+    };
+    Ok(BrowserScreenshotDiffOptions {
+        source,
+        tolerance,
+        diff_image: bool_arg("diff_image")?,
+    })
+}
+
+fn browser_screenshot_diff(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+    target: &BrowserScreenshotTarget<'_>,
+    args: &Value,
+) -> Result<BrowserScreenshotDiffResult, String> {
+    let action = "screenshot_diff";
+    let options = browser_screenshot_diff_options(args)?;
+    let region_args = browser_screenshot_region_args(args, action)?;
+    let current = target.capture(region_args.as_ref(), action)?;
+    let state = app.state::<crate::state::AppState>();
+    // Look the previous capture up before recording the new one so the
+    // history limit cannot evict the capture being compared against.
+    let (previous, capture_id) = state.with_browser_captures(pane_id, |store| {
+        let previous = match &options.source {
+            BrowserScreenshotDiffSource::Capture(capture_id) => store.capture(capture_id).map(Some),
+            BrowserScreenshotDiffSource::Baseline { name, .. } => Ok(store.baseline(name)),
+        };
+        (previous, store.record(current.clone()))
+    })?;
+    let (against, baseline) = match &options.source {
+        BrowserScreenshotDiffSource::Capture(capture_id) => (Some(capture_id.clone()), None),
+        BrowserScreenshotDiffSource::Baseline { name, .. } => (None, Some(name.clone())),
+    };
+    let Some(previous) = previous? else {
+        let name = baseline.clone().unwrap_or_default();
+        state.with_browser_captures(pane_id, |store| store.set_baseline(&name, current))??;
+        return Ok(BrowserScreenshotDiffResult {
+            capture_id,
+            against,
+            baseline,
+            baseline_created: true,
+            baseline_updated: false,
+            width: None,
+            height: None,
+            changed_pixels: 0,
+            total_pixels: 0,
+            changed_ratio: 0.0,
+            changed: false,
+            boxes: Vec::new(),
+            diff_image: None,
+        });
+    };
+    let diff = diff_captures(&previous, &current, options.tolerance, options.diff_image)?;
+    let baseline_updated = match &options.source {
+        BrowserScreenshotDiffSource::Baseline { name, update: true } => {
+            state.with_browser_captures(pane_id, |store| store.set_baseline(name, current))??;
+            true
+        }
+        _ => false,
+    };
+    let diff_image = diff.diff_png.as_deref().map(image_screenshot_payload);
+    Ok(BrowserScreenshotDiffResult {
+        capture_id,
+        against,
+        baseline,
+        baseline_created: false,
+        baseline_updated,
+        width: Some(diff.width),
+        height: Some(diff.height),
+        changed_pixels: diff.changed_pixels,
+        total_pixels: diff.total_pixels,
+        changed_ratio: diff.changed_ratio,
+        changed: diff.changed_pixels > 0,
+        boxes: diff.boxes,
+        diff_image,
+    })
+}
+
 fn capture_browser_text_snapshot(webview: &tauri::Webview) -> Result<BrowserTextLayoutSnapshot, String> {
     let wrapped = browser_drive_wrapper_script(browser_text_snapshot_script(), &Value::Null)?;
     let raw_result = evaluate_browser_script(webview, &wrapped)?;
@@ -2064,9 +3519,107 @@ fn capture_browser_screenshot_png(webview: &tauri::Webview) -> Result<Vec<u8>, S
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "linux")]
+fn capture_browser_screenshot_png(webview: &tauri::Webview) -> Result<Vec<u8>, String> {
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    let (sender, receiver) = mpsc::channel();
+    webview
+        .with_webview(move |platform_webview| {
+            let webkit_webview = platform_webview.inner();
+            webkit_webview.snapshot(
+                SnapshotRegion::Visible,
+                SnapshotOptions::NONE,
+                None::<&gtk::gio::Cancellable>,
+                move |result| {
+                    let result = result
+                        .map_err(|error| error.to_string())
+                        .and_then(|surface| encode_cairo_surface_png(&surface));
+                    let _ = sender.send(result);
+                },
+            );
+        })
+        .map_err(|error| format!("failed to access browser webview: {error}"))?;
+    match receiver.recv_timeout(BROWSER_DRIVE_TIMEOUT) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            Err("browser_drive screenshot timed out in browser webview".to_string())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err("browser_drive screenshot channel disconnected".to_string())
+        }
+    }
+}
+
+/// WebKitGTK hands snapshots back as a cairo surface that WebKit may still
+/// reference, so it is painted into a private ARGB32 surface before the pixels
+/// are read out and re-encoded as PNG.
+#[cfg(target_os = "linux")]
+fn encode_cairo_surface_png(surface: &cairo::Surface) -> Result<Vec<u8>, String> {
+    let source = cairo::ImageSurface::try_from(surface.clone())
+        .map_err(|_| "browser screenshot returned a non-image surface".to_string())?;
+    let (width, height) = (source.width(), source.height());
+    if width <= 0 || height <= 0 {
+        return Err("browser screenshot returned an empty surface".to_string());
+    }
+    let mut copy = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
+        .map_err(|error| format!("failed to allocate browser screenshot surface: {error}"))?;
+    {
+        let context = cairo::Context::new(&copy)
+            .map_err(|error| format!("failed to create browser screenshot context: {error}"))?;
+        context
+            .set_source_surface(&source, 0.0, 0.0)
+            .map_err(|error| format!("failed to read browser screenshot surface: {error}"))?;
+        context
+            .paint()
+            .map_err(|error| format!("failed to copy browser screenshot surface: {error}"))?;
+    }
+    let stride = copy.stride() as usize;
+    let data = copy
+        .data()
+        .map_err(|error| format!("failed to read browser screenshot pixels: {error}"))?;
+    let rgba = premultiplied_argb32_to_rgba(&data, width as usize, height as usize, stride);
+    drop(data);
+    let image = image::RgbaImage::from_raw(width as u32, height as u32, rgba)
+        .ok_or_else(|| "browser screenshot pixel buffer has the wrong size".to_string())?;
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|error| format!("failed to encode browser screenshot PNG: {error}"))?;
+    Ok(bytes)
+}
+
+/// Converts cairo's native-endian, premultiplied ARGB32 rows into straight
+/// RGBA bytes.
+#[cfg(any(target_os = "linux", test))]
+fn premultiplied_argb32_to_rgba(data: &[u8], width: usize, height: usize, stride: usize) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in data.chunks(stride).take(height) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            let argb = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            let alpha = (argb >> 24) as u8;
+            let unpremultiply = |channel: u32| -> u8 {
+                let channel = (channel & 0xff) as u16;
+                if alpha == 0 {
+                    0
+                } else {
+                    ((channel * 255 + alpha as u16 / 2) / alpha as u16).min(255) as u8
+                }
+            };
+            rgba.extend_from_slice(&[
+                unpremultiply(argb >> 16),
+                unpremultiply(argb >> 8),
+                unpremultiply(argb),
+                alpha,
+            ]);
+        }
+    }
+    rgba
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 fn capture_browser_screenshot_png(_webview: &tauri::Webview) -> Result<Vec<u8>, String> {
-    Err("browser_drive is currently supported only on macOS".to_string())
+    Err("browser_drive is currently supported only on macOS and Linux".to_string())
 }
 
 #[cfg(target_os = "macos")]
@@ -2111,9 +3664,67 @@ fn evaluate_browser_script(webview: &tauri::Webview, script: &str) -> Result<Str
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "linux")]
+fn evaluate_browser_script(webview: &tauri::Webview, script: &str) -> Result<String, String> {
+    use javascriptcore::ValueExt;
+    use webkit2gtk::WebViewExt;
+
+    let (sender, receiver) = mpsc::channel();
+    let script = script.to_string();
+    webview
+        .with_webview(move |platform_webview| {
+            let webkit_webview = platform_webview.inner();
+            webkit_webview.evaluate_javascript(
+                &script,
+                None,
+                None,
+                None::<&gtk::gio::Cancellable>,
+                move |result| {
+                    let result = match result {
+                        Ok(value) if value.is_string() => Ok(value.to_str().to_string()),
+                        Ok(_) => Err("browser_drive returned no result".to_string()),
+                        Err(error) => Err(error.to_string()),
+                    };
+                    let _ = sender.send(result);
+                },
+            );
+        })
+        .map_err(|error| format!("failed to access browser webview: {error}"))?;
+    match receiver.recv_timeout(BROWSER_DRIVE_TIMEOUT) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            Err("browser_drive evaluation timed out in browser webview".to_string())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err("browser_drive evaluation channel disconnected".to_string())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 fn evaluate_browser_script(_webview: &tauri::Webview, _script: &str) -> Result<String, String> {
-    Err("browser_drive is currently supported only on macOS".to_string())
+    Err("browser_drive is currently supported only on macOS and Linux".to_string())
+}
+
+/// Runs the `screenshot` and `screenshot_diff` drive actions, returning `None`
+/// for every other action.
+fn drive_browser_screenshot(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+    target: &BrowserScreenshotTarget<'_>,
+    action: &str,
+    args: &Value,
+) -> Option<Result<Value, String>> {
+    let result = match action {
+        "screenshot" => browser_screenshot(app, pane_id, target, args).and_then(|screenshot| {
+            serde_json::to_value(screenshot).map_err(|error| format!("returned invalid screenshot JSON: {error}"))
+        }),
+        "screenshot_diff" => browser_screenshot_diff(app, pane_id, target, args).and_then(|diff| {
+            serde_json::to_value(diff).map_err(|error| format!("returned invalid screenshot diff JSON: {error}"))
+        }),
+        _ => return None,
+    };
+    Some(result.map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}")))
 }
 
 pub fn drive_browser_webview(
@@ -2126,30 +3737,19 @@ pub fn drive_browser_webview(
     if browser_backend_for_pane(app, pane_id) == BrowserBackend::AgentBrowser {
         let context = agent_browser_pane_context(app, pane_id)?;
         let _ = ensure_agent_browser_started(&context, None)?;
-        if action == "screenshot" {
-            let options = browser_screenshot_options(args)
-                .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
-            let screenshot = match options.format {
-                BrowserScreenshotFormat::Text => {
-                    serde_json::to_value(agent_browser_snapshot_text(&context, options.columns)?)
-                        .map_err(|error| format!("browser_drive {action} returned invalid JSON: {error}"))?
-                }
-                BrowserScreenshotFormat::Image
-                | BrowserScreenshotFormat::Braille
-                | BrowserScreenshotFormat::Ascii
-                | BrowserScreenshotFormat::Ansi => {
-                    let png_bytes = agent_browser_screenshot_png(&context)?;
-                    serde_json::to_value(browser_screenshot_result_from_png(&png_bytes, args)?)
-                        .map_err(|error| format!("browser_drive {action} returned invalid screenshot JSON: {error}"))?
-                }
-            };
-            return Ok(screenshot);
+        if let Some(screenshot) =
+            drive_browser_screenshot(app, pane_id, &BrowserScreenshotTarget::AgentBrowser(&context), action, args)
+        {
+            return screenshot;
         }
 
         let result = match action {
             "click" => {
-                let selector = required_browser_drive_string_arg(args, "selector", action)?;
-                run_agent_browser_json_command(&context, &["click".to_string(), selector], "agent-browser click")
+                let selector = browser_drive_target_selector(args, action)?;
+                let clicked =
+                    run_agent_browser_json_command(&context, &["click".to_string(), selector], "agent-browser click");
+                install_agent_browser_console_capture(&context);
+                clicked
             }
             "select" => {
                 let selector = required_browser_drive_string_arg(args, "selector", action)?;
@@ -2161,7 +3761,7 @@ pub fn drive_browser_webview(
                 )
             }
             "type" => {
-                let selector = required_browser_drive_string_arg(args, "selector", action)?;
+                let selector = browser_drive_target_selector(args, action)?;
                 let text = required_browser_drive_string_arg(args, "text", action)?;
                 run_agent_browser_json_command(
                     &context,
@@ -2173,6 +3773,13 @@ pub fn drive_browser_webview(
                 let js = required_browser_drive_string_arg(args, "js", action)?;
                 agent_browser_eval_value(&context, &js)
             }
+            "wait_for" => browser_drive_action_script(action, args).and_then(|check_script| {
+                wait_for_browser_condition(&check_script, args, |script| {
+                    agent_browser_drive_script(&context, script, args, action)
+                })
+            }),
+            "press" | "hover" | "scroll" | "fill_form" | "check" => browser_drive_action_script(action, args)
+                .and_then(|action_script| agent_browser_drive_script(&context, &action_script, args, action)),
             other => Err(format!("unsupported browser_drive action: {other}")),
         };
         return result.map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"));
@@ -2180,29 +3787,18 @@ pub fn drive_browser_webview(
 
     let webview = get_browser_webview(app, pane_id)
         .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
-    if action == "screenshot" {
-        let options = browser_screenshot_options(args)
-            .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
-        let screenshot = match options.format {
-            BrowserScreenshotFormat::Text => {
-                let snapshot = capture_browser_text_snapshot(&webview)
-                    .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
-                text_screenshot_result_from_dom_snapshot(snapshot, options.columns)
-            }
-            BrowserScreenshotFormat::Image
-            | BrowserScreenshotFormat::Braille
-            | BrowserScreenshotFormat::Ascii
-            | BrowserScreenshotFormat::Ansi => {
-                let png_bytes = capture_browser_screenshot_png(&webview)
-                    .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
-                browser_screenshot_result_from_png(&png_bytes, args)
-            }
-        }
-        .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
-        return serde_json::to_value(screenshot)
-            .map_err(|error| format!("browser_drive {action} returned invalid screenshot JSON: {error}"));
+    if let Some(screenshot) =
+        drive_browser_screenshot(app, pane_id, &BrowserScreenshotTarget::LiveWebview(&webview), action, args)
+    {
+        return screenshot;
     }
     let action_script = browser_drive_action_script(action, args)?;
+    if action == "wait_for" {
+        return wait_for_browser_condition(&action_script, args, |script| {
+            evaluate_browser_drive_script(&webview, script, args, action)
+        })
+        .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"));
+    }
     let wrapped = browser_drive_wrapper_script(&action_script, args)?;
     let raw_result = evaluate_browser_script(&webview, &wrapped)
         .map_err(|error| format!("browser_drive {action} failed for pane {pane_id}: {error}"))?;
@@ -2217,42 +3813,205 @@ pub fn drive_browser_webview(
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrowserExtensionEmittedEvent {
+    pub event: String,
+    #[serde(default)]
+    pub payload: Value,
+    #[serde(default)]
+    pub timestamp_ms: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BrowserExtensionEventDrain {
+    #[serde(default)]
+    pub events: Vec<BrowserExtensionEmittedEvent>,
+    #[serde(default)]
+    pub dropped: u64,
+}
+
+/// Takes the events an extension page has emitted since the last drain.
+pub fn drain_browser_extension_events(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+) -> Result<BrowserExtensionEventDrain, String> {
+    let script = browser_extension_event_drain_script();
+    let args = serde_json::json!({});
+    let data = if browser_backend_for_pane(app, pane_id) == BrowserBackend::AgentBrowser {
+        let context = agent_browser_pane_context(app, pane_id)?;
+        agent_browser_drive_script(&context, &script, &args, "extension_events")?
+    } else {
+        let webview = get_browser_webview(app, pane_id)
+            .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
+        evaluate_browser_drive_script(&webview, &script, &args, "extension_events")?
+    };
+    serde_json::from_value(data).map_err(|error| format!("extension event bridge returned invalid JSON: {error}"))
+}
+
+/// Pulls console entries buffered by the capture shim into the pane's Herd-side
+/// console log.
+fn sync_browser_console(app: &tauri::AppHandle, pane_id: &str) -> Result<(), String> {
+    let state = app.state::<crate::state::AppState>();
+    let drain_args = state.with_browser_console(pane_id, |log| log.drain_args())?;
+    let script = browser_console::browser_console_drain_script();
+    let data = if browser_backend_for_pane(app, pane_id) == BrowserBackend::AgentBrowser {
+        let context = agent_browser_pane_context(app, pane_id)?;
+        agent_browser_drive_script(&context, &script, &drain_args, "console_read")?
+    } else {
+        let webview = get_browser_webview(app, pane_id)
+            .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
+        evaluate_browser_drive_script(&webview, &script, &drain_args, "console_read")?
+    };
+    let drain: BrowserConsoleDrain = serde_json::from_value(data)
+        .map_err(|error| format!("console capture returned invalid JSON: {error}"))?;
+    state.with_browser_console(pane_id, |log| log.ingest(drain))
+}
+
+pub fn read_browser_console(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+    since: Option<u64>,
+    min_level: Option<&str>,
+    limit: Option<usize>,
+) -> Result<BrowserConsoleReadResult, String> {
+    if let Some(level) = min_level {
+        browser_console::validate_console_level(level)?;
+    }
+    sync_browser_console(app, pane_id)
+        .map_err(|error| format!("console_read failed for pane {pane_id}: {error}"))?;
+    app.state::<crate::state::AppState>()
+        .with_browser_console(pane_id, |log| log.read(since, min_level, limit))
+}
+
+fn browser_viewport_png(app: &tauri::AppHandle, pane_id: &str) -> Result<Vec<u8>, String> {
+    if browser_backend_for_pane(app, pane_id) == BrowserBackend::AgentBrowser {
+        let context = agent_browser_pane_context(app, pane_id)?;
+        return agent_browser_screenshot_png(&context);
+    }
+    let webview = get_browser_webview(app, pane_id)
+        .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
+    capture_browser_screenshot_png(&webview)
+}
+
+/// Starts sampling the pane's viewport into a frame sequence on a background
+/// thread until `stop_browser_recording` or `max_frames` ends it.
+pub fn start_browser_recording(
+    app: &tauri::AppHandle,
+    pane_id: &str,
+    options: BrowserRecordingOptions,
+) -> Result<BrowserRecordingStatus, String> {
+    if browser_backend_for_pane(app, pane_id) == BrowserBackend::AgentBrowser {
+        let context = agent_browser_pane_context(app, pane_id)?;
+        let _ = ensure_agent_browser_started(&context, None)?;
+    } else if get_browser_webview(app, pane_id).is_none() {
+        return Err(format!("browser webview not found for pane {pane_id}"));
+    }
+    let recording = BrowserRecording::create(&browser_recordings_dir(), options, crate::agent::now_ms())?;
+    let status = recording.status();
+    app.state::<crate::state::AppState>()
+        .insert_browser_recording(pane_id, recording)?;
+    spawn_browser_recording_sampler(app.clone(), pane_id.to_string(), status.recording_id.clone());
+    Ok(status)
+}
+
+/// Samples frames until the recording is stopped or full. The recordings map
+/// is shared by every pane, so the PNG is written between two short critical
+/// sections instead of while holding it.
+fn spawn_browser_recording_sampler(app: tauri::AppHandle, pane_id: String, recording_id: String) {
+    std::thread::spawn(move || loop {
+        let started = std::time::Instant::now();
+        let capture = browser_viewport_png(&app, &pane_id);
+        let captured_at = crate::agent::now_ms();
+        let state = app.state::<crate::state::AppState>();
+        let Some(Some(frame_path)) =
+            state.with_browser_recording(&pane_id, &recording_id, |recording| recording.next_frame_path())
+        else {
+            return;
+        };
+        let saved = capture.and_then(|png| write_frame(&frame_path, &png));
+        let interval_ms = state.with_browser_recording(&pane_id, &recording_id, |recording| {
+            match saved {
+                Ok(()) => recording.push_frame(frame_path, captured_at),
+                Err(error) => recording.record_capture_error(error),
+            }
+            (!recording.is_full()).then_some(recording.interval_ms())
+        });
+        let Some(Some(interval_ms)) = interval_ms else {
+            return;
+        };
+        std::thread::sleep(Duration::from_millis(interval_ms).saturating_sub(started.elapsed()));
+    });
+}
+
+/// Stops the pane's recording and encodes it. Quantizing a long GIF can take
+/// minutes, so the encode runs on its own thread instead of the socket
+/// worker that dispatched `record_stop`, which only waits for the result.
+pub fn stop_browser_recording(app: &tauri::AppHandle, pane_id: &str) -> Result<BrowserRecordingResult, String> {
+    let recording = app
+        .state::<crate::state::AppState>()
+        .remove_browser_recording(pane_id)
+        .ok_or_else(|| format!("browser pane {pane_id} is not recording"))?;
+    // Socket commands already run on a blocking worker, so encoding inline
+    // does not stall the socket reader.
+    recording
+        .finish(crate::agent::now_ms())
+        .map_err(|error| format!("record_stop failed for pane {pane_id}: {error}"))
+}
+
 pub fn call_browser_extension(
     app: &tauri::AppHandle,
     pane_id: &str,
     method: &str,
     args: &Value,
     caller: &BrowserExtensionCallerContext,
+    timeout_ms: Option<u64>,
 ) -> Result<Value, String> {
     let extension = browser_extension_info_for_pane(app, pane_id)
         .ok_or_else(|| format!("browser tile {pane_id} is not hosting a browser extension page"))?;
-    if !extension.methods.iter().any(|candidate| candidate.name == method) {
+    let Some(method_spec) = extension.methods.iter().find(|candidate| candidate.name == method) else {
         return Err(format!(
             "browser extension {} does not expose method {}",
             extension.extension_id,
             method,
         ));
+    };
+    if let Some(schema) = method_spec.args_schema.as_ref() {
+        validate_browser_extension_value(schema, args, &format!("browser extension call {method} args"))?;
     }
     let script = browser_extension_call_script(method, args, caller)?;
     let backend = browser_backend_for_pane(app, pane_id);
-    let envelope: BrowserExtensionEnvelope = match backend {
-        BrowserBackend::LiveWebview => {
-            let webview = get_browser_webview(app, pane_id)
-                .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
-            let raw_result = evaluate_browser_script(&webview, &script)
-                .map_err(|error| format!("browser extension call {method} failed for pane {pane_id}: {error}"))?;
-            serde_json::from_str(&raw_result)
-                .map_err(|error| format!("browser extension call {method} returned invalid JSON: {error}"))?
-        }
-        BrowserBackend::AgentBrowser => {
-            let context = agent_browser_pane_context(app, pane_id)?;
-            let raw_result = agent_browser_eval_value(&context, &script)
-                .map_err(|error| format!("browser extension call {method} failed for pane {pane_id}: {error}"))?;
-            parse_browser_envelope_value(raw_result, &format!("browser extension call {method}"))?
+    let agent_browser_context = match backend {
+        BrowserBackend::LiveWebview => None,
+        BrowserBackend::AgentBrowser => Some(agent_browser_pane_context(app, pane_id)?),
+    };
+    let evaluate = |script: &str| -> Result<BrowserExtensionEnvelope, String> {
+        match &agent_browser_context {
+            None => {
+                let webview = get_browser_webview(app, pane_id)
+                    .ok_or_else(|| format!("browser webview not found for pane {pane_id}"))?;
+                let raw_result = evaluate_browser_script(&webview, script)
+                    .map_err(|error| format!("browser extension call {method} failed for pane {pane_id}: {error}"))?;
+                serde_json::from_str(&raw_result)
+                    .map_err(|error| format!("browser extension call {method} returned invalid JSON: {error}"))
+            }
+            Some(context) => {
+                let raw_result = agent_browser_eval_value(context, script)
+                    .map_err(|error| format!("browser extension call {method} failed for pane {pane_id}: {error}"))?;
+                parse_browser_envelope_value(raw_result, &format!("browser extension call {method}"))
+            }
         }
     };
+    let envelope = await_browser_extension_envelope(
+        evaluate(&script)?,
+        method,
+        browser_extension_call_timeout(method_spec, timeout_ms),
+        evaluate,
+    )?;
     if envelope.ok {
         let data = envelope.data.unwrap_or(Value::Null);
+        if let Some(schema) = method_spec.result_schema.as_ref() {
+            validate_browser_extension_value(schema, &data, &format!("browser extension call {method} result"))?;
+        }
         if method == "screenshot" {
             let screenshot = browser_extension_screenshot_result_from_value(data, args)
                 .map_err(|error| format!("browser extension call {method} failed for pane {pane_id}: {error}"))?;
@@ -2307,6 +4066,9 @@ fn ensure_browser_webview(
             }
         },
     );
+    builder = builder
+        .initialization_script(browser_console::BROWSER_CONSOLE_CAPTURE_SCRIPT)
+        .initialization_script(BROWSER_EXTENSION_EVENT_BRIDGE_SCRIPT);
     if browser_tile_incognito(app, pane_id) {
         builder = builder.incognito(true);
     }
@@ -2366,6 +4128,47 @@ mod braille_tests {
         assert_ne!(art, "⣿");
     }
 
+    #[test]
+    fn renders_halfblock_text_with_run_length_colors() {
+        let mut image = RgbImage::from_pixel(4, 2, Rgb([255, 255, 255]));
+        image.put_pixel(0, 0, Rgb([255, 0, 0]));
+        image.put_pixel(0, 1, Rgb([0, 0, 255]));
+        let art = halfblock_text_from_rgb_image(&image, 4).unwrap();
+        assert_eq!(art.lines().count(), 1);
+        assert!(art.starts_with("\u{1b}[48;2;0;0;255m\u{1b}[38;2;255;0;0m▀"));
+        assert_eq!(art.matches("\u{1b}[48;2;255;255;255m").count(), 1);
+        assert!(art.ends_with("   \u{1b}[0m"));
+    }
+
+    #[test]
+    fn encodes_sixel_and_kitty_graphics_sized_to_columns() {
+        let mut image = RgbImage::from_pixel(40, 20, Rgb([255, 255, 255]));
+        image.put_pixel(0, 0, Rgb([0, 0, 0]));
+        let (sixel, rows) = sixel_from_rgb_image(&image, 10).unwrap();
+        assert_eq!(rows, 3);
+        assert!(sixel.starts_with("\u{1b}P0;1;0q\"1;1;100;50#0;2;0;0;0"));
+        assert!(sixel.ends_with("-\u{1b}\\"));
+        assert_eq!(sixel.matches('-').count(), 9);
+        assert!(sixel.contains("!100~"));
+
+        let noisy = RgbImage::from_fn(200, 200, |x, y| Rgb([(x * 7 % 256) as u8, (y * 13 % 256) as u8, ((x ^ y) % 256) as u8]));
+        let (kitty, rows) = kitty_graphics_from_rgb_image(&noisy, 40).unwrap();
+        assert_eq!(rows, 20);
+        assert!(kitty.starts_with("\u{1b}_Ga=T,f=100,q=2,c=40,r=20,m=1;"));
+        assert!(kitty.contains("\u{1b}_Gm=1;") || kitty.contains("\u{1b}_Gm=0;"));
+        assert!(kitty.ends_with("\u{1b}\\"));
+        assert_eq!(kitty.matches("m=0;").count(), 1);
+
+        let mut png_bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png_bytes), ImageFormat::Png)
+            .expect("encode png");
+        let result = text_screenshot_result_from_png(&png_bytes, BrowserScreenshotFormat::Kitty, 10).unwrap();
+        let value = serde_json::to_value(result).unwrap();
+        assert_eq!(value["format"], "kitty");
+        assert_eq!(value["rows"], 3);
+    }
+
     #[test]
     fn renders_ascii_screenshot_text_for_grayscale_inputs() {
         let mut image = GrayImage::from_pixel(4, 4, Luma([255]));
@@ -2516,12 +4319,16 @@ mod braille_tests {
 pub fn close_browser_webview(app: &tauri::AppHandle, pane_id: &str) {
     let state = app.state::<crate::state::AppState>();
     state.remove_browser_page_zoom(pane_id);
+    state.remove_browser_console(pane_id);
+    state.remove_browser_captures(pane_id);
+    state.remove_browser_recording(pane_id);
     if let Some(webview) = get_browser_webview(app, pane_id) {
         let _ = webview.close();
     }
 }
 
 fn shutdown_agent_browser_for_pane(app: &tauri::AppHandle, pane_id: &str) {
+    app.state::<crate::state::AppState>().remove_browser_recording(pane_id);
     if !agent_browser_is_ready() {
         return;
     }
@@ -2765,7 +4572,13 @@ pub async fn browser_webview_preview(
                 };
                 Ok(result)
             }
-            BrowserScreenshotFormat::Image => unreachable!("browser preview should only request text formats"),
+            BrowserScreenshotFormat::Image
+            | BrowserScreenshotFormat::Halfblock
+            | BrowserScreenshotFormat::Sixel
+            | BrowserScreenshotFormat::Kitty
+            | BrowserScreenshotFormat::Accessibility => {
+                unreachable!("browser preview should only request text formats")
+            }
         };
     }
     browser_preview_result(&app, &pane_id, options.format, options.columns)
@@ -2773,7 +4586,18 @@ pub async fn browser_webview_preview(
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_browser_url, resolve_browser_file_url, sanitize_browser_page_zoom};
+    use super::{
+        accessibility_screenshot_result, await_browser_extension_envelope, browser_drive_action_script,
+        browser_drive_target_selector, browser_extension_call_timeout, browser_extension_screenshot_options,
+        browser_screenshot_diff_options, browser_screenshot_options, browser_screenshot_region_args,
+        crop_text_layout_snapshot, parse_browser_url,
+        premultiplied_argb32_to_rgba, resolve_browser_file_url, sanitize_browser_extension_info,
+        sanitize_browser_page_zoom, validate_browser_extension_value, wait_for_browser_condition,
+        BrowserExtensionEnvelope, BrowserScreenshotDiffSource, BrowserScreenshotRegion, BrowserTextLayoutFragment,
+        BrowserTextLayoutSnapshot,
+    };
+    use std::time::Duration;
+    use serde_json::json;
 
     #[test]
     fn allows_file_scheme_browser_urls() {
@@ -2807,4 +4631,265 @@ mod tests {
         assert_eq!(sanitize_browser_page_zoom(40.0), 20.0);
         assert_eq!(sanitize_browser_page_zoom(0.5), 0.5);
     }
+
+    #[test]
+    fn converts_premultiplied_cairo_pixels_to_rgba() {
+        let pixels = [0xff20_4060u32, 0x8040_0000, 0x0012_3456, 0];
+        let mut data = Vec::new();
+        for pixel in &pixels[..2] {
+            data.extend_from_slice(&pixel.to_ne_bytes());
+        }
+        data.extend_from_slice(&[0xaa; 4]);
+        for pixel in &pixels[2..] {
+            data.extend_from_slice(&pixel.to_ne_bytes());
+        }
+        data.extend_from_slice(&[0xaa; 4]);
+
+        let rgba = premultiplied_argb32_to_rgba(&data, 2, 2, 12);
+        assert_eq!(
+            rgba,
+            vec![0x20, 0x40, 0x60, 0xff, 0x80, 0x00, 0x00, 0x80, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn validates_extended_browser_drive_action_args() {
+        assert!(browser_drive_action_script("press", &json!({ "key": "ArrowUp", "modifiers": ["shift"] })).is_ok());
+        assert_eq!(
+            browser_drive_action_script("press", &json!({ "key": "a", "modifiers": ["hyper"] })).unwrap_err(),
+            "browser_drive press modifier must be one of shift, ctrl, alt, meta"
+        );
+        assert!(browser_drive_action_script("hover", &json!({})).is_err());
+        assert!(browser_drive_action_script("scroll", &json!({ "y": 400 })).is_ok());
+        assert!(browser_drive_action_script("scroll", &json!({ "to": "middle" })).is_err());
+        assert_eq!(
+            browser_drive_action_script("scroll", &json!({})).unwrap_err(),
+            "browser_drive scroll requires `selector`, `x`/`y`, or `to`"
+        );
+        assert!(browser_drive_action_script("fill_form", &json!({ "fields": {} })).is_err());
+        assert!(browser_drive_action_script("fill_form", &json!({ "fields": { "#tags": ["a"] } })).is_err());
+        assert!(
+            browser_drive_action_script("fill_form", &json!({ "fields": { "#name": "Ada", "#agree": true } })).is_ok()
+        );
+        assert!(browser_drive_action_script("check", &json!({ "selector": "#agree", "checked": "yes" })).is_err());
+        assert!(browser_drive_action_script("wait_for", &json!({ "selector": "#ready", "state": "gone" })).is_err());
+        assert!(browser_drive_action_script("wait_for", &json!({}))
+            .unwrap_err()
+            .contains("`selector` or `js`"));
+        let predicate = browser_drive_action_script("wait_for", &json!({ "js": "window.ready === true" })).unwrap();
+        assert!(predicate.contains("window.ready === true"));
+        assert!(predicate.contains("typeof value.then === 'function'"));
+    }
+
+    #[test]
+    fn polls_browser_wait_for_until_satisfied_or_timed_out() {
+        let mut calls = 0;
+        let result = wait_for_browser_condition("check", &json!({ "selector": "#ready" }), |_| {
+            calls += 1;
+            Ok(json!({ "satisfied": calls == 3 }))
+        })
+        .unwrap();
+        assert_eq!(calls, 3);
+        assert_eq!(result["satisfied"], true);
+
+        let error = wait_for_browser_condition(
+            "check",
+            &json!({ "selector": "#ready", "state": "hidden", "timeout_ms": 0 }),
+            |_| Ok(json!({ "satisfied": false })),
+        )
+        .unwrap_err();
+        assert_eq!(error, "browser_drive wait_for timed out after 0ms waiting for #ready to be hidden");
+    }
+
+    #[test]
+    fn awaits_pending_browser_extension_calls_until_settled_or_timed_out() {
+        let pending = || -> BrowserExtensionEnvelope {
+            serde_json::from_value(json!({ "ok": true, "pending": "call-1" })).unwrap()
+        };
+        let mut polls = Vec::new();
+        let envelope = await_browser_extension_envelope(pending(), "load_rom", Duration::from_secs(5), |script| {
+            polls.push(script.to_string());
+            Ok(serde_json::from_value(if polls.len() < 2 {
+                json!({ "ok": true, "pending": "call-1" })
+            } else {
+                json!({ "ok": true, "data": { "loaded": true } })
+            })
+            .unwrap())
+        })
+        .unwrap();
+        assert_eq!(envelope.data, Some(json!({ "loaded": true })));
+        assert_eq!(polls.len(), 2);
+        assert!(polls[0].contains("\"call-1\""));
+
+        let mut cancelled = false;
+        let error = await_browser_extension_envelope(pending(), "load_rom", Duration::ZERO, |script| {
+            cancelled = script.contains("if (true)");
+            Ok(serde_json::from_value(json!({ "ok": true, "data": null })).unwrap())
+        })
+        .unwrap_err();
+        assert!(cancelled);
+        assert_eq!(error, "browser extension call load_rom timed out after 0ms waiting for its promise");
+
+        let method: crate::network::BrowserExtensionMethod =
+            serde_json::from_value(json!({ "name": "load_rom", "async": true, "timeout_ms": 30000 })).unwrap();
+        assert_eq!(browser_extension_call_timeout(&method, None), Duration::from_secs(30));
+        assert_eq!(browser_extension_call_timeout(&method, Some(1_000)), Duration::from_secs(1));
+        assert_eq!(browser_extension_call_timeout(&method, Some(u64::MAX)), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn validates_browser_extension_manifest_events() {
+        let manifest = |events: serde_json::Value| {
+            sanitize_browser_extension_info(
+                json!({ "extension_id": "checkers", "label": "Checkers", "events": events }),
+                "extensions/browser/checkers/index.html".to_string(),
+            )
+        };
+        let info = manifest(json!([{ "name": "turn_started", "description": "A player may move." }])).unwrap();
+        assert_eq!(info.events[0].name, "turn_started");
+        assert!(manifest(json!([{ "name": "turn:started" }])).is_err());
+        assert!(manifest(json!([{ "name": "move" }, { "name": "move" }])).is_err());
+        assert_eq!(
+            manifest(json!([{ "name": "navigate" }])).unwrap_err(),
+            "browser extension manifest event navigate collides with a built-in browser tile action"
+        );
+    }
+
+    #[test]
+    fn validates_browser_extension_args_and_results_against_manifest_schemas() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "rom": { "type": "string" }, "speed": { "type": "number", "minimum": 1 } },
+            "required": ["rom"],
+        });
+        assert!(validate_browser_extension_value(&schema, &json!({ "rom": "tetris.gb" }), "args").is_ok());
+        assert_eq!(
+            validate_browser_extension_value(&schema, &json!({ "rom": 3 }), "browser extension call load args").unwrap_err(),
+            "browser extension call load args failed schema validation: /rom: 3 is not of type \"string\""
+        );
+        let error = validate_browser_extension_value(&schema, &json!({ "speed": 0 }), "args").unwrap_err();
+        assert!(error.contains("/: \"rom\" is a required property"));
+        assert!(error.contains("/speed: 0 is less than the minimum of 1"));
+
+        let invalid = sanitize_browser_extension_info(
+            json!({
+                "extension_id": "game-boy",
+                "label": "Game Boy",
+                "methods": [{ "name": "load", "args_schema": { "type": "strng" } }],
+            }),
+            "extensions/browser/game-boy/index.html".to_string(),
+        )
+        .unwrap_err();
+        assert!(invalid.starts_with("browser extension manifest method load has an invalid args_schema"));
+    }
+
+    #[test]
+    fn resolves_accessibility_refs_for_click_and_type() {
+        assert_eq!(
+            browser_drive_target_selector(&json!({ "ref": "e12", "selector": "#ignored" }), "click").unwrap(),
+            "[data-herd-ref=\"e12\"]"
+        );
+        assert_eq!(browser_drive_target_selector(&json!({ "selector": "#save" }), "click").unwrap(), "#save");
+        assert!(browser_drive_target_selector(&json!({ "ref": "12" }), "click").is_err());
+        assert!(browser_drive_target_selector(&json!({ "ref": "e1\"]" }), "type").is_err());
+        assert_eq!(
+            browser_drive_target_selector(&json!({}), "type").unwrap_err(),
+            "browser_drive type requires a non-empty `selector` or `ref`"
+        );
+        let script = browser_drive_action_script("type", &json!({ "ref": "e3", "text": "hi" })).unwrap();
+        assert!(script.contains("data-herd-ref"));
+        assert!(script.contains("queryDeep("));
+    }
+
+    #[test]
+    fn builds_accessibility_screenshot_results() {
+        let options = browser_screenshot_options(&json!({ "format": "outline" })).unwrap();
+        assert_eq!(options.format.as_str(), "accessibility");
+        assert!(!options.format.uses_text_columns());
+        assert!(browser_screenshot_options(&json!({ "format": "tree" }))
+            .unwrap_err()
+            .contains("`accessibility` (alias `outline`)"));
+        assert!(browser_extension_screenshot_options(&json!({ "format": "outline" })).is_err());
+
+        let result = accessibility_screenshot_result(json!({
+            "text": "- document \"Home\"\n  - link \"Docs\" [ref=e1]",
+            "refs": 1,
+        }))
+        .unwrap();
+        let value = serde_json::to_value(result).unwrap();
+        assert_eq!(value["format"], "accessibility");
+        assert_eq!(value["rows"], 2);
+        assert_eq!(value["columns"], 24);
+    }
+
+    #[test]
+    fn parses_screenshot_regions_and_diff_options() {
+        assert_eq!(browser_screenshot_region_args(&json!({ "format": "image" }), "screenshot").unwrap(), None);
+        assert_eq!(
+            browser_screenshot_region_args(&json!({ "selector": " #board " }), "screenshot").unwrap(),
+            Some(json!({ "selector": "#board" }))
+        );
+        assert_eq!(
+            browser_screenshot_region_args(&json!({ "clip": { "x": 10, "y": 20, "w": 30, "h": 40 } }), "screenshot")
+                .unwrap(),
+            Some(json!({ "clip": { "x": 10.0, "y": 20.0, "width": 30.0, "height": 40.0 } }))
+        );
+        assert!(browser_screenshot_region_args(&json!({ "clip": { "x": 0, "y": 0, "width": 0, "height": 4 } }), "screenshot")
+            .is_err());
+        assert!(browser_screenshot_region_args(&json!({ "clip": "0,0,4,4" }), "screenshot").is_err());
+        assert_eq!(
+            browser_screenshot_region_args(
+                &json!({ "selector": "#board", "clip": { "x": 0, "y": 0, "width": 4, "height": 4 } }),
+                "screenshot_diff"
+            )
+            .unwrap_err(),
+            "browser_drive screenshot_diff accepts either `selector` or `clip`, not both"
+        );
+
+        let options = browser_screenshot_diff_options(&json!({ "baseline": "board", "update_baseline": true })).unwrap();
+        assert_eq!(
+            options.source,
+            BrowserScreenshotDiffSource::Baseline { name: "board".to_string(), update: true }
+        );
+        assert_eq!(options.tolerance, 16);
+        assert!(!options.diff_image);
+        let options = browser_screenshot_diff_options(&json!({ "against": "cap-3", "tolerance": 0, "diff_image": true }))
+            .unwrap();
+        assert_eq!(options.source, BrowserScreenshotDiffSource::Capture("cap-3".to_string()));
+        assert_eq!(options.tolerance, 0);
+        assert!(options.diff_image);
+        assert!(browser_screenshot_diff_options(&json!({})).is_err());
+        assert!(browser_screenshot_diff_options(&json!({ "against": "cap-1", "baseline": "board" })).is_err());
+        assert!(browser_screenshot_diff_options(&json!({ "against": "cap-1", "tolerance": 300 })).is_err());
+    }
+
+    #[test]
+    fn crops_text_layout_snapshots_to_screenshot_regions() {
+        let fragment = |text: &str, left: f64, top: f64| BrowserTextLayoutFragment {
+            text: text.to_string(),
+            left,
+            top,
+            width: 40.0,
+            height: 16.0,
+        };
+        let snapshot = BrowserTextLayoutSnapshot {
+            viewport_width: 800.0,
+            viewport_height: 600.0,
+            fragments: vec![fragment("Header", 0.0, 0.0), fragment("Score", 110.0, 210.0), fragment("Footer", 0.0, 580.0)],
+        };
+        let cropped = crop_text_layout_snapshot(
+            snapshot,
+            BrowserScreenshotRegion {
+                x: 100.0,
+                y: 200.0,
+                width: 200.0,
+                height: 100.0,
+                viewport_width: 800.0,
+            },
+        );
+        assert_eq!((cropped.viewport_width, cropped.viewport_height), (200.0, 100.0));
+        assert_eq!(cropped.fragments.len(), 1);
+        assert_eq!(cropped.fragments[0].text, "Score");
+        assert_eq!((cropped.fragments[0].left, cropped.fragments[0].top), (10.0, 10.0));
+    }
 }
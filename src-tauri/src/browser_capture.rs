@@ -0,0 +1,366 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// Pixel captures Herd keeps per browser pane so `screenshot_diff` can refer
+/// back to them by id. Older captures are forgotten first.
+const CAPTURE_HISTORY_LIMIT: usize = 8;
+const BASELINE_LIMIT: usize = 16;
+pub const DIFF_DEFAULT_TOLERANCE: u8 = 16;
+/// Changed pixels are grouped on a grid of this many pixels before boxes are
+/// traced, so nearby edits merge into one region instead of hundreds.
+const DIFF_CELL_SIZE: u32 = 8;
+const DIFF_MAX_BOXES: usize = 20;
+
+/// A rectangle in CSS pixels relative to the top-left of the viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BrowserCaptureRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A PNG capture plus where it sits in the viewport, so diff boxes can be
+/// reported in the same CSS pixel space as `clip`.
+#[derive(Debug, Clone)]
+pub struct BrowserCapture {
+    pub png: Vec<u8>,
+    pub origin_x: f64,
+    pub origin_y: f64,
+    /// Image pixels per CSS pixel.
+    pub scale: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct BrowserCaptureStore {
+    next_id: u64,
+    captures: VecDeque<(String, BrowserCapture)>,
+    baselines: HashMap<String, BrowserCapture>,
+}
+
+impl BrowserCaptureStore {
+    /// Remembers `capture` and returns its capture id.
+    pub fn record(&mut self, capture: BrowserCapture) -> String {
+        self.next_id += 1;
+        let capture_id = format!("cap-{}", self.next_id);
+        self.captures.push_back((capture_id.clone(), capture));
+        while self.captures.len() > CAPTURE_HISTORY_LIMIT {
+            self.captures.pop_front();
+        }
+        capture_id
+    }
+
+    pub fn capture(&self, capture_id: &str) -> Result<BrowserCapture, String> {
+        self.captures
+            .iter()
+            .find(|(candidate, _)| candidate == capture_id)
+            .map(|(_, capture)| capture.clone())
+            .ok_or_else(|| {
+                format!("unknown capture id {capture_id}; only the last {CAPTURE_HISTORY_LIMIT} captures are kept")
+            })
+    }
+
+    pub fn baseline(&self, name: &str) -> Option<BrowserCapture> {
+        self.baselines.get(name).cloned()
+    }
+
+    pub fn set_baseline(&mut self, name: &str, capture: BrowserCapture) -> Result<(), String> {
+        if !self.baselines.contains_key(name) && self.baselines.len() >= BASELINE_LIMIT {
+            return Err(format!("browser tiles keep at most {BASELINE_LIMIT} screenshot baselines"));
+        }
+        self.baselines.insert(name.to_string(), capture);
+        Ok(())
+    }
+}
+
+/// Builds a capture from a full-viewport PNG, cropping it to `region` when
+/// one was requested. `viewport_width` is the viewport width in CSS pixels.
+pub fn capture_from_viewport_png(
+    png: Vec<u8>,
+    viewport_width: f64,
+    region: Option<BrowserCaptureRegion>,
+) -> Result<BrowserCapture, String> {
+    let image = decode_png(&png)?;
+    let scale = if viewport_width.is_finite() && viewport_width > 0.0 {
+        image.width() as f64 / viewport_width
+    } else {
+        1.0
+    };
+    let Some(region) = region else {
+        return Ok(BrowserCapture {
+            png,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            scale,
+        });
+    };
+    let left = ((region.x * scale).floor().max(0.0) as u32).min(image.width());
+    let top = ((region.y * scale).floor().max(0.0) as u32).min(image.height());
+    let right = (((region.x + region.width) * scale).ceil().max(0.0) as u32).min(image.width());
+    let bottom = (((region.y + region.height) * scale).ceil().max(0.0) as u32).min(image.height());
+    if right <= left || bottom <= top {
+        return Err("screenshot region does not overlap the captured viewport".to_string());
+    }
+    let cropped = image::imageops::crop_imm(&image, left, top, right - left, bottom - top).to_image();
+    Ok(BrowserCapture {
+        png: encode_png(&cropped)?,
+        origin_x: left as f64 / scale,
+        origin_y: top as f64 / scale,
+        scale,
+    })
+}
+
+/// A changed area in CSS pixels relative to the viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BrowserDiffBox {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+#[derive(Debug)]
+pub struct BrowserDiff {
+    pub width: u32,
+    pub height: u32,
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    pub changed_ratio: f64,
+    pub boxes: Vec<BrowserDiffBox>,
+    pub diff_png: Option<Vec<u8>>,
+}
+
+/// Compares two captures of the same size. A pixel counts as changed when any
+/// channel differs by more than `tolerance`.
+pub fn diff_captures(
+    previous: &BrowserCapture,
+    current: &BrowserCapture,
+    tolerance: u8,
+    include_diff_image: bool,
+) -> Result<BrowserDiff, String> {
+    let before = decode_png(&previous.png)?;
+    let after = decode_png(&current.png)?;
+    if before.dimensions() != after.dimensions() {
+        return Err(format!(
+            "captures differ in size ({}x{} vs {}x{}); take a new baseline with the same selector or clip",
+            before.width(),
+            before.height(),
+            after.width(),
+            after.height()
+        ));
+    }
+    let (width, height) = after.dimensions();
+    let cell_columns = width.div_ceil(DIFF_CELL_SIZE) as usize;
+    let cell_rows = height.div_ceil(DIFF_CELL_SIZE) as usize;
+    // Per grid cell: the changed-pixel extent as (min_x, min_y, max_x, max_y).
+    let mut cells: Vec<Option<(u32, u32, u32, u32)>> = vec![None; cell_columns * cell_rows];
+    let mut changed_pixels = 0u64;
+    let mut diff_image = include_diff_image.then(|| RgbaImage::new(width, height));
+    for (x, y, pixel) in after.enumerate_pixels() {
+        let old = before.get_pixel(x, y);
+        let changed = pixel
+            .0
+            .iter()
+            .zip(old.0.iter())
+            .any(|(new, old)| new.abs_diff(*old) > tolerance);
+        if changed {
+            changed_pixels += 1;
+            let cell = &mut cells[(y / DIFF_CELL_SIZE) as usize * cell_columns + (x / DIFF_CELL_SIZE) as usize];
+            *cell = Some(match *cell {
+                Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+        if let Some(diff_image) = diff_image.as_mut() {
+            diff_image.put_pixel(x, y, if changed { Rgba([255, 0, 0, 255]) } else { faded_pixel(pixel) });
+        }
+    }
+    let total_pixels = width as u64 * height as u64;
+    let mut boxes = changed_cell_boxes(&cells, cell_columns, cell_rows)
+        .into_iter()
+        .map(|(min_x, min_y, max_x, max_y)| {
+            let to_css = |value: u32, origin: f64| (origin + value as f64 / current.scale).round() as i64;
+            let x = to_css(min_x, current.origin_x);
+            let y = to_css(min_y, current.origin_y);
+            BrowserDiffBox {
+                x,
+                y,
+                width: (to_css(max_x + 1, current.origin_x) - x).max(1),
+                height: (to_css(max_y + 1, current.origin_y) - y).max(1),
+            }
+        })
+        .collect::<Vec<_>>();
+    boxes.sort_by_key(|entry| std::cmp::Reverse(entry.width * entry.height));
+    boxes.truncate(DIFF_MAX_BOXES);
+    Ok(BrowserDiff {
+        width,
+        height,
+        changed_pixels,
+        total_pixels,
+        changed_ratio: if total_pixels == 0 {
+            0.0
+        } else {
+            changed_pixels as f64 / total_pixels as f64
+        },
+        boxes,
+        diff_png: diff_image.map(|image| encode_png(&image)).transpose()?,
+    })
+}
+
+/// Groups 8-connected changed cells and returns each group's pixel extent.
+fn changed_cell_boxes(
+    cells: &[Option<(u32, u32, u32, u32)>],
+    cell_columns: usize,
+    cell_rows: usize,
+) -> Vec<(u32, u32, u32, u32)> {
+    let mut visited = vec![false; cells.len()];
+    let mut boxes = Vec::new();
+    for start in 0..cells.len() {
+        if visited[start] || cells[start].is_none() {
+            continue;
+        }
+        visited[start] = true;
+        let mut extent = cells[start].unwrap();
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            let (column, row) = ((index % cell_columns) as isize, (index / cell_columns) as isize);
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                let (next_column, next_row) = (column + dx, row + dy);
+                if next_column < 0 || next_row < 0 || next_column >= cell_columns as isize || next_row >= cell_rows as isize {
+                    continue;
+                }
+                let next = next_row as usize * cell_columns + next_column as usize;
+                if visited[next] {
+                    continue;
+                }
+                if let Some((min_x, min_y, max_x, max_y)) = cells[next] {
+                    visited[next] = true;
+                    extent = (extent.0.min(min_x), extent.1.min(min_y), extent.2.max(max_x), extent.3.max(max_y));
+                    stack.push(next);
+                }
+            }
+        }
+        boxes.push(extent);
+    }
+    boxes
+}
+
+/// Unchanged pixels are washed out in the diff image so red changes stand out.
+fn faded_pixel(pixel: &Rgba<u8>) -> Rgba<u8> {
+    let [red, green, blue, _] = pixel.0;
+    let luma = (red as u32 * 299 + green as u32 * 587 + blue as u32 * 114) / 1000;
+    let faded = (160 + luma * 95 / 255) as u8;
+    Rgba([faded, faded, faded, 255])
+}
+
+fn decode_png(png: &[u8]) -> Result<RgbaImage, String> {
+    image::load_from_memory_with_format(png, ImageFormat::Png)
+        .map(|image| image.to_rgba8())
+        .map_err(|error| format!("failed to decode browser screenshot PNG: {error}"))
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|error| format!("failed to encode browser screenshot PNG: {error}"))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        capture_from_viewport_png, decode_png, diff_captures, encode_png, BrowserCaptureRegion, BrowserCaptureStore,
+        BrowserDiffBox,
+    };
+    use image::{Rgba, RgbaImage};
+
+    fn solid_png(width: u32, height: u32, paint: &[(u32, u32, u32, u32)]) -> Vec<u8> {
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+        for &(left, top, right, bottom) in paint {
+            for y in top..bottom {
+                for x in left..right {
+                    image.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+                }
+            }
+        }
+        encode_png(&image).unwrap()
+    }
+
+    #[test]
+    fn crops_viewport_captures_to_css_pixel_regions() {
+        // A 2x device-pixel-ratio capture of a 50x40 CSS pixel viewport.
+        let png = solid_png(100, 80, &[(20, 20, 40, 40)]);
+        let capture = capture_from_viewport_png(
+            png,
+            50.0,
+            Some(BrowserCaptureRegion {
+                x: 10.0,
+                y: 10.0,
+                width: 10.0,
+                height: 5.0,
+            }),
+        )
+        .unwrap();
+        assert_eq!(capture.scale, 2.0);
+        assert_eq!((capture.origin_x, capture.origin_y), (10.0, 10.0));
+        let image = decode_png(&capture.png).unwrap();
+        assert_eq!(image.dimensions(), (20, 10));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+
+        let outside = BrowserCaptureRegion {
+            x: 60.0,
+            y: 0.0,
+            width: 10.0,
+            height: 10.0,
+        };
+        assert!(capture_from_viewport_png(solid_png(100, 80, &[]), 50.0, Some(outside)).is_err());
+    }
+
+    #[test]
+    fn diffs_captures_into_ratio_boxes_and_image() {
+        let before = capture_from_viewport_png(solid_png(64, 32, &[]), 64.0, None).unwrap();
+        let after = capture_from_viewport_png(solid_png(64, 32, &[(2, 2, 6, 6), (40, 20, 44, 22)]), 64.0, None).unwrap();
+        let diff = diff_captures(&before, &after, 16, true).unwrap();
+        assert_eq!(diff.changed_pixels, 16 + 8);
+        assert_eq!(diff.total_pixels, 64 * 32);
+        assert!((diff.changed_ratio - 24.0 / 2048.0).abs() < f64::EPSILON);
+        assert_eq!(
+            diff.boxes,
+            vec![
+                BrowserDiffBox { x: 2, y: 2, width: 4, height: 4 },
+                BrowserDiffBox { x: 40, y: 20, width: 4, height: 2 },
+            ]
+        );
+        let diff_image = decode_png(diff.diff_png.as_ref().unwrap()).unwrap();
+        assert_eq!(diff_image.get_pixel(3, 3).0, [255, 0, 0, 255]);
+        assert_ne!(diff_image.get_pixel(30, 10).0, [255, 0, 0, 255]);
+
+        let unchanged = diff_captures(&before, &before, 16, false).unwrap();
+        assert_eq!(unchanged.changed_pixels, 0);
+        assert!(unchanged.boxes.is_empty() && unchanged.diff_png.is_none());
+
+        let smaller = capture_from_viewport_png(solid_png(32, 32, &[]), 32.0, None).unwrap();
+        assert!(diff_captures(&before, &smaller, 16, false).is_err());
+    }
+
+    #[test]
+    fn keeps_recent_captures_and_named_baselines() {
+        let mut store = BrowserCaptureStore::default();
+        let capture = capture_from_viewport_png(solid_png(4, 4, &[]), 4.0, None).unwrap();
+        let first = store.record(capture.clone());
+        assert_eq!(first, "cap-1");
+        for _ in 0..8 {
+            store.record(capture.clone());
+        }
+        assert!(store.capture(&first).is_err());
+        assert!(store.capture("cap-9").is_ok());
+
+        assert!(store.baseline("board").is_none());
+        store.set_baseline("board", capture).unwrap();
+        assert!(store.baseline("board").is_some());
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Entries Herd keeps per browser pane once they have been drained from the
+/// page. Older entries are dropped first and counted in `dropped`.
+const CONSOLE_LOG_CAPACITY: usize = 1_000;
+const CONSOLE_READ_DEFAULT_LIMIT: usize = 200;
+pub const CONSOLE_LEVELS: &[&str] = &["debug", "log", "info", "warn", "error"];
+/// Tile-event action used when new console entries are delivered to subscribers.
+pub const BROWSER_CONSOLE_EVENT: &str = "console";
+
+/// Installed into every page of a browser tile before page scripts run. It
+/// wraps `console.*`, listens for uncaught errors and unhandled rejections,
+/// and buffers entries on `window.__herdConsole` until Herd drains them. The
+/// agent-browser backend has no initialization scripts, so it evaluates the
+/// shim after each open and click; the script is idempotent for that reason.
+pub const BROWSER_CONSOLE_CAPTURE_SCRIPT: &str = r#"
+(function() {
+  if (window.__herdConsole) {
+    return;
+  }
+  const capture = {
+    pageId: `${Date.now().toString(36)}-${Math.random().toString(36).slice(2, 10)}`,
+    seq: 0,
+    dropped: 0,
+    entries: [],
+  };
+  Object.defineProperty(window, '__herdConsole', { value: capture, enumerable: false });
+  const maxEntries = 500;
+  const maxMessageLength = 4000;
+  const describe = (value) => {
+    if (typeof value === 'string') return value;
+    if (value instanceof Error) return value.stack || `${value.name}: ${value.message}`;
+    if (value === undefined) return 'undefined';
+    if (typeof value === 'function') return `[Function ${value.name || 'anonymous'}]`;
+    if (value instanceof Element) return `<${value.tagName.toLowerCase()}${value.id ? `#${value.id}` : ''}>`;
+    try {
+      const json = JSON.stringify(value);
+      return json === undefined ? String(value) : json;
+    } catch (_error) {
+      return String(value);
+    }
+  };
+  const push = (level, kind, message, source) => {
+    capture.seq += 1;
+    capture.entries.push({
+      seq: capture.seq,
+      level,
+      kind,
+      message: message.length > maxMessageLength ? `${message.slice(0, maxMessageLength - 1)}…` : message,
+      source: source || null,
+      url: location.href,
+      timestamp_ms: Date.now(),
+    });
+    if (capture.entries.length > maxEntries) {
+      capture.entries.shift();
+      capture.dropped += 1;
+    }
+  };
+  for (const level of ['debug', 'log', 'info', 'warn', 'error']) {
+    const original = console[level];
+    if (typeof original !== 'function') continue;
+    console[level] = function(...args) {
+      try {
+        push(level, 'console', args.map(describe).join(' '), null);
+      } catch (_error) {}
+      return original.apply(this, args);
+    };
+  }
+  window.addEventListener('error', (event) => {
+    if (event.target && event.target !== window && event.target instanceof Element) {
+      const resource = event.target.src || event.target.href || event.target.tagName.toLowerCase();
+      push('error', 'resource', `Failed to load ${resource}`, resource);
+      return;
+    }
+    const message = event.error ? describe(event.error) : String(event.message || 'Uncaught error');
+    const source = event.filename ? `${event.filename}:${event.lineno || 0}:${event.colno || 0}` : null;
+    push('error', 'exception', message, source);
+  }, true);
+  window.addEventListener('unhandledrejection', (event) => {
+    push('error', 'rejection', `Unhandled rejection: ${describe(event.reason)}`, null);
+  });
+})();
+"#;
+
+/// Runs inside the browser drive wrapper: installs the shim on agent-browser
+/// pages that navigated without Herd, acknowledges entries up to `args.after`
+/// for the current page, and returns the rest.
+pub fn browser_console_drain_script() -> String {
+    format!(
+        r#"
+{BROWSER_CONSOLE_CAPTURE_SCRIPT}
+const capture = window.__herdConsole;
+const after = args.page_id === capture.pageId ? Number(args.after || 0) : 0;
+capture.entries = capture.entries.filter((entry) => entry.seq > after);
+const dropped = capture.dropped;
+capture.dropped = 0;
+return {{ page_id: capture.pageId, dropped, entries: capture.entries }};
+"#
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrowserConsolePageEntry {
+    pub seq: u64,
+    pub level: String,
+    pub kind: String,
+    pub message: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub timestamp_ms: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrowserConsoleDrain {
+    pub page_id: String,
+    #[serde(default)]
+    pub dropped: u64,
+    #[serde(default)]
+    pub entries: Vec<BrowserConsolePageEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct BrowserConsoleEntry {
+    pub cursor: u64,
+    pub level: String,
+    pub kind: String,
+    pub message: String,
+    pub source: Option<String>,
+    pub url: String,
+    pub timestamp_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserConsoleReadResult {
+    pub entries: Vec<BrowserConsoleEntry>,
+    pub next_cursor: u64,
+    pub dropped: u64,
+}
+
+/// Herd-side console history for one browser pane. Cursors are assigned here
+/// rather than in the page so they keep increasing across navigations.
+#[derive(Debug, Default)]
+pub struct BrowserConsoleLog {
+    entries: VecDeque<BrowserConsoleEntry>,
+    next_cursor: u64,
+    dropped: u64,
+    page_id: Option<String>,
+    page_seq: u64,
+}
+
+impl BrowserConsoleLog {
+    /// The `args` for [`browser_console_drain_script`], acknowledging what has
+    /// already been ingested from the current page.
+    pub fn drain_args(&self) -> serde_json::Value {
+        serde_json::json!({
+            "page_id": self.page_id,
+            "after": self.page_seq,
+        })
+    }
+
+    pub fn next_cursor(&self) -> u64 {
+        self.next_cursor
+    }
+
+    pub fn ingest(&mut self, drain: BrowserConsoleDrain) {
+        if self.page_id.as_deref() != Some(drain.page_id.as_str()) {
+            self.page_id = Some(drain.page_id);
+            self.page_seq = 0;
+        }
+        self.dropped += drain.dropped;
+        for entry in drain.entries {
+            if entry.seq <= self.page_seq {
+                continue;
+            }
+            self.page_seq = entry.seq;
+            self.next_cursor += 1;
+            self.entries.push_back(BrowserConsoleEntry {
+                cursor: self.next_cursor,
+                level: normalize_console_level(&entry.level).to_string(),
+                kind: entry.kind,
+                message: entry.message,
+                source: entry.source,
+                url: entry.url,
+                timestamp_ms: entry.timestamp_ms,
+            });
+            if self.entries.len() > CONSOLE_LOG_CAPACITY {
+                self.entries.pop_front();
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// Entries after `since` at or above `min_level`, oldest first.
+    /// `next_cursor` is the cursor to pass as `since` on the next read.
+    pub fn read(&self, since: Option<u64>, min_level: Option<&str>, limit: Option<usize>) -> BrowserConsoleReadResult {
+        let since = since.unwrap_or(0);
+        let min_rank = min_level.map(console_level_rank).unwrap_or(0);
+        let limit = limit.unwrap_or(CONSOLE_READ_DEFAULT_LIMIT).max(1);
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.cursor > since && console_level_rank(&entry.level) >= min_rank)
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+        let next_cursor = if entries.len() == limit {
+            entries.last().map(|entry| entry.cursor).unwrap_or(since)
+        } else {
+            self.next_cursor.max(since)
+        };
+        BrowserConsoleReadResult {
+            entries,
+            next_cursor,
+            dropped: self.dropped,
+        }
+    }
+}
+
+fn normalize_console_level(level: &str) -> &'static str {
+    CONSOLE_LEVELS
+        .iter()
+        .copied()
+        .find(|candidate| *candidate == level)
+        .unwrap_or("log")
+}
+
+fn console_level_rank(level: &str) -> usize {
+    match level {
+        "debug" => 0,
+        "log" | "info" => 1,
+        "warn" => 2,
+        "error" => 3,
+        _ => 1,
+    }
+}
+
+pub fn validate_console_level(level: &str) -> Result<(), String> {
+    if CONSOLE_LEVELS.contains(&level) {
+        return Ok(());
+    }
+    Err(format!(
+        "console level must be one of {}, got {level}",
+        CONSOLE_LEVELS.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BrowserConsoleDrain, BrowserConsoleLog, BrowserConsolePageEntry, validate_console_level};
+
+    fn page_entry(seq: u64, level: &str, message: &str) -> BrowserConsolePageEntry {
+        BrowserConsolePageEntry {
+            seq,
+            level: level.to_string(),
+            kind: "console".to_string(),
+            message: message.to_string(),
+            source: None,
+            url: "https://example.test/".to_string(),
+            timestamp_ms: seq as i64,
+        }
+    }
+
+    fn drain(page_id: &str, entries: Vec<BrowserConsolePageEntry>) -> BrowserConsoleDrain {
+        BrowserConsoleDrain {
+            page_id: page_id.to_string(),
+            dropped: 0,
+            entries,
+        }
+    }
+
+    #[test]
+    fn assigns_stable_cursors_across_page_loads() {
+        let mut log = BrowserConsoleLog::default();
+        log.ingest(drain("page-a", vec![page_entry(1, "log", "boot"), page_entry(2, "error", "boom")]));
+        // A repeated drain before the page saw the acknowledgement must not duplicate entries.
+        log.ingest(drain("page-a", vec![page_entry(2, "error", "boom"), page_entry(3, "warn", "slow")]));
+        assert_eq!(log.drain_args()["after"], 3);
+
+        log.ingest(drain("page-b", vec![page_entry(1, "info", "reloaded")]));
+        let all = log.read(None, None, None);
+        assert_eq!(
+            all.entries.iter().map(|entry| (entry.cursor, entry.message.as_str())).collect::<Vec<_>>(),
+            vec![(1, "boot"), (2, "boom"), (3, "slow"), (4, "reloaded")]
+        );
+        assert_eq!(all.next_cursor, 4);
+
+        let since = log.read(Some(2), None, None);
+        assert_eq!(since.entries.len(), 2);
+        assert!(log.read(Some(4), None, None).entries.is_empty());
+        assert_eq!(log.read(Some(4), None, None).next_cursor, 4);
+    }
+
+    #[test]
+    fn filters_console_entries_by_minimum_level_and_limit() {
+        let mut log = BrowserConsoleLog::default();
+        log.ingest(drain(
+            "page",
+            vec![
+                page_entry(1, "debug", "trace"),
+                page_entry(2, "log", "hello"),
+                page_entry(3, "warn", "careful"),
+                page_entry(4, "error", "broken"),
+                page_entry(5, "table", "odd"),
+            ],
+        ));
+        let warnings = log.read(None, Some("warn"), None);
+        assert_eq!(
+            warnings.entries.iter().map(|entry| entry.message.as_str()).collect::<Vec<_>>(),
+            vec!["careful", "broken"]
+        );
+        assert_eq!(log.read(None, Some("info"), None).entries.len(), 4);
+        assert_eq!(log.read(None, None, None).entries[4].level, "log");
+
+        let page = log.read(None, None, Some(2));
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.next_cursor, 2);
+
+        assert!(validate_console_level("warn").is_ok());
+        assert!(validate_console_level("fatal").is_err());
+    }
+}
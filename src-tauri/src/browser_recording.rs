@@ -0,0 +1,476 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::FilterType;
+use image::{Delay, Frame, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::runtime;
+
+const DEFAULT_INTERVAL_MS: u64 = 200;
+const MIN_INTERVAL_MS: u64 = 50;
+const MAX_INTERVAL_MS: u64 = 10_000;
+const DEFAULT_MAX_FRAMES: usize = 300;
+const MAX_FRAMES_LIMIT: usize = 3_000;
+/// Recordings are downscaled to this width unless the caller asks otherwise;
+/// full-resolution frames from a 2x display make multi-megabyte GIFs quickly.
+const DEFAULT_MAX_WIDTH: u32 = 1280;
+/// NeuQuant sampling speed for GIF palettes, from 1 (best) to 30 (fastest).
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowserRecordingFormat {
+    Gif,
+    Apng,
+}
+
+impl BrowserRecordingFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "gif" => Ok(Self::Gif),
+            "apng" => Ok(Self::Apng),
+            other => Err(format!("unsupported browser recording format: {other}; expected gif or apng")),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "apng",
+        }
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Gif => "image/gif",
+            Self::Apng => "image/apng",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrowserRecordingOptions {
+    pub format: BrowserRecordingFormat,
+    pub interval_ms: u64,
+    pub max_frames: usize,
+    pub max_width: u32,
+}
+
+impl BrowserRecordingOptions {
+    pub fn new(
+        format: Option<&str>,
+        interval_ms: Option<u64>,
+        max_frames: Option<usize>,
+        max_width: Option<u32>,
+    ) -> Result<Self, String> {
+        let format = format.map(BrowserRecordingFormat::parse).transpose()?.unwrap_or(BrowserRecordingFormat::Gif);
+        let interval_ms = interval_ms.unwrap_or(DEFAULT_INTERVAL_MS);
+        if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+            return Err(format!(
+                "record_start interval_ms must be between {MIN_INTERVAL_MS} and {MAX_INTERVAL_MS}"
+            ));
+        }
+        let max_frames = max_frames.unwrap_or(DEFAULT_MAX_FRAMES);
+        if !(1..=MAX_FRAMES_LIMIT).contains(&max_frames) {
+            return Err(format!("record_start max_frames must be between 1 and {MAX_FRAMES_LIMIT}"));
+        }
+        let max_width = max_width.unwrap_or(DEFAULT_MAX_WIDTH);
+        if max_width == 0 {
+            return Err("record_start max_width must be greater than 0".to_string());
+        }
+        Ok(Self {
+            format,
+            interval_ms,
+            max_frames,
+            max_width,
+        })
+    }
+}
+
+/// What `record_start` reports back while frames are still being sampled.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BrowserRecordingStatus {
+    pub recording_id: String,
+    pub format: BrowserRecordingFormat,
+    pub interval_ms: u64,
+    pub max_frames: usize,
+    pub frames: usize,
+    pub frames_dir: String,
+    pub started_at: i64,
+}
+
+/// The encoded recording returned by `record_stop`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BrowserRecordingResult {
+    pub recording_id: String,
+    pub format: BrowserRecordingFormat,
+    pub path: String,
+    pub media_type: String,
+    pub frames: usize,
+    pub width: u32,
+    pub height: u32,
+    pub duration_ms: u64,
+    pub started_at: i64,
+    pub stopped_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_capture_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct RecordedFrame {
+    path: PathBuf,
+    captured_at: i64,
+}
+
+/// A browser recording in progress: viewport PNGs written to `frames_dir` as
+/// `frame-00001.png`, `frame-00002.png`, ... until the recording is stopped.
+/// Dropping a recording that was never encoded removes its directory, so
+/// closed tiles and failed encodes leave nothing behind.
+#[derive(Debug)]
+pub struct BrowserRecording {
+    recording_id: String,
+    options: BrowserRecordingOptions,
+    recording_dir: PathBuf,
+    frames_dir: PathBuf,
+    started_at: i64,
+    frames: Vec<RecordedFrame>,
+    last_capture_error: Option<String>,
+    encoded: bool,
+}
+
+pub fn browser_recordings_dir() -> PathBuf {
+    runtime::project_tmp_dir().join("browser-recordings")
+}
+
+impl BrowserRecording {
+    pub fn create(root: &Path, options: BrowserRecordingOptions, started_at: i64) -> Result<Self, String> {
+        let recording_id = uuid::Uuid::new_v4().simple().to_string();
+        let recording_dir = root.join(&recording_id);
+        let frames_dir = recording_dir.join("frames");
+        fs::create_dir_all(&frames_dir)
+            .map_err(|error| format!("failed to create recording directory {}: {error}", frames_dir.display()))?;
+        Ok(Self {
+            recording_id,
+            options,
+            recording_dir,
+            frames_dir,
+            started_at,
+            frames: Vec::new(),
+            last_capture_error: None,
+            encoded: false,
+        })
+    }
+
+    pub fn recording_id(&self) -> &str {
+        &self.recording_id
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.options.interval_ms
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.options.max_frames
+    }
+
+    pub fn status(&self) -> BrowserRecordingStatus {
+        BrowserRecordingStatus {
+            recording_id: self.recording_id.clone(),
+            format: self.options.format,
+            interval_ms: self.options.interval_ms,
+            max_frames: self.options.max_frames,
+            frames: self.frames.len(),
+            frames_dir: self.frames_dir.to_string_lossy().to_string(),
+            started_at: self.started_at,
+        }
+    }
+
+    /// Where the next frame belongs, or `None` once `max_frames` is reached.
+    /// The sampler writes it with `write_frame` outside the recordings lock
+    /// and then hands it back through `push_frame`.
+    pub fn next_frame_path(&self) -> Option<PathBuf> {
+        (!self.is_full()).then(|| self.frames_dir.join(format!("frame-{:05}.png", self.frames.len() + 1)))
+    }
+
+    pub fn push_frame(&mut self, path: PathBuf, captured_at: i64) {
+        if !self.is_full() {
+            self.frames.push(RecordedFrame { path, captured_at });
+        }
+    }
+
+    pub fn record_capture_error(&mut self, error: String) {
+        self.last_capture_error = Some(error);
+    }
+
+    /// Encodes the sampled frames next to `frames_dir` and then deletes the
+    /// frames. Each frame is shown until the next one was captured, so stalls
+    /// in sampling keep real timing.
+    pub fn finish(mut self, stopped_at: i64) -> Result<BrowserRecordingResult, String> {
+        let Some(first) = self.frames.first() else {
+            let reason = self
+                .last_capture_error
+                .as_ref()
+                .map(|error| format!(": {error}"))
+                .unwrap_or_default();
+            return Err(format!("recording {} captured no frames{reason}", self.recording_id));
+        };
+        let first_image = load_frame(&first.path)?;
+        let (width, height) = scaled_dimensions(first_image.width(), first_image.height(), self.options.max_width);
+        let delays = self
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                let delay = self
+                    .frames
+                    .get(index + 1)
+                    .map(|next| (next.captured_at - frame.captured_at).max(0) as u64)
+                    .unwrap_or(self.options.interval_ms);
+                delay.max(MIN_INTERVAL_MS)
+            })
+            .collect::<Vec<_>>();
+        let frames = self
+            .frames
+            .iter()
+            .zip(&delays)
+            .map(|(frame, delay)| (frame.path.as_path(), *delay));
+        let path = self
+            .recording_dir
+            .join(format!("recording.{}", self.options.format.extension()));
+        match self.options.format {
+            BrowserRecordingFormat::Gif => encode_gif(&path, frames, width, height)?,
+            BrowserRecordingFormat::Apng => encode_apng(&path, frames, self.frames.len(), width, height)?,
+        }
+        self.encoded = true;
+        if let Err(error) = fs::remove_dir_all(&self.frames_dir) {
+            log::warn!("failed to remove recording frames {}: {error}", self.frames_dir.display());
+        }
+        Ok(BrowserRecordingResult {
+            recording_id: self.recording_id.clone(),
+            format: self.options.format,
+            path: path.to_string_lossy().to_string(),
+            media_type: self.options.format.media_type().to_string(),
+            frames: self.frames.len(),
+            width,
+            height,
+            duration_ms: delays.iter().sum(),
+            started_at: self.started_at,
+            stopped_at,
+            last_capture_error: self.last_capture_error.take(),
+            work_id: None,
+            attach_error: None,
+        })
+    }
+}
+
+impl Drop for BrowserRecording {
+    fn drop(&mut self) {
+        if !self.encoded {
+            let _ = fs::remove_dir_all(&self.recording_dir);
+        }
+    }
+}
+
+pub fn write_frame(path: &Path, png: &[u8]) -> Result<(), String> {
+    fs::write(path, png).map_err(|error| format!("failed to write recording frame {}: {error}", path.display()))
+}
+
+fn scaled_dimensions(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    if width <= max_width {
+        return (width, height);
+    }
+    let scaled_height = (u64::from(height) * u64::from(max_width) / u64::from(width)).max(1);
+    (max_width, scaled_height as u32)
+}
+
+fn load_frame(path: &Path) -> Result<RgbaImage, String> {
+    let bytes = fs::read(path).map_err(|error| format!("failed to read recording frame {}: {error}", path.display()))?;
+    image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+        .map(|image| image.to_rgba8())
+        .map_err(|error| format!("failed to decode recording frame {}: {error}", path.display()))
+}
+
+/// Loads a frame at the recording's size. The viewport can be resized while
+/// recording, so later frames are stretched to match the first one.
+fn load_frame_sized(path: &Path, width: u32, height: u32) -> Result<RgbaImage, String> {
+    let image = load_frame(path)?;
+    if image.dimensions() == (width, height) {
+        return Ok(image);
+    }
+    Ok(image::imageops::resize(&image, width, height, FilterType::Triangle))
+}
+
+fn create_output(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|error| format!("failed to create recording {}: {error}", path.display()))
+}
+
+fn encode_gif<'a>(
+    path: &Path,
+    frames: impl Iterator<Item = (&'a Path, u64)>,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let mut encoder = GifEncoder::new_with_speed(create_output(path)?, GIF_QUANTIZE_SPEED);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|error| format!("failed to configure GIF recording: {error}"))?;
+    for (frame_path, delay_ms) in frames {
+        let image = load_frame_sized(frame_path, width, height)?;
+        let delay = Delay::from_numer_denom_ms(delay_ms.min(u64::from(u32::MAX)) as u32, 1);
+        encoder
+            .encode_frame(Frame::from_parts(image, 0, 0, delay))
+            .map_err(|error| format!("failed to encode GIF recording frame: {error}"))?;
+    }
+    Ok(())
+}
+
+fn encode_apng<'a>(
+    path: &Path,
+    frames: impl Iterator<Item = (&'a Path, u64)>,
+    frame_count: usize,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let mut encoder = png::Encoder::new(create_output(path)?, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frame_count as u32, 0)
+        .map_err(|error| format!("failed to configure APNG recording: {error}"))?;
+    let mut writer = encoder
+        .write_header()
+        .map_err(|error| format!("failed to write APNG recording header: {error}"))?;
+    for (frame_path, delay_ms) in frames {
+        let image = load_frame_sized(frame_path, width, height)?;
+        writer
+            .set_frame_delay(delay_ms.min(u64::from(u16::MAX)) as u16, 1000)
+            .and_then(|_| writer.write_image_data(image.as_raw()))
+            .map_err(|error| format!("failed to encode APNG recording frame: {error}"))?;
+    }
+    writer
+        .finish()
+        .map_err(|error| format!("failed to finish APNG recording: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_frame, BrowserRecording, BrowserRecordingFormat, BrowserRecordingOptions};
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::png::PngDecoder;
+    use image::{AnimationDecoder, ImageFormat, Rgba, RgbaImage};
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("herd-browser-recording-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn solid_png(width: u32, height: u32, shade: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbaImage::from_pixel(width, height, Rgba([shade, 0, 255 - shade, 255]))
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn save_frame(recording: &mut BrowserRecording, png: &[u8], captured_at: i64) {
+        if let Some(path) = recording.next_frame_path() {
+            write_frame(&path, png).unwrap();
+            recording.push_frame(path, captured_at);
+        }
+    }
+
+    fn record(root: &Path, format: &str) -> BrowserRecording {
+        let options = BrowserRecordingOptions::new(Some(format), Some(100), Some(3), Some(40)).unwrap();
+        let mut recording = BrowserRecording::create(root, options, 1_000).unwrap();
+        save_frame(&mut recording, &solid_png(80, 60, 0), 1_000);
+        save_frame(&mut recording, &solid_png(80, 60, 128), 1_150);
+        // A resized viewport still lands in the recording at the first frame's size.
+        save_frame(&mut recording, &solid_png(40, 40, 255), 1_250);
+        save_frame(&mut recording, &solid_png(80, 60, 64), 1_350);
+        assert!(recording.is_full());
+        assert!(recording.next_frame_path().is_none());
+        recording
+    }
+
+    #[test]
+    fn validates_recording_options() {
+        let defaults = BrowserRecordingOptions::new(None, None, None, None).unwrap();
+        assert_eq!(defaults.format, BrowserRecordingFormat::Gif);
+        assert_eq!(defaults.interval_ms, 200);
+
+        assert!(BrowserRecordingOptions::new(Some("webm"), None, None, None)
+            .unwrap_err()
+            .contains("expected gif or apng"));
+        assert!(BrowserRecordingOptions::new(None, Some(10), None, None)
+            .unwrap_err()
+            .contains("interval_ms"));
+        assert!(BrowserRecordingOptions::new(None, None, Some(0), None)
+            .unwrap_err()
+            .contains("max_frames"));
+    }
+
+    #[test]
+    fn encodes_sampled_frames_as_animated_gif() {
+        let root = temp_root("gif");
+        let recording = record(&root, "gif");
+        let frames_dir = PathBuf::from(recording.status().frames_dir);
+        assert_eq!(fs::read_dir(&frames_dir).unwrap().count(), 3);
+
+        let result = recording.finish(1_400).unwrap();
+        assert_eq!(result.frames, 3);
+        assert_eq!((result.width, result.height), (40, 30));
+        assert_eq!(result.duration_ms, 150 + 100 + 100);
+        assert!(result.path.ends_with("recording.gif"));
+        assert!(!frames_dir.exists());
+
+        let decoder = GifDecoder::new(Cursor::new(fs::read(&result.path).unwrap())).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].delay().numer_denom_ms(), (150, 1));
+        assert!(frames.iter().all(|frame| frame.buffer().dimensions() == (40, 30)));
+    }
+
+    #[test]
+    fn encodes_sampled_frames_as_apng_and_rejects_empty_recordings() {
+        let root = temp_root("apng");
+        let result = record(&root, "apng").finish(1_400).unwrap();
+        assert_eq!(result.media_type, "image/apng");
+
+        let decoder = PngDecoder::new(Cursor::new(fs::read(&result.path).unwrap())).unwrap();
+        let frames = decoder.apng().unwrap().into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].delay().numer_denom_ms(), (100, 1));
+
+        let options = BrowserRecordingOptions::new(Some("apng"), None, None, None).unwrap();
+        let mut empty = BrowserRecording::create(&root, options, 1_000).unwrap();
+        let empty_dir = PathBuf::from(empty.status().frames_dir).parent().unwrap().to_path_buf();
+        empty.record_capture_error("webview not found".to_string());
+        assert!(empty.finish(2_000).unwrap_err().contains("captured no frames: webview not found"));
+        assert!(!empty_dir.exists());
+    }
+
+    #[test]
+    fn dropping_an_unfinished_recording_removes_its_directory() {
+        let root = temp_root("drop");
+        let recording = record(&root, "gif");
+        let recording_dir = PathBuf::from(recording.status().frames_dir).parent().unwrap().to_path_buf();
+        assert!(recording_dir.exists());
+        drop(recording);
+        assert!(!recording_dir.exists());
+        assert!(root.exists());
+    }
+}
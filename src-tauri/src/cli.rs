@@ -87,40 +87,61 @@ Usage:
   herd [--socket <path>] [--agent-pid <pid>] self info
   herd [--socket <path>] [--agent-pid <pid>] self led-control <json>
   herd [--socket <path>] [--agent-pid <pid>] self display-status <text>
-  herd [--socket <path>] [--agent-pid <pid>] network list [shell|agent|browser|work]
+  herd [--socket <path>] [--agent-pid <pid>] network list [shell|agent|browser|work] [--at <timestamp_ms>] [--segment <name>]
+  herd [--socket <path>] [--agent-pid <pid>] network history [--tile <tile_id>] [--since <timestamp_ms>] [--until <timestamp_ms>] [--limit <n>]
   herd [--socket <path>] [--agent-pid <pid>] network get <tile_id>
   herd [--socket <path>] [--agent-pid <pid>] network call <tile_id> <action> [json_args]
-  herd [--socket <path>] [--agent-pid <pid>] network subscribe <tile_id> <event>
-  herd [--socket <path>] [--agent-pid <pid>] network unsubscribe <tile_id> <event>
+  herd [--socket <path>] [--agent-pid <pid>] network subscribe <tile_id> <event> [--segment <name>]
+  herd [--socket <path>] [--agent-pid <pid>] network unsubscribe <tile_id> <event> [--segment <name>]
   herd [--socket <path>] [--agent-pid <pid>] network subscriptions [<tile_id>]
+  herd [--socket <path>] [--agent-pid <pid>] network path [<from_tile>] <to_tile>
+  herd [--socket <path>] [--agent-pid <pid>] network ports <tile_id> <json_ports>
+  herd [--socket <path>] [--agent-pid <pid>] network segment <tile_id> <port> [<segment>...]
+  herd [--socket <path>] [--agent-pid <pid>] network stats [<tile_id>]
   herd [--socket <path>] [--agent-pid <pid>] network connect <from_tile> <from_port> <to_tile> <to_port>
   herd [--socket <path>] [--agent-pid <pid>] network disconnect <tile> <port>
-  herd [--socket <path>] [--agent-pid <pid>] tile create <shell|agent|browser|work> [--title <text>] [--x <n>] [--y <n>] [--width <n>] [--height <n>] [--parent-session-id <id>] [--parent-tile-id <id>] [--browser-incognito <true|false>] [--browser-path <path>]
+  herd [--socket <path>] [--agent-pid <pid>] tile create <shell|agent|browser|work> [--title <text>] [--x <n>] [--y <n>] [--width <n>] [--height <n>] [--parent-session-id <id>] [--parent-tile-id <id>] [--browser-incognito <true|false>] [--browser-path <path>] [--worktree <true|false>] [--env <NAME,...>] [--launch-policy <json>]
   herd [--socket <path>] [--agent-pid <pid>] tile list [shell|agent|browser|work]
   herd [--socket <path>] [--agent-pid <pid>] tile destroy <tile_id>
   herd [--socket <path>] [--agent-pid <pid>] tile get <tile_id>
   herd [--socket <path>] [--agent-pid <pid>] tile call <tile_id> <action> [json_args]
-  herd [--socket <path>] [--agent-pid <pid>] tile subscribe <tile_id> <event> <agent_id>
-  herd [--socket <path>] [--agent-pid <pid>] tile unsubscribe <tile_id> <event> <agent_id>
+  herd [--socket <path>] [--agent-pid <pid>] tile subscribe <tile_id> <event> <agent_id> [--segment <name>]
+  herd [--socket <path>] [--agent-pid <pid>] tile unsubscribe <tile_id> <event> <agent_id> [--segment <name>]
   herd [--socket <path>] [--agent-pid <pid>] tile subscriptions [<tile_id>] [<agent_id>]
   herd [--socket <path>] [--agent-pid <pid>] tile move <tile_id> <x> <y>
   herd [--socket <path>] [--agent-pid <pid>] tile resize <tile_id> <width> <height>
   herd [--socket <path>] [--agent-pid <pid>] tile rename <tile_id> <title>
+  herd [--socket <path>] [--agent-pid <pid>] tile record <start|stop|list> [tile_id] [--work <work_id>]
+  herd [--socket <path>] [--agent-pid <pid>] tile resources [tile_id]
+  herd [--socket <path>] [--agent-pid <pid>] tile threshold <tile_id> [--cpu <percent>] [--rss-mb <n>]
+  herd [--socket <path>] [--agent-pid <pid>] agent worktree list
+  herd [--socket <path>] [--agent-pid <pid>] agent worktree status <agent_id>
+  herd [--socket <path>] [--agent-pid <pid>] agent worktree remove <agent_id> [--force] [--delete-branch]
+  herd [--socket <path>] [--agent-pid <pid>] env list
+  herd [--socket <path>] [--agent-pid <pid>] env set <name> <value> [--secret|--no-secret]
+  herd [--socket <path>] [--agent-pid <pid>] env unset <name>
   herd [--socket <path>] [--agent-pid <pid>] message direct <agent_id> <message>
   herd [--socket <path>] [--agent-pid <pid>] message public <message> [--mention <agent_id>...]
   herd [--socket <path>] [--agent-pid <pid>] message channel list
   herd [--socket <path>] [--agent-pid <pid>] message channel subscribe <agent_id> <channel>
   herd [--socket <path>] [--agent-pid <pid>] message channel unsubscribe <agent_id> <channel>
   herd [--socket <path>] [--agent-pid <pid>] message channel <channel> <message>
-  herd [--socket <path>] [--agent-pid <pid>] message network <message>
+  herd [--socket <path>] [--agent-pid <pid>] message network [--segment <name>] <message>
   herd [--socket <path>] [--agent-pid <pid>] message root <message>
   herd [--socket <path>] [--agent-pid <pid>] shell send <tile_id> <input>
-  herd [--socket <path>] [--agent-pid <pid>] shell exec <tile_id> <command>
-  herd [--socket <path>] [--agent-pid <pid>] shell read <tile_id>
+  herd [--socket <path>] [--agent-pid <pid>] shell exec <tile_id> [--wait] [--timeout <ms>] <command>
+  herd [--socket <path>] [--agent-pid <pid>] shell read <tile_id> [--since <offset>] [--max-bytes <n>]
+  herd [--socket <path>] [--agent-pid <pid>] shell capture <tile_id> [--start <line>] [--end <line>] [--ansi] [--join] [--search <text>]
+  herd [--socket <path>] [--agent-pid <pid>] shell wait <tile_id> <pattern> [--timeout <ms>] [--since <offset>] [--context <lines>]
+  herd [--socket <path>] [--agent-pid <pid>] shell screenshot <tile_id> [--format text|ansi|png]
+  herd [--socket <path>] [--agent-pid <pid>] shell ps <tile_id>
   herd [--socket <path>] [--agent-pid <pid>] shell role <tile_id> <regular|claude|output>
   herd [--socket <path>] [--agent-pid <pid>] browser navigate <tile_id> <url>
   herd [--socket <path>] [--agent-pid <pid>] browser load <tile_id> <path>
-  herd [--socket <path>] [--agent-pid <pid>] browser drive <tile_id> <click|select|type|dom_query|eval> [json_args]
+  herd [--socket <path>] [--agent-pid <pid>] browser drive <tile_id> <click|select|type|dom_query|eval|screenshot|screenshot_diff|press|hover|scroll|wait_for|fill_form|check> [json_args]
+  herd [--socket <path>] [--agent-pid <pid>] browser console <tile_id> [--since <cursor>] [--level debug|log|info|warn|error] [--limit <n>]
+  herd [--socket <path>] [--agent-pid <pid>] browser record start <tile_id> [--format gif|apng] [--interval <ms>] [--max-frames <n>] [--max-width <px>]
+  herd [--socket <path>] [--agent-pid <pid>] browser record stop <tile_id> [--work <work_id>]
   herd [--socket <path>] [--agent-pid <pid>] work stage start <work_id>
   herd [--socket <path>] [--agent-pid <pid>] work stage complete <work_id>
   herd [--socket <path>] [--agent-pid <pid>] raw <json>
@@ -190,6 +211,9 @@ fn tile_create_payload(args: &[String]) -> Result<Value, String> {
     let mut parent_tile_id = None;
     let mut browser_incognito = None;
     let mut browser_path = None;
+    let mut worktree = None;
+    let mut env = Vec::new();
+    let mut launch_policy = None;
     let mut index = 1usize;
     while index < args.len() {
         let flag = args[index].as_str();
@@ -206,13 +230,27 @@ fn tile_create_payload(args: &[String]) -> Result<Value, String> {
             "--parent-tile-id" => parent_tile_id = Some(value),
             "--browser-incognito" => browser_incognito = value.parse::<bool>().ok(),
             "--browser-path" => browser_path = Some(value),
+            "--worktree" => worktree = value.parse::<bool>().ok(),
+            "--env" => env.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string),
+            ),
+            "--launch-policy" => {
+                launch_policy = Some(parse_json_object_arg(Some(value), "invalid tile create --launch-policy JSON")?)
+            }
             _ => return Err(format!("unknown tile create flag: {flag}")),
         }
     }
+    if worktree == Some(true) && tile_type != "agent" {
+        return Err("tile create --worktree is only supported for agent tiles".to_string());
+    }
     if tile_type == "work" && title.as_deref().map(str::trim).filter(|value| !value.is_empty()).is_none() {
         return Err("tile create work requires --title <text>".to_string());
     }
-    Ok(json!({
+    let mut payload = json!({
         "command": "tile_create",
         "tile_type": tile_type,
         "title": title,
@@ -226,7 +264,17 @@ fn tile_create_payload(args: &[String]) -> Result<Value, String> {
         "browser_path": browser_path,
         "sender_agent_id": env_agent_id(),
         "sender_tile_id": env_tile_id(),
-    }))
+    });
+    if let Some(worktree) = worktree {
+        payload["worktree"] = json!(worktree);
+    }
+    if !env.is_empty() {
+        payload["env"] = json!(env);
+    }
+    if let Some(launch_policy) = launch_policy {
+        payload["launch_policy"] = launch_policy;
+    }
+    Ok(payload)
 }
 
 fn parse_optional_tile_type(args: &[String], command_name: &str) -> Result<Option<String>, String> {
@@ -254,6 +302,96 @@ fn tile_list_payload(command: &str, tile_type: Option<String>) -> Value {
     payload
 }
 
+fn network_list_payload(args: &[String]) -> Result<Value, String> {
+    let (args, segment) = split_segment_flag(args)?;
+    let mut tile_type_args = Vec::new();
+    let mut at_ms = None;
+    let mut index = 0usize;
+    while index < args.len() {
+        match args[index].as_str() {
+            "--at" => {
+                index += 1;
+                at_ms = Some(parse_timestamp_ms_arg(args.get(index), "--at requires a timestamp in milliseconds")?);
+            }
+            value => tile_type_args.push(value.to_string()),
+        }
+        index += 1;
+    }
+    let mut payload = tile_list_payload(
+        "network_list",
+        parse_optional_tile_type(&tile_type_args, "network list")?,
+    );
+    if let Some(at_ms) = at_ms {
+        payload["at_ms"] = json!(at_ms);
+    }
+    Ok(with_segment(payload, segment))
+}
+
+fn split_segment_flag(args: &[String]) -> Result<(Vec<String>, Option<String>), String> {
+    let mut remaining = Vec::new();
+    let mut segment = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--segment" {
+            segment = Some(iter.next().ok_or("--segment requires a segment name")?.clone());
+        } else {
+            remaining.push(arg.clone());
+        }
+    }
+    Ok((remaining, segment))
+}
+
+fn with_segment(mut payload: Value, segment: Option<String>) -> Value {
+    if let Some(segment) = segment {
+        payload["segment"] = json!(segment);
+    }
+    payload
+}
+
+fn network_history_payload(args: &[String]) -> Result<Value, String> {
+    let mut tile_id = None;
+    let mut since_ms = None;
+    let mut until_ms = None;
+    let mut limit = None;
+    let mut index = 0usize;
+    while index < args.len() {
+        let flag = args[index].as_str();
+        index += 1;
+        let value = args.get(index);
+        index += 1;
+        match flag {
+            "--tile" => tile_id = Some(value.ok_or("--tile requires a tile_id")?.clone()),
+            "--since" => since_ms = Some(parse_timestamp_ms_arg(value, "--since requires a timestamp in milliseconds")?),
+            "--until" => until_ms = Some(parse_timestamp_ms_arg(value, "--until requires a timestamp in milliseconds")?),
+            "--limit" => {
+                limit = Some(
+                    value
+                        .ok_or("--limit requires a number")?
+                        .parse::<usize>()
+                        .map_err(|_| "--limit requires a number".to_string())?,
+                )
+            }
+            _ => return Err(format!("unknown network history flag: {flag}")),
+        }
+    }
+    Ok(json!({
+        "command": "network_history",
+        "tile_id": tile_id,
+        "since_ms": since_ms,
+        "until_ms": until_ms,
+        "limit": limit,
+        "sender_agent_id": env_agent_id(),
+        "sender_tile_id": env_tile_id(),
+    }))
+}
+
+fn parse_timestamp_ms_arg(value: Option<&String>, error: &str) -> Result<i64, String> {
+    value
+        .ok_or_else(|| error.to_string())?
+        .parse::<i64>()
+        .map_err(|_| error.to_string())
+}
+
 fn parse_number_arg(value: Option<&String>, error: &str) -> Result<f64, String> {
     value
         .ok_or_else(|| error.to_string())?
@@ -316,10 +454,8 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
         "network" => {
             let sub = args.get(1).map(String::as_str).ok_or("missing network target")?;
             match sub {
-                "list" => Ok(tile_list_payload(
-                    "network_list",
-                    parse_optional_tile_type(&args[2..], "network list")?,
-                )),
+                "list" => network_list_payload(&args[2..]),
+                "history" => network_history_payload(&args[2..]),
                 "get" => Ok(json!({
                     "command": "network_get",
                     "tile_id": args.get(2).ok_or("network get requires a tile_id")?,
@@ -342,22 +478,81 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                         "sender_tile_id": env_tile_id(),
                     }))
                 }
-                "subscribe" => Ok(json!({
-                    "command": "network_subscribe",
-                    "tile_id": args.get(2).ok_or("network subscribe requires <tile_id> <event>")?,
-                    "event": args.get(3).ok_or("network subscribe requires <tile_id> <event>")?,
+                "subscribe" => {
+                    let (args, segment) = split_segment_flag(args)?;
+                    Ok(with_segment(
+                        json!({
+                            "command": "network_subscribe",
+                            "tile_id": args.get(2).ok_or("network subscribe requires <tile_id> <event>")?,
+                            "event": args.get(3).ok_or("network subscribe requires <tile_id> <event>")?,
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                        }),
+                        segment,
+                    ))
+                }
+                "unsubscribe" => {
+                    let (args, segment) = split_segment_flag(args)?;
+                    Ok(with_segment(
+                        json!({
+                            "command": "network_unsubscribe",
+                            "tile_id": args.get(2).ok_or("network unsubscribe requires <tile_id> <event>")?,
+                            "event": args.get(3).ok_or("network unsubscribe requires <tile_id> <event>")?,
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                        }),
+                        segment,
+                    ))
+                }
+                "subscriptions" => Ok(json!({
+                    "command": "network_subscription_list",
+                    "tile_id": args.get(2).cloned(),
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
                 })),
-                "unsubscribe" => Ok(json!({
-                    "command": "network_unsubscribe",
-                    "tile_id": args.get(2).ok_or("network unsubscribe requires <tile_id> <event>")?,
-                    "event": args.get(3).ok_or("network unsubscribe requires <tile_id> <event>")?,
+                "path" => {
+                    let (from_tile_id, to_tile_id) = match &args[2..] {
+                        [to_tile_id] => (None, to_tile_id.clone()),
+                        [from_tile_id, to_tile_id] => (Some(from_tile_id.clone()), to_tile_id.clone()),
+                        _ => return Err("network path requires [<from_tile>] <to_tile>".to_string()),
+                    };
+                    Ok(json!({
+                        "command": "network_path",
+                        "from_tile_id": from_tile_id,
+                        "to_tile_id": to_tile_id,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    }))
+                }
+                "ports" => {
+                    let tile_id = args.get(2).ok_or("network ports requires <tile_id> <json_ports>")?;
+                    let raw = args
+                        .get(3..)
+                        .filter(|values| !values.is_empty())
+                        .map(|values| values.join(" "))
+                        .ok_or("network ports requires <tile_id> <json_ports>")?;
+                    let ports = serde_json::from_str::<Value>(&raw)
+                        .ok()
+                        .filter(Value::is_array)
+                        .ok_or("network ports requires a JSON array of ports")?;
+                    Ok(json!({
+                        "command": "network_port_declare",
+                        "tile_id": tile_id,
+                        "ports": ports,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    }))
+                }
+                "segment" => Ok(json!({
+                    "command": "network_segment_set",
+                    "tile_id": args.get(2).ok_or("network segment requires <tile_id> <port> [<segment>...]")?,
+                    "port": args.get(3).ok_or("network segment requires <tile_id> <port> [<segment>...]")?,
+                    "segments": args.get(4..).unwrap_or_default(),
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
                 })),
-                "subscriptions" => Ok(json!({
-                    "command": "network_subscription_list",
+                "stats" => Ok(json!({
+                    "command": "network_stats",
                     "tile_id": args.get(2).cloned(),
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
@@ -408,6 +603,82 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
                 })),
+                "record" => {
+                    let command = match args.get(2).map(String::as_str) {
+                        Some("start") => "tile_record_start",
+                        Some("stop") => "tile_record_stop",
+                        Some("list") => "tile_record_list",
+                        Some(other) => return Err(format!("unknown tile record action: {other}")),
+                        None => return Err("tile record requires start, stop, or list".to_string()),
+                    };
+                    let mut payload = json!({
+                        "command": command,
+                        "tile_id": Value::Null,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 3usize;
+                    while index < args.len() {
+                        match args[index].as_str() {
+                            "--work" if command == "tile_record_stop" => {
+                                payload["work_id"] = json!(args.get(index + 1).ok_or("--work requires a work_id")?);
+                                index += 2;
+                            }
+                            tile_id if payload["tile_id"].is_null() && !tile_id.starts_with("--") => {
+                                payload["tile_id"] = json!(tile_id);
+                                index += 1;
+                            }
+                            other => return Err(format!("unknown tile record option: {other}")),
+                        }
+                    }
+                    Ok(payload)
+                }
+                "resources" => {
+                    let mut payload = json!({
+                        "command": "resource_summary",
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 2usize;
+                    while index < args.len() {
+                        match args[index].as_str() {
+                            tile_id if payload.get("tile_id").is_none() && !tile_id.starts_with("--") => {
+                                payload["tile_id"] = json!(tile_id);
+                                index += 1;
+                            }
+                            other => return Err(format!("unknown tile resources option: {other}")),
+                        }
+                    }
+                    Ok(payload)
+                }
+                "threshold" => {
+                    let mut payload = json!({
+                        "command": "tile_resource_threshold_set",
+                        "tile_id": args.get(2).ok_or("tile threshold requires a tile_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 3usize;
+                    while index < args.len() {
+                        let value = args.get(index + 1);
+                        match args[index].as_str() {
+                            "--cpu" => {
+                                payload["cpu_percent"] = json!(value
+                                    .and_then(|value| value.parse::<f64>().ok())
+                                    .ok_or("--cpu requires a percentage")?);
+                            }
+                            "--rss-mb" => {
+                                let megabytes = value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--rss-mb requires a number of megabytes")?;
+                                payload["rss_bytes"] = json!(megabytes * 1024 * 1024);
+                            }
+                            other => return Err(format!("unknown tile threshold option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
                 "call" => {
                     let tile_id = args.get(2).ok_or("tile call requires <tile_id> <action> [json_args]")?;
                     let action = args.get(3).ok_or("tile call requires <tile_id> <action> [json_args]")?;
@@ -424,22 +695,34 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                         "sender_tile_id": env_tile_id(),
                     }))
                 }
-                "subscribe" => Ok(json!({
-                    "command": "tile_subscribe",
-                    "tile_id": args.get(2).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
-                    "event": args.get(3).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
-                    "agent_id": args.get(4).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
-                    "sender_agent_id": env_agent_id(),
-                    "sender_tile_id": env_tile_id(),
-                })),
-                "unsubscribe" => Ok(json!({
-                    "command": "tile_unsubscribe",
-                    "tile_id": args.get(2).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
-                    "event": args.get(3).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
-                    "agent_id": args.get(4).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
-                    "sender_agent_id": env_agent_id(),
-                    "sender_tile_id": env_tile_id(),
-                })),
+                "subscribe" => {
+                    let (args, segment) = split_segment_flag(args)?;
+                    Ok(with_segment(
+                        json!({
+                            "command": "tile_subscribe",
+                            "tile_id": args.get(2).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
+                            "event": args.get(3).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
+                            "agent_id": args.get(4).ok_or("tile subscribe requires <tile_id> <event> <agent_id>")?,
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                        }),
+                        segment,
+                    ))
+                }
+                "unsubscribe" => {
+                    let (args, segment) = split_segment_flag(args)?;
+                    Ok(with_segment(
+                        json!({
+                            "command": "tile_unsubscribe",
+                            "tile_id": args.get(2).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
+                            "event": args.get(3).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
+                            "agent_id": args.get(4).ok_or("tile unsubscribe requires <tile_id> <event> <agent_id>")?,
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                        }),
+                        segment,
+                    ))
+                }
                 "subscriptions" => Ok(json!({
                     "command": "tile_subscription_list",
                     "tile_id": args.get(2).cloned(),
@@ -499,6 +782,83 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                         "sender_tile_id": env_tile_id(),
                     }))
                 }
+                "console" => {
+                    let mut payload = json!({
+                        "command": "browser_console_read",
+                        "tile_id": args.get(2).ok_or("browser console requires a tile_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 3usize;
+                    while index < args.len() {
+                        let flag = args[index].as_str();
+                        let value = args.get(index + 1);
+                        match flag {
+                            "--since" => {
+                                payload["since"] = json!(value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--since requires a console cursor")?);
+                            }
+                            "--level" => {
+                                payload["level"] = json!(value.ok_or("--level requires debug, log, info, warn, or error")?);
+                            }
+                            "--limit" => {
+                                payload["limit"] = json!(value
+                                    .and_then(|value| value.parse::<usize>().ok())
+                                    .ok_or("--limit requires a number of entries")?);
+                            }
+                            other => return Err(format!("unknown browser console option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
+                "record" => {
+                    let action = args.get(2).map(String::as_str);
+                    let command = match action {
+                        Some("start") => "browser_record_start",
+                        Some("stop") => "browser_record_stop",
+                        Some(other) => return Err(format!("unknown browser record action: {other}")),
+                        None => return Err("browser record requires start or stop".to_string()),
+                    };
+                    let mut payload = json!({
+                        "command": command,
+                        "tile_id": args.get(3).ok_or("browser record requires <start|stop> <tile_id>")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 4usize;
+                    while index < args.len() {
+                        let flag = args[index].as_str();
+                        let value = args.get(index + 1);
+                        match (action, flag) {
+                            (Some("start"), "--format") => {
+                                payload["format"] = json!(value.ok_or("--format requires gif or apng")?);
+                            }
+                            (Some("start"), "--interval") => {
+                                payload["interval_ms"] = json!(value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--interval requires a number of milliseconds")?);
+                            }
+                            (Some("start"), "--max-frames") => {
+                                payload["max_frames"] = json!(value
+                                    .and_then(|value| value.parse::<usize>().ok())
+                                    .ok_or("--max-frames requires a number of frames")?);
+                            }
+                            (Some("start"), "--max-width") => {
+                                payload["max_width"] = json!(value
+                                    .and_then(|value| value.parse::<u32>().ok())
+                                    .ok_or("--max-width requires a number of pixels")?);
+                            }
+                            (Some("stop"), "--work") => {
+                                payload["work_id"] = json!(value.ok_or("--work requires a work_id")?);
+                            }
+                            (_, other) => return Err(format!("unknown browser record option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
                 _ => Err(format!("unknown browser target: {sub}")),
             }
         }
@@ -543,13 +903,25 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                         "sender_agent_pid": ctx.agent_pid,
                     }))
                 }
-                "network" => Ok(json!({
-                    "command": "message_network",
-                    "message": args.get(2..).ok_or("message network requires a message")?.join(" "),
-                    "sender_agent_id": env_agent_id(),
-                    "sender_tile_id": env_tile_id(),
-                    "sender_agent_pid": ctx.agent_pid,
-                })),
+                "network" => {
+                    let (segment, message_args) = match args.get(2).map(String::as_str) {
+                        Some("--segment") => (
+                            Some(args.get(3).ok_or("--segment requires a segment name")?.clone()),
+                            args.get(4..),
+                        ),
+                        _ => (None, args.get(2..)),
+                    };
+                    Ok(with_segment(
+                        json!({
+                            "command": "message_network",
+                            "message": message_args.ok_or("message network requires a message")?.join(" "),
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                            "sender_agent_pid": ctx.agent_pid,
+                        }),
+                        segment,
+                    ))
+                }
                 "root" => Ok(json!({
                     "command": "message_root",
                     "message": args.get(2..).ok_or("message root requires a message")?.join(" "),
@@ -616,9 +988,78 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                         "agent_id": agent_id,
                     }))
                 }
+                "worktree" => match args.get(2).map(String::as_str) {
+                    Some("list") => Ok(json!({
+                        "command": "worktree_list",
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    })),
+                    Some("status") => Ok(json!({
+                        "command": "worktree_status",
+                        "agent_id": args.get(3).ok_or("agent worktree status requires an agent_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    })),
+                    Some("remove") => {
+                        let agent_id = args.get(3).ok_or("agent worktree remove requires an agent_id")?;
+                        let mut force = false;
+                        let mut delete_branch = false;
+                        for flag in &args[4..] {
+                            match flag.as_str() {
+                                "--force" => force = true,
+                                "--delete-branch" => delete_branch = true,
+                                other => return Err(format!("unknown agent worktree remove option: {other}")),
+                            }
+                        }
+                        Ok(json!({
+                            "command": "worktree_remove",
+                            "agent_id": agent_id,
+                            "force": force,
+                            "delete_branch": delete_branch,
+                            "sender_agent_id": env_agent_id(),
+                            "sender_tile_id": env_tile_id(),
+                        }))
+                    }
+                    Some(other) => Err(format!("unknown agent worktree action: {other}")),
+                    None => Err("agent worktree requires list, status, or remove".to_string()),
+                },
                 _ => Err(format!("unknown agent target: {sub}")),
             }
         }
+        "env" => match args.get(1).map(String::as_str) {
+            Some("list") => Ok(json!({
+                "command": "env_list",
+                "sender_agent_id": env_agent_id(),
+                "sender_tile_id": env_tile_id(),
+            })),
+            Some("set") => {
+                let name = args.get(2).ok_or("env set requires a name")?;
+                let value = args.get(3).ok_or("env set requires a value")?;
+                let mut payload = json!({
+                    "command": "env_set",
+                    "name": name,
+                    "value": value,
+                    "sender_agent_id": env_agent_id(),
+                    "sender_tile_id": env_tile_id(),
+                });
+                for flag in &args[4..] {
+                    match flag.as_str() {
+                        "--secret" => payload["secret"] = json!(true),
+                        "--no-secret" => payload["secret"] = json!(false),
+                        other => return Err(format!("unknown env set option: {other}")),
+                    }
+                }
+                Ok(payload)
+            }
+            Some("unset") => Ok(json!({
+                "command": "env_unset",
+                "name": args.get(2).ok_or("env unset requires a name")?,
+                "sender_agent_id": env_agent_id(),
+                "sender_tile_id": env_tile_id(),
+            })),
+            Some(other) => Err(format!("unknown env action: {other}")),
+            None => Err("env requires list, set, or unset".to_string()),
+        },
         "shell" => {
             let sub = args.get(1).map(String::as_str).ok_or("missing shell target")?;
             match sub {
@@ -629,19 +1070,166 @@ fn build_command_payload(ctx: &CliContext, args: &[String]) -> Result<Value, Str
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
                 })),
-                "exec" => Ok(json!({
-                    "command": "shell_exec",
-                    "tile_id": args.get(2).ok_or("shell exec requires <tile_id> <command>")?,
-                    "shell_command": args.get(3..).ok_or("shell exec requires a command")?.join(" "),
-                    "sender_agent_id": env_agent_id(),
-                    "sender_tile_id": env_tile_id(),
-                })),
-                "read" => Ok(json!({
-                    "command": "shell_output_read",
-                    "tile_id": args.get(2).ok_or("shell read requires a tile_id")?,
+                "exec" => {
+                    let tile_id = args.get(2).ok_or("shell exec requires <tile_id> <command>")?;
+                    let mut wait = false;
+                    let mut timeout_ms = None;
+                    let mut index = 3usize;
+                    loop {
+                        match args.get(index).map(String::as_str) {
+                            Some("--wait") => wait = true,
+                            Some("--timeout") => {
+                                index += 1;
+                                timeout_ms = Some(
+                                    args.get(index)
+                                        .and_then(|value| value.parse::<u64>().ok())
+                                        .ok_or("--timeout requires a number of milliseconds")?,
+                                );
+                            }
+                            _ => break,
+                        }
+                        index += 1;
+                    }
+                    let mut payload = json!({
+                        "command": "shell_exec",
+                        "tile_id": tile_id,
+                        "shell_command": args.get(index..).ok_or("shell exec requires a command")?.join(" "),
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    if wait || timeout_ms.is_some() {
+                        payload["wait"] = json!(true);
+                    }
+                    if let Some(timeout_ms) = timeout_ms {
+                        payload["timeout_ms"] = json!(timeout_ms);
+                    }
+                    Ok(payload)
+                }
+                "read" => {
+                    let mut payload = json!({
+                        "command": "shell_output_read",
+                        "tile_id": args.get(2).ok_or("shell read requires a tile_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 3usize;
+                    while index < args.len() {
+                        let flag = args[index].as_str();
+                        let value = args.get(index + 1);
+                        match flag {
+                            "--since" => {
+                                payload["since_offset"] = json!(value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--since requires a byte offset")?);
+                            }
+                            "--max-bytes" => {
+                                payload["max_bytes"] = json!(value
+                                    .and_then(|value| value.parse::<usize>().ok())
+                                    .ok_or("--max-bytes requires a byte count")?);
+                            }
+                            other => return Err(format!("unknown shell read option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
+                "capture" => {
+                    let mut payload = json!({
+                        "command": "shell_output_capture",
+                        "tile_id": args.get(2).ok_or("shell capture requires a tile_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 3usize;
+                    while index < args.len() {
+                        let flag = args[index].as_str();
+                        let value = args.get(index + 1);
+                        match flag {
+                            "--ansi" => {
+                                payload["preserve_ansi"] = json!(true);
+                                index += 1;
+                                continue;
+                            }
+                            "--join" => {
+                                payload["join_wrapped"] = json!(true);
+                                index += 1;
+                                continue;
+                            }
+                            "--start" => {
+                                payload["start_line"] = json!(value
+                                    .and_then(|value| value.parse::<i64>().ok())
+                                    .ok_or("--start requires a line number")?);
+                            }
+                            "--end" => {
+                                payload["end_line"] = json!(value
+                                    .and_then(|value| value.parse::<i64>().ok())
+                                    .ok_or("--end requires a line number")?);
+                            }
+                            "--search" => {
+                                payload["search"] = json!(value.ok_or("--search requires text")?);
+                            }
+                            other => return Err(format!("unknown shell capture option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
+                "wait" => {
+                    let mut payload = json!({
+                        "command": "shell_wait_for_output",
+                        "tile_id": args.get(2).ok_or("shell wait requires <tile_id> <pattern>")?,
+                        "pattern": args.get(3).ok_or("shell wait requires a pattern")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    let mut index = 4usize;
+                    while index < args.len() {
+                        let flag = args[index].as_str();
+                        let value = args.get(index + 1);
+                        match flag {
+                            "--timeout" => {
+                                payload["timeout_ms"] = json!(value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--timeout requires a number of milliseconds")?);
+                            }
+                            "--since" => {
+                                payload["since_offset"] = json!(value
+                                    .and_then(|value| value.parse::<u64>().ok())
+                                    .ok_or("--since requires a byte offset")?);
+                            }
+                            "--context" => {
+                                payload["context_lines"] = json!(value
+                                    .and_then(|value| value.parse::<usize>().ok())
+                                    .ok_or("--context requires a line count")?);
+                            }
+                            other => return Err(format!("unknown shell wait option: {other}")),
+                        }
+                        index += 2;
+                    }
+                    Ok(payload)
+                }
+                "ps" => Ok(json!({
+                    "command": "shell_process_info",
+                    "tile_id": args.get(2).ok_or("shell ps requires a tile_id")?,
                     "sender_agent_id": env_agent_id(),
                     "sender_tile_id": env_tile_id(),
                 })),
+                "screenshot" => {
+                    let mut payload = json!({
+                        "command": "shell_screenshot",
+                        "tile_id": args.get(2).ok_or("shell screenshot requires a tile_id")?,
+                        "sender_agent_id": env_agent_id(),
+                        "sender_tile_id": env_tile_id(),
+                    });
+                    match args.get(3).map(String::as_str) {
+                        None => {}
+                        Some("--format") => {
+                            payload["format"] = json!(args.get(4).ok_or("--format requires text, ansi, or png")?);
+                        }
+                        Some(other) => return Err(format!("unknown shell screenshot option: {other}")),
+                    }
+                    Ok(payload)
+                }
                 "role" => Ok(json!({
                     "command": "shell_role_set",
                     "tile_id": args.get(2).ok_or("shell role requires <tile_id> <role>")?,
@@ -857,6 +1445,67 @@ mod tests {
         });
     }
 
+    #[test]
+    fn serializes_network_history_and_list_at_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "network".into(),
+                    "list".into(),
+                    "agent".into(),
+                    "--at".into(),
+                    "1700000000000".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "network_list",
+                    "tile_type": "agent",
+                    "at_ms": 1700000000000i64,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "network".into(),
+                    "history".into(),
+                    "--tile".into(),
+                    "tile9".into(),
+                    "--since".into(),
+                    "100".into(),
+                    "--limit".into(),
+                    "20".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "network_history",
+                    "tile_id": "tile9",
+                    "since_ms": 100,
+                    "until_ms": null,
+                    "limit": 20,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let error = build_command_payload(
+                &ctx(),
+                &["network".into(), "list".into(), "--at".into(), "yesterday".into()],
+            )
+            .unwrap_err();
+            assert_eq!(error, "--at requires a timestamp in milliseconds");
+        });
+    }
+
     #[test]
     fn serializes_network_get_payload_with_sender_context() {
         with_agent_and_tile_env("agent-7", "tile7", || {
@@ -944,13 +1593,149 @@ mod tests {
     }
 
     #[test]
-    fn serializes_network_call_payload_with_sender_context() {
+    fn serializes_network_path_payloads_with_optional_origin() {
         with_agent_and_tile_env("agent-7", "tile7", || {
-            let payload = build_command_payload(
-                &ctx(),
-                &[
-                    "network".into(),
-                    "call".into(),
+            let payload = build_command_payload(&ctx(), &["network".into(), "path".into(), "tile9".into()]).unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "network_path",
+                    "from_tile_id": null,
+                    "to_tile_id": "tile9",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let payload = build_command_payload(
+                &ctx(),
+                &["network".into(), "path".into(), "tile3".into(), "tile9".into()],
+            )
+            .unwrap();
+            assert_eq!(payload["from_tile_id"], "tile3");
+            assert_eq!(payload["to_tile_id"], "tile9");
+
+            assert!(build_command_payload(&ctx(), &["network".into(), "path".into()]).is_err());
+        });
+    }
+
+    #[test]
+    fn serializes_network_segment_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            assert_eq!(
+                build_command_payload(
+                    &ctx(),
+                    &[
+                        "network".into(),
+                        "segment".into(),
+                        "tile7".into(),
+                        "events".into(),
+                        "team-a".into(),
+                        "ops".into(),
+                    ],
+                )
+                .unwrap(),
+                json!({
+                    "command": "network_segment_set",
+                    "tile_id": "tile7",
+                    "port": "events",
+                    "segments": ["team-a", "ops"],
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let list = build_command_payload(
+                &ctx(),
+                &["network".into(), "list".into(), "agent".into(), "--segment".into(), "team-a".into()],
+            )
+            .unwrap();
+            assert_eq!(list["segment"], json!("team-a"));
+            assert_eq!(list["tile_type"], json!("agent"));
+
+            let subscribe = build_command_payload(
+                &ctx(),
+                &[
+                    "network".into(),
+                    "subscribe".into(),
+                    "tile9".into(),
+                    "--segment".into(),
+                    "team-a".into(),
+                    "in:exec".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(subscribe["event"], json!("in:exec"));
+            assert_eq!(subscribe["segment"], json!("team-a"));
+        });
+    }
+
+    #[test]
+    fn serializes_network_port_declare_payload() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "network".into(),
+                    "ports".into(),
+                    "tile7".into(),
+                    r#"[{"name":"events","access_mode":"read"}]"#.into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "network_port_declare",
+                    "tile_id": "tile7",
+                    "ports": [{ "name": "events", "access_mode": "read" }],
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            assert_eq!(
+                build_command_payload(
+                    &ctx(),
+                    &["network".into(), "ports".into(), "tile7".into(), "{}".into()],
+                )
+                .unwrap_err(),
+                "network ports requires a JSON array of ports"
+            );
+        });
+    }
+
+    #[test]
+    fn serializes_network_stats_payload_with_sender_context() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(&ctx(), &["network".into(), "stats".into()]).unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "network_stats",
+                    "tile_id": null,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let payload = build_command_payload(
+                &ctx(),
+                &["network".into(), "stats".into(), "tile9".into()],
+            )
+            .unwrap();
+            assert_eq!(payload["tile_id"], "tile9");
+        });
+    }
+
+    #[test]
+    fn serializes_network_call_payload_with_sender_context() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "network".into(),
+                    "call".into(),
                     "tile9".into(),
                     "input_send".into(),
                     r#"{"input":"ls\n"}"#.into(),
@@ -1475,6 +2260,94 @@ mod tests {
                     "sender_tile_id": "tile7",
                 })
             );
+
+            let console = build_command_payload(
+                &ctx(),
+                &[
+                    "browser".into(),
+                    "console".into(),
+                    "tile9".into(),
+                    "--since".into(),
+                    "12".into(),
+                    "--level".into(),
+                    "warn".into(),
+                    "--limit".into(),
+                    "50".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                console,
+                json!({
+                    "command": "browser_console_read",
+                    "tile_id": "tile9",
+                    "since": 12,
+                    "level": "warn",
+                    "limit": 50,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let record_start = build_command_payload(
+                &ctx(),
+                &[
+                    "browser".into(),
+                    "record".into(),
+                    "start".into(),
+                    "tile9".into(),
+                    "--format".into(),
+                    "apng".into(),
+                    "--interval".into(),
+                    "250".into(),
+                    "--max-frames".into(),
+                    "40".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                record_start,
+                json!({
+                    "command": "browser_record_start",
+                    "tile_id": "tile9",
+                    "format": "apng",
+                    "interval_ms": 250,
+                    "max_frames": 40,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let record_stop = build_command_payload(
+                &ctx(),
+                &[
+                    "browser".into(),
+                    "record".into(),
+                    "stop".into(),
+                    "tile9".into(),
+                    "--work".into(),
+                    "work-s1-001".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                record_stop,
+                json!({
+                    "command": "browser_record_stop",
+                    "tile_id": "tile9",
+                    "work_id": "work-s1-001",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+            assert_eq!(
+                build_command_payload(
+                    &ctx(),
+                    &["browser".into(), "record".into(), "stop".into(), "tile9".into(), "--format".into(), "gif".into()],
+                )
+                .unwrap_err(),
+                "unknown browser record option: --format"
+            );
         });
     }
 
@@ -1536,6 +2409,351 @@ mod tests {
         });
     }
 
+    #[test]
+    fn serializes_shell_exec_wait_payload() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "shell".into(),
+                    "exec".into(),
+                    "tile9".into(),
+                    "--wait".into(),
+                    "--timeout".into(),
+                    "5000".into(),
+                    "make".into(),
+                    "test".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "shell_exec",
+                    "tile_id": "tile9",
+                    "shell_command": "make test",
+                    "wait": true,
+                    "timeout_ms": 5000,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let plain = build_command_payload(&ctx(), &["shell".into(), "exec".into(), "tile9".into(), "ls".into()])
+                .unwrap();
+            assert!(plain.get("wait").is_none());
+        });
+    }
+
+    #[test]
+    fn serializes_shell_capture_payload() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "shell".into(),
+                    "capture".into(),
+                    "tile9".into(),
+                    "--start".into(),
+                    "-200".into(),
+                    "--ansi".into(),
+                    "--join".into(),
+                    "--search".into(),
+                    "error".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "shell_output_capture",
+                    "tile_id": "tile9",
+                    "start_line": -200,
+                    "preserve_ansi": true,
+                    "join_wrapped": true,
+                    "search": "error",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let error = build_command_payload(
+                &ctx(),
+                &["shell".into(), "capture".into(), "tile9".into(), "--end".into(), "last".into()],
+            )
+            .unwrap_err();
+            assert_eq!(error, "--end requires a line number");
+        });
+    }
+
+    #[test]
+    fn serializes_shell_wait_payload() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "shell".into(),
+                    "wait".into(),
+                    "tile9".into(),
+                    "Tests passed".into(),
+                    "--timeout".into(),
+                    "60000".into(),
+                    "--context".into(),
+                    "4".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "shell_wait_for_output",
+                    "tile_id": "tile9",
+                    "pattern": "Tests passed",
+                    "timeout_ms": 60000,
+                    "context_lines": 4,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let error = build_command_payload(&ctx(), &["shell".into(), "wait".into(), "tile9".into()]).unwrap_err();
+            assert_eq!(error, "shell wait requires a pattern");
+        });
+    }
+
+    #[test]
+    fn serializes_shell_screenshot_payload() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(
+                &ctx(),
+                &["shell".into(), "screenshot".into(), "tile9".into(), "--format".into(), "png".into()],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "shell_screenshot",
+                    "tile_id": "tile9",
+                    "format": "png",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let error = build_command_payload(&ctx(), &["shell".into(), "screenshot".into(), "tile9".into(), "--ansi".into()])
+                .unwrap_err();
+            assert_eq!(error, "unknown shell screenshot option: --ansi");
+        });
+    }
+
+    #[test]
+    fn serializes_process_and_resource_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload = build_command_payload(&ctx(), &["shell".into(), "ps".into(), "tile9".into()]).unwrap();
+            assert_eq!(payload["command"], "shell_process_info");
+            assert_eq!(payload["tile_id"], "tile9");
+
+            let payload = build_command_payload(
+                &ctx(),
+                &["tile".into(), "resources".into(), "tile9".into()],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "resource_summary",
+                    "tile_id": "tile9",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let payload = build_command_payload(
+                &ctx(),
+                &[
+                    "tile".into(),
+                    "threshold".into(),
+                    "tile9".into(),
+                    "--cpu".into(),
+                    "150".into(),
+                    "--rss-mb".into(),
+                    "512".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "tile_resource_threshold_set",
+                    "tile_id": "tile9",
+                    "cpu_percent": 150.0,
+                    "rss_bytes": 536_870_912u64,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let error = build_command_payload(&ctx(), &["tile".into(), "threshold".into(), "tile9".into(), "--cpu".into()])
+                .unwrap_err();
+            assert_eq!(error, "--cpu requires a percentage");
+        });
+    }
+
+    #[test]
+    fn serializes_agent_worktree_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let create = build_command_payload(
+                &ctx(),
+                &["tile".into(), "create".into(), "agent".into(), "--worktree".into(), "true".into()],
+            )
+            .unwrap();
+            assert_eq!(create["command"], "tile_create");
+            assert_eq!(create["worktree"], true);
+
+            let error = build_command_payload(
+                &ctx(),
+                &["tile".into(), "create".into(), "shell".into(), "--worktree".into(), "true".into()],
+            )
+            .unwrap_err();
+            assert_eq!(error, "tile create --worktree is only supported for agent tiles");
+
+            let sandboxed = build_command_payload(
+                &ctx(),
+                &[
+                    "tile".into(),
+                    "create".into(),
+                    "agent".into(),
+                    "--launch-policy".into(),
+                    r#"{"sandbox":"bubblewrap","no_network":true}"#.into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(sandboxed["launch_policy"], json!({"sandbox": "bubblewrap", "no_network": true}));
+
+            let remove = build_command_payload(
+                &ctx(),
+                &[
+                    "agent".into(),
+                    "worktree".into(),
+                    "remove".into(),
+                    "agent-3".into(),
+                    "--delete-branch".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                remove,
+                json!({
+                    "command": "worktree_remove",
+                    "agent_id": "agent-3",
+                    "force": false,
+                    "delete_branch": true,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let list = build_command_payload(&ctx(), &["agent".into(), "worktree".into(), "list".into()]).unwrap();
+            assert_eq!(list["command"], "worktree_list");
+        });
+    }
+
+    #[test]
+    fn serializes_session_env_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let set = build_command_payload(
+                &ctx(),
+                &["env".into(), "set".into(), "API_TOKEN".into(), "s3cret".into(), "--secret".into()],
+            )
+            .unwrap();
+            assert_eq!(
+                set,
+                json!({
+                    "command": "env_set",
+                    "name": "API_TOKEN",
+                    "value": "s3cret",
+                    "secret": true,
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let rotate =
+                build_command_payload(&ctx(), &["env".into(), "set".into(), "API_TOKEN".into(), "n3w".into()]).unwrap();
+            assert!(rotate.get("secret").is_none());
+
+                        let unset = build_command_payload(&ctx(), &["env".into(), "unset".into(), "API_TOKEN".into()]).unwrap();
+            assert_eq!(unset["command"], "env_unset");
+            assert_eq!(unset["name"], "API_TOKEN");
+
+            let create = build_command_payload(
+                &ctx(),
+                &[
+                    "tile".into(),
+                    "create".into(),
+                    "shell".into(),
+                    "--env".into(),
+                    "API_TOKEN, RUST_LOG".into(),
+                    "--env".into(),
+                    "EDITOR".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(create["env"], json!(["API_TOKEN", "RUST_LOG", "EDITOR"]));
+
+            let error = build_command_payload(&ctx(), &["env".into()]).unwrap_err();
+            assert_eq!(error, "env requires list, set, or unset");
+        });
+    }
+
+    #[test]
+    fn serializes_tile_record_payloads() {
+        with_agent_and_tile_env("agent-7", "tile7", || {
+            let payload =
+                build_command_payload(&ctx(), &["tile".into(), "record".into(), "start".into(), "tile9".into()])
+                    .unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "command": "tile_record_start",
+                    "tile_id": "tile9",
+                    "sender_agent_id": "agent-7",
+                    "sender_tile_id": "tile7",
+                })
+            );
+
+            let session_wide = build_command_payload(&ctx(), &["tile".into(), "record".into(), "list".into()]).unwrap();
+            assert_eq!(session_wide["command"], "tile_record_list");
+            assert!(session_wide["tile_id"].is_null());
+
+            let attached = build_command_payload(
+                &ctx(),
+                &[
+                    "tile".into(),
+                    "record".into(),
+                    "stop".into(),
+                    "tile9".into(),
+                    "--work".into(),
+                    "work-s1-001".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(attached["command"], "tile_record_stop");
+            assert_eq!(attached["tile_id"], "tile9");
+            assert_eq!(attached["work_id"], "work-s1-001");
+
+            let error = build_command_payload(&ctx(), &["tile".into(), "record".into(), "pause".into()]).unwrap_err();
+            assert_eq!(error, "unknown tile record action: pause");
+
+            let error = build_command_payload(
+                &ctx(),
+                &["tile".into(), "record".into(), "start".into(), "--work".into(), "work-s1-001".into()],
+            )
+            .unwrap_err();
+            assert_eq!(error, "unknown tile record option: --work");
+        });
+    }
+
     #[test]
     fn serializes_message_network_and_root_payloads() {
         with_agent_and_tile_env("agent-7", "tile7", || {
@@ -1555,6 +2773,20 @@ mod tests {
                 })
             );
 
+            let segmented = build_command_payload(
+                &ctx(),
+                &[
+                    "message".into(),
+                    "network".into(),
+                    "--segment".into(),
+                    "team-a".into(),
+                    "hello".into(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(segmented["segment"], json!("team-a"));
+            assert_eq!(segmented["message"], json!("hello"));
+
             let root = build_command_payload(
                 &ctx(),
                 &["message".into(), "root".into(), "need".into(), "help".into()],
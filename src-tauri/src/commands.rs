@@ -1,14 +1,18 @@
 use crate::{
     agent::{now_ms, AgentChannelEvent, AgentChannelEventKind, AgentDebugState, AgentRole, AgentType},
     browser,
+    launch_policy::{self, LaunchPolicy, SandboxHostPaths, SessionLaunchPolicies},
     network::{self, NetworkConnection, NetworkTileDescriptor, NetworkTileKind},
+    network_history::NetworkChangeActor,
     persist::TileState,
     runtime,
+    session_env::{self, PaneEnvFile, SessionEnvEntry},
     state::AppState,
     tile_registry::{self, TileRecord, TileRecordKind},
     tmux,
     tmux_state,
     work,
+    worktree,
 };
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -253,7 +257,7 @@ fn migrate_network_connections_to_tile_ids(
             && left.to_tile_id == right.to_tile_id
             && left.to_port == right.to_port
     });
-    network::replace_connections_at(db_path, &next)
+    network::replace_connections_at(db_path, &next, &NetworkChangeActor::system())
 }
 
 fn migrate_agents_to_tile_ids(
@@ -620,10 +624,30 @@ fn default_shell_program() -> String {
     std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
 }
 
-fn build_shell_launch_command(cwd: &str) -> String {
+/// Host paths a sandboxed tile sees differently: the Herd socket directory and
+/// the tmux socket directory are masked so the tile cannot reach other sockets
+/// or drive tmux, and only the Herd socket itself is bound back so agents and
+/// the `herd` CLI can still reach the app.
+fn sandbox_host_paths() -> SandboxHostPaths {
+    let socket_path = Path::new(runtime::socket_path());
+    let mut masked = socket_path
+        .parent()
+        .filter(|parent| *parent != Path::new("/") && !parent.as_os_str().is_empty())
+        .map(|parent| vec![parent.to_string_lossy().to_string()])
+        .unwrap_or_default();
+    masked.push(runtime::tmux_socket_dir().to_string_lossy().to_string());
+    SandboxHostPaths {
+        masked,
+        writable: vec![socket_path.to_string_lossy().to_string()],
+    }
+}
+
+fn build_shell_launch_command(cwd: &str, policy: &LaunchPolicy) -> String {
     format!(
-        "cd {} || exit 1\nexec {}",
+        "cd {} || exit 1\n{}exec {}{}",
         shell_single_quote(cwd),
+        policy.ulimit_prelude(),
+        policy.exec_prefix(cwd, &sandbox_host_paths()),
         shell_single_quote(&default_shell_program())
     )
 }
@@ -641,39 +665,49 @@ fn agent_role_prompt_path(role: AgentRole) -> String {
         .to_string()
 }
 
-fn build_agent_launch_command(cwd: &str, pane_id: &str, server_name: &str, role: AgentRole) -> String {
+fn build_agent_launch_command(
+    cwd: &str,
+    pane_id: &str,
+    server_name: &str,
+    role: AgentRole,
+    policy: &LaunchPolicy,
+) -> String {
     let mcp_config = runtime::project_mcp_config_path()
         .to_string_lossy()
         .to_string();
     let prompt_file = agent_role_prompt_path(role);
     let tmux_server = runtime::tmux_server_name().to_string();
     format!(
-        "(sleep 1; tmux -f /dev/null -L {} send-keys -t {} Enter >/dev/null 2>&1) &\ncd {} || exit 1\nHERD_ROLE_CLAUDE_MD={}\n[ -f \"$HERD_ROLE_CLAUDE_MD\" ] || {{ echo \"Missing role prompt file: $HERD_ROLE_CLAUDE_MD\"; exit 1; }}\nexec claude --append-system-prompt \"$(cat \"$HERD_ROLE_CLAUDE_MD\")\" --mcp-config {} --teammate-mode tmux --dangerously-load-development-channels server:{}",
+        "(sleep 1; tmux -f /dev/null -L {} send-keys -t {} Enter >/dev/null 2>&1) &\ncd {} || exit 1\nHERD_ROLE_CLAUDE_MD={}\n[ -f \"$HERD_ROLE_CLAUDE_MD\" ] || {{ echo \"Missing role prompt file: $HERD_ROLE_CLAUDE_MD\"; exit 1; }}\n{}exec {}claude --append-system-prompt \"$(cat \"$HERD_ROLE_CLAUDE_MD\")\" --mcp-config {} --teammate-mode tmux --dangerously-load-development-channels server:{}",
         shell_single_quote(&tmux_server),
         shell_single_quote(pane_id),
         shell_single_quote(cwd),
         shell_single_quote(&prompt_file),
+        policy.ulimit_prelude(),
+        policy.exec_prefix(cwd, &sandbox_host_paths()),
         shell_single_quote(&mcp_config),
         server_name,
     )
 }
 
-fn build_claude_launch_command(cwd: &str, pane_id: &str) -> String {
-    build_agent_launch_command(cwd, pane_id, "herd", AgentRole::Worker)
+fn build_claude_launch_command(cwd: &str, pane_id: &str, policy: &LaunchPolicy) -> String {
+    build_agent_launch_command(cwd, pane_id, "herd", AgentRole::Worker, policy)
 }
 
 fn build_root_agent_launch_command(cwd: &str, pane_id: &str) -> String {
-    build_agent_launch_command(cwd, pane_id, "herd", AgentRole::Root)
+    build_agent_launch_command(cwd, pane_id, "herd", AgentRole::Root, &LaunchPolicy::default())
 }
 
-fn build_fixture_agent_launch_command(cwd: &str) -> String {
+fn build_fixture_agent_launch_command(cwd: &str, policy: &LaunchPolicy) -> String {
     format!(
-        "cd {} || exit 1\nprintf '__HERD_FIXTURE_AGENT__\\n'\nexec tail -f /dev/null",
+        "cd {} || exit 1\n{}printf '__HERD_FIXTURE_AGENT__\\n'\nexec {}tail -f /dev/null",
         shell_single_quote(cwd),
+        policy.ulimit_prelude(),
+        policy.exec_prefix(cwd, &sandbox_host_paths()),
     )
 }
 
-fn default_runtime_agent_type() -> AgentType {
+pub fn default_runtime_agent_type() -> AgentType {
     if runtime::fixture_agents_enabled() {
         AgentType::Fixture
     } else {
@@ -681,16 +715,50 @@ fn default_runtime_agent_type() -> AgentType {
     }
 }
 
-fn build_role_agent_launch_command(cwd: &str, pane_id: &str, role: AgentRole, agent_type: AgentType) -> String {
+/// Worker agents run under `policy`; the Root agent is always launched
+/// unrestricted because it drives the rest of the session.
+fn build_role_agent_launch_command(
+    cwd: &str,
+    pane_id: &str,
+    role: AgentRole,
+    agent_type: AgentType,
+    policy: &LaunchPolicy,
+) -> String {
+    let policy = match role {
+        AgentRole::Root => LaunchPolicy::default(),
+        AgentRole::Worker => policy.clone(),
+    };
     match agent_type {
         AgentType::Claude => match role {
             AgentRole::Root => build_root_agent_launch_command(cwd, pane_id),
-            AgentRole::Worker => build_claude_launch_command(cwd, pane_id),
+            AgentRole::Worker => build_claude_launch_command(cwd, pane_id, &policy),
         },
-        AgentType::Fixture => build_fixture_agent_launch_command(cwd),
+        AgentType::Fixture => build_fixture_agent_launch_command(cwd, &policy),
     }
 }
 
+/// The policy a tile's pane is (re)spawned under: the override stored for the
+/// tile by `tile_create`, else the session policy for agents or shells.
+fn tile_launch_policy(session_id: &str, tile_id: &str, kind: TileRecordKind) -> Result<LaunchPolicy, String> {
+    let db_path = Path::new(runtime::database_path());
+    if let Some(policy) = launch_policy::tile_policy_at(db_path, tile_id)? {
+        return Ok(policy);
+    }
+    let policies = launch_policy::session_policies_at(db_path, session_id)?;
+    Ok(match kind {
+        TileRecordKind::Agent => policies.agent,
+        _ => policies.shell,
+    })
+}
+
+fn store_tile_launch_policy(session_id: &str, tile_id: &str, policy: Option<&LaunchPolicy>) -> Result<(), String> {
+    let Some(policy) = policy else {
+        return Ok(());
+    };
+    policy.validate()?;
+    launch_policy::set_tile_policy_at(Path::new(runtime::database_path()), session_id, tile_id, policy)
+}
+
 fn root_agent_id(session_id: &str) -> String {
     format!("root:{session_id}")
 }
@@ -711,6 +779,9 @@ pub fn respawn_root_agent_in_pane(
         "Root",
         AgentRole::Root,
         agent_type,
+        false,
+        &[],
+        None,
     )
 }
 
@@ -723,6 +794,9 @@ fn launch_agent_in_pane(
     title: &str,
     role: AgentRole,
     agent_type: AgentType,
+    use_worktree: bool,
+    env: &[(String, String)],
+    launch_policy: Option<&LaunchPolicy>,
 ) -> Result<serde_json::Value, String> {
     let state = app.state::<AppState>();
     let tile = ensure_tmux_tile_record_for_backing(
@@ -734,44 +808,80 @@ fn launch_agent_in_pane(
         false,
         None,
     )?;
-    let cwd = tmux_state::ensure_session_root_cwd(&session_id)?;
-    let shell_command = build_role_agent_launch_command(&cwd, &pane_id, role, agent_type);
-
-    tmux::output(&[
-        "respawn-pane",
-        "-k",
-        "-t",
-        &pane_id,
-        "-e",
-        &format!("HERD_SOCK={}", runtime::socket_path()),
-        "-e",
-        &format!("HERD_AGENT_ID={agent_id}"),
-        "-e",
-        &format!("HERD_AGENT_ROLE={}", match role {
-            AgentRole::Root => "root",
-            AgentRole::Worker => "worker",
-        }),
-        "-e",
-        &format!("HERD_TILE_ID={}", tile.tile_id),
-        "-e",
-        &format!("HERD_PANE_ID={pane_id}"),
-        "-e",
-        &format!("HERD_SESSION_ID={session_id}"),
-        "/bin/bash",
-        "-lc",
-        &shell_command,
-    ])
-    .map_err(|error| format!("tmux respawn-pane failed: {error}"))
-    .and_then(|output| {
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(format!(
-                "tmux respawn-pane failed: {}",
-                String::from_utf8_lossy(&output.stderr).trim()
-            ))
+    let session_cwd = tmux_state::ensure_session_root_cwd(&session_id)?;
+    let agent_worktree = if use_worktree {
+        Some(worktree::create_agent_worktree(Path::new(&session_cwd), &agent_id, &session_id, &tile.tile_id)?)
+    } else {
+        None
+    };
+    let cwd = agent_worktree
+        .as_ref()
+        .map(|created| created.cwd.clone())
+        .unwrap_or(session_cwd);
+    let respawn = || -> Result<(), String> {
+        if let Some(created) = agent_worktree.as_ref() {
+            worktree::upsert_worktree_at(Path::new(runtime::database_path()), created)?;
         }
-    })?;
+        store_tile_launch_policy(&session_id, &tile.tile_id, launch_policy)?;
+        let policy = tile_launch_policy(&session_id, &tile.tile_id, TileRecordKind::Agent)?;
+        let env_file = PaneEnvFile::write(env)?;
+        let shell_command = format!(
+            "{}{}",
+            env_file.as_ref().map(PaneEnvFile::source_line).unwrap_or_default(),
+            build_role_agent_launch_command(&cwd, &pane_id, role, agent_type, &policy)
+        );
+        let mut respawn_args = vec![
+            "respawn-pane".to_string(),
+            "-k".to_string(),
+            "-t".to_string(),
+            pane_id.clone(),
+            "-e".to_string(),
+            format!("HERD_SOCK={}", runtime::socket_path()),
+            "-e".to_string(),
+            format!("HERD_AGENT_ID={agent_id}"),
+            "-e".to_string(),
+            format!("HERD_AGENT_ROLE={}", match role {
+                AgentRole::Root => "root",
+                AgentRole::Worker => "worker",
+            }),
+            "-e".to_string(),
+            format!("HERD_TILE_ID={}", tile.tile_id),
+            "-e".to_string(),
+            format!("HERD_PANE_ID={pane_id}"),
+            "-e".to_string(),
+            format!("HERD_SESSION_ID={session_id}"),
+        ];
+        respawn_args.extend(["/bin/bash".to_string(), "-lc".to_string(), shell_command]);
+        let result = tmux::output(&respawn_args.iter().map(String::as_str).collect::<Vec<_>>())
+            .map_err(|error| format!("tmux respawn-pane failed: {error}"))
+            .and_then(|output| {
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "tmux respawn-pane failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ))
+                }
+            });
+        if result.is_err() {
+            if let Some(env_file) = env_file {
+                env_file.discard();
+            }
+        }
+        result
+    };
+    if let Err(error) = respawn() {
+        // The agent never started, so its fresh worktree and branch hold nothing
+        // worth keeping; leaving them would strand a checkout no tile owns.
+        if let Some(created) = agent_worktree.as_ref() {
+            if let Err(cleanup_error) = worktree::discard_agent_worktree_at(Path::new(runtime::database_path()), created)
+            {
+                return Err(format!("{error}; removing worktree {} also failed: {cleanup_error}", created.path));
+            }
+        }
+        return Err(error);
+    }
 
     let _ = set_pane_title(app.clone(), pane_id.clone(), title.to_string());
     let _ = app.emit(
@@ -816,6 +926,7 @@ fn launch_agent_in_pane(
         "window_id": window_id,
         "session_id": session_id,
         "cwd": cwd,
+        "worktree": agent_worktree,
     }))
 }
 
@@ -964,6 +1075,8 @@ pub fn ensure_root_agent_for_session(
         "Root",
         AgentRole::Root,
         default_runtime_agent_type(),
+        false,
+        &[],
     )?;
     let _ = prune_duplicate_root_panes(&app, &session_id, &keep_pane_id);
     Ok(launched)
@@ -1327,6 +1440,7 @@ pub fn delete_work_item(
         Path::new(runtime::database_path()),
         &item.session_id,
         &item.tile_id,
+        &NetworkChangeActor::user(),
     )
     .unwrap_or_default();
     work::delete_work_item_at(
@@ -1384,9 +1498,18 @@ pub fn connect_network_tiles(
     let session_id = active_session_id(&snapshot)?;
     let from = resolve_ui_network_tile_descriptor(state.inner(), &snapshot, &session_id, &from_tile_id)?;
     let to = resolve_ui_network_tile_descriptor(state.inner(), &snapshot, &session_id, &to_tile_id)?;
-    let from_port = network::parse_port(&from_port).map_err(|_| "invalid from_port".to_string())?;
-    let to_port = network::parse_port(&to_port).map_err(|_| "invalid to_port".to_string())?;
-    let connection = network::connect_at(Path::new(runtime::database_path()), &from, from_port, &to, to_port)?;
+    let from_port = network::resolve_port_at(Path::new(runtime::database_path()), &from.tile_id, &from_port)
+        .map_err(|error| format!("invalid from_port: {error}"))?;
+    let to_port = network::resolve_port_at(Path::new(runtime::database_path()), &to.tile_id, &to_port)
+        .map_err(|error| format!("invalid to_port: {error}"))?;
+    let connection = network::connect_at(
+        Path::new(runtime::database_path()),
+        &from,
+        from_port,
+        &to,
+        to_port,
+        &NetworkChangeActor::user(),
+    )?;
     notify_agents_about_connection_change(state.inner(), &connection, true);
     emit_agent_debug_state(&app, &state);
     for work_id in touched_work_ids_from_connections(std::slice::from_ref(&connection)) {
@@ -1407,8 +1530,15 @@ pub fn disconnect_network_port(
     let snapshot = tmux_state::snapshot(&state)?;
     let session_id = active_session_id(&snapshot)?;
     let descriptor = resolve_ui_network_tile_descriptor(state.inner(), &snapshot, &session_id, &tile_id)?;
-    let port = network::parse_port(&port).map_err(|_| "invalid port".to_string())?;
-    let removed = network::disconnect_at(Path::new(runtime::database_path()), &descriptor.session_id, &descriptor.tile_id, port)?;
+    let port = network::resolve_port_at(Path::new(runtime::database_path()), &descriptor.tile_id, &port)
+        .map_err(|error| format!("invalid port: {error}"))?;
+    let removed = network::disconnect_at(
+        Path::new(runtime::database_path()),
+        &descriptor.session_id,
+        &descriptor.tile_id,
+        port,
+        &NetworkChangeActor::user(),
+    )?;
     if let Some(connection) = removed.as_ref() {
         notify_agents_about_connection_change(state.inner(), connection, false);
     }
@@ -1435,7 +1565,8 @@ pub fn set_network_port_settings(
     let snapshot = tmux_state::snapshot(&state)?;
     let session_id = active_session_id(&snapshot)?;
     let descriptor = resolve_ui_network_tile_descriptor(state.inner(), &snapshot, &session_id, &tile_id)?;
-    let port = network::parse_port(&port).map_err(|_| "invalid port".to_string())?;
+    let port = network::resolve_port_at(Path::new(runtime::database_path()), &descriptor.tile_id, &port)
+        .map_err(|error| format!("invalid port: {error}"))?;
     let access_mode = access_mode
         .as_deref()
         .map(network::parse_port_mode)
@@ -1455,6 +1586,7 @@ pub fn set_network_port_settings(
         port,
         access_mode,
         networking_mode,
+        &NetworkChangeActor::user(),
     )?;
 
     let connections = network::list_connections_at(Path::new(runtime::database_path()), &descriptor.session_id)?;
@@ -1475,6 +1607,7 @@ pub fn set_network_port_settings(
             &descriptor.session_id,
             &descriptor.tile_id,
             port,
+            &NetworkChangeActor::user(),
         )? {
             notify_agents_about_connection_change(state.inner(), &connection, false);
             for work_id in touched_work_ids_from_connections(std::slice::from_ref(&connection)) {
@@ -1561,6 +1694,12 @@ pub fn kill_session(app: tauri::AppHandle, session_id: String) -> Result<(), Str
     }
 
     tmux_state::kill_session(&session_id)?;
+    if let Err(error) = session_env::clear_session_env_at(Path::new(runtime::database_path()), &session_id) {
+        log::warn!("Failed to clear session env for {session_id}: {error}");
+    }
+    if let Err(error) = launch_policy::clear_session_policies_at(Path::new(runtime::database_path()), &session_id) {
+        log::warn!("Failed to clear launch policies for {session_id}: {error}");
+    }
     tmux_state::emit_snapshot(&app)?;
     Ok(())
 }
@@ -1642,6 +1781,48 @@ pub fn set_session_browser_backend(
     Ok(backend.as_str().to_string())
 }
 
+#[tauri::command]
+pub fn set_session_launch_policies(
+    app: tauri::AppHandle,
+    session_id: String,
+    policies: SessionLaunchPolicies,
+) -> Result<SessionLaunchPolicies, String> {
+    policies.validate()?;
+    launch_policy::set_session_policies_at(Path::new(runtime::database_path()), &session_id, &policies)?;
+    tmux_state::emit_snapshot(&app)?;
+    Ok(policies)
+}
+
+#[tauri::command]
+pub fn list_session_env(session_id: String) -> Result<Vec<SessionEnvEntry>, String> {
+    Ok(session_env::list_env_at(Path::new(runtime::database_path()), &session_id)?
+        .iter()
+        .map(SessionEnvEntry::masked)
+        .collect())
+}
+
+#[tauri::command]
+pub fn set_session_env_entry(
+    session_id: String,
+    name: String,
+    value: String,
+    secret: Option<bool>,
+) -> Result<SessionEnvEntry, String> {
+    let entry = session_env::set_env_at(
+        Path::new(runtime::database_path()),
+        &session_id,
+        name.trim(),
+        &value,
+        secret,
+    )?;
+    Ok(entry.masked())
+}
+
+#[tauri::command]
+pub fn unset_session_env_entry(session_id: String, name: String) -> Result<bool, String> {
+    session_env::unset_env_at(Path::new(runtime::database_path()), &session_id, name.trim())
+}
+
 fn new_backing_window_internal(
     app: tauri::AppHandle,
     target_session_id: Option<String>,
@@ -1662,7 +1843,9 @@ fn new_backing_window_internal(
         .ok_or("tmux did not report the new window for the created pane")?;
     let cwd = tmux_state::ensure_session_root_cwd(&session_id)?;
 
-    tmux_state::respawn_pane_shell_command(&pane_id, &build_shell_launch_command(&cwd), None)?;
+    let shell_policy = launch_policy::session_policies_at(Path::new(runtime::database_path()), &session_id)?.shell;
+    let shell_command = build_shell_launch_command(&cwd, &shell_policy);
+    tmux_state::respawn_pane_shell_command(&pane_id, &shell_command, None)?;
 
     if select_new_window {
         tmux_state::select_window(&window_id)?;
@@ -1675,10 +1858,13 @@ fn new_shell_window_internal(
     app: tauri::AppHandle,
     target_session_id: Option<String>,
     select_new_window: bool,
+    env_names: &[String],
+    launch_policy: Option<&LaunchPolicy>,
 ) -> Result<ShellWindowSpawn, String> {
     let state = app.state::<AppState>();
     let before = tmux_state::snapshot(state.inner())?;
     let session_id = target_session_id.unwrap_or(active_session_id(&before)?);
+    let env = session_env::resolve_env_at(Path::new(runtime::database_path()), &session_id, env_names)?;
     let target_pane_id = active_pane_id_for_session(&before, &session_id)?;
     let pane_id = tmux_state::create_window(Some(&target_pane_id), None)?;
 
@@ -1700,7 +1886,11 @@ fn new_shell_window_internal(
         None,
     )?;
 
-    tmux_state::respawn_pane_shell_command(&pane_id, &build_shell_launch_command(&cwd), Some(&tile.tile_id))?;
+    store_tile_launch_policy(&session_id, &tile.tile_id, launch_policy)?;
+    session_env::set_tile_env_names_at(Path::new(runtime::database_path()), &session_id, &tile.tile_id, env_names)?;
+    let shell_policy = tile_launch_policy(&session_id, &tile.tile_id, TileRecordKind::Shell)?;
+    let shell_command = build_shell_launch_command(&cwd, &shell_policy);
+    tmux_state::respawn_pane_shell_command_with_env(&pane_id, &shell_command, Some(&tile.tile_id), &env)?;
 
     if select_new_window {
         tmux_state::select_window(&window_id)?;
@@ -1716,7 +1906,7 @@ fn new_shell_window_internal(
 
 #[tauri::command]
 pub fn new_window(app: tauri::AppHandle, target_session_id: Option<String>) -> Result<String, String> {
-    Ok(new_shell_window_internal(app, target_session_id, true)?.window_id)
+    Ok(new_shell_window_internal(app, target_session_id, true, &[], None)?.window_id)
 }
 
 pub fn new_window_detached(app: tauri::AppHandle, target_session_id: Option<String>) -> Result<String, String> {
@@ -1726,8 +1916,10 @@ pub fn new_window_detached(app: tauri::AppHandle, target_session_id: Option<Stri
 pub fn new_shell_window_detached(
     app: tauri::AppHandle,
     target_session_id: Option<String>,
+    env_names: &[String],
+    launch_policy: Option<&LaunchPolicy>,
 ) -> Result<ShellWindowSpawn, String> {
-    new_shell_window_internal(app, target_session_id, false)
+    new_shell_window_internal(app, target_session_id, false, env_names, launch_policy)
 }
 
 pub fn new_work_window_detached(
@@ -1758,7 +1950,9 @@ pub fn new_work_window_detached(
         preferred_tile_id,
     )?;
 
-    tmux_state::respawn_pane_shell_command(&pane_id, &build_shell_launch_command(&cwd), Some(&tile.tile_id))?;
+    let shell_policy = tile_launch_policy(&session_id, &tile.tile_id, TileRecordKind::Work)?;
+    let shell_command = build_shell_launch_command(&cwd, &shell_policy);
+    tmux_state::respawn_pane_shell_command(&pane_id, &shell_command, Some(&tile.tile_id))?;
     let _ = tmux_state::rename_window(&window_id, "Work");
     let _ = set_pane_title(app.clone(), pane_id.clone(), title.to_string());
     tmux_state::emit_snapshot(&app)?;
@@ -1770,14 +1964,20 @@ pub fn new_work_window_detached(
     })
 }
 
+/// Spawns a worker agent tile. With `use_worktree` the agent starts in a fresh
+/// git worktree on its own branch instead of the shared session spawn directory.
 pub fn spawn_agent_window_with_type(
     app: tauri::AppHandle,
     target_session_id: Option<String>,
     agent_type: AgentType,
+    use_worktree: bool,
+    env_names: &[String],
+    launch_policy: Option<&LaunchPolicy>,
 ) -> Result<serde_json::Value, String> {
     let state = app.state::<AppState>();
     let before = tmux_state::snapshot(state.inner())?;
     let session_id = target_session_id.unwrap_or(active_session_id(&before)?);
+    let env = session_env::resolve_env_at(Path::new(runtime::database_path()), &session_id, env_names)?;
     let window_id = new_window_detached(app.clone(), Some(session_id.clone()))?;
     let after = tmux_state::snapshot(state.inner())?;
     let pane_id = after
@@ -1797,6 +1997,9 @@ pub fn spawn_agent_window_with_type(
         "Agent",
         AgentRole::Worker,
         agent_type,
+        use_worktree,
+        &env,
+        launch_policy,
     )
 }
 
@@ -1804,8 +2007,17 @@ pub fn spawn_agent_window_with_type(
 pub fn spawn_agent_window(
     app: tauri::AppHandle,
     target_session_id: Option<String>,
+    worktree: Option<bool>,
+    env: Option<Vec<String>>,
 ) -> Result<serde_json::Value, String> {
-    spawn_agent_window_with_type(app, target_session_id, default_runtime_agent_type())
+    spawn_agent_window_with_type(
+        app,
+        target_session_id,
+        default_runtime_agent_type(),
+        worktree.unwrap_or(false),
+        &env.unwrap_or_default(),
+        None,
+    )
 }
 
 fn spawn_browser_window_internal(
@@ -1826,8 +2038,12 @@ fn spawn_browser_window_internal(
         .and_then(|window| window.pane_ids.first().cloned())
         .ok_or("tmux did not report a pane for the new Browser window".to_string())?;
 
+    // The page renders in an app webview, which launch policies cannot reach,
+    // so the backing pane is launched unrestricted rather than under the shell
+    // policy it would not actually enforce.
     let cwd = tmux_state::ensure_session_root_cwd(&session_id)?;
-    tmux_state::respawn_pane_shell_command(&pane_id, &build_shell_launch_command(&cwd), None)?;
+    let shell_command = build_shell_launch_command(&cwd, &LaunchPolicy::default());
+    tmux_state::respawn_pane_shell_command(&pane_id, &shell_command, None)?;
     let _ = tmux_state::rename_window(&window_id, "Browser");
     let _ = set_pane_title(app.clone(), pane_id.clone(), "Browser".to_string());
     let tile = ensure_tmux_tile_record_for_backing(
@@ -1908,7 +2124,7 @@ pub fn split_pane(app: tauri::AppHandle, target_pane_id: Option<String>) -> Resu
     } else {
         active_session_id(&snapshot)?
     };
-    Ok(new_shell_window_internal(app, Some(session_id), true)?.window_id)
+    Ok(new_shell_window_internal(app, Some(session_id), true, &[])?.window_id)
 }
 
 #[tauri::command]
@@ -1971,7 +2187,10 @@ pub fn kill_window(app: tauri::AppHandle, window_id: String) -> Result<(), Strin
                 None,
             )?;
             let cwd = tmux_state::ensure_session_root_cwd(&window.session_id)?;
-            tmux_state::respawn_pane_shell_command(&pane_id, &build_shell_launch_command(&cwd), Some(&tile.tile_id))?;
+            let shell_policy = tile_launch_policy(&window.session_id, &tile.tile_id, TileRecordKind::Shell)?;
+            let shell_command = build_shell_launch_command(&cwd, &shell_policy);
+            let env = session_env::tile_env_at(Path::new(runtime::database_path()), &window.session_id, &tile.tile_id)?;
+            tmux_state::respawn_pane_shell_command_with_env(&pane_id, &shell_command, Some(&tile.tile_id), &env)?;
         } else {
             tmux_state::respawn_window(&window_id)?;
         }
@@ -2038,7 +2257,7 @@ pub fn set_pane_title(app: tauri::AppHandle, pane_id: String, title: String) ->
 // Compatibility alias: create a new single-pane tmux window in the active session.
 #[tauri::command]
 pub fn create_pty(app: tauri::AppHandle, _cols: u16, _rows: u16) -> Result<String, String> {
-    Ok(new_shell_window_internal(app, None, true)?.pane_id)
+    Ok(new_shell_window_internal(app, None, true, &[])?.pane_id)
 }
 
 // Compatibility alias: pane IDs are still the IO identity, but tiles are windows.
@@ -2058,30 +2277,27 @@ pub fn write_pty(
     writer.send_input_by_id(&session_id, data.as_bytes())
 }
 
+/// A redraw of the pane's current screen plus the output stream offset it
+/// reflects; `pty-output` chunks starting at or after `offset` apply on top.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaneOutputSeed {
+    pub data: String,
+    pub offset: u64,
+}
+
 #[tauri::command]
 pub fn read_pty_output(
     state: tauri::State<'_, AppState>,
     session_id: String,
-) -> Result<String, String> {
-    let buffered = state.read_output(&session_id).unwrap_or_default();
-    if !buffered.is_empty() {
-        return Ok(buffered);
-    }
-
-    let output = tmux::output(&[
-        "capture-pane",
-        "-t",
-        &session_id,
-        "-e",
-        "-p",
-    ])
-    .map_err(|e| format!("capture-pane failed: {e}"))?;
-
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        Ok(String::new())
-    }
+) -> Result<PaneOutputSeed, String> {
+    // Take the offset first: a chunk landing during the capture is then replayed
+    // on top of a screen that already shows it, rather than lost.
+    let offset = state.output_end_offset(&session_id).unwrap_or(0);
+    let seed = crate::terminal_screen::capture_seed(&session_id)?;
+    Ok(PaneOutputSeed {
+        data: String::from_utf8_lossy(&seed).to_string(),
+        offset,
+    })
 }
 
 // Compatibility alias: visual resize is now frontend-only, so this is a no-op.
@@ -2316,6 +2532,7 @@ mod tests {
     use super::{
         build_claude_launch_command,
         build_fixture_agent_launch_command,
+        build_role_agent_launch_command,
         build_root_agent_launch_command,
         build_shell_launch_command,
         control_client_tty_from_output,
@@ -2326,6 +2543,8 @@ mod tests {
         tmux_control_client_alive,
     };
     use crate::agent::{AgentInfo, AgentRole, AgentType};
+    use crate::launch_policy::{LaunchPolicy, SandboxMode};
+    use crate::runtime;
     use crate::tmux_state::{TmuxPane, TmuxSession, TmuxSnapshot, TmuxWindow};
     use std::fs;
     use std::path::PathBuf;
@@ -2372,6 +2591,7 @@ mod tests {
                 active_window_id: Some("@1".to_string()),
                 root_cwd: Some("/tmp/herd".to_string()),
                 browser_backend: crate::browser::BrowserBackend::LiveWebview,
+                launch_policies: Default::default(),
             }],
             windows: vec![
                 TmuxWindow {
@@ -2481,6 +2701,7 @@ mod tests {
             chatter_subscribed: true,
             channels: Vec::new(),
             agent_pid: None,
+            worktree: None,
         };
 
         assert_eq!(
@@ -2515,6 +2736,7 @@ mod tests {
             chatter_subscribed: true,
             channels: Vec::new(),
             agent_pid: None,
+            worktree: None,
         };
         let worker = AgentInfo {
             agent_id: "worker-1".to_string(),
@@ -2530,6 +2752,7 @@ mod tests {
             chatter_subscribed: true,
             channels: Vec::new(),
             agent_pid: None,
+            worktree: None,
         };
 
         assert_eq!(
@@ -2583,14 +2806,46 @@ mod tests {
 
     #[test]
     fn shell_launch_command_starts_in_the_requested_directory() {
-        let command = build_shell_launch_command("/tmp/herd-shell");
+        let command = build_shell_launch_command("/tmp/herd-shell", &LaunchPolicy::default());
         assert!(command.contains("cd '/tmp/herd-shell' || exit 1"));
         assert!(command.contains("exec "));
+        assert!(!command.contains("ulimit"));
+    }
+
+    #[test]
+    fn shell_launch_command_applies_limits_and_sandbox_before_exec() {
+        let policy = LaunchPolicy {
+            memory_limit_mb: Some(256),
+            sandbox: SandboxMode::Bubblewrap,
+            no_network: true,
+            ..LaunchPolicy::default()
+        };
+        let command = build_shell_launch_command("/tmp/herd-shell", &policy);
+        let lines = command.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "cd '/tmp/herd-shell' || exit 1");
+        assert!(lines[1].starts_with("exec systemd-run --user --scope --quiet -p MemoryMax=256M -- bwrap --ro-bind / / "));
+        assert!(lines[1].contains("--tmpfs '/tmp'"));
+        assert!(lines[1].contains(&format!("--bind '{0}' '{0}'", runtime::socket_path())));
+        assert!(lines[1].contains("--bind '/tmp/herd-shell' '/tmp/herd-shell'"));
+        assert!(lines[1].contains("--unshare-net"));
+    }
+
+    #[test]
+    fn root_agent_ignores_agent_launch_policy() {
+        let policy = LaunchPolicy {
+            max_processes: Some(64),
+            ..LaunchPolicy::default()
+        };
+        let worker =
+            build_role_agent_launch_command("/tmp/herd-worker", "%3", AgentRole::Worker, AgentType::Fixture, &policy);
+        let root = build_role_agent_launch_command("/tmp/herd-root", "%4", AgentRole::Root, AgentType::Fixture, &policy);
+        assert!(worker.contains("exec systemd-run --user --scope --quiet -p TasksMax=64 -- tail -f /dev/null"));
+        assert!(!root.contains("systemd-run"));
     }
 
     #[test]
     fn worker_agent_launch_command_uses_worker_mcp_server() {
-        let command = build_claude_launch_command("/tmp/herd-claude", "%12");
+        let command = build_claude_launch_command("/tmp/herd-claude", "%12", &LaunchPolicy::default());
         assert!(command.contains("cd '/tmp/herd-claude' || exit 1"));
         assert!(command.contains("HERD_ROLE_CLAUDE_MD="));
         assert!(command.contains(".claude/roles/worker/CLAUDE.md"));
@@ -2616,7 +2871,7 @@ mod tests {
 
     #[test]
     fn fixture_agent_launch_command_never_mentions_claude() {
-        let command = build_fixture_agent_launch_command("/tmp/herd-fixture");
+        let command = build_fixture_agent_launch_command("/tmp/herd-fixture", &LaunchPolicy::default());
         assert!(command.contains("cd '/tmp/herd-fixture' || exit 1"));
         assert!(command.contains("__HERD_FIXTURE_AGENT__"));
         assert!(command.contains("exec tail -f /dev/null"));
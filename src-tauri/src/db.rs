@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::agent::{now_ms, AgentInfo, AgentRole, AgentType, TileSubscriptionRecord};
 use crate::runtime;
+use crate::worktree::AgentWorktree;
 
 const SCHEMA_SQL: &str = r#"
 PRAGMA journal_mode = WAL;
@@ -30,6 +31,16 @@ CREATE TABLE IF NOT EXISTS tile_registry (
   updated_at INTEGER NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS tile_named_port (
+  tile_id TEXT NOT NULL,
+  name TEXT NOT NULL,
+  port TEXT NOT NULL,
+  access_mode TEXT NOT NULL,
+  accepts_json TEXT NOT NULL DEFAULT '[]',
+  PRIMARY KEY (tile_id, name),
+  UNIQUE (tile_id, port)
+);
+
 CREATE TABLE IF NOT EXISTS chatter (
   id INTEGER PRIMARY KEY AUTOINCREMENT,
   kind TEXT NOT NULL,
@@ -79,9 +90,10 @@ CREATE TABLE IF NOT EXISTS tile_subscription (
   subject_tile_id TEXT NOT NULL,
   direction TEXT NOT NULL,
   action TEXT NOT NULL,
+  segment TEXT NOT NULL DEFAULT '',
   data_json TEXT NOT NULL,
   updated_at INTEGER NOT NULL,
-  PRIMARY KEY (session_id, scope, subscriber_tile_id, subject_tile_id, direction, action)
+  PRIMARY KEY (session_id, scope, subscriber_tile_id, subject_tile_id, direction, action, segment)
 );
 
 CREATE TABLE IF NOT EXISTS network_connection (
@@ -90,7 +102,9 @@ CREATE TABLE IF NOT EXISTS network_connection (
   from_tile_id TEXT NOT NULL,
   from_port TEXT NOT NULL,
   to_tile_id TEXT NOT NULL,
-  to_port TEXT NOT NULL
+  to_port TEXT NOT NULL,
+  from_port_name TEXT,
+  to_port_name TEXT
 );
 
 CREATE TABLE IF NOT EXISTS tile_port_setting (
@@ -102,6 +116,84 @@ CREATE TABLE IF NOT EXISTS tile_port_setting (
   PRIMARY KEY (session_id, tile_id, port)
 );
 
+CREATE TABLE IF NOT EXISTS tile_port_segment (
+  session_id TEXT NOT NULL,
+  tile_id TEXT NOT NULL,
+  port TEXT NOT NULL,
+  segment TEXT NOT NULL,
+  PRIMARY KEY (session_id, tile_id, port, segment)
+);
+
+CREATE TABLE IF NOT EXISTS network_history_event (
+  event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+  session_id TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  actor_json TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  timestamp_ms INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS network_traffic_stat (
+  session_id TEXT NOT NULL,
+  scope TEXT NOT NULL,
+  subject_id TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  last_activity_ms INTEGER,
+  PRIMARY KEY (session_id, scope, subject_id)
+);
+
+CREATE TABLE IF NOT EXISTS tile_recording (
+  recording_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  tile_id TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  started_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tile_resource_threshold (
+  tile_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS agent_worktree (
+  agent_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  tile_id TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session_env (
+  session_id TEXT NOT NULL,
+  name TEXT NOT NULL,
+  value TEXT NOT NULL,
+  secret INTEGER NOT NULL DEFAULT 0,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (session_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS tile_env (
+  tile_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  names_json TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session_launch_policy (
+  session_id TEXT PRIMARY KEY,
+  data_json TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tile_launch_policy (
+  tile_id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  data_json TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS work_item (
   work_id TEXT PRIMARY KEY,
   tile_id TEXT,
@@ -130,6 +222,16 @@ CREATE TABLE IF NOT EXISTS work_review (
   comment TEXT,
   created_at INTEGER NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS work_attachment (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  work_id TEXT NOT NULL,
+  stage_name TEXT NOT NULL,
+  path TEXT NOT NULL,
+  media_type TEXT NOT NULL,
+  label TEXT,
+  created_at INTEGER NOT NULL
+);
 "#;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -161,6 +263,8 @@ struct PersistedAgentInfo {
     channels: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     agent_pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    worktree: Option<AgentWorktree>,
 }
 
 impl From<PersistedAgentInfo> for AgentInfo {
@@ -179,6 +283,7 @@ impl From<PersistedAgentInfo> for AgentInfo {
             chatter_subscribed: value.chatter_subscribed,
             channels: value.channels,
             agent_pid: value.agent_pid,
+            worktree: value.worktree,
         }
     }
 }
@@ -199,6 +304,7 @@ impl From<&AgentInfo> for PersistedAgentInfo {
             chatter_subscribed: value.chatter_subscribed,
             channels: value.channels.clone(),
             agent_pid: value.agent_pid,
+            worktree: value.worktree.clone(),
         }
     }
 }
@@ -219,6 +325,8 @@ pub fn open_at(path: &Path) -> Result<Connection, String> {
     ensure_tile_state_locked_column(&conn)?;
     ensure_optional_work_item_tile_id_column(&conn)?;
     ensure_tile_registry_browser_incognito_column(&conn)?;
+    ensure_network_connection_port_name_columns(&conn)?;
+    ensure_tile_subscription_segment_key(&mut conn)?;
     ensure_work_stage_content_storage(&mut conn)?;
     conn.execute(
         "CREATE UNIQUE INDEX IF NOT EXISTS idx_work_item_tile_id ON work_item(tile_id)",
@@ -276,6 +384,54 @@ fn ensure_tile_registry_browser_incognito_column(conn: &Connection) -> Result<()
     Ok(())
 }
 
+fn ensure_network_connection_port_name_columns(conn: &Connection) -> Result<(), String> {
+    for column in ["from_port_name", "to_port_name"] {
+        if table_has_column(conn, "network_connection", column)? {
+            continue;
+        }
+        conn.execute(&format!("ALTER TABLE network_connection ADD COLUMN {column} TEXT"), [])
+            .map_err(|error| format!("failed to add network_connection.{column} column: {error}"))?;
+    }
+    Ok(())
+}
+
+/// Subscriptions that differ only by `segment` are distinct, so the column has
+/// to be part of the primary key. SQLite cannot change a primary key in place,
+/// so older tables are rebuilt.
+fn ensure_tile_subscription_segment_key(conn: &mut Connection) -> Result<(), String> {
+    if table_has_column(conn, "tile_subscription", "segment")? {
+        return Ok(());
+    }
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("failed to begin tile_subscription migration transaction: {error}"))?;
+    tx.execute_batch(
+        "CREATE TABLE tile_subscription_new (
+          session_id TEXT NOT NULL,
+          scope TEXT NOT NULL,
+          subscriber_tile_id TEXT NOT NULL,
+          subject_tile_id TEXT NOT NULL,
+          direction TEXT NOT NULL,
+          action TEXT NOT NULL,
+          segment TEXT NOT NULL DEFAULT '',
+          data_json TEXT NOT NULL,
+          updated_at INTEGER NOT NULL,
+          PRIMARY KEY (session_id, scope, subscriber_tile_id, subject_tile_id, direction, action, segment)
+        );
+        INSERT INTO tile_subscription_new (
+          session_id, scope, subscriber_tile_id, subject_tile_id, direction, action, data_json, updated_at
+        )
+        SELECT session_id, scope, subscriber_tile_id, subject_tile_id, direction, action, data_json, updated_at
+        FROM tile_subscription;
+        DROP TABLE tile_subscription;
+        ALTER TABLE tile_subscription_new RENAME TO tile_subscription;",
+    )
+    .map_err(|error| format!("failed to migrate tile_subscription primary key: {error}"))?;
+    tx.commit()
+        .map_err(|error| format!("failed to commit tile_subscription migration transaction: {error}"))?;
+    Ok(())
+}
+
 fn ensure_work_stage_content_storage(conn: &mut Connection) -> Result<(), String> {
     let has_content = table_has_column(conn, "work_stage", "content")?;
     let has_file_path = table_has_column(conn, "work_stage", "file_path")?;
@@ -480,7 +636,7 @@ pub fn load_tile_subscriptions_at(path: &Path) -> Result<Vec<TileSubscriptionRec
         .prepare(
             "SELECT data_json
              FROM tile_subscription
-             ORDER BY session_id, subscriber_tile_id, subject_tile_id, scope, direction, action",
+             ORDER BY session_id, subscriber_tile_id, subject_tile_id, scope, direction, action, segment",
         )
         .map_err(|error| format!("failed to prepare tile subscription query: {error}"))?;
     let rows = stmt
@@ -527,9 +683,10 @@ pub fn replace_tile_subscriptions_at(path: &Path, subscriptions: &[TileSubscript
               subject_tile_id,
               direction,
               action,
+              segment,
               data_json,
               updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 subscription.session_id,
                 serde_json::to_string(&subscription.scope).unwrap_or_default(),
@@ -537,6 +694,7 @@ pub fn replace_tile_subscriptions_at(path: &Path, subscriptions: &[TileSubscript
                 subscription.subject_tile_id,
                 serde_json::to_string(&subscription.direction).unwrap_or_default(),
                 subscription.action,
+                subscription.segment.as_deref().unwrap_or_default(),
                 data_json,
                 updated_at
             ],
@@ -617,10 +775,21 @@ mod tests {
         assert!(names.contains(&"topic".to_string()));
         assert!(names.contains(&"network_connection".to_string()));
         assert!(names.contains(&"tile_port_setting".to_string()));
+        assert!(names.contains(&"tile_port_segment".to_string()));
+        assert!(names.contains(&"network_history_event".to_string()));
+        assert!(names.contains(&"network_traffic_stat".to_string()));
+        assert!(names.contains(&"tile_recording".to_string()));
+        assert!(names.contains(&"tile_resource_threshold".to_string()));
+        assert!(names.contains(&"agent_worktree".to_string()));
+        assert!(names.contains(&"session_env".to_string()));
+        assert!(names.contains(&"tile_env".to_string()));
+        assert!(names.contains(&"session_launch_policy".to_string()));
+        assert!(names.contains(&"tile_launch_policy".to_string()));
         assert!(names.contains(&"work_item".to_string()));
         assert!(names.contains(&"work_stage".to_string()));
         assert!(names.contains(&"work_review".to_string()));
         assert!(names.contains(&"tile_registry".to_string()));
+        assert!(names.contains(&"tile_named_port".to_string()));
         assert!(names.contains(&"tile_subscription".to_string()));
 
         let _ = fs::remove_file(path);
@@ -643,6 +812,7 @@ mod tests {
             chatter_subscribed: true,
             channels: vec!["#work-s1-001".to_string()],
             agent_pid: Some(42),
+            worktree: None,
         }];
         let channels = vec![PersistedChannelRecord {
             session_id: "$1".to_string(),
@@ -676,6 +846,7 @@ mod tests {
                 subject_tile_id: "%shell".to_string(),
                 direction: TileSubscriptionDirection::In,
                 action: "exec".to_string(),
+                segment: None,
             },
             TileSubscriptionRecord {
                 session_id: "$1".to_string(),
@@ -684,6 +855,16 @@ mod tests {
                 subject_tile_id: "%browser".to_string(),
                 direction: TileSubscriptionDirection::Out,
                 action: "extension_call".to_string(),
+                segment: Some("team-a".to_string()),
+            },
+            TileSubscriptionRecord {
+                session_id: "$1".to_string(),
+                scope: TileSubscriptionScope::Network,
+                subscriber_tile_id: "%worker".to_string(),
+                subject_tile_id: "%browser".to_string(),
+                direction: TileSubscriptionDirection::Out,
+                action: "extension_call".to_string(),
+                segment: Some("team-b".to_string()),
             },
         ];
 
@@ -694,6 +875,56 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn migrates_tile_subscriptions_to_a_segment_aware_key() {
+        let path = temp_db_path("tile-subscriptions-migrate");
+        let legacy = TileSubscriptionRecord {
+            session_id: "$1".to_string(),
+            scope: TileSubscriptionScope::Tile,
+            subscriber_tile_id: "%root".to_string(),
+            subject_tile_id: "%shell".to_string(),
+            direction: TileSubscriptionDirection::In,
+            action: "exec".to_string(),
+            segment: None,
+        };
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE tile_subscription (
+              session_id TEXT NOT NULL,
+              scope TEXT NOT NULL,
+              subscriber_tile_id TEXT NOT NULL,
+              subject_tile_id TEXT NOT NULL,
+              direction TEXT NOT NULL,
+              action TEXT NOT NULL,
+              data_json TEXT NOT NULL,
+              updated_at INTEGER NOT NULL,
+              PRIMARY KEY (session_id, scope, subscriber_tile_id, subject_tile_id, direction, action)
+            );
+            "#,
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tile_subscription VALUES ('$1', '\"tile\"', '%root', '%shell', '\"in\"', 'exec', ?1, 1)",
+            params![serde_json::to_string(&legacy).unwrap()],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(load_tile_subscriptions_at(&path).unwrap(), vec![legacy.clone()]);
+        let segmented = ["team-a", "team-b"]
+            .into_iter()
+            .map(|segment| TileSubscriptionRecord {
+                segment: Some(segment.to_string()),
+                ..legacy.clone()
+            })
+            .collect::<Vec<_>>();
+        replace_tile_subscriptions_at(&path, &segmented).unwrap();
+        assert_eq!(load_tile_subscriptions_at(&path).unwrap(), segmented);
+
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn startup_reset_clears_alive_agents() {
         let path = temp_db_path("reset");
@@ -711,6 +942,7 @@ mod tests {
             chatter_subscribed: true,
             channels: vec![],
             agent_pid: None,
+            worktree: None,
         }];
         replace_agents_at(&path, &agents).unwrap();
 
@@ -0,0 +1,652 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{agent, db};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxMode {
+    #[default]
+    None,
+    Bubblewrap,
+    Unshare,
+}
+
+impl SandboxMode {
+    pub fn is_none(&self) -> bool {
+        matches!(self, SandboxMode::None)
+    }
+
+    /// `unshare` only remounts the listed paths read-only, while `bubblewrap`
+    /// makes the whole filesystem read-only, so it is the stronger sandbox.
+    fn strength(self) -> u8 {
+        match self {
+            SandboxMode::None => 0,
+            SandboxMode::Unshare => 1,
+            SandboxMode::Bubblewrap => 2,
+        }
+    }
+}
+
+/// Limits and isolation applied to a process launched into a tmux pane.
+///
+/// `memory_limit_mb` and `max_processes` become `MemoryMax`/`TasksMax` on a
+/// transient `systemd-run --user --scope`, so they cap the tile's whole process
+/// tree; `cpu_time_limit_secs` is a per-process `ulimit -t`. `read_only_paths`
+/// are remounted read-only inside the sandbox; with `bubblewrap` the whole
+/// filesystem is read-only except the tile cwd and `writable_paths`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit_mb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_time_limit_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_processes: Option<u64>,
+    #[serde(skip_serializing_if = "SandboxMode::is_none")]
+    pub sandbox: SandboxMode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub read_only_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writable_paths: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_network: bool,
+}
+
+/// Host paths a sandbox handles beyond the policy's own lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxHostPaths {
+    /// Hidden behind an empty tmpfs, e.g. the tmux socket directory, so a
+    /// sandboxed tile cannot ask the tmux server to run commands outside it.
+    pub masked: Vec<String>,
+    /// Bound writable after masking, e.g. the Herd socket so agents and the
+    /// `herd` CLI can still reach the app.
+    pub writable: Vec<String>,
+}
+
+/// Launch policies for the tile kinds that own a shell process. The Root agent
+/// is never restricted because it drives the rest of the session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionLaunchPolicies {
+    pub shell: LaunchPolicy,
+    pub agent: LaunchPolicy,
+}
+
+impl SessionLaunchPolicies {
+    pub fn is_unrestricted(&self) -> bool {
+        self.shell.is_unrestricted() && self.agent.is_unrestricted()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.shell
+            .validate()
+            .map_err(|error| format!("shell launch policy: {error}"))?;
+        self.agent
+            .validate()
+            .map_err(|error| format!("agent launch policy: {error}"))
+    }
+}
+
+impl LaunchPolicy {
+    pub fn is_unrestricted(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("memory_limit_mb", self.memory_limit_mb),
+            ("cpu_time_limit_secs", self.cpu_time_limit_secs),
+            ("max_processes", self.max_processes),
+        ] {
+            if value == Some(0) {
+                return Err(format!("{name} must be greater than zero"));
+            }
+        }
+        for path in self.read_only_paths.iter().chain(self.writable_paths.iter()) {
+            if !path.starts_with('/') {
+                return Err(format!("sandbox path must be absolute: {path}"));
+            }
+        }
+        if self.sandbox.is_none() && !(self.read_only_paths.is_empty() && self.writable_paths.is_empty()) {
+            return Err("read_only_paths and writable_paths require a sandbox".to_string());
+        }
+        Ok(())
+    }
+
+    /// Fails unless this policy is at least as restrictive as `baseline`: every
+    /// baseline limit is kept or lowered, the sandbox is no weaker, baseline
+    /// read-only paths stay read-only, a bubblewrap sandbox writes no further
+    /// than the baseline allows, and network isolation is kept.
+    pub fn ensure_within(&self, baseline: &LaunchPolicy) -> Result<(), String> {
+        for (name, value, limit) in [
+            ("memory_limit_mb", self.memory_limit_mb, baseline.memory_limit_mb),
+            ("cpu_time_limit_secs", self.cpu_time_limit_secs, baseline.cpu_time_limit_secs),
+            ("max_processes", self.max_processes, baseline.max_processes),
+        ] {
+            if let Some(limit) = limit {
+                if !value.is_some_and(|value| value <= limit) {
+                    return Err(format!("{name} must be at most {limit}"));
+                }
+            }
+        }
+        if self.sandbox.strength() < baseline.sandbox.strength() {
+            let required = match baseline.sandbox {
+                SandboxMode::None => "none",
+                SandboxMode::Unshare => "unshare",
+                SandboxMode::Bubblewrap => "bubblewrap",
+            };
+            return Err(format!("sandbox must be at least {required}"));
+        }
+        if let Some(path) = baseline
+            .read_only_paths
+            .iter()
+            .find(|path| !self.read_only_paths.iter().any(|own| Path::new(path).starts_with(own)))
+        {
+            return Err(format!("read_only_paths must include {path}"));
+        }
+        if baseline.sandbox == SandboxMode::Bubblewrap {
+            if let Some(path) = self.writable_paths.iter().find(|path| {
+                !baseline
+                    .writable_paths
+                    .iter()
+                    .any(|allowed| Path::new(path.as_str()).starts_with(allowed))
+            }) {
+                return Err(format!("writable_paths may not add {path}"));
+            }
+        }
+        if baseline.no_network && !self.no_network {
+            return Err("no_network must stay enabled".to_string());
+        }
+        Ok(())
+    }
+
+    /// Shell lines that apply the per-process limits. Each line aborts the
+    /// launch if the limit cannot be set instead of starting an unbounded process.
+    pub fn ulimit_prelude(&self) -> String {
+        self.cpu_time_limit_secs
+            .map(|secs| format!("ulimit -t {secs} || exit 1\n"))
+            .unwrap_or_default()
+    }
+
+    /// Command prefix placed between `exec` and the launched program.
+    pub fn exec_prefix(&self, cwd: &str, host: &SandboxHostPaths) -> String {
+        let sandbox = match self.sandbox {
+            SandboxMode::None if self.no_network => "unshare --user --map-root-user --net -- ".to_string(),
+            SandboxMode::None => String::new(),
+            SandboxMode::Bubblewrap => self.bubblewrap_prefix(cwd, host),
+            SandboxMode::Unshare => self.unshare_prefix(host),
+        };
+        format!("{}{sandbox}", self.scope_prefix())
+    }
+
+    /// Runs the program in a transient systemd user scope whose cgroup caps
+    /// memory and task count. `systemd-run` fails the launch when no user
+    /// manager is available rather than starting the tile unbounded.
+    fn scope_prefix(&self) -> String {
+        let mut properties = Vec::new();
+        if let Some(mb) = self.memory_limit_mb {
+            properties.push(format!("-p MemoryMax={mb}M"));
+        }
+        if let Some(count) = self.max_processes {
+            properties.push(format!("-p TasksMax={count}"));
+        }
+        if properties.is_empty() {
+            return String::new();
+        }
+        format!("systemd-run --user --scope --quiet {} -- ", properties.join(" "))
+    }
+
+    fn bubblewrap_prefix(&self, cwd: &str, host: &SandboxHostPaths) -> String {
+        let mut args = vec!["bwrap", "--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        for path in &host.masked {
+            args.extend(["--tmpfs".to_string(), shell_single_quote(path)]);
+        }
+        let writable = std::iter::once(cwd)
+            .chain(self.writable_paths.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        for path in &writable {
+            args.extend(["--bind".to_string(), shell_single_quote(path), shell_single_quote(path)]);
+        }
+        // A writable bind such as a cwd of `/tmp` would re-expose a mask below it.
+        for path in host.masked.iter().filter(|masked| {
+            writable
+                .iter()
+                .any(|bound| Path::new(masked.as_str()).starts_with(bound) && masked.as_str() != *bound)
+        }) {
+            args.extend(["--tmpfs".to_string(), shell_single_quote(path)]);
+        }
+        for path in &host.writable {
+            args.extend(["--bind".to_string(), shell_single_quote(path), shell_single_quote(path)]);
+        }
+        // Read-only binds come last so they win over a writable parent.
+        for path in &self.read_only_paths {
+            args.extend([
+                "--ro-bind".to_string(),
+                shell_single_quote(path),
+                shell_single_quote(path),
+            ]);
+        }
+        if self.no_network {
+            args.push("--unshare-net".to_string());
+        }
+        args.extend([
+            "--unsetenv".to_string(),
+            "TMUX".to_string(),
+            "--die-with-parent".to_string(),
+            "--chdir".to_string(),
+            shell_single_quote(cwd),
+        ]);
+        format!("{} -- ", args.join(" "))
+    }
+
+    /// `unshare` keeps the host filesystem, so it cannot bind a writable path
+    /// back over a mask; masked paths that contain one are left visible.
+    fn unshare_prefix(&self, host: &SandboxHostPaths) -> String {
+        let mut flags = vec!["--user", "--map-root-user", "--mount"];
+        if self.no_network {
+            flags.push("--net");
+        }
+        let mut script = String::new();
+        for path in host
+            .masked
+            .iter()
+            .filter(|masked| !host.writable.iter().any(|path| Path::new(path).starts_with(masked.as_str())))
+        {
+            let quoted = shell_single_quote(path);
+            script.push_str(&format!("mount -t tmpfs tmpfs {quoted} || exit 1; "));
+        }
+        for path in &self.read_only_paths {
+            let quoted = shell_single_quote(path);
+            script.push_str(&format!(
+                "mount --bind {quoted} {quoted} && mount -o remount,bind,ro {quoted} {quoted} || exit 1; "
+            ));
+        }
+        script.push_str("unset TMUX; exec \"$@\"");
+        format!(
+            "unshare {} -- /bin/bash -c {} herd-sandbox ",
+            flags.join(" "),
+            shell_single_quote(&script)
+        )
+    }
+}
+
+/// Session policies live in SQLite rather than the tmux session environment so
+/// panes in the session cannot read or rewrite them with `tmux set-environment`.
+pub fn session_policies_at(db_path: &Path, session_id: &str) -> Result<SessionLaunchPolicies, String> {
+    let conn = db::open_at(db_path)?;
+    let data_json = conn
+        .query_row(
+            "SELECT data_json FROM session_launch_policy WHERE session_id = ?1",
+            [session_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|error| format!("failed to query session launch policies: {error}"))?;
+    data_json
+        .map(|data_json| {
+            serde_json::from_str(&data_json)
+                .map_err(|error| format!("failed to parse launch policies for {session_id}: {error}"))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+pub fn list_session_policies_at(db_path: &Path) -> Result<HashMap<String, SessionLaunchPolicies>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT session_id, data_json FROM session_launch_policy")
+        .map_err(|error| format!("failed to prepare session launch policy query: {error}"))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|error| format!("failed to query session launch policies: {error}"))?;
+    let mut policies = HashMap::new();
+    for row in rows {
+        let (session_id, data_json) =
+            row.map_err(|error| format!("failed to decode session launch policy row: {error}"))?;
+        let parsed = serde_json::from_str(&data_json)
+            .map_err(|error| format!("failed to parse launch policies for {session_id}: {error}"))?;
+        policies.insert(session_id, parsed);
+    }
+    Ok(policies)
+}
+
+pub fn set_session_policies_at(db_path: &Path, session_id: &str, policies: &SessionLaunchPolicies) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    if policies.is_unrestricted() {
+        conn.execute("DELETE FROM session_launch_policy WHERE session_id = ?1", [session_id])
+            .map_err(|error| format!("failed to clear session launch policies: {error}"))?;
+        return Ok(());
+    }
+    let data_json = serde_json::to_string(policies)
+        .map_err(|error| format!("failed to encode session launch policies: {error}"))?;
+    conn.execute(
+        "INSERT INTO session_launch_policy (session_id, data_json, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(session_id) DO UPDATE SET
+           data_json = excluded.data_json,
+           updated_at = excluded.updated_at",
+        params![session_id, data_json, agent::now_ms()],
+    )
+    .map_err(|error| format!("failed to store session launch policies: {error}"))?;
+    Ok(())
+}
+
+/// Drops the session policies and every per-tile override in the session.
+/// tmux reuses session ids across server restarts, so policies must not carry
+/// over to an unrelated session.
+pub fn clear_session_policies_at(db_path: &Path, session_id: &str) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    conn.execute("DELETE FROM session_launch_policy WHERE session_id = ?1", [session_id])
+        .map_err(|error| format!("failed to clear session launch policies: {error}"))?;
+    conn.execute("DELETE FROM tile_launch_policy WHERE session_id = ?1", [session_id])
+        .map_err(|error| format!("failed to clear tile launch policies: {error}"))?;
+    Ok(())
+}
+
+pub fn tile_policy_at(db_path: &Path, tile_id: &str) -> Result<Option<LaunchPolicy>, String> {
+    let conn = db::open_at(db_path)?;
+    let data_json = conn
+        .query_row(
+            "SELECT data_json FROM tile_launch_policy WHERE tile_id = ?1",
+            [tile_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|error| format!("failed to query tile launch policy: {error}"))?;
+    data_json
+        .map(|data_json| {
+            serde_json::from_str(&data_json)
+                .map_err(|error| format!("failed to parse launch policy for {tile_id}: {error}"))
+        })
+        .transpose()
+}
+
+/// Stores a policy that overrides the session policy for one tile, including
+/// when its pane is respawned.
+pub fn set_tile_policy_at(db_path: &Path, session_id: &str, tile_id: &str, policy: &LaunchPolicy) -> Result<(), String> {
+    let data_json =
+        serde_json::to_string(policy).map_err(|error| format!("failed to encode tile launch policy: {error}"))?;
+    let conn = db::open_at(db_path)?;
+    conn.execute(
+        "INSERT INTO tile_launch_policy (tile_id, session_id, data_json, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(tile_id) DO UPDATE SET
+           session_id = excluded.session_id,
+           data_json = excluded.data_json,
+           updated_at = excluded.updated_at",
+        params![tile_id, session_id, data_json, agent::now_ms()],
+    )
+    .map_err(|error| format!("failed to store tile launch policy: {error}"))?;
+    Ok(())
+}
+
+pub fn delete_tile_policies_at(db_path: &Path, tile_ids: &HashSet<String>) -> Result<(), String> {
+    if tile_ids.is_empty() {
+        return Ok(());
+    }
+    let conn = db::open_at(db_path)?;
+    for tile_id in tile_ids {
+        conn.execute("DELETE FROM tile_launch_policy WHERE tile_id = ?1", [tile_id])
+            .map_err(|error| format!("failed to delete tile launch policy: {error}"))?;
+    }
+    Ok(())
+}
+
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{
+        clear_session_policies_at, delete_tile_policies_at, list_session_policies_at, session_policies_at,
+        set_session_policies_at, set_tile_policy_at, tile_policy_at, LaunchPolicy, SandboxHostPaths, SandboxMode,
+        SessionLaunchPolicies,
+    };
+
+    fn temp_db(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("herd-launch-policy-{name}-{}.sqlite", uuid::Uuid::new_v4()))
+    }
+
+    fn herd_host_paths() -> SandboxHostPaths {
+        SandboxHostPaths {
+            masked: vec!["/tmp".to_string(), "/run/tmux".to_string()],
+            writable: vec!["/tmp/herd.sock".to_string()],
+        }
+    }
+
+    #[test]
+    fn unrestricted_policy_adds_nothing_to_the_launch() {
+        let policy = LaunchPolicy::default();
+        assert!(policy.is_unrestricted());
+        assert_eq!(policy.ulimit_prelude(), "");
+        assert_eq!(policy.exec_prefix("/tmp/work", &herd_host_paths()), "");
+        assert_eq!(serde_json::to_value(&policy).unwrap(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn resource_limits_render_as_a_systemd_scope_and_cpu_ulimit() {
+        let policy = LaunchPolicy {
+            memory_limit_mb: Some(512),
+            cpu_time_limit_secs: Some(60),
+            max_processes: Some(128),
+            ..LaunchPolicy::default()
+        };
+        assert_eq!(policy.ulimit_prelude(), "ulimit -t 60 || exit 1\n");
+        assert_eq!(
+            policy.exec_prefix("/tmp/work", &herd_host_paths()),
+            "systemd-run --user --scope --quiet -p MemoryMax=512M -p TasksMax=128 -- "
+        );
+
+        let sandboxed = LaunchPolicy {
+            sandbox: SandboxMode::Bubblewrap,
+            ..policy
+        };
+        assert!(sandboxed
+            .exec_prefix("/tmp/work", &herd_host_paths())
+            .starts_with("systemd-run --user --scope --quiet -p MemoryMax=512M -p TasksMax=128 -- bwrap "));
+    }
+
+    #[test]
+    fn bubblewrap_masks_host_sockets_and_binds_read_only_paths_last() {
+        let policy = LaunchPolicy {
+            sandbox: SandboxMode::Bubblewrap,
+            read_only_paths: vec!["/tmp/work/.git".to_string()],
+            writable_paths: vec!["/tmp/cache".to_string()],
+            no_network: true,
+            ..LaunchPolicy::default()
+        };
+        assert_eq!(
+            policy.exec_prefix("/tmp/work", &herd_host_paths()),
+            "bwrap --ro-bind / / --dev /dev --proc /proc --tmpfs '/tmp' --tmpfs '/run/tmux' \
+             --bind '/tmp/work' '/tmp/work' --bind '/tmp/cache' '/tmp/cache' --bind '/tmp/herd.sock' '/tmp/herd.sock' \
+             --ro-bind '/tmp/work/.git' '/tmp/work/.git' --unshare-net --unsetenv TMUX --die-with-parent \
+             --chdir '/tmp/work' -- "
+        );
+    }
+
+    #[test]
+    fn bubblewrap_remasks_sockets_below_a_writable_cwd() {
+        let policy = LaunchPolicy {
+            sandbox: SandboxMode::Bubblewrap,
+            ..LaunchPolicy::default()
+        };
+        let host = SandboxHostPaths {
+            masked: vec!["/tmp".to_string(), "/tmp/tmux-1000".to_string()],
+            writable: vec!["/tmp/herd.sock".to_string()],
+        };
+        assert_eq!(
+            policy.exec_prefix("/tmp", &host),
+            "bwrap --ro-bind / / --dev /dev --proc /proc --tmpfs '/tmp' --tmpfs '/tmp/tmux-1000' \
+             --bind '/tmp' '/tmp' --tmpfs '/tmp/tmux-1000' --bind '/tmp/herd.sock' '/tmp/herd.sock' \
+             --unsetenv TMUX --die-with-parent --chdir '/tmp' -- "
+        );
+    }
+
+    #[test]
+    fn unshare_and_network_only_policies_use_namespaces() {
+        let network_only = LaunchPolicy {
+            no_network: true,
+            ..LaunchPolicy::default()
+        };
+        assert_eq!(
+            network_only.exec_prefix("/tmp/work", &herd_host_paths()),
+            "unshare --user --map-root-user --net -- "
+        );
+
+        let policy = LaunchPolicy {
+            sandbox: SandboxMode::Unshare,
+            read_only_paths: vec!["/srv/data".to_string()],
+            ..LaunchPolicy::default()
+        };
+        let prefix = policy.exec_prefix("/tmp/work", &herd_host_paths());
+        assert!(prefix.starts_with("unshare --user --map-root-user --mount -- /bin/bash -c '"));
+        assert!(prefix.contains("mount -t tmpfs tmpfs '\"'\"'/run/tmux'\"'\"'"));
+        assert!(!prefix.contains("'\"'\"'/tmp'\"'\"'"), "the Herd socket dir stays visible: {prefix}");
+        assert!(prefix.contains("mount -o remount,bind,ro '\"'\"'/srv/data'\"'\"'"));
+        assert!(prefix.ends_with("unset TMUX; exec \"$@\"' herd-sandbox "));
+    }
+
+    #[test]
+    fn validates_limits_and_sandbox_paths() {
+        let mut policies = SessionLaunchPolicies::default();
+        assert!(policies.validate().is_ok());
+
+        policies.shell.memory_limit_mb = Some(0);
+        assert_eq!(
+            policies.validate().unwrap_err(),
+            "shell launch policy: memory_limit_mb must be greater than zero"
+        );
+
+        policies.shell.memory_limit_mb = None;
+        policies.agent.writable_paths = vec!["relative/dir".to_string()];
+        assert_eq!(
+            policies.validate().unwrap_err(),
+            "agent launch policy: sandbox path must be absolute: relative/dir"
+        );
+
+        policies.agent.writable_paths = vec!["/tmp/cache".to_string()];
+        assert_eq!(
+            policies.validate().unwrap_err(),
+            "agent launch policy: read_only_paths and writable_paths require a sandbox"
+        );
+
+        let parsed: SessionLaunchPolicies =
+            serde_json::from_str(r#"{"agent":{"sandbox":"bubblewrap","no_network":true}}"#).unwrap();
+        assert!(parsed.shell.is_unrestricted());
+        assert_eq!(parsed.agent.sandbox, SandboxMode::Bubblewrap);
+        assert!(parsed.agent.no_network);
+    }
+
+    #[test]
+    fn tile_policies_may_only_tighten_the_session_policy() {
+        let session = LaunchPolicy {
+            memory_limit_mb: Some(512),
+            sandbox: SandboxMode::Bubblewrap,
+            read_only_paths: vec!["/srv".to_string()],
+            writable_paths: vec!["/tmp/cache".to_string()],
+            no_network: true,
+            ..LaunchPolicy::default()
+        };
+        assert!(session.ensure_within(&session).is_ok());
+        assert!(LaunchPolicy {
+            memory_limit_mb: Some(256),
+            max_processes: Some(64),
+            read_only_paths: vec!["/".to_string()],
+            writable_paths: vec!["/tmp/cache/build".to_string()],
+            ..session.clone()
+        }
+        .ensure_within(&session)
+        .is_ok());
+
+        let looser = [
+            (
+                LaunchPolicy {
+                    memory_limit_mb: None,
+                    ..session.clone()
+                },
+                "memory_limit_mb must be at most 512",
+            ),
+            (
+                LaunchPolicy {
+                    sandbox: SandboxMode::Unshare,
+                    writable_paths: Vec::new(),
+                    ..session.clone()
+                },
+                "sandbox must be at least bubblewrap",
+            ),
+            (
+                LaunchPolicy {
+                    read_only_paths: Vec::new(),
+                    ..session.clone()
+                },
+                "read_only_paths must include /srv",
+            ),
+            (
+                LaunchPolicy {
+                    writable_paths: vec!["/home".to_string()],
+                    ..session.clone()
+                },
+                "writable_paths may not add /home",
+            ),
+            (
+                LaunchPolicy {
+                    no_network: false,
+                    ..session.clone()
+                },
+                "no_network must stay enabled",
+            ),
+        ];
+        for (policy, error) in looser {
+            assert_eq!(policy.ensure_within(&session).unwrap_err(), error);
+        }
+        assert!(LaunchPolicy::default().ensure_within(&LaunchPolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn stores_session_and_tile_policies_in_sqlite() {
+        let path = temp_db("store");
+        assert!(session_policies_at(&path, "$1").unwrap().is_unrestricted());
+
+        let policies = SessionLaunchPolicies {
+            agent: LaunchPolicy {
+                sandbox: SandboxMode::Bubblewrap,
+                ..LaunchPolicy::default()
+            },
+            ..SessionLaunchPolicies::default()
+        };
+        set_session_policies_at(&path, "$1", &policies).unwrap();
+        set_session_policies_at(&path, "$2", &policies).unwrap();
+        assert_eq!(session_policies_at(&path, "$1").unwrap(), policies);
+        set_session_policies_at(&path, "$2", &SessionLaunchPolicies::default()).unwrap();
+        assert_eq!(list_session_policies_at(&path).unwrap().keys().collect::<Vec<_>>(), vec!["$1"]);
+
+        let tile_policy = LaunchPolicy {
+            no_network: true,
+            ..LaunchPolicy::default()
+        };
+        set_tile_policy_at(&path, "$1", "tile-a", &tile_policy).unwrap();
+        set_tile_policy_at(&path, "$1", "tile-b", &tile_policy).unwrap();
+        assert_eq!(tile_policy_at(&path, "tile-a").unwrap(), Some(tile_policy.clone()));
+        delete_tile_policies_at(&path, &HashSet::from(["tile-a".to_string()])).unwrap();
+        assert_eq!(tile_policy_at(&path, "tile-a").unwrap(), None);
+
+        clear_session_policies_at(&path, "$1").unwrap();
+        assert!(session_policies_at(&path, "$1").unwrap().is_unrestricted());
+        assert_eq!(tile_policy_at(&path, "tile-b").unwrap(), None);
+
+        let _ = fs::remove_file(path);
+    }
+}
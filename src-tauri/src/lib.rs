@@ -1,20 +1,31 @@
 mod agent;
 mod browser;
+mod browser_capture;
+mod browser_console;
+mod browser_recording;
 mod cli;
 mod commands;
 mod db;
+mod launch_policy;
 mod network;
+mod network_history;
+mod network_traffic;
 mod persist;
+mod process_info;
+mod recording;
 mod runtime;
 mod session_config;
+mod session_env;
 mod socket;
 mod state;
 mod tile_registry;
+mod terminal_screen;
 mod tile_message;
 mod tmux;
 mod tmux_control;
 mod tmux_state;
 mod work;
+mod worktree;
 
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
@@ -153,6 +164,10 @@ fn run_gui() {
             commands::rename_session,
             commands::set_session_root_cwd,
             commands::set_session_browser_backend,
+            commands::set_session_launch_policies,
+            commands::list_session_env,
+            commands::set_session_env_entry,
+            commands::unset_session_env_entry,
             session_config::list_saved_session_configurations,
             session_config::save_session_configuration,
             session_config::load_session_configuration,
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::path::Path;
 
 use rusqlite::{params, Connection};
@@ -7,8 +7,9 @@ use serde::{Deserialize, Serialize};
 use crate::{
     agent::{AgentInfo, AgentRole, AgentType},
     db,
-    tile_registry::TileRecordKind,
-    work::{WorkReviewEntry, WorkStage, WorkStageState},
+    network_history::{self, NetworkChangeActor, NetworkHistoryEventKind},
+    tile_registry::{self, TileRecordKind},
+    work::{WorkAttachment, WorkReviewEntry, WorkStage, WorkStageState},
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -125,7 +126,8 @@ impl PortNetworkingMode {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum TileRpcAccess {
     Read,
     ReadWrite,
@@ -141,6 +143,18 @@ pub enum NetworkTileKind {
     Browser,
 }
 
+impl NetworkTileKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Agent => "agent",
+            Self::RootAgent => "root_agent",
+            Self::Shell => "shell",
+            Self::Work => "work",
+            Self::Browser => "browser",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NetworkTileDescriptor {
     pub tile_id: String,
@@ -166,6 +180,42 @@ pub struct TilePortSetting {
     pub networking_mode: PortNetworkingMode,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamedPort {
+    pub tile_id: String,
+    pub name: String,
+    pub port: TilePort,
+    pub access_mode: PortMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepts: Vec<NetworkTileKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortSegment {
+    pub session_id: String,
+    pub tile_id: String,
+    pub port: TilePort,
+    pub segment: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkSegment {
+    pub name: String,
+    pub tile_ids: Vec<String>,
+    pub connections: Vec<NetworkConnection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamedPortSpec {
+    pub name: String,
+    #[serde(default)]
+    pub port: Option<TilePort>,
+    #[serde(default)]
+    pub access_mode: Option<PortMode>,
+    #[serde(default)]
+    pub accepts: Vec<NetworkTileKind>,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TileTypeFilter {
@@ -197,6 +247,8 @@ pub struct AgentTileDetails {
     pub channels: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<crate::worktree::AgentWorktree>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -234,6 +286,8 @@ pub struct WorkTileDetails {
     pub current_stage: WorkStage,
     pub stages: Vec<WorkStageState>,
     pub reviews: Vec<WorkReviewEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<WorkAttachment>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -257,6 +311,14 @@ pub struct TileMessageSubcommandSpec {
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<TileMessageArgSpec>,
+    #[serde(rename = "async", default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_async: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args_schema: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -277,6 +339,27 @@ pub struct BrowserExtensionMethod {
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<TileMessageArgSpec>,
+    /// The method returns a promise; Herd waits for it to settle.
+    #[serde(rename = "async", default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_async: bool,
+    /// Default wait for an async method when the caller does not pass `timeout_ms`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// JSON Schema the call's `args` object must satisfy before Herd runs the method.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args_schema: Option<serde_json::Value>,
+    /// JSON Schema the method's return value must satisfy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_schema: Option<serde_json::Value>,
+}
+
+/// An event the page may push with `HerdBrowserExtension.emit`. Agents
+/// subscribe to it like a tile action, e.g. `in:turn_started`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BrowserExtensionEvent {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -287,6 +370,11 @@ pub struct BrowserExtensionInfo {
     pub source_path: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub methods: Vec<BrowserExtensionMethod>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<BrowserExtensionEvent>,
+    /// Named ports the page declares for its tile, applied like `network_port_declare`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<NamedPortSpec>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -320,6 +408,8 @@ pub struct SessionTileInfo {
     pub responds_to: Vec<String>,
     #[serde(default)]
     pub message_api: Vec<TileMessageSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<NamedPort>,
     pub details: TileDetails,
 }
 
@@ -338,6 +428,7 @@ impl SessionTileInfo {
             command: None,
             responds_to: Vec::new(),
             message_api: Vec::new(),
+            ports: Vec::new(),
             details: TileDetails::Shell(PaneTileDetails {
                 window_name: String::new(),
                 window_index: 0,
@@ -354,6 +445,35 @@ impl SessionTileInfo {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkPathHop {
+    pub from_tile_id: String,
+    pub from_port: TilePort,
+    pub from_access_mode: PortMode,
+    pub to_tile_id: String,
+    pub to_port: TilePort,
+    pub to_access_mode: PortMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkPathBlock {
+    pub tile_id: String,
+    pub port: TilePort,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkPath {
+    pub session_id: String,
+    pub from_tile_id: String,
+    pub to_tile_id: String,
+    pub reachable: bool,
+    pub hops: Vec<NetworkPathHop>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access: Option<TileRpcAccess>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<NetworkPathBlock>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct NetworkComponent {
     pub session_id: String,
@@ -363,6 +483,8 @@ pub struct NetworkComponent {
     pub connections: Vec<NetworkConnection>,
     #[serde(default, skip_serializing)]
     pub port_settings: Vec<TilePortSetting>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<NetworkSegment>,
 }
 
 pub fn filter_tiles(mut tiles: Vec<SessionTileInfo>, tile_type: Option<TileTypeFilter>) -> Vec<SessionTileInfo> {
@@ -515,6 +637,10 @@ pub fn list_connections_at(db_path: &Path, session_id: &str) -> Result<Vec<Netwo
 
 pub fn list_all_connections_at(db_path: &Path) -> Result<Vec<NetworkConnection>, String> {
     let conn = db::open_at(db_path)?;
+    list_all_connections_with_conn(&conn)
+}
+
+pub fn list_all_connections_with_conn(conn: &Connection) -> Result<Vec<NetworkConnection>, String> {
     let mut stmt = conn
         .prepare(
             "SELECT session_id, from_tile_id, from_port, to_tile_id, to_port
@@ -537,34 +663,26 @@ pub fn list_all_connections_at(db_path: &Path) -> Result<Vec<NetworkConnection>,
         .map_err(|error| format!("failed to decode network connection rows: {error}"))
 }
 
-pub fn replace_connections_at(db_path: &Path, connections: &[NetworkConnection]) -> Result<(), String> {
+pub fn replace_connections_at(
+    db_path: &Path,
+    connections: &[NetworkConnection],
+    actor: &NetworkChangeActor,
+) -> Result<(), String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
         .transaction()
         .map_err(|error| format!("failed to begin network replace transaction: {error}"))?;
+    let previous = list_all_connections_with_conn(&tx)?;
+    for connection in previous.iter().filter(|connection| !connections.contains(connection)) {
+        network_history::record_connection_with_conn(&tx, NetworkHistoryEventKind::Disconnect, connection, actor)?;
+    }
+    for connection in connections.iter().filter(|connection| !previous.contains(connection)) {
+        network_history::record_connection_with_conn(&tx, NetworkHistoryEventKind::Connect, connection, actor)?;
+    }
     tx.execute("DELETE FROM network_connection", [])
         .map_err(|error| format!("failed to clear network connections: {error}"))?;
     for connection in connections {
-        tx.execute(
-            "INSERT INTO network_connection (session_id, from_tile_id, from_port, to_tile_id, to_port)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                connection.session_id,
-                connection.from_tile_id,
-                connection.from_port.as_str(),
-                connection.to_tile_id,
-                connection.to_port.as_str(),
-            ],
-        )
-        .map_err(|error| {
-            format!(
-                "failed to replace network connection {}:{} <-> {}:{}: {error}",
-                connection.from_tile_id,
-                connection.from_port.as_str(),
-                connection.to_tile_id,
-                connection.to_port.as_str()
-            )
-        })?;
+        insert_connection_with_conn(&tx, connection)?;
     }
     tx.commit()
         .map_err(|error| format!("failed to commit network replace transaction: {error}"))?;
@@ -658,11 +776,43 @@ pub fn list_port_settings_with_conn(
         .map_err(|error| format!("failed to decode port setting rows: {error}"))
 }
 
-pub fn replace_port_settings_at(db_path: &Path, settings: &[TilePortSetting]) -> Result<(), String> {
+pub fn replace_port_settings_at(
+    db_path: &Path,
+    settings: &[TilePortSetting],
+    actor: &NetworkChangeActor,
+) -> Result<(), String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
         .transaction()
         .map_err(|error| format!("failed to begin port setting replace transaction: {error}"))?;
+    let previous = list_all_port_settings_with_conn(&tx)?;
+    let same_port = |left: &TilePortSetting, right: &TilePortSetting| {
+        left.session_id == right.session_id && left.tile_id == right.tile_id && left.port == right.port
+    };
+    for setting in &previous {
+        if !settings.iter().any(|next| same_port(next, setting)) {
+            network_history::record_port_setting_with_conn(
+                &tx,
+                &setting.session_id,
+                &setting.tile_id,
+                setting.port,
+                Some(setting),
+                None,
+                actor,
+            )?;
+        }
+    }
+    for setting in settings {
+        network_history::record_port_setting_with_conn(
+            &tx,
+            &setting.session_id,
+            &setting.tile_id,
+            setting.port,
+            previous.iter().find(|existing| same_port(existing, setting)),
+            Some(setting),
+            actor,
+        )?;
+    }
     tx.execute("DELETE FROM tile_port_setting", [])
         .map_err(|error| format!("failed to clear port settings: {error}"))?;
     for setting in settings {
@@ -699,12 +849,13 @@ pub fn set_port_settings_at(
     port: TilePort,
     access_mode: Option<PortMode>,
     networking_mode: Option<PortNetworkingMode>,
+    actor: &NetworkChangeActor,
 ) -> Result<TilePortSetting, String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
         .transaction()
         .map_err(|error| format!("failed to begin port setting transaction: {error}"))?;
-    let updated = set_port_settings_with_conn(&tx, session_id, tile_id, kind, port, access_mode, networking_mode)?;
+    let updated = set_port_settings_with_conn(&tx, session_id, tile_id, kind, port, access_mode, networking_mode, actor)?;
     tx.commit()
         .map_err(|error| format!("failed to commit port setting transaction: {error}"))?;
     Ok(updated)
@@ -718,6 +869,7 @@ pub fn set_port_settings_with_conn(
     port: TilePort,
     access_mode: Option<PortMode>,
     networking_mode: Option<PortNetworkingMode>,
+    actor: &NetworkChangeActor,
 ) -> Result<TilePortSetting, String> {
     let default_access_mode = port_mode(kind, port);
     let default_networking_mode = default_port_networking_mode();
@@ -755,13 +907,24 @@ pub fn set_port_settings_with_conn(
         .map_err(|error| format!("failed to upsert port setting: {error}"))?;
     }
 
-    Ok(TilePortSetting {
+    let updated = TilePortSetting {
         session_id: session_id.to_string(),
         tile_id: tile_id.to_string(),
         port,
         access_mode: next_access_mode,
         networking_mode: next_networking_mode,
-    })
+    };
+    let is_default = next_access_mode == default_access_mode && next_networking_mode == default_networking_mode;
+    network_history::record_port_setting_with_conn(
+        conn,
+        session_id,
+        tile_id,
+        port,
+        existing.as_ref(),
+        (!is_default).then_some(&updated),
+        actor,
+    )?;
+    Ok(updated)
 }
 
 pub fn connect_at(
@@ -770,12 +933,13 @@ pub fn connect_at(
     from_port: TilePort,
     to: &NetworkTileDescriptor,
     to_port: TilePort,
+    actor: &NetworkChangeActor,
 ) -> Result<NetworkConnection, String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
         .transaction()
         .map_err(|error| format!("failed to begin network connect transaction: {error}"))?;
-    let connection = connect_with_conn(&tx, from, from_port, to, to_port)?;
+    let connection = connect_with_conn(&tx, from, from_port, to, to_port, actor)?;
     tx.commit()
         .map_err(|error| format!("failed to commit network connect transaction: {error}"))?;
     Ok(connection)
@@ -787,6 +951,7 @@ pub fn connect_with_conn(
     from_port: TilePort,
     to: &NetworkTileDescriptor,
     to_port: TilePort,
+    actor: &NetworkChangeActor,
 ) -> Result<NetworkConnection, String> {
     let port_settings = list_port_settings_with_conn(conn, &from.session_id)?;
     validate_connect(conn, from, from_port, to, to_port, &port_settings)?;
@@ -797,9 +962,49 @@ pub fn connect_with_conn(
         to.tile_id.clone(),
         to_port,
     );
+    insert_connection_with_conn(conn, &connection)?;
+    network_history::record_connection_with_conn(conn, NetworkHistoryEventKind::Connect, &connection, actor)?;
+    Ok(connection)
+}
+
+/// Inserts a connection row, recording the name each endpoint's slot carries
+/// at the time so a later redeclaration can follow the name instead of the slot.
+fn insert_connection_with_conn(conn: &Connection, connection: &NetworkConnection) -> Result<(), String> {
+    let from_port_name = port_name_with_conn(conn, &connection.from_tile_id, connection.from_port)?;
+    let to_port_name = port_name_with_conn(conn, &connection.to_tile_id, connection.to_port)?;
+    conn.execute(
+        "INSERT INTO network_connection (session_id, from_tile_id, from_port, to_tile_id, to_port, from_port_name, to_port_name)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            connection.session_id,
+            connection.from_tile_id,
+            connection.from_port.as_str(),
+            connection.to_tile_id,
+            connection.to_port.as_str(),
+            from_port_name,
+            to_port_name,
+        ],
+    )
+    .map_err(|error| {
+        format!(
+            "failed to insert network connection {}:{} <-> {}:{}: {error}",
+            connection.from_tile_id,
+            connection.from_port.as_str(),
+            connection.to_tile_id,
+            connection.to_port.as_str()
+        )
+    })?;
+    Ok(())
+}
+
+fn delete_connection_with_conn(conn: &Connection, connection: &NetworkConnection) -> Result<(), String> {
     conn.execute(
-        "INSERT INTO network_connection (session_id, from_tile_id, from_port, to_tile_id, to_port)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
+        "DELETE FROM network_connection
+         WHERE session_id = ?1
+           AND from_tile_id = ?2
+           AND from_port = ?3
+           AND to_tile_id = ?4
+           AND to_port = ?5",
         params![
             connection.session_id,
             connection.from_tile_id,
@@ -808,8 +1013,51 @@ pub fn connect_with_conn(
             connection.to_port.as_str()
         ],
     )
-    .map_err(|error| format!("failed to insert network connection: {error}"))?;
-    Ok(connection)
+    .map_err(|error| format!("failed to delete network connection: {error}"))?;
+    Ok(())
+}
+
+fn port_name_with_conn(conn: &Connection, tile_id: &str, port: TilePort) -> Result<Option<String>, String> {
+    Ok(tile_registry::list_named_ports_with_conn(conn, Some(tile_id))?
+        .into_iter()
+        .find(|named_port| named_port.port == port)
+        .map(|named_port| named_port.name))
+}
+
+/// Connections touching `tile_id` with the port name stored for that tile's
+/// endpoint, or `None` when the slot was unnamed at connect time.
+fn list_tile_connection_port_names_with_conn(
+    conn: &Connection,
+    session_id: &str,
+    tile_id: &str,
+) -> Result<Vec<(NetworkConnection, Option<String>)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT from_tile_id, from_port, to_tile_id, to_port, from_port_name, to_port_name
+             FROM network_connection
+             WHERE session_id = ?1 AND (from_tile_id = ?2 OR to_tile_id = ?2)
+             ORDER BY from_tile_id ASC, from_port ASC, to_tile_id ASC, to_port ASC",
+        )
+        .map_err(|error| format!("failed to prepare named connection query: {error}"))?;
+    let rows = stmt
+        .query_map(params![session_id, tile_id], |row| {
+            let connection = NetworkConnection {
+                session_id: session_id.to_string(),
+                from_tile_id: row.get(0)?,
+                from_port: parse_port(&row.get::<_, String>(1)?)?,
+                to_tile_id: row.get(2)?,
+                to_port: parse_port(&row.get::<_, String>(3)?)?,
+            };
+            let name = if connection.from_tile_id == tile_id {
+                row.get::<_, Option<String>>(4)?
+            } else {
+                row.get::<_, Option<String>>(5)?
+            };
+            Ok((connection, name))
+        })
+        .map_err(|error| format!("failed to query named connections: {error}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("failed to decode named connection rows: {error}"))
 }
 
 pub fn disconnect_at(
@@ -817,12 +1065,13 @@ pub fn disconnect_at(
     session_id: &str,
     tile_id: &str,
     port: TilePort,
+    actor: &NetworkChangeActor,
 ) -> Result<Option<NetworkConnection>, String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
         .transaction()
         .map_err(|error| format!("failed to begin network disconnect transaction: {error}"))?;
-    let removed = disconnect_with_conn(&tx, session_id, tile_id, port)?;
+    let removed = disconnect_with_conn(&tx, session_id, tile_id, port, actor)?;
     tx.commit()
         .map_err(|error| format!("failed to commit network disconnect transaction: {error}"))?;
     Ok(removed)
@@ -833,6 +1082,7 @@ pub fn disconnect_with_conn(
     session_id: &str,
     tile_id: &str,
     port: TilePort,
+    actor: &NetworkChangeActor,
 ) -> Result<Option<NetworkConnection>, String> {
     let existing = find_connection_for_port_with_conn(conn, session_id, tile_id, port)?;
     let Some(connection) = existing else {
@@ -854,6 +1104,7 @@ pub fn disconnect_with_conn(
         ],
     )
     .map_err(|error| format!("failed to delete network connection: {error}"))?;
+    network_history::record_connection_with_conn(conn, NetworkHistoryEventKind::Disconnect, &connection, actor)?;
     Ok(Some(connection))
 }
 
@@ -861,6 +1112,7 @@ pub fn disconnect_all_for_tile_at(
     db_path: &Path,
     session_id: &str,
     tile_id: &str,
+    actor: &NetworkChangeActor,
 ) -> Result<Vec<NetworkConnection>, String> {
     let mut conn = db::open_at(db_path)?;
     let tx = conn
@@ -888,160 +1140,527 @@ pub fn disconnect_all_for_tile_at(
             ],
         )
         .map_err(|error| format!("failed to delete network connection: {error}"))?;
+        network_history::record_connection_with_conn(&tx, NetworkHistoryEventKind::Disconnect, connection, actor)?;
     }
     tx.commit()
         .map_err(|error| format!("failed to commit network tile disconnect transaction: {error}"))?;
     Ok(removed)
 }
 
-pub fn component_for_tile(
+/// Replaces a tile's named ports. Connections follow their port name to its new
+/// slot and are dropped when the name goes away; slots that lose their name go
+/// back to default settings. Fails if a live connection would violate `accepts`
+/// or two connections would land on one slot. `peer_kinds` maps session tile ids
+/// to kinds for the `accepts` check.
+pub fn declare_named_ports_at(
+    db_path: &Path,
     session_id: &str,
-    start_tile_id: &str,
-    session_tiles: &[SessionTileInfo],
-    connections: &[NetworkConnection],
-) -> NetworkComponent {
-    let tile_by_id = session_tiles
-        .iter()
-        .cloned()
-        .map(|tile| (tile.tile_id.clone(), tile))
-        .collect::<HashMap<_, _>>();
-
-    let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
-    for connection in connections.iter().filter(|connection| connection.session_id == session_id) {
-        adjacency
-            .entry(connection.from_tile_id.as_str())
-            .or_default()
-            .push(connection.to_tile_id.as_str());
-        adjacency
-            .entry(connection.to_tile_id.as_str())
-            .or_default()
-            .push(connection.from_tile_id.as_str());
-    }
-
-    let mut visited = HashSet::new();
-    let mut queue = VecDeque::from([start_tile_id.to_string()]);
-    while let Some(tile_id) = queue.pop_front() {
-        if !visited.insert(tile_id.clone()) {
+    tile_id: &str,
+    kind: NetworkTileKind,
+    specs: &[NamedPortSpec],
+    peer_kinds: &HashMap<String, NetworkTileKind>,
+    actor: &NetworkChangeActor,
+) -> Result<Vec<NamedPort>, String> {
+    let mut conn = db::open_at(db_path)?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("failed to begin named port transaction: {error}"))?;
+    let previous = tile_registry::list_named_ports_with_conn(&tx, Some(tile_id))?;
+    let named_ports = plan_named_ports(tile_id, kind, specs, &previous)?;
+    let connections = list_tile_connection_port_names_with_conn(&tx, session_id, tile_id)?;
+    tile_registry::replace_named_ports_with_conn(&tx, tile_id, &named_ports)?;
+
+    let mut moved = Vec::new();
+    for (connection, name) in connections {
+        let slot = if connection.from_tile_id == tile_id {
+            connection.from_port
+        } else {
+            connection.to_port
+        };
+        let target = match name {
+            Some(name) => named_ports
+                .iter()
+                .find(|named_port| named_port.name == name)
+                .map(|named_port| named_port.port),
+            None => Some(slot),
+        };
+        if target == Some(slot) {
+            delete_connection_with_conn(&tx, &connection)?;
+            insert_connection_with_conn(&tx, &connection)?;
             continue;
         }
-        for neighbor in adjacency.get(tile_id.as_str()).into_iter().flatten() {
-            if !visited.contains(*neighbor) {
-                queue.push_back((*neighbor).to_string());
-            }
+        delete_connection_with_conn(&tx, &connection)?;
+        network_history::record_connection_with_conn(&tx, NetworkHistoryEventKind::Disconnect, &connection, actor)?;
+        if let Some(target) = target {
+            let (from_port, to_port) = if connection.from_tile_id == tile_id {
+                (target, connection.to_port)
+            } else {
+                (connection.from_port, target)
+            };
+            moved.push(canonical_connection(
+                connection.session_id.clone(),
+                connection.from_tile_id.clone(),
+                from_port,
+                connection.to_tile_id.clone(),
+                to_port,
+            ));
         }
     }
-
-    if visited.is_empty() {
-        visited.insert(start_tile_id.to_string());
+    for connection in &moved {
+        let port = if connection.from_tile_id == tile_id {
+            connection.from_port
+        } else {
+            connection.to_port
+        };
+        if find_connection_for_port_with_conn(&tx, session_id, tile_id, port)?.is_some() {
+            return Err(format!(
+                "port {} on {tile_id} would carry more than one connection",
+                port.as_str()
+            ));
+        }
+        insert_connection_with_conn(&tx, connection)?;
+        network_history::record_connection_with_conn(&tx, NetworkHistoryEventKind::Connect, connection, actor)?;
     }
 
-    let mut tiles = visited
-        .iter()
-        .map(|tile_id| {
-            tile_by_id
-                .get(tile_id)
-                .cloned()
-                .unwrap_or(SessionTileInfo::placeholder(tile_id.clone(), session_id))
-        })
-        .collect::<Vec<_>>();
-    tiles.sort_by(|left, right| left.tile_id.cmp(&right.tile_id));
+    for (connection, _) in list_tile_connection_port_names_with_conn(&tx, session_id, tile_id)? {
+        let (port, peer_tile_id) = if connection.from_tile_id == tile_id {
+            (connection.from_port, &connection.to_tile_id)
+        } else {
+            (connection.to_port, &connection.from_tile_id)
+        };
+        let (Some(named_port), Some(peer_kind)) = (
+            named_ports.iter().find(|named_port| named_port.port == port),
+            peer_kinds.get(peer_tile_id),
+        ) else {
+            continue;
+        };
+        if !named_port_accepts(named_port, *peer_kind) {
+            return Err(format!(
+                "port {} on {tile_id} is connected to {peer_tile_id} but would only accept {} tiles",
+                named_port.name,
+                accepted_kind_list(named_port)
+            ));
+        }
+    }
 
-    let mut tile_ids = visited;
-    tile_ids.insert(start_tile_id.to_string());
-    let mut component_connections = connections
+    for removed in previous
         .iter()
-        .filter(|connection| connection.session_id == session_id)
-        .filter(|connection| {
-            tile_ids.contains(&connection.from_tile_id) && tile_ids.contains(&connection.to_tile_id)
-        })
-        .cloned()
-        .collect::<Vec<_>>();
-    component_connections.sort_by(|left, right| {
-        left.from_tile_id
-            .cmp(&right.from_tile_id)
-            .then_with(|| left.from_port.cmp(&right.from_port))
-            .then_with(|| left.to_tile_id.cmp(&right.to_tile_id))
-            .then_with(|| left.to_port.cmp(&right.to_port))
-    });
-
-    NetworkComponent {
-        session_id: session_id.to_string(),
-        sender_tile_id: Some(start_tile_id.to_string()),
-        tiles,
-        connections: component_connections,
-        port_settings: Vec::new(),
+        .filter(|removed| !named_ports.iter().any(|named_port| named_port.port == removed.port))
+    {
+        set_port_settings_with_conn(
+            &tx,
+            session_id,
+            tile_id,
+            kind,
+            removed.port,
+            Some(port_mode(kind, removed.port)),
+            Some(default_port_networking_mode()),
+            actor,
+        )?;
+    }
+    for named_port in &named_ports {
+        set_port_settings_with_conn(
+            &tx,
+            session_id,
+            tile_id,
+            kind,
+            named_port.port,
+            Some(named_port.access_mode),
+            None,
+            actor,
+        )?;
     }
+    tx.commit()
+        .map_err(|error| format!("failed to commit named port transaction: {error}"))?;
+    Ok(named_ports)
 }
 
-pub fn sender_visible_component_for_tile(
-    session_id: &str,
-    start_tile_id: &str,
-    session_tiles: &[SessionTileInfo],
-    connections: &[NetworkConnection],
-    port_settings: &[TilePortSetting],
+/// Assigns each spec a slot: its explicit `port`, else the slot the same name
+/// held in `previous` when still free, else the first free slot. Names sit on
+/// top of the 16 physical slots, so a tile can declare at most 16 of them.
+pub fn plan_named_ports(
+    tile_id: &str,
+    kind: NetworkTileKind,
+    specs: &[NamedPortSpec],
+    previous: &[NamedPort],
+) -> Result<Vec<NamedPort>, String> {
+    if specs.len() > TilePort::ALL.len() {
+        return Err(format!(
+            "tile {tile_id} declares {} named ports, but a tile has at most {} port slots",
+            specs.len(),
+            TilePort::ALL.len()
+        ));
+    }
+    let mut used_names = HashSet::new();
+    let mut used_ports = specs.iter().filter_map(|spec| spec.port).collect::<Vec<_>>();
+    if let Some(duplicate) = used_ports
+        .iter()
+        .enumerate()
+        .find_map(|(index, port)| used_ports[..index].contains(port).then_some(*port))
+    {
+        return Err(format!("port {} is named more than once", duplicate.as_str()));
+    }
+
+    let mut named_ports = Vec::new();
+    for spec in specs {
+        if !is_valid_port_name(&spec.name) {
+            return Err(format!("invalid port name: {}", spec.name));
+        }
+        if !used_names.insert(spec.name.as_str()) {
+            return Err(format!("port name {} is declared more than once", spec.name));
+        }
+        let port = match spec.port {
+            Some(port) => port,
+            None => {
+                let port = previous
+                    .iter()
+                    .find(|named_port| named_port.name == spec.name)
+                    .map(|named_port| named_port.port)
+                    .filter(|candidate| !used_ports.contains(candidate))
+                    .or_else(|| TilePort::ALL.into_iter().find(|candidate| !used_ports.contains(candidate)))
+                    .ok_or_else(|| format!("no free port slot for named port {}", spec.name))?;
+                used_ports.push(port);
+                port
+            }
+        };
+        named_ports.push(NamedPort {
+            tile_id: tile_id.to_string(),
+            name: spec.name.clone(),
+            port,
+            access_mode: spec.access_mode.unwrap_or_else(|| port_mode(kind, port)),
+            accepts: spec.accepts.clone(),
+        });
+    }
+    Ok(named_ports)
+}
+
+pub fn is_valid_port_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 32
+        && chars.next().is_some_and(|first| first.is_ascii_lowercase())
+        && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_')
+        && parse_port(name).is_err()
+}
+
+pub fn resolve_port(value: &str, named_ports: &[NamedPort]) -> Result<TilePort, String> {
+    if let Ok(port) = parse_port(value) {
+        return Ok(port);
+    }
+    named_ports
+        .iter()
+        .find(|named_port| named_port.name == value)
+        .map(|named_port| named_port.port)
+        .ok_or_else(|| format!("unknown port: {value}"))
+}
+
+pub fn resolve_port_at(db_path: &Path, tile_id: &str, value: &str) -> Result<TilePort, String> {
+    if let Ok(port) = parse_port(value) {
+        return Ok(port);
+    }
+    let named_ports = tile_registry::list_named_ports_at(db_path, Some(tile_id))?;
+    resolve_port(value, &named_ports).map_err(|error| format!("{error} on {tile_id}"))
+}
+
+pub fn list_port_segments_at(db_path: &Path, session_id: &str) -> Result<Vec<PortSegment>, String> {
+    let conn = db::open_at(db_path)?;
+    list_port_segments_with_conn(&conn, session_id)
+}
+
+pub fn list_port_segments_with_conn(conn: &Connection, session_id: &str) -> Result<Vec<PortSegment>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tile_id, port, segment
+             FROM tile_port_segment
+             WHERE session_id = ?1
+             ORDER BY tile_id ASC, port ASC, segment ASC",
+        )
+        .map_err(|error| format!("failed to prepare port segment query: {error}"))?;
+    let rows = stmt
+        .query_map([session_id], |row| {
+            Ok(PortSegment {
+                session_id: session_id.to_string(),
+                tile_id: row.get(0)?,
+                port: parse_port(&row.get::<_, String>(1)?)?,
+                segment: row.get(2)?,
+            })
+        })
+        .map_err(|error| format!("failed to query port segments: {error}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("failed to decode port segment rows: {error}"))
+}
+
+pub fn set_port_segments_at(
+    db_path: &Path,
+    session_id: &str,
+    tile_id: &str,
+    port: TilePort,
+    segments: &[String],
+) -> Result<Vec<String>, String> {
+    if let Some(invalid) = segments.iter().find(|segment| !is_valid_segment_name(segment)) {
+        return Err(format!("invalid segment name: {invalid}"));
+    }
+    let segments = segments.iter().cloned().collect::<BTreeSet<_>>();
+    let mut conn = db::open_at(db_path)?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("failed to begin port segment transaction: {error}"))?;
+    tx.execute(
+        "DELETE FROM tile_port_segment WHERE session_id = ?1 AND tile_id = ?2 AND port = ?3",
+        params![session_id, tile_id, port.as_str()],
+    )
+    .map_err(|error| format!("failed to clear segments for {tile_id}:{}: {error}", port.as_str()))?;
+    for segment in &segments {
+        tx.execute(
+            "INSERT INTO tile_port_segment (session_id, tile_id, port, segment) VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, tile_id, port.as_str(), segment],
+        )
+        .map_err(|error| format!("failed to insert segment {segment} for {tile_id}:{}: {error}", port.as_str()))?;
+    }
+    tx.commit()
+        .map_err(|error| format!("failed to commit port segment transaction: {error}"))?;
+    Ok(segments.into_iter().collect())
+}
+
+pub fn replace_session_port_segments_at(
+    db_path: &Path,
+    session_id: &str,
+    segments: &[PortSegment],
+) -> Result<(), String> {
+    let mut conn = db::open_at(db_path)?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("failed to begin port segment transaction: {error}"))?;
+    tx.execute("DELETE FROM tile_port_segment WHERE session_id = ?1", [session_id])
+        .map_err(|error| format!("failed to clear port segments for {session_id}: {error}"))?;
+    for labeled in segments {
+        tx.execute(
+            "INSERT OR IGNORE INTO tile_port_segment (session_id, tile_id, port, segment) VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, labeled.tile_id, labeled.port.as_str(), labeled.segment],
+        )
+        .map_err(|error| format!("failed to insert segment {} for {}: {error}", labeled.segment, labeled.tile_id))?;
+    }
+    tx.commit()
+        .map_err(|error| format!("failed to commit port segment transaction: {error}"))?;
+    Ok(())
+}
+
+pub fn is_valid_segment_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 32
+        && chars.next().is_some_and(|first| first.is_ascii_lowercase())
+        && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_')
+}
+
+pub fn connection_segments(connection: &NetworkConnection, port_segments: &[PortSegment]) -> BTreeSet<String> {
+    port_segments
+        .iter()
+        .filter(|labeled| {
+            (labeled.tile_id == connection.from_tile_id && labeled.port == connection.from_port)
+                || (labeled.tile_id == connection.to_tile_id && labeled.port == connection.to_port)
+        })
+        .map(|labeled| labeled.segment.clone())
+        .collect()
+}
+
+// A tile belongs to a segment when one of its ports carries the label or it
+// sits on either end of a connection that does.
+pub fn network_segments(
+    tile_ids: &HashSet<String>,
+    connections: &[NetworkConnection],
+    port_segments: &[PortSegment],
+) -> Vec<NetworkSegment> {
+    let mut segments: BTreeMap<String, (BTreeSet<String>, Vec<NetworkConnection>)> = BTreeMap::new();
+    for labeled in port_segments.iter().filter(|labeled| tile_ids.contains(&labeled.tile_id)) {
+        segments
+            .entry(labeled.segment.clone())
+            .or_default()
+            .0
+            .insert(labeled.tile_id.clone());
+    }
+    for connection in connections {
+        for segment in connection_segments(connection, port_segments) {
+            let entry = segments.entry(segment).or_default();
+            entry.0.insert(connection.from_tile_id.clone());
+            entry.0.insert(connection.to_tile_id.clone());
+            entry.1.push(connection.clone());
+        }
+    }
+    segments
+        .into_iter()
+        .map(|(name, (tile_ids, connections))| NetworkSegment {
+            name,
+            tile_ids: tile_ids.into_iter().collect(),
+            connections,
+        })
+        .collect()
+}
+
+pub fn annotate_segments(mut component: NetworkComponent, port_segments: &[PortSegment]) -> NetworkComponent {
+    let tile_ids = component
+        .tiles
+        .iter()
+        .map(|tile| tile.tile_id.clone())
+        .collect::<HashSet<_>>();
+    component.segments = network_segments(&tile_ids, &component.connections, port_segments);
+    component
+}
+
+pub fn segment_tile_ids(component: &NetworkComponent, segment: &str) -> HashSet<String> {
+    component
+        .segments
+        .iter()
+        .find(|candidate| candidate.name == segment)
+        .map(|candidate| candidate.tile_ids.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn filter_component_by_segment(mut component: NetworkComponent, segment: Option<&str>) -> NetworkComponent {
+    let Some(segment) = segment else {
+        return component;
+    };
+    let tile_ids = segment_tile_ids(&component, segment);
+    let segment_connections = component
+        .segments
+        .iter()
+        .find(|candidate| candidate.name == segment)
+        .map(|candidate| candidate.connections.clone())
+        .unwrap_or_default();
+    component.tiles.retain(|tile| tile_ids.contains(&tile.tile_id));
+    component
+        .connections
+        .retain(|connection| segment_connections.contains(connection));
+    component
+        .port_settings
+        .retain(|setting| tile_ids.contains(&setting.tile_id));
+    component.segments.retain(|candidate| candidate.name == segment);
+    component
+}
+
+pub fn component_for_tile(
+    session_id: &str,
+    start_tile_id: &str,
+    session_tiles: &[SessionTileInfo],
+    connections: &[NetworkConnection],
 ) -> NetworkComponent {
     let tile_by_id = session_tiles
         .iter()
         .cloned()
         .map(|tile| (tile.tile_id.clone(), tile))
         .collect::<HashMap<_, _>>();
-    let session_connections = connections
-        .iter()
-        .filter(|connection| connection.session_id == session_id)
-        .cloned()
-        .collect::<Vec<_>>();
 
-    let mut adjacency: HashMap<&str, Vec<usize>> = HashMap::new();
-    for (index, connection) in session_connections.iter().enumerate() {
+    let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+    for connection in connections.iter().filter(|connection| connection.session_id == session_id) {
         adjacency
             .entry(connection.from_tile_id.as_str())
             .or_default()
-            .push(index);
+            .push(connection.to_tile_id.as_str());
         adjacency
             .entry(connection.to_tile_id.as_str())
             .or_default()
-            .push(index);
+            .push(connection.from_tile_id.as_str());
     }
 
-    let mut visited_states = HashSet::new();
-    let mut visible_tile_ids = HashSet::new();
-    let mut visible_connection_keys = HashSet::new();
-    let mut queue = VecDeque::from([(start_tile_id.to_string(), None::<TilePort>)]);
-
-    while let Some((tile_id, ingress_port)) = queue.pop_front() {
-        if !visited_states.insert((tile_id.clone(), ingress_port)) {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([start_tile_id.to_string()]);
+    while let Some(tile_id) = queue.pop_front() {
+        if !visited.insert(tile_id.clone()) {
             continue;
         }
-        visible_tile_ids.insert(tile_id.clone());
-
-        if matches!(
-            ingress_port,
-            Some(port) if effective_port_networking_mode(&tile_id, port, port_settings) == PortNetworkingMode::Gateway
-        ) {
-            continue;
+        for neighbor in adjacency.get(tile_id.as_str()).into_iter().flatten() {
+            if !visited.contains(*neighbor) {
+                queue.push_back((*neighbor).to_string());
+            }
         }
+    }
 
-        for connection_index in adjacency.get(tile_id.as_str()).into_iter().flatten() {
-            let connection = &session_connections[*connection_index];
-            let (local_port, next_tile_id, next_port) = if connection.from_tile_id == tile_id {
-                (connection.from_port, connection.to_tile_id.as_str(), connection.to_port)
-            } else {
-                (connection.to_port, connection.from_tile_id.as_str(), connection.from_port)
-            };
+    if visited.is_empty() {
+        visited.insert(start_tile_id.to_string());
+    }
 
-            if let Some(in_port) = ingress_port {
-                if local_port == in_port {
-                    continue;
-                }
-                if effective_port_networking_mode(&tile_id, local_port, port_settings) != PortNetworkingMode::Broadcast {
-                    continue;
-                }
-            }
+    let mut tiles = visited
+        .iter()
+        .map(|tile_id| {
+            tile_by_id
+                .get(tile_id)
+                .cloned()
+                .unwrap_or(SessionTileInfo::placeholder(tile_id.clone(), session_id))
+        })
+        .collect::<Vec<_>>();
+    tiles.sort_by(|left, right| left.tile_id.cmp(&right.tile_id));
 
-            visible_connection_keys.insert(connection_identity(connection));
-            queue.push_back((next_tile_id.to_string(), Some(next_port)));
-        }
+    let mut tile_ids = visited;
+    tile_ids.insert(start_tile_id.to_string());
+    let mut component_connections = connections
+        .iter()
+        .filter(|connection| connection.session_id == session_id)
+        .filter(|connection| {
+            tile_ids.contains(&connection.from_tile_id) && tile_ids.contains(&connection.to_tile_id)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    component_connections.sort_by(|left, right| {
+        left.from_tile_id
+            .cmp(&right.from_tile_id)
+            .then_with(|| left.from_port.cmp(&right.from_port))
+            .then_with(|| left.to_tile_id.cmp(&right.to_tile_id))
+            .then_with(|| left.to_port.cmp(&right.to_port))
+    });
+
+    NetworkComponent {
+        session_id: session_id.to_string(),
+        sender_tile_id: Some(start_tile_id.to_string()),
+        tiles,
+        connections: component_connections,
+        port_settings: Vec::new(),
+        segments: Vec::new(),
     }
+}
+
+/// Narrows the current tiles to the ones that existed in a rewound topology,
+/// standing in placeholders of the recorded kind for tiles destroyed since.
+pub fn historical_session_tiles(
+    session_id: &str,
+    current: Vec<SessionTileInfo>,
+    past_tiles: &[network_history::NetworkTopologyTile],
+) -> Vec<SessionTileInfo> {
+    let mut current_by_id = current
+        .into_iter()
+        .map(|tile| (tile.tile_id.clone(), tile))
+        .collect::<HashMap<_, _>>();
+    past_tiles
+        .iter()
+        .map(|past| {
+            current_by_id.remove(&past.tile_id).unwrap_or_else(|| SessionTileInfo {
+                kind: network_tile_kind_from_record_kind(past.kind, None, "", ""),
+                ..SessionTileInfo::placeholder(past.tile_id.clone(), session_id)
+            })
+        })
+        .collect()
+}
+
+pub fn sender_visible_component_for_tile(
+    session_id: &str,
+    start_tile_id: &str,
+    session_tiles: &[SessionTileInfo],
+    connections: &[NetworkConnection],
+    port_settings: &[TilePortSetting],
+) -> NetworkComponent {
+    let tile_by_id = session_tiles
+        .iter()
+        .cloned()
+        .map(|tile| (tile.tile_id.clone(), tile))
+        .collect::<HashMap<_, _>>();
+    let session_connections = connections
+        .iter()
+        .filter(|connection| connection.session_id == session_id)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let SenderVisibleTraversal {
+        mut visible_tile_ids,
+        visible_connection_indexes,
+        ..
+    } = sender_visible_traversal(start_tile_id, &session_connections, port_settings);
 
     if visible_tile_ids.is_empty() {
         visible_tile_ids.insert(start_tile_id.to_string());
@@ -1060,7 +1679,9 @@ pub fn sender_visible_component_for_tile(
 
     let mut component_connections = session_connections
         .into_iter()
-        .filter(|connection| visible_connection_keys.contains(&connection_identity(connection)))
+        .enumerate()
+        .filter(|(index, _)| visible_connection_indexes.contains(index))
+        .map(|(_, connection)| connection)
         .collect::<Vec<_>>();
     component_connections.sort_by(|left, right| {
         left.from_tile_id
@@ -1083,7 +1704,231 @@ pub fn sender_visible_component_for_tile(
         tiles,
         connections: component_connections,
         port_settings: component_port_settings,
+        segments: Vec::new(),
+    }
+}
+
+pub fn network_path_between(
+    session_id: &str,
+    from_tile_id: &str,
+    to_tile_id: &str,
+    session_tiles: &[SessionTileInfo],
+    connections: &[NetworkConnection],
+    port_settings: &[TilePortSetting],
+) -> NetworkPath {
+    let kind_by_id = session_tiles
+        .iter()
+        .map(|tile| (tile.tile_id.as_str(), tile.kind))
+        .collect::<HashMap<_, _>>();
+    let kind_for = |tile_id: &str| kind_by_id.get(tile_id).copied().unwrap_or(NetworkTileKind::Shell);
+    let session_connections = connections
+        .iter()
+        .filter(|connection| connection.session_id == session_id)
+        .cloned()
+        .collect::<Vec<_>>();
+    let traversal = sender_visible_traversal(from_tile_id, &session_connections, port_settings);
+
+    let mut path = NetworkPath {
+        session_id: session_id.to_string(),
+        from_tile_id: from_tile_id.to_string(),
+        to_tile_id: to_tile_id.to_string(),
+        reachable: false,
+        hops: Vec::new(),
+        access: None,
+        blocked_by: None,
+    };
+
+    if from_tile_id == to_tile_id {
+        path.reachable = true;
+        return path;
+    }
+
+    let Some(arrival) = traversal.first_arrivals.get(to_tile_id) else {
+        path.blocked_by = first_gateway_on_unrestricted_path(from_tile_id, to_tile_id, &session_connections, port_settings);
+        return path;
+    };
+
+    let mut state = arrival.clone();
+    let mut hops = Vec::new();
+    while let Some((previous, connection_index)) = traversal.parents.get(&state) {
+        let connection = &session_connections[*connection_index];
+        let (from_port, to_port) = if connection.from_tile_id == previous.0 && connection.to_tile_id == state.0 {
+            (connection.from_port, connection.to_port)
+        } else {
+            (connection.to_port, connection.from_port)
+        };
+        hops.push(NetworkPathHop {
+            from_tile_id: previous.0.clone(),
+            from_port,
+            from_access_mode: effective_port_mode(&previous.0, kind_for(&previous.0), from_port, port_settings),
+            to_tile_id: state.0.clone(),
+            to_port,
+            to_access_mode: effective_port_mode(&state.0, kind_for(&state.0), to_port, port_settings),
+        });
+        state = previous.clone();
+    }
+    hops.reverse();
+
+    path.reachable = true;
+    path.hops = hops;
+    path.access = Some(rpc_access_for_sender_to_tile(
+        Some(from_tile_id),
+        to_tile_id,
+        kind_for(to_tile_id),
+        &session_connections,
+        port_settings,
+    ));
+    path
+}
+
+type TraversalState = (String, Option<TilePort>);
+
+struct SenderVisibleTraversal {
+    visible_tile_ids: HashSet<String>,
+    visible_connection_indexes: HashSet<usize>,
+    first_arrivals: HashMap<String, TraversalState>,
+    parents: HashMap<TraversalState, (TraversalState, usize)>,
+}
+
+// Traffic entering a tile through a gateway port stops there, and traffic
+// passing through a tile may only leave through broadcast ports.
+fn sender_visible_traversal(
+    start_tile_id: &str,
+    session_connections: &[NetworkConnection],
+    port_settings: &[TilePortSetting],
+) -> SenderVisibleTraversal {
+    let mut adjacency: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, connection) in session_connections.iter().enumerate() {
+        adjacency
+            .entry(connection.from_tile_id.as_str())
+            .or_default()
+            .push(index);
+        adjacency
+            .entry(connection.to_tile_id.as_str())
+            .or_default()
+            .push(index);
+    }
+
+    let mut visited_states = HashSet::new();
+    let mut traversal = SenderVisibleTraversal {
+        visible_tile_ids: HashSet::new(),
+        visible_connection_indexes: HashSet::new(),
+        first_arrivals: HashMap::new(),
+        parents: HashMap::new(),
+    };
+    let mut queue = VecDeque::from([(start_tile_id.to_string(), None::<TilePort>)]);
+
+    while let Some(state) = queue.pop_front() {
+        if !visited_states.insert(state.clone()) {
+            continue;
+        }
+        let (tile_id, ingress_port) = &state;
+        if traversal.visible_tile_ids.insert(tile_id.clone()) {
+            traversal.first_arrivals.insert(tile_id.clone(), state.clone());
+        }
+
+        if matches!(
+            ingress_port,
+            Some(port) if effective_port_networking_mode(tile_id, *port, port_settings) == PortNetworkingMode::Gateway
+        ) {
+            continue;
+        }
+
+        for connection_index in adjacency.get(tile_id.as_str()).into_iter().flatten() {
+            let connection = &session_connections[*connection_index];
+            let (local_port, next_tile_id, next_port) = if connection.from_tile_id == *tile_id {
+                (connection.from_port, connection.to_tile_id.as_str(), connection.to_port)
+            } else {
+                (connection.to_port, connection.from_tile_id.as_str(), connection.from_port)
+            };
+
+            if let Some(in_port) = ingress_port {
+                if local_port == *in_port {
+                    continue;
+                }
+                if effective_port_networking_mode(tile_id, local_port, port_settings) != PortNetworkingMode::Broadcast {
+                    continue;
+                }
+            }
+
+            traversal.visible_connection_indexes.insert(*connection_index);
+            let next_state = (next_tile_id.to_string(), Some(next_port));
+            traversal
+                .parents
+                .entry(next_state.clone())
+                .or_insert_with(|| (state.clone(), *connection_index));
+            queue.push_back(next_state);
+        }
+    }
+
+    traversal
+}
+
+fn first_gateway_on_unrestricted_path(
+    from_tile_id: &str,
+    to_tile_id: &str,
+    session_connections: &[NetworkConnection],
+    port_settings: &[TilePortSetting],
+) -> Option<NetworkPathBlock> {
+    let mut parents: HashMap<&str, &NetworkConnection> = HashMap::new();
+    let mut visited = HashSet::from([from_tile_id]);
+    let mut queue = VecDeque::from([from_tile_id]);
+    while let Some(tile_id) = queue.pop_front() {
+        if tile_id == to_tile_id {
+            break;
+        }
+        for connection in session_connections {
+            let next_tile_id = if connection.from_tile_id == tile_id {
+                connection.to_tile_id.as_str()
+            } else if connection.to_tile_id == tile_id {
+                connection.from_tile_id.as_str()
+            } else {
+                continue;
+            };
+            if visited.insert(next_tile_id) {
+                parents.insert(next_tile_id, connection);
+                queue.push_back(next_tile_id);
+            }
+        }
+    }
+
+    let mut current = to_tile_id;
+    let mut tile_path = vec![current];
+    let mut hops = Vec::new();
+    while let Some(connection) = parents.get(current) {
+        current = if connection.to_tile_id == current {
+            connection.from_tile_id.as_str()
+        } else {
+            connection.to_tile_id.as_str()
+        };
+        hops.push(*connection);
+        tile_path.push(current);
     }
+    if current != from_tile_id {
+        return None;
+    }
+    tile_path.reverse();
+    hops.reverse();
+
+    for (index, connection) in hops.iter().enumerate().skip(1) {
+        let tile_id = tile_path[index];
+        let ingress = hops[index - 1];
+        let ingress_port = if ingress.to_tile_id == tile_id { ingress.to_port } else { ingress.from_port };
+        if effective_port_networking_mode(tile_id, ingress_port, port_settings) == PortNetworkingMode::Gateway {
+            return Some(NetworkPathBlock {
+                tile_id: tile_id.to_string(),
+                port: ingress_port,
+            });
+        }
+        let egress_port = if connection.from_tile_id == tile_id { connection.from_port } else { connection.to_port };
+        if effective_port_networking_mode(tile_id, egress_port, port_settings) != PortNetworkingMode::Broadcast {
+            return Some(NetworkPathBlock {
+                tile_id: tile_id.to_string(),
+                port: egress_port,
+            });
+        }
+    }
+    None
 }
 
 pub fn filter_component(mut component: NetworkComponent, tile_type: Option<TileTypeFilter>) -> NetworkComponent {
@@ -1102,15 +1947,6 @@ pub fn filter_component(mut component: NetworkComponent, tile_type: Option<TileT
     component
 }
 
-fn connection_identity(connection: &NetworkConnection) -> (String, TilePort, String, TilePort) {
-    (
-        connection.from_tile_id.clone(),
-        connection.from_port,
-        connection.to_tile_id.clone(),
-        connection.to_port,
-    )
-}
-
 pub fn port_mode(kind: NetworkTileKind, port: TilePort) -> PortMode {
     match kind {
         NetworkTileKind::Work => {
@@ -1158,19 +1994,29 @@ pub fn effective_port_networking_mode(
 
 pub fn dispatchable_messages(kind: NetworkTileKind) -> &'static [&'static str] {
     match kind {
-        NetworkTileKind::Shell => &["get", "output_read", "input_send", "exec", "role_set"],
-        NetworkTileKind::Browser => &["get", "navigate", "load", "drive"],
-        NetworkTileKind::Agent | NetworkTileKind::RootAgent => {
-            &["get", "output_read", "input_send", "exec", "role_set"]
-        }
+        NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent => &[
+            "get",
+            "output_read",
+            "output_capture",
+            "wait_for_output",
+            "screenshot",
+            "process_info",
+            "input_send",
+            "exec",
+            "role_set",
+        ],
+        NetworkTileKind::Browser => &["get", "navigate", "load", "drive", "console_read", "record_start", "record_stop"],
         NetworkTileKind::Work => &["get", "stage_start", "stage_complete", "review_approve", "review_improve"],
     }
 }
 
 pub fn readable_messages(kind: NetworkTileKind) -> &'static [&'static str] {
     match kind {
-        NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent => &["get", "output_read"],
-        NetworkTileKind::Browser | NetworkTileKind::Work => &["get"],
+        NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent => {
+            &["get", "output_read", "output_capture", "wait_for_output", "screenshot", "process_info"]
+        }
+        NetworkTileKind::Browser => &["get", "console_read"],
+        NetworkTileKind::Work => &["get"],
     }
 }
 
@@ -1287,9 +2133,59 @@ fn tile_subcommand(
         name: name.to_string(),
         description: Some(description.to_string()),
         args,
+        is_async: false,
+        timeout_ms: None,
+        args_schema: None,
+        result_schema: None,
     }
 }
 
+/// Flattens the top-level properties of an object `args_schema` into arg specs
+/// so schema-only methods still list their arguments like built-in messages.
+fn message_args_from_schema(schema: &serde_json::Value) -> Vec<TileMessageArgSpec> {
+    let Some(properties) = schema.get("properties").and_then(serde_json::Value::as_object) else {
+        return Vec::new();
+    };
+    let required = schema
+        .get("required")
+        .and_then(serde_json::Value::as_array)
+        .map(|names| names.iter().filter_map(serde_json::Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let arg_type = match property.get("type") {
+                Some(serde_json::Value::String(arg_type)) => arg_type.clone(),
+                Some(serde_json::Value::Array(types)) => types
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("|"),
+                _ => "any".to_string(),
+            };
+            TileMessageArgSpec {
+                name: name.clone(),
+                arg_type,
+                required: required.contains(&name.as_str()),
+                description: property
+                    .get("description")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string),
+                enum_values: property
+                    .get("enum")
+                    .and_then(serde_json::Value::as_array)
+                    .map(|values| {
+                        values
+                            .iter()
+                            .map(|value| value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
 fn call_actions_for_access(kind: NetworkTileKind, access: TileRpcAccess) -> Vec<&'static str> {
     std::iter::once("get")
         .chain(
@@ -1328,7 +2224,73 @@ fn message_spec_for_kind(kind: NetworkTileKind, message_name: &str) -> TileMessa
         (NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent, "output_read") => {
             tile_message(
                 "output_read",
-                "Read captured terminal output from the tile.",
+                "Read captured terminal output from the tile without consuming it.",
+                vec![
+                    optional_message_arg(
+                        "since_offset",
+                        "number",
+                        "Byte offset to resume from, usually the previous next_offset. Defaults to the oldest retained byte.",
+                    ),
+                    optional_message_arg("max_bytes", "number", "Maximum number of bytes to return."),
+                ],
+                Vec::new(),
+            )
+        }
+        (NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent, "output_capture") => {
+            tile_message(
+                "output_capture",
+                "Capture terminal scrollback from tmux history by line range, optionally searching it.",
+                vec![
+                    optional_message_arg(
+                        "start_line",
+                        "number",
+                        "First line to capture. 0 is the top of the visible screen; negative values reach into history.",
+                    ),
+                    optional_message_arg("end_line", "number", "Last line to capture. Defaults to the bottom of the visible screen."),
+                    optional_message_arg("preserve_ansi", "boolean", "Keep ANSI color and attribute escapes instead of plain text."),
+                    optional_message_arg("join_wrapped", "boolean", "Join lines that were soft-wrapped by the terminal width."),
+                    optional_message_arg(
+                        "search",
+                        "string",
+                        "Return matching lines with their line numbers. Without start_line the whole history is searched.",
+                    ),
+                ],
+                Vec::new(),
+            )
+        }
+        (NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent, "wait_for_output") => {
+            tile_message(
+                "wait_for_output",
+                "Block until new terminal output matches a regex or the timeout elapses.",
+                vec![
+                    required_message_arg("pattern", "string", "Regular expression matched against ANSI-stripped output."),
+                    optional_message_arg("timeout_ms", "number", "Maximum time to wait. Defaults to 30000."),
+                    optional_message_arg(
+                        "since_offset",
+                        "number",
+                        "Output offset to start matching from, usually a previous next_offset. Defaults to output arriving after the call.",
+                    ),
+                    optional_message_arg("context_lines", "number", "Lines of surrounding output to return with the match. Defaults to 2."),
+                ],
+                Vec::new(),
+            )
+        }
+        (NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent, "screenshot") => {
+            tile_message(
+                "screenshot",
+                "Return the tile's visible terminal screen with cursor position.",
+                vec![optional_message_arg(
+                    "format",
+                    "string",
+                    "One of `text`, `ansi`, or `png`. Defaults to `text`.",
+                )],
+                Vec::new(),
+            )
+        }
+        (NetworkTileKind::Shell | NetworkTileKind::Agent | NetworkTileKind::RootAgent, "process_info") => {
+            tile_message(
+                "process_info",
+                "List the process tree under the tile's terminal with command lines, CPU time, RSS and start time.",
                 Vec::new(),
                 Vec::new(),
             )
@@ -1345,7 +2307,15 @@ fn message_spec_for_kind(kind: NetworkTileKind, message_name: &str) -> TileMessa
             tile_message(
                 "exec",
                 "Send a shell command to the existing terminal process and press Enter.",
-                vec![required_message_arg("command", "string", "Shell command to send to the tile terminal.")],
+                vec![
+                    required_message_arg("command", "string", "Shell command to send to the tile terminal."),
+                    optional_message_arg(
+                        "wait",
+                        "boolean",
+                        "Wait for the command to finish and return exit_code, duration_ms, and output.",
+                    ),
+                    optional_message_arg("timeout_ms", "number", "Maximum time to wait when wait is true. Defaults to 30000."),
+                ],
                 Vec::new(),
             )
         }
@@ -1373,6 +2343,65 @@ fn message_spec_for_kind(kind: NetworkTileKind, message_name: &str) -> TileMessa
             )],
             Vec::new(),
         ),
+        (NetworkTileKind::Browser, "console_read") => tile_message(
+            "console_read",
+            "Read console messages, uncaught errors, and unhandled rejections captured from the browser page.",
+            vec![
+                optional_message_arg(
+                    "since",
+                    "number",
+                    "Return entries after this cursor, usually the previous next_cursor. Defaults to the oldest retained entry.",
+                ),
+                message_arg(
+                    "level",
+                    "string",
+                    false,
+                    "Minimum level to return. Defaults to debug.",
+                    &["debug", "log", "info", "warn", "error"],
+                ),
+                optional_message_arg("limit", "number", "Maximum entries to return. Defaults to 200."),
+            ],
+            Vec::new(),
+        ),
+        (NetworkTileKind::Browser, "record_start") => tile_message(
+            "record_start",
+            "Start sampling the browser viewport into PNG frames that record_stop encodes as an animation.",
+            vec![
+                message_arg(
+                    "format",
+                    "string",
+                    false,
+                    "Animation format to encode when the recording stops. Defaults to gif.",
+                    &["gif", "apng"],
+                ),
+                optional_message_arg(
+                    "interval_ms",
+                    "number",
+                    "Milliseconds between frames, from 50 to 10000. Defaults to 200.",
+                ),
+                optional_message_arg(
+                    "max_frames",
+                    "number",
+                    "Stop sampling after this many frames, up to 3000. Defaults to 300.",
+                ),
+                optional_message_arg(
+                    "max_width",
+                    "number",
+                    "Downscale frames wider than this many pixels when encoding. Defaults to 1280.",
+                ),
+            ],
+            Vec::new(),
+        ),
+        (NetworkTileKind::Browser, "record_stop") => tile_message(
+            "record_stop",
+            "Stop the active recording and encode its frames, returning the animation path and frame directory.",
+            vec![optional_message_arg(
+                "work_id",
+                "string",
+                "Attach the encoded animation to this work item's current stage.",
+            )],
+            Vec::new(),
+        ),
         (NetworkTileKind::Browser, "drive") => tile_message(
             "drive",
             "Drive the browser tile through one of the supported browser automation subcommands.",
@@ -1381,67 +2410,246 @@ fn message_spec_for_kind(kind: NetworkTileKind, message_name: &str) -> TileMessa
                     "action",
                     "string",
                     "Browser drive subcommand to execute.",
-                    &["click", "select", "type", "dom_query", "eval", "screenshot"],
+                    &[
+                        "click",
+                        "select",
+                        "type",
+                        "dom_query",
+                        "eval",
+                        "screenshot",
+                        "screenshot_diff",
+                        "press",
+                        "hover",
+                        "scroll",
+                        "wait_for",
+                        "fill_form",
+                        "check",
+                    ],
                 ),
                 optional_message_arg("args", "object", "Nested args for the selected browser drive subcommand."),
             ],
             vec![
                 tile_subcommand(
                     "click",
-                    "Click the first element matching a selector.",
-                    vec![required_message_arg("selector", "string", "CSS selector for the target element.")],
+                    "Click the first element matching a selector or accessibility ref.",
+                    vec![
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector for the target element. Required unless ref is provided.",
+                        ),
+                        optional_message_arg(
+                            "ref",
+                            "string",
+                            "Element ref from the latest accessibility screenshot, used in place of selector.",
+                        ),
+                    ],
                 ),
                 tile_subcommand(
                     "type",
                     "Type text into an input, textarea, or contenteditable element.",
                     vec![
-                        required_message_arg("selector", "string", "CSS selector for the target element."),
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector for the target element. Required unless ref is provided.",
+                        ),
+                        optional_message_arg(
+                            "ref",
+                            "string",
+                            "Element ref from the latest accessibility screenshot, used in place of selector.",
+                        ),
                         required_message_arg("text", "string", "Text to insert into the target element."),
                         optional_message_arg(
-                            "clear",
+                            "clear",
+                            "boolean",
+                            "Whether to clear the existing value first. Defaults to true.",
+                        ),
+                    ],
+                ),
+                tile_subcommand(
+                    "select",
+                    "Choose an option in a select element by value.",
+                    vec![
+                        required_message_arg("selector", "string", "CSS selector for the target select element."),
+                        required_message_arg("value", "string", "Option value to select."),
+                    ],
+                ),
+                tile_subcommand(
+                    "dom_query",
+                    "Evaluate JavaScript as an expression and return its result.",
+                    vec![required_message_arg(
+                        "js",
+                        "string",
+                        "JavaScript expression to evaluate in the browser DOM.",
+                    )],
+                ),
+                tile_subcommand(
+                    "eval",
+                    "Run JavaScript statements in the browser DOM.",
+                    vec![required_message_arg("js", "string", "JavaScript source to execute in the browser DOM.")],
+                ),
+                tile_subcommand(
+                    "screenshot",
+                    "Capture the current browser tile view as a PNG image, text output, or accessibility outline.",
+                    vec![
+                        message_arg(
+                            "format",
+                            "string",
+                            false,
+                            "Screenshot output format. Defaults to image.",
+                            &["image", "braille", "ascii", "ansi", "halfblock", "sixel", "kitty", "text", "accessibility"],
+                        ),
+                        optional_message_arg(
+                            "columns",
+                            "number",
+                            "Requested text width in characters, or terminal cells for sixel and kitty, when format is not image or accessibility.",
+                        ),
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector of an element to scroll into view and capture instead of the whole viewport.",
+                        ),
+                        optional_message_arg(
+                            "clip",
+                            "object",
+                            "Viewport region to capture as {x, y, width, height} in CSS pixels. Cannot be combined with selector.",
+                        ),
+                    ],
+                ),
+                tile_subcommand(
+                    "screenshot_diff",
+                    "Capture the view and compare it with an earlier capture id or a named baseline.",
+                    vec![
+                        optional_message_arg(
+                            "against",
+                            "string",
+                            "Capture id returned by an earlier screenshot or screenshot_diff. Required unless baseline is provided.",
+                        ),
+                        optional_message_arg(
+                            "baseline",
+                            "string",
+                            "Named baseline to compare with. The first diff against a new name stores the capture as that baseline.",
+                        ),
+                        optional_message_arg(
+                            "update_baseline",
+                            "boolean",
+                            "Replace the named baseline with this capture after diffing. Defaults to false.",
+                        ),
+                        optional_message_arg(
+                            "tolerance",
+                            "number",
+                            "Per-channel difference from 0 to 255 ignored as noise. Defaults to 16.",
+                        ),
+                        optional_message_arg(
+                            "diff_image",
                             "boolean",
-                            "Whether to clear the existing value first. Defaults to true.",
+                            "Whether to return a PNG highlighting changed pixels in red. Defaults to false.",
+                        ),
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector of an element to capture instead of the whole viewport.",
+                        ),
+                        optional_message_arg(
+                            "clip",
+                            "object",
+                            "Viewport region to capture as {x, y, width, height} in CSS pixels.",
                         ),
                     ],
                 ),
                 tile_subcommand(
-                    "select",
-                    "Choose an option in a select element by value.",
+                    "press",
+                    "Dispatch a key press with optional modifiers to the focused or selected element.",
                     vec![
-                        required_message_arg("selector", "string", "CSS selector for the target select element."),
-                        required_message_arg("value", "string", "Option value to select."),
+                        required_message_arg(
+                            "key",
+                            "string",
+                            "Key value to press, such as Enter, Escape, ArrowDown, or a single character.",
+                        ),
+                        message_arg(
+                            "modifiers",
+                            "array",
+                            false,
+                            "Modifier keys held during the press.",
+                            &["shift", "ctrl", "alt", "meta"],
+                        ),
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector to focus before pressing. Defaults to the active element.",
+                        ),
+                        optional_message_arg("hold_ms", "number", "Delay before keyup in milliseconds. Defaults to 0."),
                     ],
                 ),
                 tile_subcommand(
-                    "dom_query",
-                    "Evaluate JavaScript as an expression and return its result.",
-                    vec![required_message_arg(
-                        "js",
-                        "string",
-                        "JavaScript expression to evaluate in the browser DOM.",
-                    )],
+                    "hover",
+                    "Move the pointer over the first element matching a selector.",
+                    vec![required_message_arg("selector", "string", "CSS selector for the target element.")],
                 ),
                 tile_subcommand(
-                    "eval",
-                    "Run JavaScript statements in the browser DOM.",
-                    vec![required_message_arg("js", "string", "JavaScript source to execute in the browser DOM.")],
+                    "scroll",
+                    "Scroll an element or the page by a delta, to an edge, or until an element is in view.",
+                    vec![
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector for the element to scroll. Defaults to the page.",
+                        ),
+                        optional_message_arg("x", "number", "Horizontal scroll delta in pixels."),
+                        optional_message_arg("y", "number", "Vertical scroll delta in pixels."),
+                        message_arg(
+                            "to",
+                            "string",
+                            false,
+                            "Scroll to an edge instead of by a delta.",
+                            &["top", "bottom", "left", "right"],
+                        ),
+                    ],
                 ),
                 tile_subcommand(
-                    "screenshot",
-                    "Capture the current browser tile view as a PNG image or text output.",
+                    "wait_for",
+                    "Wait until a selector reaches a state or a JavaScript predicate becomes truthy.",
                     vec![
+                        optional_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector to wait on. Required unless js is provided.",
+                        ),
                         message_arg(
-                            "format",
+                            "state",
                             "string",
                             false,
-                            "Screenshot output format. Defaults to image.",
-                            &["image", "braille", "ascii", "ansi", "text"],
+                            "Selector state to wait for. Defaults to visible.",
+                            &["visible", "hidden", "attached", "detached"],
                         ),
+                        optional_message_arg("js", "string", "JavaScript predicate expression to poll until truthy."),
                         optional_message_arg(
-                            "columns",
+                            "timeout_ms",
                             "number",
-                            "Requested text width in characters when format is braille, ascii, ansi, or text.",
+                            "Maximum wait in milliseconds. Defaults to 5000, capped at 60000.",
+                        ),
+                    ],
+                ),
+                tile_subcommand(
+                    "fill_form",
+                    "Fill several form fields at once from a map of selector to value.",
+                    vec![required_message_arg(
+                        "fields",
+                        "object",
+                        "Map of CSS selector to string, number, or boolean value.",
+                    )],
+                ),
+                tile_subcommand(
+                    "check",
+                    "Check or uncheck a checkbox, or check a radio input.",
+                    vec![
+                        required_message_arg(
+                            "selector",
+                            "string",
+                            "CSS selector for the target checkbox or radio input.",
                         ),
+                        optional_message_arg("checked", "boolean", "Desired checked state. Defaults to true."),
                     ],
                 ),
             ],
@@ -1496,15 +2704,29 @@ fn browser_extension_call_message_spec(extension: &BrowserExtensionInfo) -> Tile
                 &method_names,
             ),
             optional_message_arg("args", "object", "Optional argument object for the selected extension method."),
+            optional_message_arg(
+                "timeout_ms",
+                "number",
+                "Milliseconds to wait for an async extension method to settle.",
+            ),
         ],
         extension
             .methods
             .iter()
-            .map(|method| tile_subcommand(
-                &method.name,
-                method.description.as_deref().unwrap_or("Browser extension method."),
-                method.args.clone(),
-            ))
+            .map(|method| TileMessageSubcommandSpec {
+                is_async: method.is_async,
+                timeout_ms: method.timeout_ms,
+                args_schema: method.args_schema.clone(),
+                result_schema: method.result_schema.clone(),
+                ..tile_subcommand(
+                    &method.name,
+                    method.description.as_deref().unwrap_or("Browser extension method."),
+                    match (&method.args_schema, method.args.is_empty()) {
+                        (Some(schema), true) => message_args_from_schema(schema),
+                        _ => method.args.clone(),
+                    },
+                )
+            })
             .collect(),
     )
 }
@@ -1711,6 +2933,8 @@ fn validate_connect(
     }
     validate_controlled_port(from, from_port, to)?;
     validate_controlled_port(to, to_port, from)?;
+    validate_named_port(conn, from, from_port, to)?;
+    validate_named_port(conn, to, to_port, from)?;
 
     if find_connection_for_port_with_conn(conn, &from.session_id, &from.tile_id, from_port)?.is_some() {
         return Err(format!("port {} on {} is already connected", from_port.as_str(), from.tile_id));
@@ -1743,6 +2967,44 @@ fn validate_controlled_port(
     Ok(())
 }
 
+fn validate_named_port(
+    conn: &Connection,
+    tile: &NetworkTileDescriptor,
+    port: TilePort,
+    other: &NetworkTileDescriptor,
+) -> Result<(), String> {
+    let named_ports = tile_registry::list_named_ports_with_conn(conn, Some(&tile.tile_id))?;
+    let Some(named_port) = named_ports.iter().find(|named_port| named_port.port == port) else {
+        return Ok(());
+    };
+    if !named_port_accepts(named_port, other.kind) {
+        return Err(format!(
+            "port {} on {} only accepts {} tiles",
+            named_port.name,
+            tile.tile_id,
+            accepted_kind_list(named_port)
+        ));
+    }
+    Ok(())
+}
+
+fn named_port_accepts(named_port: &NamedPort, kind: NetworkTileKind) -> bool {
+    named_port.accepts.is_empty()
+        || named_port
+            .accepts
+            .iter()
+            .any(|accepted| *accepted == kind || (is_agent_kind(*accepted) && is_agent_kind(kind)))
+}
+
+fn accepted_kind_list(named_port: &NamedPort) -> String {
+    named_port
+        .accepts
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn is_agent_kind(kind: NetworkTileKind) -> bool {
     matches!(kind, NetworkTileKind::Agent | NetworkTileKind::RootAgent)
 }
@@ -1763,21 +3025,23 @@ fn find_connection_for_port_with_conn(
 #[cfg(test)]
 mod tests {
     use super::{
-        component_for_tile, connect_at, derived_work_owner_agent_id_at,
-        disconnect_all_for_tile_at, dispatchable_messages_for_access, filter_component,
-        inferred_tmux_tile_record_kind, list_connections_at, message_api, message_api_for_access,
-        list_port_settings_at, network_tile_kind_from_record_kind, parse_port, port_mode, readable_messages,
-        reconciled_tmux_tile_record_kind, responds_to, responds_to_for_access,
-        rpc_access_for_sender_to_tile, sender_visible_component_for_tile, set_port_settings_at,
-        NetworkConnection, NetworkTileDescriptor, NetworkTileKind, PaneTileDetails, PortMode,
-        PortNetworkingMode, SessionTileInfo, TileDetails, TilePortSetting, TileRpcAccess,
-        TileTypeFilter, TilePort, WorkTileDetails,
+        annotate_segments, component_for_tile, connect_at, declare_named_ports_at, derived_work_owner_agent_id_at,
+        disconnect_all_for_tile_at, dispatchable_messages_for_access, extend_browser_api_with_extension,
+        filter_component, filter_component_by_segment, inferred_tmux_tile_record_kind, list_connections_at,
+        list_port_segments_at, list_port_settings_at, message_api, message_api_for_access, network_path_between,
+        network_tile_kind_from_record_kind, parse_port, plan_named_ports, port_mode, readable_messages,
+        reconciled_tmux_tile_record_kind, resolve_port_at, responds_to, responds_to_for_access,
+        rpc_access_for_sender_to_tile, sender_visible_component_for_tile, set_port_segments_at, set_port_settings_at,
+        BrowserExtensionInfo, NamedPortSpec, NetworkConnection, NetworkPathBlock, NetworkTileDescriptor,
+        NetworkTileKind, PaneTileDetails, PortMode, PortNetworkingMode, SessionTileInfo, TileDetails, TilePort,
+        TilePortSetting, TileRpcAccess, TileTypeFilter, WorkTileDetails,
     };
     use crate::agent::{AgentInfo, AgentRole, AgentType};
     use crate::db;
+    use crate::network_history::NetworkChangeActor;
     use crate::tile_registry::TileRecordKind;
     use crate::work;
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeSet, HashMap};
     use std::fs;
     use std::path::PathBuf;
 
@@ -1807,6 +3071,7 @@ mod tests {
             chatter_subscribed: true,
             channels: Vec::new(),
             agent_pid: None,
+            worktree: None,
         }
     }
 
@@ -1830,6 +3095,7 @@ mod tests {
                 chatter_subscribed: true,
                 channels: Vec::new(),
                 agent_pid: None,
+                worktree: None,
             }),
             NetworkTileKind::Browser => TileDetails::Browser(super::BrowserTileDetails {
                 window_name: "Browser".to_string(),
@@ -1849,6 +3115,7 @@ mod tests {
                 current_stage: crate::work::WorkStage::Plan,
                 stages: Vec::new(),
                 reviews: Vec::new(),
+                attachments: Vec::new(),
                 created_at: 0,
                 updated_at: 0,
             }),
@@ -1877,10 +3144,25 @@ mod tests {
             command,
             responds_to: responds_to(kind),
             message_api: message_api(kind),
+            ports: Vec::new(),
             details,
         }
     }
 
+    fn named_port_spec(
+        name: &str,
+        port: Option<TilePort>,
+        access_mode: Option<PortMode>,
+        accepts: Vec<NetworkTileKind>,
+    ) -> NamedPortSpec {
+        NamedPortSpec {
+            name: name.to_string(),
+            port,
+            access_mode,
+            accepts,
+        }
+    }
+
     fn port_setting(
         tile_id: &str,
         session_id: &str,
@@ -1945,23 +3227,48 @@ mod tests {
         assert_eq!(port_mode(NetworkTileKind::Work, TilePort::Left3), PortMode::ReadWrite);
         assert_eq!(port_mode(NetworkTileKind::Work, TilePort::Top), PortMode::Read);
         assert_eq!(port_mode(NetworkTileKind::Browser, TilePort::Right), PortMode::ReadWrite);
-        assert_eq!(readable_messages(NetworkTileKind::Shell), &["get", "output_read"]);
-        assert_eq!(readable_messages(NetworkTileKind::Browser), &["get"]);
+        assert_eq!(
+            readable_messages(NetworkTileKind::Shell),
+            &["get", "output_read", "output_capture", "wait_for_output", "screenshot", "process_info"]
+        );
+        assert_eq!(readable_messages(NetworkTileKind::Browser), &["get", "console_read"]);
         assert_eq!(
             dispatchable_messages_for_access(NetworkTileKind::Browser, TileRpcAccess::Read),
-            &["get"]
+            &["get", "console_read"]
         );
         assert_eq!(
             responds_to(NetworkTileKind::Shell),
-            vec!["get", "call", "output_read", "input_send", "exec", "role_set",]
+            vec![
+                "get",
+                "call",
+                "output_read",
+                "output_capture",
+                "wait_for_output",
+                "screenshot",
+                "process_info",
+                "input_send",
+                "exec",
+                "role_set",
+            ]
         );
         assert_eq!(
             responds_to(NetworkTileKind::Browser),
-            vec!["get", "call", "navigate", "load", "drive"]
+            vec!["get", "call", "navigate", "load", "drive", "console_read", "record_start", "record_stop"]
         );
         assert_eq!(
             responds_to(NetworkTileKind::Agent),
-            vec!["get", "call", "output_read", "input_send", "exec", "role_set",]
+            vec![
+                "get",
+                "call",
+                "output_read",
+                "output_capture",
+                "wait_for_output",
+                "screenshot",
+                "process_info",
+                "input_send",
+                "exec",
+                "role_set",
+            ]
         );
         assert_eq!(
             responds_to(NetworkTileKind::Work),
@@ -1969,11 +3276,11 @@ mod tests {
         );
         assert_eq!(
             responds_to_for_access(NetworkTileKind::Shell, TileRpcAccess::Read),
-            vec!["get", "call", "output_read"]
+            vec!["get", "call", "output_read", "output_capture", "wait_for_output", "screenshot", "process_info"]
         );
         assert_eq!(
             responds_to_for_access(NetworkTileKind::Browser, TileRpcAccess::Read),
-            vec!["get", "call"]
+            vec!["get", "call", "console_read"]
         );
     }
 
@@ -1991,6 +3298,7 @@ mod tests {
             TilePort::Top,
             Some(PortMode::Read),
             None,
+            &NetworkChangeActor::user(),
         )
         .unwrap();
         assert_eq!(
@@ -2012,6 +3320,7 @@ mod tests {
             TilePort::Top,
             None,
             Some(PortNetworkingMode::Gateway),
+            &NetworkChangeActor::user(),
         )
         .unwrap();
         assert_eq!(
@@ -2034,6 +3343,7 @@ mod tests {
             TilePort::Top,
             Some(PortMode::ReadWrite),
             Some(PortNetworkingMode::Broadcast),
+            &NetworkChangeActor::user(),
         )
         .unwrap();
         assert_eq!(
@@ -2049,6 +3359,80 @@ mod tests {
         assert!(list_port_settings_at(&path, "$1").unwrap().is_empty());
     }
 
+    #[test]
+    fn documents_async_browser_extension_methods_in_extension_call_spec() {
+        let extension: BrowserExtensionInfo = serde_json::from_value(serde_json::json!({
+            "extension_id": "game-boy",
+            "label": "Game Boy",
+            "methods": [
+                { "name": "state" },
+                { "name": "load_rom", "description": "Fetch and boot a ROM.", "async": true, "timeout_ms": 30000 },
+            ],
+        }))
+        .unwrap();
+        assert!(extension.methods[1].is_async);
+
+        let mut responds_to = responds_to(NetworkTileKind::Browser);
+        let mut api = message_api(NetworkTileKind::Browser);
+        extend_browser_api_with_extension(&mut responds_to, &mut api, TileRpcAccess::ReadWrite, Some(&extension));
+        let spec = api.iter().find(|message| message.name == "extension_call").unwrap();
+        assert!(spec.args.iter().any(|arg| arg.name == "timeout_ms" && !arg.required));
+        assert_eq!(
+            serde_json::to_value(&spec.subcommands).unwrap(),
+            serde_json::json!([
+                { "name": "state", "description": "Browser extension method." },
+                { "name": "load_rom", "description": "Fetch and boot a ROM.", "async": true, "timeout_ms": 30000 },
+            ])
+        );
+    }
+
+    #[test]
+    fn renders_browser_extension_schemas_into_extension_call_subcommands() {
+        let args_schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "rom": { "type": "string", "description": "Bundled ROM file name.", "enum": ["tetris.gb", "zelda.gb"] },
+                "speed": { "type": ["number", "null"] },
+            },
+            "required": ["rom"],
+            "additionalProperties": false,
+        });
+        let extension: BrowserExtensionInfo = serde_json::from_value(serde_json::json!({
+            "extension_id": "game-boy",
+            "label": "Game Boy",
+            "methods": [{
+                "name": "load_bundled_rom",
+                "args_schema": args_schema,
+                "result_schema": { "type": "object", "required": ["state"] },
+            }],
+        }))
+        .unwrap();
+
+        let mut responds_to = responds_to(NetworkTileKind::Browser);
+        let mut api = message_api(NetworkTileKind::Browser);
+        extend_browser_api_with_extension(&mut responds_to, &mut api, TileRpcAccess::ReadWrite, Some(&extension));
+        let spec = api.iter().find(|message| message.name == "extension_call").unwrap();
+        assert_eq!(
+            serde_json::to_value(&spec.subcommands[0]).unwrap(),
+            serde_json::json!({
+                "name": "load_bundled_rom",
+                "description": "Browser extension method.",
+                "args": [
+                    {
+                        "name": "rom",
+                        "type": "string",
+                        "required": true,
+                        "description": "Bundled ROM file name.",
+                        "enum_values": ["tetris.gb", "zelda.gb"],
+                    },
+                    { "name": "speed", "type": "number|null", "required": false },
+                ],
+                "args_schema": args_schema,
+                "result_schema": { "type": "object", "required": ["state"] },
+            })
+        );
+    }
+
     #[test]
     fn exposes_structured_browser_message_api_with_drive_subcommands() {
         assert_eq!(
@@ -2067,7 +3451,7 @@ mod tests {
                             "type": "string",
                             "required": true,
                             "description": "Message name to invoke on this tile.",
-                            "enum_values": ["get", "navigate", "load", "drive"]
+                            "enum_values": ["get", "navigate", "load", "drive", "console_read", "record_start", "record_stop"]
                         },
                         {
                             "name": "args",
@@ -2110,7 +3494,7 @@ mod tests {
                             "type": "string",
                             "required": true,
                             "description": "Browser drive subcommand to execute.",
-                            "enum_values": ["click", "select", "type", "dom_query", "eval", "screenshot"]
+                            "enum_values": ["click", "select", "type", "dom_query", "eval", "screenshot", "screenshot_diff", "press", "hover", "scroll", "wait_for", "fill_form", "check"]
                         },
                         {
                             "name": "args",
@@ -2122,13 +3506,19 @@ mod tests {
                     "subcommands": [
                         {
                             "name": "click",
-                            "description": "Click the first element matching a selector.",
+                            "description": "Click the first element matching a selector or accessibility ref.",
                             "args": [
                                 {
                                     "name": "selector",
                                     "type": "string",
-                                    "required": true,
-                                    "description": "CSS selector for the target element."
+                                    "required": false,
+                                    "description": "CSS selector for the target element. Required unless ref is provided."
+                                },
+                                {
+                                    "name": "ref",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Element ref from the latest accessibility screenshot, used in place of selector."
                                 }
                             ]
                         },
@@ -2139,8 +3529,14 @@ mod tests {
                                 {
                                     "name": "selector",
                                     "type": "string",
-                                    "required": true,
-                                    "description": "CSS selector for the target element."
+                                    "required": false,
+                                    "description": "CSS selector for the target element. Required unless ref is provided."
+                                },
+                                {
+                                    "name": "ref",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Element ref from the latest accessibility screenshot, used in place of selector."
                                 },
                                 {
                                     "name": "text",
@@ -2157,67 +3553,330 @@ mod tests {
                             ]
                         },
                         {
-                            "name": "select",
-                            "description": "Choose an option in a select element by value.",
+                            "name": "select",
+                            "description": "Choose an option in a select element by value.",
+                            "args": [
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "CSS selector for the target select element."
+                                },
+                                {
+                                    "name": "value",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "Option value to select."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "dom_query",
+                            "description": "Evaluate JavaScript as an expression and return its result.",
+                            "args": [
+                                {
+                                    "name": "js",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "JavaScript expression to evaluate in the browser DOM."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "eval",
+                            "description": "Run JavaScript statements in the browser DOM.",
+                            "args": [
+                                {
+                                    "name": "js",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "JavaScript source to execute in the browser DOM."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "screenshot",
+                            "description": "Capture the current browser tile view as a PNG image, text output, or accessibility outline.",
+                            "args": [
+                                {
+                                    "name": "format",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Screenshot output format. Defaults to image.",
+                                    "enum_values": ["image", "braille", "ascii", "ansi", "halfblock", "sixel", "kitty", "text", "accessibility"]
+                                },
+                                {
+                                    "name": "columns",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Requested text width in characters, or terminal cells for sixel and kitty, when format is not image or accessibility."
+                                },
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "CSS selector of an element to scroll into view and capture instead of the whole viewport."
+                                },
+                                {
+                                    "name": "clip",
+                                    "type": "object",
+                                    "required": false,
+                                    "description": "Viewport region to capture as {x, y, width, height} in CSS pixels. Cannot be combined with selector."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "screenshot_diff",
+                            "description": "Capture the view and compare it with an earlier capture id or a named baseline.",
+                            "args": [
+                                {
+                                    "name": "against",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Capture id returned by an earlier screenshot or screenshot_diff. Required unless baseline is provided."
+                                },
+                                {
+                                    "name": "baseline",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Named baseline to compare with. The first diff against a new name stores the capture as that baseline."
+                                },
+                                {
+                                    "name": "update_baseline",
+                                    "type": "boolean",
+                                    "required": false,
+                                    "description": "Replace the named baseline with this capture after diffing. Defaults to false."
+                                },
+                                {
+                                    "name": "tolerance",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Per-channel difference from 0 to 255 ignored as noise. Defaults to 16."
+                                },
+                                {
+                                    "name": "diff_image",
+                                    "type": "boolean",
+                                    "required": false,
+                                    "description": "Whether to return a PNG highlighting changed pixels in red. Defaults to false."
+                                },
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "CSS selector of an element to capture instead of the whole viewport."
+                                },
+                                {
+                                    "name": "clip",
+                                    "type": "object",
+                                    "required": false,
+                                    "description": "Viewport region to capture as {x, y, width, height} in CSS pixels."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "press",
+                            "description": "Dispatch a key press with optional modifiers to the focused or selected element.",
+                            "args": [
+                                {
+                                    "name": "key",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "Key value to press, such as Enter, Escape, ArrowDown, or a single character."
+                                },
+                                {
+                                    "name": "modifiers",
+                                    "type": "array",
+                                    "required": false,
+                                    "description": "Modifier keys held during the press.",
+                                    "enum_values": ["shift", "ctrl", "alt", "meta"]
+                                },
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "CSS selector to focus before pressing. Defaults to the active element."
+                                },
+                                {
+                                    "name": "hold_ms",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Delay before keyup in milliseconds. Defaults to 0."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "hover",
+                            "description": "Move the pointer over the first element matching a selector.",
+                            "args": [
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": true,
+                                    "description": "CSS selector for the target element."
+                                }
+                            ]
+                        },
+                        {
+                            "name": "scroll",
+                            "description": "Scroll an element or the page by a delta, to an edge, or until an element is in view.",
                             "args": [
                                 {
                                     "name": "selector",
                                     "type": "string",
-                                    "required": true,
-                                    "description": "CSS selector for the target select element."
+                                    "required": false,
+                                    "description": "CSS selector for the element to scroll. Defaults to the page."
                                 },
                                 {
-                                    "name": "value",
+                                    "name": "x",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Horizontal scroll delta in pixels."
+                                },
+                                {
+                                    "name": "y",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Vertical scroll delta in pixels."
+                                },
+                                {
+                                    "name": "to",
                                     "type": "string",
-                                    "required": true,
-                                    "description": "Option value to select."
+                                    "required": false,
+                                    "description": "Scroll to an edge instead of by a delta.",
+                                    "enum_values": ["top", "bottom", "left", "right"]
                                 }
                             ]
                         },
                         {
-                            "name": "dom_query",
-                            "description": "Evaluate JavaScript as an expression and return its result.",
+                            "name": "wait_for",
+                            "description": "Wait until a selector reaches a state or a JavaScript predicate becomes truthy.",
                             "args": [
+                                {
+                                    "name": "selector",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "CSS selector to wait on. Required unless js is provided."
+                                },
+                                {
+                                    "name": "state",
+                                    "type": "string",
+                                    "required": false,
+                                    "description": "Selector state to wait for. Defaults to visible.",
+                                    "enum_values": ["visible", "hidden", "attached", "detached"]
+                                },
                                 {
                                     "name": "js",
                                     "type": "string",
-                                    "required": true,
-                                    "description": "JavaScript expression to evaluate in the browser DOM."
+                                    "required": false,
+                                    "description": "JavaScript predicate expression to poll until truthy."
+                                },
+                                {
+                                    "name": "timeout_ms",
+                                    "type": "number",
+                                    "required": false,
+                                    "description": "Maximum wait in milliseconds. Defaults to 5000, capped at 60000."
                                 }
                             ]
                         },
                         {
-                            "name": "eval",
-                            "description": "Run JavaScript statements in the browser DOM.",
+                            "name": "fill_form",
+                            "description": "Fill several form fields at once from a map of selector to value.",
                             "args": [
                                 {
-                                    "name": "js",
-                                    "type": "string",
+                                    "name": "fields",
+                                    "type": "object",
                                     "required": true,
-                                    "description": "JavaScript source to execute in the browser DOM."
+                                    "description": "Map of CSS selector to string, number, or boolean value."
                                 }
                             ]
                         },
                         {
-                            "name": "screenshot",
-                            "description": "Capture the current browser tile view as a PNG image or text output.",
+                            "name": "check",
+                            "description": "Check or uncheck a checkbox, or check a radio input.",
                             "args": [
                                 {
-                                    "name": "format",
+                                    "name": "selector",
                                     "type": "string",
-                                    "required": false,
-                                    "description": "Screenshot output format. Defaults to image.",
-                                    "enum_values": ["image", "braille", "ascii", "ansi", "text"]
+                                    "required": true,
+                                    "description": "CSS selector for the target checkbox or radio input."
                                 },
                                 {
-                                    "name": "columns",
-                                    "type": "number",
+                                    "name": "checked",
+                                    "type": "boolean",
                                     "required": false,
-                                    "description": "Requested text width in characters when format is braille, ascii, ansi, or text."
+                                    "description": "Desired checked state. Defaults to true."
                                 }
                             ]
                         }
                     ]
+                },
+                {
+                    "name": "console_read",
+                    "description": "Read console messages, uncaught errors, and unhandled rejections captured from the browser page.",
+                    "args": [
+                        {
+                            "name": "since",
+                            "type": "number",
+                            "required": false,
+                            "description": "Return entries after this cursor, usually the previous next_cursor. Defaults to the oldest retained entry."
+                        },
+                        {
+                            "name": "level",
+                            "type": "string",
+                            "required": false,
+                            "description": "Minimum level to return. Defaults to debug.",
+                            "enum_values": ["debug", "log", "info", "warn", "error"]
+                        },
+                        {
+                            "name": "limit",
+                            "type": "number",
+                            "required": false,
+                            "description": "Maximum entries to return. Defaults to 200."
+                        }
+                    ]
+                },
+                {
+                    "name": "record_start",
+                    "description": "Start sampling the browser viewport into PNG frames that record_stop encodes as an animation.",
+                    "args": [
+                        {
+                            "name": "format",
+                            "type": "string",
+                            "required": false,
+                            "description": "Animation format to encode when the recording stops. Defaults to gif.",
+                            "enum_values": ["gif", "apng"]
+                        },
+                        {
+                            "name": "interval_ms",
+                            "type": "number",
+                            "required": false,
+                            "description": "Milliseconds between frames, from 50 to 10000. Defaults to 200."
+                        },
+                        {
+                            "name": "max_frames",
+                            "type": "number",
+                            "required": false,
+                            "description": "Stop sampling after this many frames, up to 3000. Defaults to 300."
+                        },
+                        {
+                            "name": "max_width",
+                            "type": "number",
+                            "required": false,
+                            "description": "Downscale frames wider than this many pixels when encoding. Defaults to 1280."
+                        }
+                    ]
+                },
+                {
+                    "name": "record_stop",
+                    "description": "Stop the active recording and encode its frames, returning the animation path and frame directory.",
+                    "args": [
+                        {
+                            "name": "work_id",
+                            "type": "string",
+                            "required": false,
+                            "description": "Attach the encoded animation to this work item's current stage."
+                        }
+                    ]
                 }
             ])
         );
@@ -2241,7 +3900,7 @@ mod tests {
                             "type": "string",
                             "required": true,
                             "description": "Message name to invoke on this tile.",
-                            "enum_values": ["get"]
+                            "enum_values": ["get", "console_read"]
                         },
                         {
                             "name": "args",
@@ -2250,6 +3909,31 @@ mod tests {
                             "description": "Optional message-specific argument object."
                         }
                     ]
+                },
+                {
+                    "name": "console_read",
+                    "description": "Read console messages, uncaught errors, and unhandled rejections captured from the browser page.",
+                    "args": [
+                        {
+                            "name": "since",
+                            "type": "number",
+                            "required": false,
+                            "description": "Return entries after this cursor, usually the previous next_cursor. Defaults to the oldest retained entry."
+                        },
+                        {
+                            "name": "level",
+                            "type": "string",
+                            "required": false,
+                            "description": "Minimum level to return. Defaults to debug.",
+                            "enum_values": ["debug", "log", "info", "warn", "error"]
+                        },
+                        {
+                            "name": "limit",
+                            "type": "number",
+                            "required": false,
+                            "description": "Maximum entries to return. Defaults to 200."
+                        }
+                    ]
                 }
             ])
         );
@@ -2339,11 +4023,11 @@ mod tests {
         );
         assert_eq!(
             responds_to_for_access(NetworkTileKind::Agent, TileRpcAccess::Read),
-            vec!["get", "call", "output_read"]
+            vec!["get", "call", "output_read", "output_capture", "wait_for_output", "screenshot", "process_info"]
         );
         assert_eq!(
             responds_to_for_access(NetworkTileKind::RootAgent, TileRpcAccess::Read),
-            vec!["get", "call", "output_read"]
+            vec!["get", "call", "output_read", "output_capture", "wait_for_output", "screenshot", "process_info"]
         );
     }
 
@@ -2408,6 +4092,240 @@ mod tests {
         assert_eq!(from_gate.connections.len(), 3);
     }
 
+    #[test]
+    fn network_paths_follow_sender_visible_rules_and_report_blocking_gateways() {
+        let session_tiles = vec![
+            session_tile("%a", "$1", NetworkTileKind::Shell),
+            session_tile("%gate", "$1", NetworkTileKind::Shell),
+            session_tile("%b", "$1", NetworkTileKind::Shell),
+            session_tile("%c", "$1", NetworkTileKind::Shell),
+        ];
+        let connections = vec![
+            NetworkConnection {
+                session_id: "$1".to_string(),
+                from_tile_id: "%a".to_string(),
+                from_port: TilePort::Right,
+                to_tile_id: "%gate".to_string(),
+                to_port: TilePort::Left,
+            },
+            NetworkConnection {
+                session_id: "$1".to_string(),
+                from_tile_id: "%gate".to_string(),
+                from_port: TilePort::Right,
+                to_tile_id: "%b".to_string(),
+                to_port: TilePort::Left,
+            },
+            NetworkConnection {
+                session_id: "$1".to_string(),
+                from_tile_id: "%gate".to_string(),
+                from_port: TilePort::Bottom,
+                to_tile_id: "%c".to_string(),
+                to_port: TilePort::Top,
+            },
+        ];
+        let settings = vec![port_setting(
+            "%gate",
+            "$1",
+            TilePort::Left,
+            PortMode::ReadWrite,
+            PortNetworkingMode::Gateway,
+        )];
+
+        let direct = network_path_between("$1", "%a", "%gate", &session_tiles, &connections, &settings);
+        assert!(direct.reachable);
+        assert_eq!(direct.hops.len(), 1);
+        assert_eq!(direct.access, Some(TileRpcAccess::ReadWrite));
+
+        let relayed = network_path_between("$1", "%b", "%c", &session_tiles, &connections, &settings);
+        assert!(relayed.reachable);
+        assert_eq!(
+            relayed
+                .hops
+                .iter()
+                .map(|hop| (hop.from_tile_id.as_str(), hop.from_port, hop.to_tile_id.as_str(), hop.to_port))
+                .collect::<Vec<_>>(),
+            vec![
+                ("%b", TilePort::Left, "%gate", TilePort::Right),
+                ("%gate", TilePort::Bottom, "%c", TilePort::Top),
+            ]
+        );
+        assert_eq!(relayed.access, Some(TileRpcAccess::Read));
+        assert_eq!(relayed.blocked_by, None);
+
+        let blocked = network_path_between("$1", "%a", "%b", &session_tiles, &connections, &settings);
+        assert!(!blocked.reachable);
+        assert!(blocked.hops.is_empty());
+        assert_eq!(blocked.access, None);
+        assert_eq!(
+            blocked.blocked_by,
+            Some(NetworkPathBlock {
+                tile_id: "%gate".to_string(),
+                port: TilePort::Left,
+            })
+        );
+
+        let disconnected = network_path_between("$1", "%a", "%z", &session_tiles, &connections, &settings);
+        assert!(!disconnected.reachable);
+        assert_eq!(disconnected.blocked_by, None);
+    }
+
+    #[test]
+    fn named_ports_resolve_to_slots_and_restrict_accepted_tile_kinds() {
+        let path = temp_db_path("named-ports");
+        let service = NetworkTileDescriptor {
+            tile_id: "%svc".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let shell = NetworkTileDescriptor {
+            tile_id: "%shell".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let agent = NetworkTileDescriptor {
+            tile_id: "%agent".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Agent,
+        };
+
+        assert!(declare_named_ports_at(
+            &path,
+            "$1",
+            "%svc",
+            NetworkTileKind::Shell,
+            &[named_port_spec("left", None, None, Vec::new())],
+            &HashMap::new(),
+            &NetworkChangeActor::user(),
+        )
+        .is_err());
+        assert!(plan_named_ports(
+            "%svc",
+            NetworkTileKind::Shell,
+            &[
+                named_port_spec("input", None, None, Vec::new()),
+                named_port_spec("input", None, None, Vec::new()),
+            ],
+            &[],
+        )
+        .is_err());
+        let full = (1..=TilePort::ALL.len())
+            .map(|index| named_port_spec(&format!("port-{index}"), None, None, Vec::new()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            plan_named_ports("%svc", NetworkTileKind::Shell, &full, &[]).unwrap().len(),
+            TilePort::ALL.len()
+        );
+        let mut overfull = full.clone();
+        overfull.push(named_port_spec("extra", None, None, Vec::new()));
+        assert_eq!(
+            plan_named_ports("%svc", NetworkTileKind::Shell, &overfull, &[]).unwrap_err(),
+            "tile %svc declares 17 named ports, but a tile has at most 16 port slots"
+        );
+
+        let declared = declare_named_ports_at(
+            &path,
+            "$1",
+            "%svc",
+            NetworkTileKind::Shell,
+            &[
+                named_port_spec("events", None, Some(PortMode::Read), Vec::new()),
+                named_port_spec("admin", Some(TilePort::Right), None, vec![NetworkTileKind::Agent]),
+            ],
+            &HashMap::new(),
+            &NetworkChangeActor::user(),
+        )
+        .unwrap();
+        assert_eq!(declared[0].port, TilePort::Left);
+        assert_eq!(declared[1].port, TilePort::Right);
+        assert_eq!(resolve_port_at(&path, "%svc", "events").unwrap(), TilePort::Left);
+        assert_eq!(resolve_port_at(&path, "%svc", "top-2").unwrap(), TilePort::Top2);
+        assert!(resolve_port_at(&path, "%shell", "events").is_err());
+        assert_eq!(
+            list_port_settings_at(&path, "$1").unwrap(),
+            vec![port_setting("%svc", "$1", TilePort::Left, PortMode::Read, PortNetworkingMode::Broadcast)]
+        );
+
+        let error = connect_at(&path, &shell, TilePort::Left, &service, TilePort::Right, &NetworkChangeActor::user())
+            .unwrap_err();
+        assert!(error.contains("port admin on %svc only accepts agent tiles"));
+        connect_at(&path, &agent, TilePort::Left, &service, TilePort::Right, &NetworkChangeActor::user()).unwrap();
+    }
+
+    #[test]
+    fn redeclaring_named_ports_moves_drops_and_revalidates_connections() {
+        let path = temp_db_path("named-ports-redeclare");
+        let service = NetworkTileDescriptor {
+            tile_id: "%svc".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let shell = NetworkTileDescriptor {
+            tile_id: "%shell".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let agent = NetworkTileDescriptor {
+            tile_id: "%agent".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Agent,
+        };
+        let peer_kinds = HashMap::from([
+            ("%shell".to_string(), NetworkTileKind::Shell),
+            ("%agent".to_string(), NetworkTileKind::Agent),
+        ]);
+        declare_named_ports_at(
+            &path,
+            "$1",
+            "%svc",
+            NetworkTileKind::Shell,
+            &[
+                named_port_spec("events", Some(TilePort::Left), Some(PortMode::Read), Vec::new()),
+                named_port_spec("admin", Some(TilePort::Right), None, Vec::new()),
+            ],
+            &peer_kinds,
+            &NetworkChangeActor::user(),
+        )
+        .unwrap();
+        connect_at(&path, &shell, TilePort::Right, &service, TilePort::Left, &NetworkChangeActor::user()).unwrap();
+        connect_at(&path, &agent, TilePort::Left, &service, TilePort::Right, &NetworkChangeActor::user()).unwrap();
+
+        let error = declare_named_ports_at(
+            &path,
+            "$1",
+            "%svc",
+            NetworkTileKind::Shell,
+            &[
+                named_port_spec("events", Some(TilePort::Left), None, vec![NetworkTileKind::Agent]),
+                named_port_spec("admin", Some(TilePort::Right), None, Vec::new()),
+            ],
+            &peer_kinds,
+            &NetworkChangeActor::user(),
+        )
+        .unwrap_err();
+        assert!(error.contains("port events on %svc is connected to %shell"));
+        assert_eq!(list_connections_at(&path, "$1").unwrap().len(), 2);
+
+        declare_named_ports_at(
+            &path,
+            "$1",
+            "%svc",
+            NetworkTileKind::Shell,
+            &[named_port_spec("events", Some(TilePort::Bottom), None, Vec::new())],
+            &peer_kinds,
+            &NetworkChangeActor::user(),
+        )
+        .unwrap();
+        let connections = list_connections_at(&path, "$1").unwrap();
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].from_tile_id, "%shell");
+        assert_eq!(connections[0].to_tile_id, "%svc");
+        assert_eq!(connections[0].to_port, TilePort::Bottom);
+        assert!(list_port_settings_at(&path, "$1")
+            .unwrap()
+            .iter()
+            .all(|setting| setting.port != TilePort::Left));
+    }
+
     #[test]
     fn rejects_invalid_connection_shapes_and_enforces_port_uniqueness() {
         let path = temp_db_path("validation");
@@ -2437,17 +4355,17 @@ mod tests {
             kind: NetworkTileKind::Agent,
         };
 
-        let error = connect_at(&path, &work, TilePort::Top, &work, TilePort::Right).unwrap_err();
+        let error = connect_at(&path, &work, TilePort::Top, &work, TilePort::Right, &NetworkChangeActor::user()).unwrap_err();
         assert!(error.contains("cannot connect a tile to itself"));
 
-        let error = connect_at(&path, &work, TilePort::Top, &other_work, TilePort::Right).unwrap_err();
+        let error = connect_at(&path, &work, TilePort::Top, &other_work, TilePort::Right, &NetworkChangeActor::user()).unwrap_err();
         assert!(error.contains("read-only"));
 
-        let error = connect_at(&path, &work, TilePort::Left, &shell_a, TilePort::Top).unwrap_err();
+        let error = connect_at(&path, &work, TilePort::Left, &shell_a, TilePort::Top, &NetworkChangeActor::user()).unwrap_err();
         assert!(error.contains("only accepts agent"));
 
-        connect_at(&path, &agent, TilePort::Left, &shell_a, TilePort::Right).unwrap();
-        let error = connect_at(&path, &shell_b, TilePort::Left, &agent, TilePort::Left).unwrap_err();
+        connect_at(&path, &agent, TilePort::Left, &shell_a, TilePort::Right, &NetworkChangeActor::user()).unwrap();
+        let error = connect_at(&path, &shell_b, TilePort::Left, &agent, TilePort::Left, &NetworkChangeActor::user()).unwrap_err();
         assert!(error.contains("already connected"));
     }
 
@@ -2473,10 +4391,10 @@ mod tests {
             kind: NetworkTileKind::Agent,
         };
 
-        let error = connect_at(&path, &work, TilePort::Left2, &shell, TilePort::Top).unwrap_err();
+        let error = connect_at(&path, &work, TilePort::Left2, &shell, TilePort::Top, &NetworkChangeActor::user()).unwrap_err();
         assert!(error.contains("only accepts agent"));
 
-        connect_at(&path, &agent, TilePort::Right2, &work, TilePort::Left2).unwrap();
+        connect_at(&path, &agent, TilePort::Right2, &work, TilePort::Left2, &NetworkChangeActor::user()).unwrap();
         let connections = list_connections_at(&path, "$1").unwrap();
         assert_eq!(connections[0].to_port, TilePort::Left2);
     }
@@ -2510,8 +4428,8 @@ mod tests {
             kind: NetworkTileKind::Shell,
         };
 
-        connect_at(&path, &a, TilePort::Right, &b, TilePort::Left).unwrap();
-        connect_at(&path, &b, TilePort::Top, &c, TilePort::Bottom).unwrap();
+        connect_at(&path, &a, TilePort::Right, &b, TilePort::Left, &NetworkChangeActor::user()).unwrap();
+        connect_at(&path, &b, TilePort::Top, &c, TilePort::Bottom, &NetworkChangeActor::user()).unwrap();
 
         let session_tiles = vec![
             session_tile(&a.tile_id, "$1", a.kind),
@@ -2559,6 +4477,7 @@ mod tests {
                 },
             ],
             port_settings: Vec::new(),
+            segments: Vec::new(),
         };
 
         let filtered = filter_component(component, Some(TileTypeFilter::Agent));
@@ -2567,6 +4486,85 @@ mod tests {
         assert!(filtered.connections.is_empty());
     }
 
+    #[test]
+    fn labels_connections_into_segments_and_filters_components_by_segment() {
+        let path = temp_db_path("segments");
+        let hub = NetworkTileDescriptor {
+            tile_id: "%hub".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let a1 = NetworkTileDescriptor {
+            tile_id: "%a1".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Agent,
+        };
+        let a2 = NetworkTileDescriptor {
+            tile_id: "%a2".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Shell,
+        };
+        let b1 = NetworkTileDescriptor {
+            tile_id: "%b1".to_string(),
+            session_id: "$1".to_string(),
+            kind: NetworkTileKind::Agent,
+        };
+        let actor = NetworkChangeActor::user();
+        connect_at(&path, &a1, TilePort::Right, &hub, TilePort::Left, &actor).unwrap();
+        connect_at(&path, &a2, TilePort::Bottom, &hub, TilePort::Top, &actor).unwrap();
+        connect_at(&path, &b1, TilePort::Right, &hub, TilePort::Right, &actor).unwrap();
+
+        assert_eq!(
+            set_port_segments_at(&path, "$1", "%hub", TilePort::Left, &["Team A".to_string()]).unwrap_err(),
+            "invalid segment name: Team A"
+        );
+        assert_eq!(
+            set_port_segments_at(
+                &path,
+                "$1",
+                "%hub",
+                TilePort::Left,
+                &["team-a".to_string(), "team-a".to_string()],
+            )
+            .unwrap(),
+            vec!["team-a".to_string()]
+        );
+        set_port_segments_at(&path, "$1", "%a2", TilePort::Bottom, &["team-a".to_string()]).unwrap();
+        set_port_segments_at(&path, "$1", "%b1", TilePort::Right, &["team-b".to_string()]).unwrap();
+
+        let session_tiles = vec![
+            session_tile(&hub.tile_id, "$1", hub.kind),
+            session_tile(&a1.tile_id, "$1", a1.kind),
+            session_tile(&a2.tile_id, "$1", a2.kind),
+            session_tile(&b1.tile_id, "$1", b1.kind),
+        ];
+        let component = annotate_segments(
+            component_for_tile("$1", &hub.tile_id, &session_tiles, &list_connections_at(&path, "$1").unwrap()),
+            &list_port_segments_at(&path, "$1").unwrap(),
+        );
+        assert_eq!(
+            component
+                .segments
+                .iter()
+                .map(|segment| (segment.name.as_str(), segment.tile_ids.clone(), segment.connections.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("team-a", vec!["%a1".to_string(), "%a2".to_string(), "%hub".to_string()], 2),
+                ("team-b", vec!["%b1".to_string(), "%hub".to_string()], 1),
+            ]
+        );
+
+        let team_b = filter_component_by_segment(component.clone(), Some("team-b"));
+        assert_eq!(
+            team_b.tiles.iter().map(|tile| tile.tile_id.as_str()).collect::<BTreeSet<_>>(),
+            BTreeSet::from(["%b1", "%hub"])
+        );
+        assert_eq!(team_b.connections.len(), 1);
+        assert_eq!(team_b.segments.len(), 1);
+        assert!(filter_component_by_segment(component.clone(), Some("team-c")).tiles.is_empty());
+        assert_eq!(filter_component_by_segment(component, None).tiles.len(), 4);
+    }
+
     #[test]
     fn derives_work_owner_from_live_agent_connection_and_clears_on_disconnect() {
         let path = temp_db_path("owner");
@@ -2586,13 +4584,13 @@ mod tests {
             kind: NetworkTileKind::Agent,
         };
 
-        connect_at(&path, &agent_tile, TilePort::Left, &work, TilePort::Left).unwrap();
+        connect_at(&path, &agent_tile, TilePort::Left, &work, TilePort::Left, &NetworkChangeActor::user()).unwrap();
         assert_eq!(
             derived_work_owner_agent_id_at(&path, "$1", &created.work_id).unwrap(),
             Some("agent-1".to_string())
         );
 
-        let removed = disconnect_all_for_tile_at(&path, "$1", "%1").unwrap();
+        let removed = disconnect_all_for_tile_at(&path, "$1", "%1", &NetworkChangeActor::user()).unwrap();
         assert_eq!(removed.len(), 1);
         assert_eq!(derived_work_owner_agent_id_at(&path, "$1", &created.work_id).unwrap(), None);
     }
@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::now_ms;
+use crate::db;
+use crate::network::{self, NetworkConnection, TilePort, TilePortSetting};
+use crate::tile_registry::{self, TileRecord, TileRecordKind};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkActorKind {
+    User,
+    Agent,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkChangeActor {
+    pub kind: NetworkActorKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_id: Option<String>,
+}
+
+impl NetworkChangeActor {
+    pub fn user() -> Self {
+        Self {
+            kind: NetworkActorKind::User,
+            agent_id: None,
+            tile_id: None,
+        }
+    }
+
+    pub fn system() -> Self {
+        Self {
+            kind: NetworkActorKind::System,
+            agent_id: None,
+            tile_id: None,
+        }
+    }
+
+    pub fn agent(agent_id: Option<String>, tile_id: Option<String>) -> Self {
+        Self {
+            kind: NetworkActorKind::Agent,
+            agent_id,
+            tile_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkHistoryEventKind {
+    Connect,
+    Disconnect,
+    PortSetting,
+    TileCreated,
+    TileDestroyed,
+}
+
+impl NetworkHistoryEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Connect => "connect",
+            Self::Disconnect => "disconnect",
+            Self::PortSetting => "port_setting",
+            Self::TileCreated => "tile_created",
+            Self::TileDestroyed => "tile_destroyed",
+        }
+    }
+}
+
+fn parse_event_kind(value: &str) -> Result<NetworkHistoryEventKind, rusqlite::Error> {
+    match value {
+        "connect" => Ok(NetworkHistoryEventKind::Connect),
+        "disconnect" => Ok(NetworkHistoryEventKind::Disconnect),
+        "port_setting" => Ok(NetworkHistoryEventKind::PortSetting),
+        "tile_created" => Ok(NetworkHistoryEventKind::TileCreated),
+        "tile_destroyed" => Ok(NetworkHistoryEventKind::TileDestroyed),
+        _ => Err(rusqlite::Error::FromSqlConversionFailure(
+            value.len(),
+            rusqlite::types::Type::Text,
+            Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unknown network history event kind: {value}"),
+            )),
+        )),
+    }
+}
+
+/// Port setting events carry the override before and after the change; `None`
+/// means the port was on its default modes. Tile events carry the tile's kind so
+/// destroyed tiles can be rebuilt when rewinding.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkHistoryEvent {
+    #[serde(default)]
+    pub event_id: i64,
+    pub session_id: String,
+    pub kind: NetworkHistoryEventKind,
+    pub actor: NetworkChangeActor,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<NetworkConnection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<TilePort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_port_setting: Option<TilePortSetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_setting: Option<TilePortSetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_kind: Option<TileRecordKind>,
+    pub timestamp_ms: i64,
+}
+
+impl NetworkHistoryEvent {
+    fn touches_tile(&self, matches: impl Fn(&str) -> bool) -> bool {
+        self.tile_id.as_deref().is_some_and(&matches)
+            || self.connection.as_ref().is_some_and(|connection| {
+                matches(&connection.from_tile_id) || matches(&connection.to_tile_id)
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkTopologyTile {
+    pub tile_id: String,
+    pub kind: TileRecordKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkTopology {
+    pub tiles: Vec<NetworkTopologyTile>,
+    pub connections: Vec<NetworkConnection>,
+    pub port_settings: Vec<TilePortSetting>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkHistoryQuery {
+    pub tile_id: Option<String>,
+    pub visible_tile_ids: Option<HashSet<String>>,
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+pub fn record_connection_with_conn(
+    conn: &Connection,
+    kind: NetworkHistoryEventKind,
+    connection: &NetworkConnection,
+    actor: &NetworkChangeActor,
+) -> Result<(), String> {
+    append_event_with_conn(
+        conn,
+        &NetworkHistoryEvent {
+            event_id: 0,
+            session_id: connection.session_id.clone(),
+            kind,
+            actor: actor.clone(),
+            connection: Some(connection.clone()),
+            tile_id: None,
+            port: None,
+            previous_port_setting: None,
+            port_setting: None,
+            tile_kind: None,
+            timestamp_ms: now_ms(),
+        },
+    )
+}
+
+pub fn record_port_setting_with_conn(
+    conn: &Connection,
+    session_id: &str,
+    tile_id: &str,
+    port: TilePort,
+    previous: Option<&TilePortSetting>,
+    next: Option<&TilePortSetting>,
+    actor: &NetworkChangeActor,
+) -> Result<(), String> {
+    if previous == next {
+        return Ok(());
+    }
+    append_event_with_conn(
+        conn,
+        &NetworkHistoryEvent {
+            event_id: 0,
+            session_id: session_id.to_string(),
+            kind: NetworkHistoryEventKind::PortSetting,
+            actor: actor.clone(),
+            connection: None,
+            tile_id: Some(tile_id.to_string()),
+            port: Some(port),
+            previous_port_setting: previous.cloned(),
+            port_setting: next.cloned(),
+            tile_kind: None,
+            timestamp_ms: now_ms(),
+        },
+    )
+}
+
+pub fn record_tile_with_conn(
+    conn: &Connection,
+    kind: NetworkHistoryEventKind,
+    record: &TileRecord,
+    actor: &NetworkChangeActor,
+) -> Result<(), String> {
+    append_event_with_conn(
+        conn,
+        &NetworkHistoryEvent {
+            event_id: 0,
+            session_id: record.session_id.clone(),
+            kind,
+            actor: actor.clone(),
+            connection: None,
+            tile_id: Some(record.tile_id.clone()),
+            port: None,
+            previous_port_setting: None,
+            port_setting: None,
+            tile_kind: Some(record.kind),
+            timestamp_ms: now_ms(),
+        },
+    )
+}
+
+pub fn append_event_with_conn(conn: &Connection, event: &NetworkHistoryEvent) -> Result<(), String> {
+    let actor_json = serde_json::to_string(&event.actor)
+        .map_err(|error| format!("failed to encode network history actor: {error}"))?;
+    let data_json = serde_json::to_string(event)
+        .map_err(|error| format!("failed to encode network history event: {error}"))?;
+    conn.execute(
+        "INSERT INTO network_history_event (session_id, kind, actor_json, data_json, timestamp_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            event.session_id,
+            event.kind.as_str(),
+            actor_json,
+            data_json,
+            event.timestamp_ms,
+        ],
+    )
+    .map_err(|error| format!("failed to append network history event: {error}"))?;
+    Ok(())
+}
+
+pub fn list_history_at(
+    db_path: &Path,
+    session_id: &str,
+    query: &NetworkHistoryQuery,
+) -> Result<Vec<NetworkHistoryEvent>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut events = list_events_with_conn(
+        &conn,
+        session_id,
+        query.since_ms.unwrap_or(i64::MIN),
+        query.until_ms.unwrap_or(i64::MAX),
+    )?;
+    if let Some(tile_id) = query.tile_id.as_deref() {
+        events.retain(|event| event.touches_tile(|candidate| candidate == tile_id));
+    }
+    if let Some(visible_tile_ids) = query.visible_tile_ids.as_ref() {
+        events.retain(|event| event.touches_tile(|candidate| visible_tile_ids.contains(candidate)));
+    }
+    if let Some(limit) = query.limit {
+        let skip = events.len().saturating_sub(limit);
+        events.drain(..skip);
+    }
+    Ok(events)
+}
+
+/// Rebuilds a session's topology as of `at_ms` by undoing every later event
+/// against the current tables, so state from before history was recorded survives.
+pub fn topology_at(db_path: &Path, session_id: &str, at_ms: i64) -> Result<NetworkTopology, String> {
+    let conn = db::open_at(db_path)?;
+    let current = NetworkTopology {
+        tiles: tile_registry::load_with_conn(&conn)?
+            .into_iter()
+            .filter(|record| record.session_id == session_id)
+            .map(|record| NetworkTopologyTile {
+                tile_id: record.tile_id,
+                kind: record.kind,
+            })
+            .collect(),
+        connections: network::list_connections_with_conn(&conn, session_id)?,
+        port_settings: network::list_port_settings_with_conn(&conn, session_id)?,
+    };
+    let later_events = list_events_with_conn(&conn, session_id, at_ms.saturating_add(1), i64::MAX)?;
+    Ok(rewind_topology(current, &later_events))
+}
+
+pub fn rewind_topology(current: NetworkTopology, later_events: &[NetworkHistoryEvent]) -> NetworkTopology {
+    let mut tiles = current
+        .tiles
+        .into_iter()
+        .map(|tile| (tile.tile_id, tile.kind))
+        .collect::<BTreeMap<_, _>>();
+    let mut connections = current.connections;
+    let mut port_settings = current
+        .port_settings
+        .into_iter()
+        .map(|setting| ((setting.tile_id.clone(), setting.port.as_str()), setting))
+        .collect::<BTreeMap<_, _>>();
+
+    for event in later_events.iter().rev() {
+        match event.kind {
+            NetworkHistoryEventKind::Connect => {
+                if let Some(connection) = event.connection.as_ref() {
+                    connections.retain(|existing| existing != connection);
+                }
+            }
+            NetworkHistoryEventKind::Disconnect => {
+                if let Some(connection) = event.connection.as_ref() {
+                    if !connections.contains(connection) {
+                        connections.push(connection.clone());
+                    }
+                }
+            }
+            NetworkHistoryEventKind::PortSetting => {
+                let (Some(tile_id), Some(port)) = (event.tile_id.as_ref(), event.port) else {
+                    continue;
+                };
+                let key = (tile_id.clone(), port.as_str());
+                match event.previous_port_setting.as_ref() {
+                    Some(previous) => {
+                        port_settings.insert(key, previous.clone());
+                    }
+                    None => {
+                        port_settings.remove(&key);
+                    }
+                }
+            }
+            NetworkHistoryEventKind::TileCreated => {
+                if let Some(tile_id) = event.tile_id.as_ref() {
+                    tiles.remove(tile_id);
+                }
+            }
+            NetworkHistoryEventKind::TileDestroyed => {
+                if let (Some(tile_id), Some(kind)) = (event.tile_id.as_ref(), event.tile_kind) {
+                    tiles.insert(tile_id.clone(), kind);
+                }
+            }
+        }
+    }
+
+    connections.sort_by(|left, right| {
+        left.from_tile_id
+            .cmp(&right.from_tile_id)
+            .then_with(|| left.from_port.as_str().cmp(right.from_port.as_str()))
+            .then_with(|| left.to_tile_id.cmp(&right.to_tile_id))
+            .then_with(|| left.to_port.as_str().cmp(right.to_port.as_str()))
+    });
+    NetworkTopology {
+        tiles: tiles
+            .into_iter()
+            .map(|(tile_id, kind)| NetworkTopologyTile { tile_id, kind })
+            .collect(),
+        connections,
+        port_settings: port_settings.into_values().collect(),
+    }
+}
+
+fn list_events_with_conn(
+    conn: &Connection,
+    session_id: &str,
+    since_ms: i64,
+    until_ms: i64,
+) -> Result<Vec<NetworkHistoryEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_id, kind, data_json
+             FROM network_history_event
+             WHERE session_id = ?1
+               AND timestamp_ms >= ?2
+               AND timestamp_ms <= ?3
+             ORDER BY event_id ASC",
+        )
+        .map_err(|error| format!("failed to prepare network history query: {error}"))?;
+    let rows = stmt
+        .query_map(params![session_id, since_ms, until_ms], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                parse_event_kind(&row.get::<_, String>(1)?)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|error| format!("failed to query network history: {error}"))?;
+    let mut events = Vec::new();
+    for row in rows {
+        let (event_id, kind, data_json) =
+            row.map_err(|error| format!("failed to decode network history row: {error}"))?;
+        let mut event: NetworkHistoryEvent = serde_json::from_str(&data_json)
+            .map_err(|error| format!("failed to parse network history event {event_id}: {error}"))?;
+        event.event_id = event_id;
+        event.kind = kind;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append_event_with_conn, list_history_at, rewind_topology, topology_at, NetworkChangeActor,
+        NetworkHistoryEvent, NetworkHistoryEventKind, NetworkHistoryQuery, NetworkTopology, NetworkTopologyTile,
+    };
+    use crate::db;
+    use crate::tile_registry::{self, TileRecord, TileRecordKind};
+    use crate::network::{NetworkConnection, PortMode, PortNetworkingMode, TilePort, TilePortSetting};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_db_path(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("herd-network-history-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.join("herd.sqlite")
+    }
+
+    fn connection(from_tile_id: &str, to_tile_id: &str) -> NetworkConnection {
+        NetworkConnection {
+            session_id: "$1".to_string(),
+            from_tile_id: from_tile_id.to_string(),
+            from_port: TilePort::Left,
+            to_tile_id: to_tile_id.to_string(),
+            to_port: TilePort::Right,
+        }
+    }
+
+    fn connection_event(
+        kind: NetworkHistoryEventKind,
+        connection: NetworkConnection,
+        timestamp_ms: i64,
+    ) -> NetworkHistoryEvent {
+        NetworkHistoryEvent {
+            event_id: 0,
+            session_id: connection.session_id.clone(),
+            kind,
+            actor: NetworkChangeActor::agent(Some("agent-3".to_string()), Some("%3".to_string())),
+            connection: Some(connection),
+            tile_id: None,
+            port: None,
+            previous_port_setting: None,
+            port_setting: None,
+            tile_kind: None,
+            timestamp_ms,
+        }
+    }
+
+    fn read_only_setting(tile_id: &str) -> TilePortSetting {
+        TilePortSetting {
+            session_id: "$1".to_string(),
+            tile_id: tile_id.to_string(),
+            port: TilePort::Left,
+            access_mode: PortMode::Read,
+            networking_mode: PortNetworkingMode::Broadcast,
+        }
+    }
+
+    #[test]
+    fn rewinds_connects_disconnects_and_port_settings() {
+        let current = NetworkTopology {
+            tiles: Vec::new(),
+            connections: vec![connection("%2", "%3")],
+            port_settings: vec![read_only_setting("%2")],
+        };
+        let later_events = vec![
+            connection_event(NetworkHistoryEventKind::Disconnect, connection("%1", "%2"), 20),
+            connection_event(NetworkHistoryEventKind::Connect, connection("%2", "%3"), 30),
+            NetworkHistoryEvent {
+                event_id: 0,
+                session_id: "$1".to_string(),
+                kind: NetworkHistoryEventKind::PortSetting,
+                actor: NetworkChangeActor::user(),
+                connection: None,
+                tile_id: Some("%2".to_string()),
+                port: Some(TilePort::Left),
+                previous_port_setting: None,
+                port_setting: Some(read_only_setting("%2")),
+                tile_kind: None,
+                timestamp_ms: 40,
+            },
+        ];
+
+        let rewound = rewind_topology(current, &later_events);
+        assert_eq!(rewound.connections, vec![connection("%1", "%2")]);
+        assert!(rewound.port_settings.is_empty());
+    }
+
+    #[test]
+    fn topology_at_restores_tiles_destroyed_since() {
+        let path = temp_db_path("tiles");
+        let record = |tile_id: &str, kind: TileRecordKind| TileRecord {
+            tile_id: tile_id.to_string(),
+            session_id: "$1".to_string(),
+            kind,
+            window_id: "@1".to_string(),
+            pane_id: format!("%{tile_id}"),
+            browser_incognito: false,
+            created_at: 1,
+            updated_at: 1,
+        };
+        tile_registry::replace_at(&path, &[record("AbCdEf", TileRecordKind::Browser)]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let before_swap = crate::agent::now_ms();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        tile_registry::replace_at(&path, &[record("GhIjKl", TileRecordKind::Shell)]).unwrap();
+
+        let past = topology_at(&path, "$1", before_swap).unwrap();
+        assert_eq!(
+            past.tiles,
+            vec![NetworkTopologyTile {
+                tile_id: "AbCdEf".to_string(),
+                kind: TileRecordKind::Browser,
+            }]
+        );
+        let now = topology_at(&path, "$1", i64::MAX - 1).unwrap();
+        assert_eq!(now.tiles.len(), 1);
+        assert_eq!(now.tiles[0].tile_id, "GhIjKl");
+
+        let history = list_history_at(
+            &path,
+            "$1",
+            &NetworkHistoryQuery {
+                tile_id: Some("AbCdEf".to_string()),
+                ..NetworkHistoryQuery::default()
+            },
+        )
+        .unwrap();
+        let kinds = history.iter().map(|event| event.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![NetworkHistoryEventKind::TileCreated, NetworkHistoryEventKind::TileDestroyed]);
+    }
+
+    #[test]
+    fn history_round_trips_and_reconstructs_past_topology_from_sqlite() {
+        let path = temp_db_path("round-trip");
+        let conn = db::open_at(&path).unwrap();
+        conn.execute(
+            "INSERT INTO network_connection (session_id, from_tile_id, from_port, to_tile_id, to_port)
+             VALUES ('$1', '%2', 'left', '%3', 'right')",
+            [],
+        )
+        .unwrap();
+        append_event_with_conn(
+            &conn,
+            &connection_event(NetworkHistoryEventKind::Disconnect, connection("%1", "%2"), 100),
+        )
+        .unwrap();
+        append_event_with_conn(
+            &conn,
+            &connection_event(NetworkHistoryEventKind::Connect, connection("%2", "%3"), 200),
+        )
+        .unwrap();
+
+        let history = list_history_at(&path, "$1", &NetworkHistoryQuery::default()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].event_id < history[1].event_id);
+        assert_eq!(history[0].actor.agent_id.as_deref(), Some("agent-3"));
+
+        let for_tile = list_history_at(
+            &path,
+            "$1",
+            &NetworkHistoryQuery {
+                tile_id: Some("%1".to_string()),
+                ..NetworkHistoryQuery::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(for_tile.len(), 1);
+        assert_eq!(for_tile[0].kind, NetworkHistoryEventKind::Disconnect);
+
+        let visible = list_history_at(
+            &path,
+            "$1",
+            &NetworkHistoryQuery {
+                visible_tile_ids: Some(["%3".to_string()].into_iter().collect()),
+                ..NetworkHistoryQuery::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].kind, NetworkHistoryEventKind::Connect);
+
+        let latest = list_history_at(
+            &path,
+            "$1",
+            &NetworkHistoryQuery {
+                limit: Some(1),
+                ..NetworkHistoryQuery::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(latest[0].kind, NetworkHistoryEventKind::Connect);
+
+        assert_eq!(topology_at(&path, "$1", 50).unwrap().connections, vec![connection("%1", "%2")]);
+        assert!(topology_at(&path, "$1", 150).unwrap().connections.is_empty());
+        assert_eq!(topology_at(&path, "$1", 250).unwrap().connections, vec![connection("%2", "%3")]);
+    }
+}
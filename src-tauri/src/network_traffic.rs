@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::network::NetworkConnection;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficScope {
+    Tile,
+    Connection,
+}
+
+impl TrafficScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tile => "tile",
+            Self::Connection => "connection",
+        }
+    }
+}
+
+/// For connection rollups, `messages_out` counts calls initiated from the
+/// connection's `from` tile and `messages_in` counts calls initiated from its `to` tile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrafficCounters {
+    pub messages_in: u64,
+    pub messages_out: u64,
+    pub errors: u64,
+    pub args_bytes: u64,
+    pub result_bytes: u64,
+    #[serde(default)]
+    pub calls_by_action: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_activity_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrafficStat {
+    pub session_id: String,
+    pub scope: TrafficScope,
+    pub subject_id: String,
+    #[serde(flatten)]
+    pub counters: TrafficCounters,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkTrafficStats {
+    pub tiles: Vec<TrafficStat>,
+    pub connections: Vec<TrafficStat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficSample {
+    pub session_id: String,
+    pub caller_tile_id: Option<String>,
+    pub target_tile_id: String,
+    pub action: String,
+    pub args_bytes: u64,
+    pub result_bytes: u64,
+    pub error: bool,
+    pub timestamp_ms: i64,
+}
+
+pub type TrafficStatMap = HashMap<String, TrafficStat>;
+
+pub fn connection_key(connection: &NetworkConnection) -> String {
+    format!(
+        "{}:{}->{}:{}",
+        connection.from_tile_id,
+        connection.from_port.as_str(),
+        connection.to_tile_id,
+        connection.to_port.as_str(),
+    )
+}
+
+pub fn stat_key(session_id: &str, scope: TrafficScope, subject_id: &str) -> String {
+    format!("{session_id}::{}::{subject_id}", scope.as_str())
+}
+
+pub fn direct_connection_between<'a>(
+    connections: &'a [NetworkConnection],
+    left_tile_id: &str,
+    right_tile_id: &str,
+) -> Option<&'a NetworkConnection> {
+    connections.iter().find(|connection| {
+        (connection.from_tile_id == left_tile_id && connection.to_tile_id == right_tile_id)
+            || (connection.from_tile_id == right_tile_id && connection.to_tile_id == left_tile_id)
+    })
+}
+
+fn entry_for<'a>(
+    stats: &'a mut TrafficStatMap,
+    session_id: &str,
+    scope: TrafficScope,
+    subject_id: &str,
+) -> &'a mut TrafficStat {
+    stats
+        .entry(stat_key(session_id, scope, subject_id))
+        .or_insert_with(|| TrafficStat {
+            session_id: session_id.to_string(),
+            scope,
+            subject_id: subject_id.to_string(),
+            counters: TrafficCounters::default(),
+        })
+}
+
+fn bump(counters: &mut TrafficCounters, sample: &TrafficSample, inbound: bool) {
+    if inbound {
+        counters.messages_in += 1;
+    } else {
+        counters.messages_out += 1;
+    }
+    if sample.error {
+        counters.errors += 1;
+    }
+    counters.args_bytes += sample.args_bytes;
+    counters.result_bytes += sample.result_bytes;
+    *counters.calls_by_action.entry(sample.action.clone()).or_insert(0) += 1;
+    counters.last_activity_ms = Some(
+        counters
+            .last_activity_ms
+            .map_or(sample.timestamp_ms, |previous| previous.max(sample.timestamp_ms)),
+    );
+}
+
+/// Applies a sample to the in-memory rollups and returns the touched stats so
+/// callers can persist only what changed.
+pub fn apply_sample(
+    stats: &mut TrafficStatMap,
+    sample: &TrafficSample,
+    connection: Option<&NetworkConnection>,
+) -> Vec<TrafficStat> {
+    let mut touched = Vec::new();
+
+    let target = entry_for(stats, &sample.session_id, TrafficScope::Tile, &sample.target_tile_id);
+    bump(&mut target.counters, sample, true);
+    touched.push(target.clone());
+
+    if let Some(caller_tile_id) = sample
+        .caller_tile_id
+        .as_deref()
+        .filter(|caller_tile_id| *caller_tile_id != sample.target_tile_id)
+    {
+        let caller = entry_for(stats, &sample.session_id, TrafficScope::Tile, caller_tile_id);
+        bump(&mut caller.counters, sample, false);
+        touched.push(caller.clone());
+
+        if let Some(connection) = connection {
+            let inbound = connection.to_tile_id == caller_tile_id;
+            let link = entry_for(stats, &sample.session_id, TrafficScope::Connection, &connection_key(connection));
+            bump(&mut link.counters, sample, inbound);
+            touched.push(link.clone());
+        }
+    }
+
+    touched
+}
+
+pub fn stats_in_session(stats: &TrafficStatMap, session_id: &str) -> NetworkTrafficStats {
+    let mut tiles = Vec::new();
+    let mut connections = Vec::new();
+    for stat in stats.values().filter(|stat| stat.session_id == session_id) {
+        match stat.scope {
+            TrafficScope::Tile => tiles.push(stat.clone()),
+            TrafficScope::Connection => connections.push(stat.clone()),
+        }
+    }
+    tiles.sort_by(|left, right| left.subject_id.cmp(&right.subject_id));
+    connections.sort_by(|left, right| left.subject_id.cmp(&right.subject_id));
+    NetworkTrafficStats { tiles, connections }
+}
+
+pub fn connection_stat_endpoints(stat: &TrafficStat) -> Option<(&str, &str)> {
+    if stat.scope != TrafficScope::Connection {
+        return None;
+    }
+    let (from, to) = stat.subject_id.split_once("->")?;
+    let (from_tile_id, _) = from.rsplit_once(':')?;
+    let (to_tile_id, _) = to.rsplit_once(':')?;
+    Some((from_tile_id, to_tile_id))
+}
+
+pub fn retain_visible_tiles(mut stats: NetworkTrafficStats, tile_ids: &HashSet<String>) -> NetworkTrafficStats {
+    stats.tiles.retain(|stat| tile_ids.contains(&stat.subject_id));
+    stats.connections.retain(|stat| {
+        connection_stat_endpoints(stat)
+            .is_some_and(|(from_tile_id, to_tile_id)| tile_ids.contains(from_tile_id) && tile_ids.contains(to_tile_id))
+    });
+    stats
+}
+
+pub fn retain_tile(mut stats: NetworkTrafficStats, tile_id: &str) -> NetworkTrafficStats {
+    stats.tiles.retain(|stat| stat.subject_id == tile_id);
+    stats.connections.retain(|stat| {
+        connection_stat_endpoints(stat)
+            .is_some_and(|(from_tile_id, to_tile_id)| from_tile_id == tile_id || to_tile_id == tile_id)
+    });
+    stats
+}
+
+/// Drops the rollups for destroyed tiles, including every connection stat
+/// that has one of them as an endpoint, and returns what was removed.
+pub fn remove_tiles(stats: &mut TrafficStatMap, tile_ids: &HashSet<String>) -> Vec<TrafficStat> {
+    let removed_keys = stats
+        .iter()
+        .filter(|(_, stat)| match stat.scope {
+            TrafficScope::Tile => tile_ids.contains(&stat.subject_id),
+            TrafficScope::Connection => connection_stat_endpoints(stat).is_some_and(|(from_tile_id, to_tile_id)| {
+                tile_ids.contains(from_tile_id) || tile_ids.contains(to_tile_id)
+            }),
+        })
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    removed_keys
+        .into_iter()
+        .filter_map(|key| stats.remove(&key))
+        .collect()
+}
+
+pub fn load_traffic_stats_at(path: &Path) -> Result<TrafficStatMap, String> {
+    let conn = db::open_at(path)?;
+    let mut stmt = conn
+        .prepare("SELECT data_json FROM network_traffic_stat ORDER BY session_id, scope, subject_id")
+        .map_err(|error| format!("failed to prepare network traffic query: {error}"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|error| format!("failed to query network traffic rows: {error}"))?;
+    let mut stats = HashMap::new();
+    for row in rows {
+        let json = row.map_err(|error| format!("failed to decode network traffic row: {error}"))?;
+        let stat = serde_json::from_str::<TrafficStat>(&json)
+            .map_err(|error| format!("failed to parse network traffic json: {error}"))?;
+        stats.insert(stat_key(&stat.session_id, stat.scope, &stat.subject_id), stat);
+    }
+    Ok(stats)
+}
+
+pub fn upsert_traffic_stats_at(path: &Path, stats: &[TrafficStat]) -> Result<(), String> {
+    let conn = db::open_at(path)?;
+    upsert_traffic_stats_with_conn(&conn, stats)
+}
+
+pub fn upsert_traffic_stats_with_conn(conn: &Connection, stats: &[TrafficStat]) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|error| format!("failed to begin network traffic transaction: {error}"))?;
+    for stat in stats {
+        let data_json = serde_json::to_string(stat)
+            .map_err(|error| format!("failed to serialize network traffic {}: {error}", stat.subject_id))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO network_traffic_stat (session_id, scope, subject_id, data_json, last_activity_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                stat.session_id,
+                stat.scope.as_str(),
+                stat.subject_id,
+                data_json,
+                stat.counters.last_activity_ms,
+            ],
+        )
+        .map_err(|error| format!("failed to upsert network traffic {}: {error}", stat.subject_id))?;
+    }
+    tx.commit()
+        .map_err(|error| format!("failed to commit network traffic transaction: {error}"))?;
+    Ok(())
+}
+
+pub fn delete_traffic_stats_at(path: &Path, stats: &[TrafficStat]) -> Result<(), String> {
+    if stats.is_empty() {
+        return Ok(());
+    }
+    let conn = db::open_at(path)?;
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|error| format!("failed to begin network traffic transaction: {error}"))?;
+    for stat in stats {
+        tx.execute(
+            "DELETE FROM network_traffic_stat WHERE session_id = ?1 AND scope = ?2 AND subject_id = ?3",
+            params![stat.session_id, stat.scope.as_str(), stat.subject_id],
+        )
+        .map_err(|error| format!("failed to delete network traffic {}: {error}", stat.subject_id))?;
+    }
+    tx.commit()
+        .map_err(|error| format!("failed to commit network traffic transaction: {error}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_sample, connection_key, delete_traffic_stats_at, direct_connection_between, load_traffic_stats_at,
+        remove_tiles, retain_tile, retain_visible_tiles, stats_in_session, upsert_traffic_stats_at, TrafficSample,
+        TrafficScope, TrafficStatMap,
+    };
+    use crate::network::{NetworkConnection, TilePort};
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_db_path(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("herd-network-traffic-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root.join("herd.sqlite")
+    }
+
+    fn sample(caller: Option<&str>, target: &str, action: &str, error: bool, timestamp_ms: i64) -> TrafficSample {
+        TrafficSample {
+            session_id: "$1".to_string(),
+            caller_tile_id: caller.map(str::to_string),
+            target_tile_id: target.to_string(),
+            action: action.to_string(),
+            args_bytes: 10,
+            result_bytes: 32,
+            error,
+            timestamp_ms,
+        }
+    }
+
+    fn link() -> NetworkConnection {
+        NetworkConnection {
+            session_id: "$1".to_string(),
+            from_tile_id: "%agent".to_string(),
+            from_port: TilePort::Left,
+            to_tile_id: "%shell".to_string(),
+            to_port: TilePort::Right,
+        }
+    }
+
+    #[test]
+    fn rolls_up_tile_and_connection_counters() {
+        let mut stats = TrafficStatMap::new();
+        let connection = link();
+        apply_sample(&mut stats, &sample(Some("%agent"), "%shell", "exec", false, 5), Some(&connection));
+        apply_sample(&mut stats, &sample(Some("%agent"), "%shell", "output_read", true, 9), Some(&connection));
+        apply_sample(&mut stats, &sample(Some("%shell"), "%agent", "get", false, 7), Some(&connection));
+
+        let session = stats_in_session(&stats, "$1");
+        let shell = session.tiles.iter().find(|stat| stat.subject_id == "%shell").unwrap();
+        assert_eq!(shell.counters.messages_in, 2);
+        assert_eq!(shell.counters.messages_out, 1);
+        assert_eq!(shell.counters.errors, 1);
+        assert_eq!(shell.counters.args_bytes, 30);
+        assert_eq!(shell.counters.result_bytes, 96);
+        assert_eq!(shell.counters.calls_by_action.get("exec"), Some(&1));
+        assert_eq!(shell.counters.last_activity_ms, Some(9));
+
+        assert_eq!(session.connections.len(), 1);
+        let edge = &session.connections[0];
+        assert_eq!(edge.scope, TrafficScope::Connection);
+        assert_eq!(edge.subject_id, connection_key(&connection));
+        assert_eq!(edge.counters.messages_out, 2);
+        assert_eq!(edge.counters.messages_in, 1);
+    }
+
+    #[test]
+    fn skips_caller_and_connection_counters_for_uncorrelated_calls() {
+        let mut stats = TrafficStatMap::new();
+        let touched = apply_sample(&mut stats, &sample(None, "%shell", "get", false, 1), None);
+        assert_eq!(touched.len(), 1);
+        assert!(stats_in_session(&stats, "$1").connections.is_empty());
+        assert!(stats_in_session(&stats, "$2").tiles.is_empty());
+    }
+
+    #[test]
+    fn finds_direct_connections_in_either_direction() {
+        let connections = vec![link()];
+        assert!(direct_connection_between(&connections, "%shell", "%agent").is_some());
+        assert!(direct_connection_between(&connections, "%agent", "%browser").is_none());
+    }
+
+    #[test]
+    fn filters_stats_by_visibility_and_tile() {
+        let mut stats = TrafficStatMap::new();
+        let connection = NetworkConnection {
+            session_id: "$1".to_string(),
+            from_tile_id: "work:work-s1-001".to_string(),
+            from_port: TilePort::Left,
+            to_tile_id: "%agent".to_string(),
+            to_port: TilePort::Right,
+        };
+        apply_sample(&mut stats, &sample(Some("%agent"), "work:work-s1-001", "get", false, 1), Some(&connection));
+        apply_sample(&mut stats, &sample(Some("%agent"), "%shell", "exec", false, 2), Some(&link()));
+
+        let visible = retain_visible_tiles(
+            stats_in_session(&stats, "$1"),
+            &HashSet::from(["%agent".to_string(), "work:work-s1-001".to_string()]),
+        );
+        assert_eq!(visible.tiles.len(), 2);
+        assert_eq!(visible.connections.len(), 1);
+        assert_eq!(visible.connections[0].subject_id, connection_key(&connection));
+
+        let shell = retain_tile(stats_in_session(&stats, "$1"), "%shell");
+        assert_eq!(shell.tiles.len(), 1);
+        assert_eq!(shell.connections.len(), 1);
+        assert_eq!(shell.connections[0].subject_id, connection_key(&link()));
+    }
+
+    #[test]
+    fn traffic_rollups_round_trip_through_sqlite() {
+        let path = temp_db_path("round-trip");
+        let mut stats = TrafficStatMap::new();
+        let touched = apply_sample(&mut stats, &sample(Some("%agent"), "%shell", "exec", false, 5), Some(&link()));
+        upsert_traffic_stats_at(&path, &touched).unwrap();
+        let touched = apply_sample(&mut stats, &sample(Some("%agent"), "%shell", "exec", false, 6), Some(&link()));
+        upsert_traffic_stats_at(&path, &touched).unwrap();
+
+        let loaded = load_traffic_stats_at(&path).unwrap();
+        assert_eq!(loaded, stats);
+
+        let removed = remove_tiles(&mut stats, &HashSet::from(["%shell".to_string()]));
+        assert_eq!(removed.len(), 2);
+        delete_traffic_stats_at(&path, &removed).unwrap();
+        let loaded = load_traffic_stats_at(&path).unwrap();
+        assert_eq!(loaded, stats);
+        assert_eq!(loaded.len(), 1);
+
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+
+/// One process in a pane's process tree, read from `/proc/<pid>`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub ppid: u32,
+    pub depth: usize,
+    pub name: String,
+    pub command: String,
+    pub state: String,
+    pub threads: u32,
+    pub cpu_time_ms: u64,
+    pub rss_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProcessTree {
+    pub root_pid: u32,
+    pub process_count: usize,
+    pub cpu_time_ms: u64,
+    pub rss_bytes: u64,
+    pub processes: Vec<ProcessEntry>,
+}
+
+impl ProcessTree {
+    /// The process that has used the most CPU time, excluding the pane's own shell when it has children.
+    pub fn busiest(&self) -> Option<&ProcessEntry> {
+        self.processes
+            .iter()
+            .filter(|process| process.depth > 0 || self.processes.len() == 1)
+            .max_by_key(|process| process.cpu_time_ms)
+    }
+}
+
+/// Kernel constants needed to turn `/proc/<pid>/stat` fields into milliseconds and bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcClock {
+    pub ticks_per_second: u64,
+    pub page_size: u64,
+    pub boot_time_ms: Option<i64>,
+}
+
+impl ProcClock {
+    pub fn detect(proc_root: &Path) -> Self {
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        Self {
+            ticks_per_second: u64::try_from(ticks_per_second).ok().filter(|value| *value > 0).unwrap_or(100),
+            page_size: u64::try_from(page_size).ok().filter(|value| *value > 0).unwrap_or(4096),
+            boot_time_ms: fs::read_to_string(proc_root.join("stat"))
+                .ok()
+                .and_then(|raw| parse_boot_time_ms(&raw)),
+        }
+    }
+
+    fn ticks_to_ms(&self, ticks: u64) -> u64 {
+        ticks.saturating_mul(1000) / self.ticks_per_second
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcStat {
+    pid: u32,
+    name: String,
+    state: String,
+    ppid: u32,
+    cpu_ticks: u64,
+    threads: u32,
+    start_ticks: u64,
+    rss_pages: u64,
+}
+
+/// Parses `/proc/<pid>/stat`. The command name is parenthesised and may itself contain spaces or
+/// parentheses, so fields are counted from the last `)`.
+fn parse_proc_stat(raw: &str) -> Option<ProcStat> {
+    let open = raw.find('(')?;
+    let close = raw.rfind(')')?;
+    let pid = raw[..open].trim().parse().ok()?;
+    let name = raw[open + 1..close].to_string();
+    let fields = raw[close + 1..].split_whitespace().collect::<Vec<_>>();
+    // Field numbers in proc(5) start at 1 for pid, so field N is fields[N - 3] here.
+    let field = |number: usize| fields.get(number - 3).copied();
+    let number = |number: usize| field(number).and_then(|value| value.parse::<u64>().ok());
+    Some(ProcStat {
+        pid,
+        name,
+        state: field(3)?.to_string(),
+        ppid: number(4)? as u32,
+        cpu_ticks: number(14)? + number(15)?,
+        threads: number(20)? as u32,
+        start_ticks: number(22)?,
+        rss_pages: field(24)?.parse::<i64>().ok()?.max(0) as u64,
+    })
+}
+
+fn parse_boot_time_ms(raw: &str) -> Option<i64> {
+    raw.lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .map(|seconds| seconds * 1000)
+}
+
+fn read_command_line(process_dir: &Path, name: &str) -> String {
+    let raw = fs::read(process_dir.join("cmdline")).unwrap_or_default();
+    let command = raw
+        .split(|byte| *byte == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if command.is_empty() {
+        format!("[{name}]")
+    } else {
+        command
+    }
+}
+
+pub fn process_tree(root_pid: u32) -> Result<ProcessTree, String> {
+    ProcessTable::scan()?.tree(root_pid)
+}
+
+/// Walks every process under `proc_root` and returns `root_pid` with its descendants in depth-first order.
+pub fn process_tree_at(proc_root: &Path, root_pid: u32, clock: &ProcClock) -> Result<ProcessTree, String> {
+    ProcessTable::scan_at(proc_root, *clock)?.tree(root_pid)
+}
+
+/// One pass over `/proc`, so the trees of several panes can be built without rescanning for each.
+pub struct ProcessTable {
+    proc_root: PathBuf,
+    clock: ProcClock,
+    stats: HashMap<u32, ProcStat>,
+    children: BTreeMap<u32, Vec<u32>>,
+}
+
+impl ProcessTable {
+    pub fn scan() -> Result<Self, String> {
+        let proc_root = Path::new("/proc");
+        Self::scan_at(proc_root, ProcClock::detect(proc_root))
+    }
+
+    pub fn scan_at(proc_root: &Path, clock: ProcClock) -> Result<Self, String> {
+        let entries =
+            fs::read_dir(proc_root).map_err(|error| format!("failed to read {}: {error}", proc_root.display()))?;
+        let mut stats = HashMap::new();
+        let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            // Processes can exit between listing and reading; skip them.
+            let Some(stat) = fs::read_to_string(entry.path().join("stat"))
+                .ok()
+                .and_then(|raw| parse_proc_stat(&raw))
+            else {
+                continue;
+            };
+            children.entry(stat.ppid).or_default().push(pid);
+            stats.insert(pid, stat);
+        }
+        for pids in children.values_mut() {
+            pids.sort_unstable();
+        }
+        Ok(Self {
+            proc_root: proc_root.to_path_buf(),
+            clock,
+            stats,
+            children,
+        })
+    }
+
+    /// Returns `root_pid` with its descendants in depth-first order.
+    pub fn tree(&self, root_pid: u32) -> Result<ProcessTree, String> {
+        if !self.stats.contains_key(&root_pid) {
+            return Err(format!("process {root_pid} is not running"));
+        }
+        let clock = &self.clock;
+        let mut processes = Vec::new();
+        let mut stack = vec![(root_pid, 0usize)];
+        while let Some((pid, depth)) = stack.pop() {
+            let Some(stat) = self.stats.get(&pid) else {
+                continue;
+            };
+            processes.push(ProcessEntry {
+                pid,
+                ppid: stat.ppid,
+                depth,
+                name: stat.name.clone(),
+                command: read_command_line(&self.proc_root.join(pid.to_string()), &stat.name),
+                state: stat.state.clone(),
+                threads: stat.threads,
+                cpu_time_ms: clock.ticks_to_ms(stat.cpu_ticks),
+                rss_bytes: stat.rss_pages.saturating_mul(clock.page_size),
+                started_at: clock
+                    .boot_time_ms
+                    .map(|boot_time_ms| boot_time_ms + clock.ticks_to_ms(stat.start_ticks) as i64),
+            });
+            if let Some(child_pids) = self.children.get(&pid) {
+                stack.extend(child_pids.iter().rev().map(|child_pid| (*child_pid, depth + 1)));
+            }
+        }
+
+        Ok(ProcessTree {
+            root_pid,
+            process_count: processes.len(),
+            cpu_time_ms: processes.iter().map(|process| process.cpu_time_ms).sum(),
+            rss_bytes: processes.iter().map(|process| process.rss_bytes).sum(),
+            processes,
+        })
+    }
+}
+
+/// CPU usage between two samples of a tree's total CPU time, where 100.0 is one full core.
+pub fn cpu_percent(previous_cpu_time_ms: u64, current_cpu_time_ms: u64, elapsed_ms: u64) -> f64 {
+    if elapsed_ms == 0 {
+        return 0.0;
+    }
+    let used = current_cpu_time_ms.saturating_sub(previous_cpu_time_ms) as f64;
+    (used * 1000.0 / elapsed_ms as f64).round() / 10.0
+}
+
+/// The latest background sample of a tile's process tree. CPU usage is measured against the previous sample of
+/// the same pane process, so the first sample of a process has none.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ResourceSample {
+    pub pane_pid: u32,
+    pub process_count: usize,
+    pub cpu_percent: Option<f64>,
+    pub cpu_time_ms: u64,
+    pub rss_bytes: u64,
+    pub busiest: Option<ProcessEntry>,
+    pub sampled_at: i64,
+}
+
+impl ResourceSample {
+    pub fn from_tree(tree: &ProcessTree, previous: Option<&ResourceSample>, sampled_at: i64) -> Self {
+        let cpu_percent = previous
+            .filter(|previous| previous.pane_pid == tree.root_pid && previous.sampled_at < sampled_at)
+            .map(|previous| {
+                cpu_percent(
+                    previous.cpu_time_ms,
+                    tree.cpu_time_ms,
+                    (sampled_at - previous.sampled_at) as u64,
+                )
+            });
+        Self {
+            pane_pid: tree.root_pid,
+            process_count: tree.process_count,
+            cpu_percent,
+            cpu_time_ms: tree.cpu_time_ms,
+            rss_bytes: tree.rss_bytes,
+            busiest: tree.busiest().cloned(),
+            sampled_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResourceThreshold {
+    pub session_id: String,
+    pub tile_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    pub updated_at: i64,
+}
+
+impl ResourceThreshold {
+    /// Names of the limits the given usage is over.
+    pub fn exceeded(&self, cpu_percent: Option<f64>, rss_bytes: u64) -> Vec<&'static str> {
+        let mut exceeded = Vec::new();
+        if let (Some(limit), Some(usage)) = (self.cpu_percent, cpu_percent) {
+            if usage > limit {
+                exceeded.push("cpu_percent");
+            }
+        }
+        if self.rss_bytes.is_some_and(|limit| rss_bytes > limit) {
+            exceeded.push("rss_bytes");
+        }
+        exceeded
+    }
+}
+
+pub fn upsert_threshold_at(db_path: &Path, threshold: &ResourceThreshold) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    upsert_threshold_with_conn(&conn, threshold)
+}
+
+fn upsert_threshold_with_conn(conn: &Connection, threshold: &ResourceThreshold) -> Result<(), String> {
+    let data_json = serde_json::to_string(threshold)
+        .map_err(|error| format!("failed to encode resource threshold: {error}"))?;
+    conn.execute(
+        "INSERT INTO tile_resource_threshold (tile_id, session_id, data_json, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(tile_id) DO UPDATE SET
+           session_id = excluded.session_id,
+           data_json = excluded.data_json,
+           updated_at = excluded.updated_at",
+        params![threshold.tile_id, threshold.session_id, data_json, threshold.updated_at],
+    )
+    .map_err(|error| format!("failed to store resource threshold: {error}"))?;
+    Ok(())
+}
+
+pub fn delete_threshold_at(db_path: &Path, tile_id: &str) -> Result<bool, String> {
+    let conn = db::open_at(db_path)?;
+    let removed = conn
+        .execute("DELETE FROM tile_resource_threshold WHERE tile_id = ?1", [tile_id])
+        .map_err(|error| format!("failed to delete resource threshold: {error}"))?;
+    Ok(removed > 0)
+}
+
+/// Deletes the thresholds of tiles that no longer exist.
+pub fn delete_tile_thresholds_at(db_path: &Path, tile_ids: &HashSet<String>) -> Result<(), String> {
+    if tile_ids.is_empty() {
+        return Ok(());
+    }
+    let conn = db::open_at(db_path)?;
+    for tile_id in tile_ids {
+        conn.execute("DELETE FROM tile_resource_threshold WHERE tile_id = ?1", [tile_id])
+            .map_err(|error| format!("failed to delete resource threshold: {error}"))?;
+    }
+    Ok(())
+}
+
+pub fn list_thresholds_at(db_path: &Path, session_id: Option<&str>) -> Result<Vec<ResourceThreshold>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT tile_id, data_json
+             FROM tile_resource_threshold
+             WHERE (?1 IS NULL OR session_id = ?1)
+             ORDER BY session_id ASC, tile_id ASC",
+        )
+        .map_err(|error| format!("failed to prepare resource threshold query: {error}"))?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|error| format!("failed to query resource thresholds: {error}"))?;
+    let mut thresholds = Vec::new();
+    for row in rows {
+        let (tile_id, data_json) =
+            row.map_err(|error| format!("failed to decode resource threshold row: {error}"))?;
+        let threshold = serde_json::from_str(&data_json)
+            .map_err(|error| format!("failed to parse resource threshold for {tile_id}: {error}"))?;
+        thresholds.push(threshold);
+    }
+    Ok(thresholds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cpu_percent, delete_threshold_at, delete_tile_thresholds_at, list_thresholds_at, parse_proc_stat,
+        process_tree_at, upsert_threshold_at, ProcClock, ProcessTable, ResourceSample, ResourceThreshold,
+    };
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("herd-process-info-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn write_process(proc_root: &Path, pid: u32, name: &str, ppid: u32, cpu_ticks: (u64, u64), rss_pages: u64, cmdline: &str) {
+        let dir = proc_root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("stat"),
+            format!(
+                "{pid} ({name}) S {ppid} {pid} {pid} 0 -1 4194304 100 0 0 0 {} {} 0 0 20 0 1 0 {} 1000000 {rss_pages} 18446744073709551615\n",
+                cpu_ticks.0,
+                cpu_ticks.1,
+                500 + pid,
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("cmdline"), cmdline.replace(' ', "\0")).unwrap();
+    }
+
+    #[test]
+    fn parses_proc_stat_with_awkward_command_names() {
+        let stat = parse_proc_stat(
+            "4242 (tmux: server (x)) R 1 4242 4242 0 -1 4194560 10 0 0 0 150 50 0 0 20 0 3 0 9000 2000000 256 18446744073709551615",
+        )
+        .unwrap();
+        assert_eq!(stat.pid, 4242);
+        assert_eq!(stat.name, "tmux: server (x)");
+        assert_eq!(stat.state, "R");
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.cpu_ticks, 200);
+        assert_eq!(stat.threads, 3);
+        assert_eq!(stat.start_ticks, 9000);
+        assert_eq!(stat.rss_pages, 256);
+        assert!(parse_proc_stat("garbage").is_none());
+    }
+
+    #[test]
+    fn walks_the_process_tree_depth_first_from_the_pane_pid() {
+        let proc_root = temp_root("tree");
+        fs::write(proc_root.join("stat"), "cpu  1 2 3\nbtime 1700000000\n").unwrap();
+        write_process(&proc_root, 100, "zsh", 1, (10, 5), 100, "-zsh");
+        write_process(&proc_root, 120, "cargo", 100, (200, 100), 1000, "cargo test");
+        write_process(&proc_root, 130, "rustc", 120, (500, 0), 5000, "rustc --crate-name herd");
+        write_process(&proc_root, 110, "sleep", 100, (0, 0), 50, "");
+        write_process(&proc_root, 200, "other", 1, (900, 0), 9000, "other");
+        fs::create_dir_all(proc_root.join("self")).unwrap();
+
+        let clock = ProcClock {
+            ticks_per_second: 100,
+            page_size: 4096,
+            boot_time_ms: ProcClock::detect(&proc_root).boot_time_ms,
+        };
+        let tree = process_tree_at(&proc_root, 100, &clock).unwrap();
+        assert_eq!(
+            tree.processes.iter().map(|process| (process.pid, process.depth)).collect::<Vec<_>>(),
+            vec![(100, 0), (110, 1), (120, 1), (130, 2)]
+        );
+        assert_eq!(tree.process_count, 4);
+        assert_eq!(tree.cpu_time_ms, 150 + 3000 + 5000);
+        assert_eq!(tree.rss_bytes, (100 + 1000 + 5000 + 50) * 4096);
+        assert_eq!(tree.processes[1].command, "[sleep]");
+        assert_eq!(tree.processes[3].command, "rustc --crate-name herd");
+        assert_eq!(tree.processes[3].started_at, Some(1_700_000_000_000 + 6300));
+        assert_eq!(tree.busiest().map(|process| process.pid), Some(130));
+
+        let first = ResourceSample::from_tree(&tree, None, 1_000);
+        assert_eq!(first.cpu_percent, None);
+        let mut busier = tree.clone();
+        busier.cpu_time_ms += 500;
+        let second = ResourceSample::from_tree(&busier, Some(&first), 2_000);
+        assert_eq!(second.cpu_percent, Some(50.0));
+        assert_eq!(second.busiest.as_ref().map(|process| process.pid), Some(130));
+        busier.root_pid += 1;
+        assert_eq!(ResourceSample::from_tree(&busier, Some(&second), 3_000).cpu_percent, None);
+
+        assert!(process_tree_at(&proc_root, 999, &clock).is_err());
+
+        let table = ProcessTable::scan_at(&proc_root, clock).unwrap();
+        assert_eq!(table.tree(100).unwrap(), tree);
+        assert_eq!(
+            table.tree(200).unwrap().processes.iter().map(|process| process.pid).collect::<Vec<_>>(),
+            vec![200]
+        );
+        let _ = fs::remove_dir_all(proc_root);
+    }
+
+    #[test]
+    fn evaluates_and_persists_resource_thresholds() {
+        assert_eq!(cpu_percent(1000, 1500, 1000), 50.0);
+        assert_eq!(cpu_percent(1000, 3000, 1000), 200.0);
+        assert_eq!(cpu_percent(1000, 900, 1000), 0.0);
+
+        let threshold = ResourceThreshold {
+            session_id: "$1".to_string(),
+            tile_id: "tile-a".to_string(),
+            cpu_percent: Some(80.0),
+            rss_bytes: Some(1024),
+            updated_at: 10,
+        };
+        assert!(threshold.exceeded(Some(50.0), 512).is_empty());
+        assert_eq!(threshold.exceeded(Some(95.0), 2048), vec!["cpu_percent", "rss_bytes"]);
+        assert_eq!(threshold.exceeded(None, 2048), vec!["rss_bytes"]);
+
+        let root = temp_root("thresholds");
+        let db_path = root.join("herd.sqlite");
+        upsert_threshold_at(&db_path, &threshold).unwrap();
+        upsert_threshold_at(
+            &db_path,
+            &ResourceThreshold {
+                tile_id: "tile-b".to_string(),
+                session_id: "$2".to_string(),
+                ..threshold.clone()
+            },
+        )
+        .unwrap();
+        upsert_threshold_at(
+            &db_path,
+            &ResourceThreshold {
+                cpu_percent: None,
+                updated_at: 20,
+                ..threshold.clone()
+            },
+        )
+        .unwrap();
+
+        let listed = list_thresholds_at(&db_path, Some("$1")).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].cpu_percent, None);
+        assert_eq!(listed[0].updated_at, 20);
+        assert_eq!(list_thresholds_at(&db_path, None).unwrap().len(), 2);
+
+        assert!(delete_threshold_at(&db_path, "tile-a").unwrap());
+        assert!(!delete_threshold_at(&db_path, "tile-a").unwrap());
+        assert!(list_thresholds_at(&db_path, Some("$1")).unwrap().is_empty());
+        delete_tile_thresholds_at(&db_path, &HashSet::from(["tile-b".to_string()])).unwrap();
+        assert!(list_thresholds_at(&db_path, None).unwrap().is_empty());
+        let _ = fs::remove_dir_all(root);
+    }
+}
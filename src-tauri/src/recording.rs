@@ -0,0 +1,549 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::now_ms;
+use crate::work::{self, WorkItem};
+use crate::{db, runtime, tmux};
+
+/// Metadata for an asciicast v2 recording of one terminal tile.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TileRecording {
+    pub recording_id: String,
+    pub session_id: String,
+    pub tile_id: String,
+    pub pane_id: String,
+    pub path: String,
+    pub width: u16,
+    pub height: u16,
+    pub started_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopped_at: Option<i64>,
+    #[serde(default)]
+    pub bytes: u64,
+}
+
+/// A recording in progress. Output arrives as decoded `%output` bytes, which can split
+/// UTF-8 sequences across chunks, so incomplete trailing bytes wait for the next chunk.
+pub struct ActiveRecording {
+    recording: TileRecording,
+    writer: BufWriter<File>,
+    started: Instant,
+    pending: Vec<u8>,
+}
+
+/// Recordings in progress keyed by pane id, plus the sessions recorded as a whole so terminal
+/// tiles created after the start are picked up. Each pane has its own lock, so the control
+/// reader writes a chunk without holding the shared map.
+#[derive(Default)]
+pub struct RecordingState {
+    panes: HashMap<String, Arc<Mutex<ActiveRecording>>>,
+    sessions: HashSet<String>,
+}
+
+impl RecordingState {
+    /// Moves every recording into `self`, used when the tmux control connection is replaced.
+    pub fn inherit(&mut self, old: &mut RecordingState) {
+        self.panes.extend(old.panes.drain());
+        self.sessions.extend(old.sessions.drain());
+    }
+}
+
+/// Recording state shared with the tmux control reader thread.
+pub type ActiveRecordings = Arc<Mutex<RecordingState>>;
+
+impl ActiveRecording {
+    fn create(recording: TileRecording, title: &str) -> Result<Self, String> {
+        let path = Path::new(&recording.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("failed to create recordings directory {}: {error}", parent.display()))?;
+        }
+        let file = File::create(path)
+            .map_err(|error| format!("failed to create recording {}: {error}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let header = cast_header(recording.width, recording.height, recording.started_at / 1000, title);
+        writeln!(writer, "{header}")
+            .and_then(|_| writer.flush())
+            .map_err(|error| format!("failed to write recording header: {error}"))?;
+        Ok(Self {
+            recording,
+            writer,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn recording(&self) -> &TileRecording {
+        &self.recording
+    }
+
+    pub fn write_output(&mut self, data: &[u8]) -> Result<(), String> {
+        self.pending.extend_from_slice(data);
+        let complete = complete_utf8_prefix_len(&self.pending);
+        if complete == 0 {
+            return Ok(());
+        }
+        let bytes = self.pending.drain(..complete).collect::<Vec<_>>();
+        let text = String::from_utf8_lossy(&bytes);
+        let line = cast_output_event(self.started.elapsed().as_secs_f64(), &text);
+        writeln!(self.writer, "{line}")
+            .and_then(|_| self.writer.flush())
+            .map_err(|error| format!("failed to append to recording {}: {error}", self.recording.path))?;
+        self.recording.bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> TileRecording {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            let text = String::from_utf8_lossy(&pending).to_string();
+            let line = cast_output_event(self.started.elapsed().as_secs_f64(), &text);
+            let _ = writeln!(self.writer, "{line}");
+            self.recording.bytes += pending.len() as u64;
+        }
+        if let Err(error) = self.writer.flush() {
+            log::warn!("failed to flush recording {}: {error}", self.recording.path);
+        }
+        self.recording.stopped_at = Some(now_ms());
+        self.recording.clone()
+    }
+}
+
+pub const CAST_MEDIA_TYPE: &str = "application/x-asciicast";
+
+pub fn recordings_dir() -> PathBuf {
+    runtime::project_tmp_dir().join("recordings")
+}
+
+pub fn new_active_recordings() -> ActiveRecordings {
+    Arc::new(Mutex::new(RecordingState::default()))
+}
+
+/// Starts recording a terminal pane. Fails if the pane is already being recorded.
+pub fn start_recording(
+    recordings: &ActiveRecordings,
+    db_path: &Path,
+    session_id: &str,
+    tile_id: &str,
+    pane_id: &str,
+    title: &str,
+) -> Result<TileRecording, String> {
+    if recordings
+        .lock()
+        .map_err(|error| error.to_string())?
+        .panes
+        .contains_key(pane_id)
+    {
+        return Err(format!("tile {tile_id} is already recording"));
+    }
+    let (width, height) = pane_size(pane_id)?;
+    let recording_id = uuid::Uuid::new_v4().simple().to_string();
+    let recording = TileRecording {
+        path: recordings_dir()
+            .join(format!("{recording_id}.cast"))
+            .to_string_lossy()
+            .to_string(),
+        recording_id,
+        session_id: session_id.to_string(),
+        tile_id: tile_id.to_string(),
+        pane_id: pane_id.to_string(),
+        width,
+        height,
+        started_at: now_ms(),
+        stopped_at: None,
+        bytes: 0,
+    };
+    let active = ActiveRecording::create(recording.clone(), title)?;
+    upsert_recording_at(db_path, &recording)?;
+    recordings
+        .lock()
+        .map_err(|error| error.to_string())?
+        .panes
+        .insert(pane_id.to_string(), Arc::new(Mutex::new(active)));
+    Ok(recording)
+}
+
+/// Stops the recording for a pane, returning its final metadata, or `None` when idle.
+pub fn stop_recording(
+    recordings: &ActiveRecordings,
+    db_path: &Path,
+    pane_id: &str,
+) -> Result<Option<TileRecording>, String> {
+    let active = recordings
+        .lock()
+        .map_err(|error| error.to_string())?
+        .panes
+        .remove(pane_id);
+    let Some(active) = active else {
+        return Ok(None);
+    };
+    let recording = finish_active(&active)?;
+    upsert_recording_at(db_path, &recording)?;
+    Ok(Some(recording))
+}
+
+/// Attaches a stopped recording's cast file to a work item's current stage.
+pub fn attach_recording_to_work_at(db_path: &Path, work_id: &str, recording: &TileRecording) -> Result<WorkItem, String> {
+    work::attach_work_item_file_at(
+        db_path,
+        work_id,
+        Path::new(&recording.path),
+        CAST_MEDIA_TYPE,
+        Some(&format!("Terminal recording of {}", recording.tile_id)),
+    )
+}
+
+/// Stops every recording of the given tiles, used when their panes are torn down.
+pub fn stop_tile_recordings(
+    recordings: &ActiveRecordings,
+    db_path: &Path,
+    tile_ids: &HashSet<String>,
+) -> Result<Vec<TileRecording>, String> {
+    let removed = {
+        let mut state = recordings.lock().map_err(|error| error.to_string())?;
+        let pane_ids = state
+            .panes
+            .iter()
+            .filter(|(_, active)| {
+                active
+                    .lock()
+                    .map(|active| tile_ids.contains(&active.recording.tile_id))
+                    .unwrap_or(false)
+            })
+            .map(|(pane_id, _)| pane_id.clone())
+            .collect::<Vec<_>>();
+        pane_ids
+            .into_iter()
+            .filter_map(|pane_id| state.panes.remove(&pane_id))
+            .collect::<Vec<_>>()
+    };
+    let mut stopped = Vec::new();
+    for active in removed {
+        let recording = finish_active(&active)?;
+        upsert_recording_at(db_path, &recording)?;
+        stopped.push(recording);
+    }
+    Ok(stopped)
+}
+
+/// Drops a recording that was started as part of a batch that failed, removing its file and row.
+pub fn discard_recording(recordings: &ActiveRecordings, db_path: &Path, pane_id: &str) -> Result<(), String> {
+    let active = recordings
+        .lock()
+        .map_err(|error| error.to_string())?
+        .panes
+        .remove(pane_id);
+    let Some(active) = active else {
+        return Ok(());
+    };
+    let recording = finish_active(&active)?;
+    let _ = fs::remove_file(&recording.path);
+    let conn = db::open_at(db_path)?;
+    conn.execute(
+        "DELETE FROM tile_recording WHERE recording_id = ?1",
+        params![recording.recording_id],
+    )
+    .map_err(|error| format!("failed to delete tile recording: {error}"))?;
+    Ok(())
+}
+
+fn finish_active(active: &Mutex<ActiveRecording>) -> Result<TileRecording, String> {
+    Ok(active.lock().map_err(|error| error.to_string())?.finish())
+}
+
+/// Appends decoded pane output to the pane's recording, if any. The shared map is only held
+/// long enough to look the recording up.
+pub fn write_output(recordings: &ActiveRecordings, pane_id: &str, data: &[u8]) -> Result<(), String> {
+    let active = recordings
+        .lock()
+        .map_err(|error| error.to_string())?
+        .panes
+        .get(pane_id)
+        .cloned();
+    let Some(active) = active else {
+        return Ok(());
+    };
+    let mut active = active.lock().map_err(|error| error.to_string())?;
+    active.write_output(data)
+}
+
+pub fn active_recording(recordings: &ActiveRecordings, pane_id: &str) -> Option<TileRecording> {
+    let active = recordings.lock().ok()?.panes.get(pane_id).cloned()?;
+    let recording = active.lock().ok()?.recording().clone();
+    Some(recording)
+}
+
+/// Marks or clears a session as recorded as a whole.
+pub fn set_session_recording(recordings: &ActiveRecordings, session_id: &str, enabled: bool) -> Result<(), String> {
+    let mut state = recordings.lock().map_err(|error| error.to_string())?;
+    if enabled {
+        state.sessions.insert(session_id.to_string());
+    } else {
+        state.sessions.remove(session_id);
+    }
+    Ok(())
+}
+
+pub fn is_session_recording(recordings: &ActiveRecordings, session_id: &str) -> bool {
+    recordings
+        .lock()
+        .map(|state| state.sessions.contains(session_id))
+        .unwrap_or(false)
+}
+
+fn pane_size(pane_id: &str) -> Result<(u16, u16), String> {
+    let output = tmux::output(&["display-message", "-p", "-t", pane_id, "#{pane_width} #{pane_height}"])?;
+    if !output.status.success() {
+        return Err(format!(
+            "failed to read pane size for {pane_id}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let size = String::from_utf8_lossy(&output.stdout).to_string();
+    let mut values = size.split_whitespace().map(|value| value.parse::<u16>().ok());
+    match (values.next().flatten(), values.next().flatten()) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(format!("failed to parse pane size for {pane_id}: {}", size.trim())),
+    }
+}
+
+fn cast_header(width: u16, height: u16, timestamp_secs: i64, title: &str) -> serde_json::Value {
+    serde_json::json!({
+        "version": 2,
+        "width": width,
+        "height": height,
+        "timestamp": timestamp_secs,
+        "title": title,
+        "env": { "TERM": "xterm-256color" },
+    })
+}
+
+fn cast_output_event(elapsed_secs: f64, text: &str) -> serde_json::Value {
+    let elapsed_secs = (elapsed_secs * 1_000_000.0).round() / 1_000_000.0;
+    serde_json::json!([elapsed_secs, "o", text])
+}
+
+fn complete_utf8_prefix_len(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(_) => bytes.len(),
+    }
+}
+
+pub fn upsert_recording_at(db_path: &Path, recording: &TileRecording) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    upsert_recording_with_conn(&conn, recording)
+}
+
+fn upsert_recording_with_conn(conn: &Connection, recording: &TileRecording) -> Result<(), String> {
+    let data_json = serde_json::to_string(recording)
+        .map_err(|error| format!("failed to encode tile recording: {error}"))?;
+    conn.execute(
+        "INSERT INTO tile_recording (recording_id, session_id, tile_id, data_json, started_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(recording_id) DO UPDATE SET data_json = excluded.data_json",
+        params![
+            recording.recording_id,
+            recording.session_id,
+            recording.tile_id,
+            data_json,
+            recording.started_at,
+        ],
+    )
+    .map_err(|error| format!("failed to store tile recording: {error}"))?;
+    Ok(())
+}
+
+pub fn list_recordings_at(
+    db_path: &Path,
+    session_id: &str,
+    tile_id: Option<&str>,
+) -> Result<Vec<TileRecording>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT recording_id, data_json
+             FROM tile_recording
+             WHERE session_id = ?1
+               AND (?2 IS NULL OR tile_id = ?2)
+             ORDER BY started_at ASC, recording_id ASC",
+        )
+        .map_err(|error| format!("failed to prepare tile recording query: {error}"))?;
+    let rows = stmt
+        .query_map(params![session_id, tile_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|error| format!("failed to query tile recordings: {error}"))?;
+    let mut recordings = Vec::new();
+    for row in rows {
+        let (recording_id, data_json) =
+            row.map_err(|error| format!("failed to decode tile recording row: {error}"))?;
+        let recording = serde_json::from_str(&data_json)
+            .map_err(|error| format!("failed to parse tile recording {recording_id}: {error}"))?;
+        recordings.push(recording);
+    }
+    Ok(recordings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        active_recording, attach_recording_to_work_at, cast_header, cast_output_event, complete_utf8_prefix_len,
+        discard_recording, list_recordings_at, new_active_recordings, stop_tile_recordings, upsert_recording_at,
+        write_output, ActiveRecording, TileRecording, CAST_MEDIA_TYPE,
+    };
+    use crate::work;
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("herd-recording-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn recording(root: &Path, recording_id: &str, tile_id: &str, started_at: i64) -> TileRecording {
+        TileRecording {
+            recording_id: recording_id.to_string(),
+            session_id: "$1".to_string(),
+            tile_id: tile_id.to_string(),
+            pane_id: "%3".to_string(),
+            path: root.join(format!("{recording_id}.cast")).to_string_lossy().to_string(),
+            width: 80,
+            height: 24,
+            started_at,
+            stopped_at: None,
+            bytes: 0,
+        }
+    }
+
+    #[test]
+    fn formats_asciicast_v2_header_and_output_events() {
+        assert_eq!(
+            cast_header(120, 40, 1_700_000_000, "shell"),
+            serde_json::json!({
+                "version": 2,
+                "width": 120,
+                "height": 40,
+                "timestamp": 1_700_000_000,
+                "title": "shell",
+                "env": { "TERM": "xterm-256color" },
+            })
+        );
+        assert_eq!(cast_output_event(1.23456789, "hi\r\n").to_string(), r#"[1.234568,"o","hi\r\n"]"#);
+        assert_eq!(complete_utf8_prefix_len("aé".as_bytes()), 3);
+        assert_eq!(complete_utf8_prefix_len(&"aé".as_bytes()[..2]), 1);
+    }
+
+    #[test]
+    fn writes_cast_files_without_splitting_utf8_across_chunks() {
+        let root = temp_root("cast");
+        let mut active = ActiveRecording::create(recording(&root, "rec1", "tile1", 1_700_000_000_000), "shell").unwrap();
+        let bytes = "é!".as_bytes();
+        active.write_output(&bytes[..1]).unwrap();
+        active.write_output(&bytes[1..]).unwrap();
+        let finished = active.finish();
+        assert_eq!(finished.bytes, 3);
+        assert!(finished.stopped_at.is_some());
+
+        let contents = fs::read_to_string(&finished.path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["timestamp"], 1_700_000_000);
+        let event: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(event[1], "o");
+        assert_eq!(event[2], "é!");
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn lists_recordings_by_session_and_tile() {
+        let root = temp_root("list");
+        let db_path = root.join("herd.sqlite");
+        let first = recording(&root, "rec1", "tile1", 10);
+        let mut second = recording(&root, "rec2", "tile2", 20);
+        upsert_recording_at(&db_path, &first).unwrap();
+        upsert_recording_at(&db_path, &second).unwrap();
+        second.stopped_at = Some(30);
+        second.bytes = 42;
+        upsert_recording_at(&db_path, &second).unwrap();
+
+        assert_eq!(list_recordings_at(&db_path, "$1", None).unwrap(), vec![first, second.clone()]);
+        assert_eq!(list_recordings_at(&db_path, "$1", Some("tile2")).unwrap(), vec![second]);
+        assert!(list_recordings_at(&db_path, "$2", None).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn stops_recordings_of_destroyed_tiles_and_discards_rolled_back_ones() {
+        let root = temp_root("registry");
+        let db_path = root.join("herd.sqlite");
+        let recordings = new_active_recordings();
+        for (recording_id, tile_id, pane_id) in [("rec1", "tile1", "%1"), ("rec2", "tile2", "%2")] {
+            let mut metadata = recording(&root, recording_id, tile_id, 10);
+            metadata.pane_id = pane_id.to_string();
+            upsert_recording_at(&db_path, &metadata).unwrap();
+            let active = ActiveRecording::create(metadata, "shell").unwrap();
+            recordings
+                .lock()
+                .unwrap()
+                .panes
+                .insert(pane_id.to_string(), Arc::new(Mutex::new(active)));
+        }
+
+        write_output(&recordings, "%1", b"hello").unwrap();
+        write_output(&recordings, "%9", b"ignored").unwrap();
+        let stopped = stop_tile_recordings(&recordings, &db_path, &HashSet::from(["tile1".to_string()])).unwrap();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0].bytes, 5);
+        assert!(stopped[0].stopped_at.is_some());
+        assert!(active_recording(&recordings, "%1").is_none());
+        assert_eq!(list_recordings_at(&db_path, "$1", Some("tile1")).unwrap(), stopped);
+
+        let discarded_path = active_recording(&recordings, "%2").unwrap().path;
+        discard_recording(&recordings, &db_path, "%2").unwrap();
+        assert!(active_recording(&recordings, "%2").is_none());
+        assert!(!Path::new(&discarded_path).exists());
+        assert!(list_recordings_at(&db_path, "$1", Some("tile2")).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn attaches_stopped_recordings_to_work_items() {
+        let root = temp_root("attach");
+        let db_path = root.join("herd.sqlite");
+        let item = work::create_work_item_at(&db_path, "$1", "Demo").unwrap();
+        let mut active = ActiveRecording::create(recording(&root, "rec1", "tile1", 10), "shell").unwrap();
+        active.write_output(b"hello").unwrap();
+        let finished = active.finish();
+
+        let attached = attach_recording_to_work_at(&db_path, &item.work_id, &finished).unwrap();
+        assert_eq!(attached.attachments.len(), 1);
+        assert_eq!(attached.attachments[0].path, finished.path);
+        assert_eq!(attached.attachments[0].media_type, CAST_MEDIA_TYPE);
+        assert_eq!(attached.attachments[0].label.as_deref(), Some("Terminal recording of tile1"));
+
+        let missing = recording(&root, "rec2", "tile2", 20);
+        assert!(attach_recording_to_work_at(&db_path, &item.work_id, &missing)
+            .unwrap_err()
+            .contains("is not a file"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
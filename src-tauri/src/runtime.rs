@@ -13,8 +13,13 @@ pub struct RuntimeConfig {
     dom_result_path: String,
     test_driver_enabled: bool,
     fixture_agents_enabled: bool,
+    output_buffer_bytes: usize,
 }
 
+pub const DEFAULT_OUTPUT_BUFFER_BYTES: usize = 65536;
+const MIN_OUTPUT_BUFFER_BYTES: usize = 4096;
+const MAX_OUTPUT_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+
 static CONFIG: OnceLock<RuntimeConfig> = OnceLock::new();
 
 fn sanitize_runtime_id(value: &str) -> Option<String> {
@@ -38,6 +43,13 @@ fn fixture_agents_enabled_from_env(test_driver_enabled: bool, value: Option<&str
         )
 }
 
+fn output_buffer_bytes_from_env(value: Option<&str>) -> usize {
+    value
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .map(|bytes| bytes.clamp(MIN_OUTPUT_BUFFER_BYTES, MAX_OUTPUT_BUFFER_BYTES))
+        .unwrap_or(DEFAULT_OUTPUT_BUFFER_BYTES)
+}
+
 fn build_runtime_config() -> RuntimeConfig {
     let test_driver_enabled = cfg!(debug_assertions)
         || matches!(std::env::var("HERD_ENABLE_TEST_DRIVER").ok().as_deref(), Some("1" | "true" | "yes"));
@@ -73,6 +85,7 @@ fn build_runtime_config() -> RuntimeConfig {
             test_driver_enabled,
             std::env::var("HERD_TEST_AGENT_MODE").ok().as_deref(),
         ),
+        output_buffer_bytes: output_buffer_bytes_from_env(std::env::var("HERD_OUTPUT_BUFFER_BYTES").ok().as_deref()),
     }
 }
 
@@ -166,6 +179,16 @@ pub fn tmux_socket_file_path() -> PathBuf {
     PathBuf::from(format!("/private/tmp/tmux-{uid}/{}", tmux_server_name()))
 }
 
+/// Directory holding this user's tmux server sockets, as tmux itself resolves it.
+pub fn tmux_socket_dir() -> PathBuf {
+    let uid = unsafe { libc::geteuid() };
+    let base = std::env::var("TMUX_TMPDIR")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "/tmp".to_string());
+    PathBuf::from(base).join(format!("tmux-{uid}"))
+}
+
 pub fn test_driver_enabled() -> bool {
     config().test_driver_enabled
 }
@@ -174,9 +197,16 @@ pub fn fixture_agents_enabled() -> bool {
     config().fixture_agents_enabled
 }
 
+pub fn output_buffer_bytes() -> usize {
+    config().output_buffer_bytes
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{database_file_name, detect_project_root_from, fixture_agents_enabled_from_env, looks_like_project_root};
+    use super::{
+        database_file_name, detect_project_root_from, fixture_agents_enabled_from_env, looks_like_project_root,
+        output_buffer_bytes_from_env, DEFAULT_OUTPUT_BUFFER_BYTES,
+    };
     use std::fs;
     use std::path::PathBuf;
 
@@ -227,4 +257,12 @@ mod tests {
         assert!(!fixture_agents_enabled_from_env(true, Some("claude")));
         assert!(!fixture_agents_enabled_from_env(true, None));
     }
+
+    #[test]
+    fn output_buffer_size_reads_env_and_clamps() {
+        assert_eq!(output_buffer_bytes_from_env(None), DEFAULT_OUTPUT_BUFFER_BYTES);
+        assert_eq!(output_buffer_bytes_from_env(Some("not-a-number")), DEFAULT_OUTPUT_BUFFER_BYTES);
+        assert_eq!(output_buffer_bytes_from_env(Some(" 1048576 ")), 1_048_576);
+        assert_eq!(output_buffer_bytes_from_env(Some("10")), 4096);
+    }
 }
@@ -8,9 +8,12 @@ use crate::{
     },
     browser::{self, BrowserBackend},
     commands,
+    launch_policy::{self, LaunchPolicy, SessionLaunchPolicies},
     network::{self, NetworkConnection, TilePort, TilePortSetting},
+    network_history::NetworkChangeActor,
     persist::TileState,
     runtime,
+    session_env::{self, SessionEnvEntry},
     state::AppState,
     tile_registry::TileRecordKind,
     tmux_state,
@@ -80,6 +83,8 @@ pub struct SavedSessionTile {
     pub browser: Option<SavedBrowserTile>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub work: Option<SavedWorkTile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_policy: Option<LaunchPolicy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -98,6 +103,13 @@ pub struct SavedSessionPortSetting {
     pub networking_mode: network::PortNetworkingMode,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SavedSessionPortSegment {
+    pub node_id: String,
+    pub port: TilePort,
+    pub segment: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SavedSessionSubscription {
     pub scope: TileSubscriptionScope,
@@ -105,6 +117,8 @@ pub struct SavedSessionSubscription {
     pub subject_node_id: String,
     pub direction: TileSubscriptionDirection,
     pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -115,9 +129,16 @@ pub struct SavedSessionConfiguration {
     pub root_cwd: Option<String>,
     #[serde(default = "default_saved_session_browser_backend")]
     pub browser_backend: BrowserBackend,
+    #[serde(default, skip_serializing_if = "SessionLaunchPolicies::is_unrestricted")]
+    pub launch_policies: SessionLaunchPolicies,
+    /// Session env entries; secret values are always saved masked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<SessionEnvEntry>,
     pub tiles: Vec<SavedSessionTile>,
     pub connections: Vec<SavedSessionConnection>,
     pub port_settings: Vec<SavedSessionPortSetting>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub port_segments: Vec<SavedSessionPortSegment>,
     #[serde(default)]
     pub subscriptions: Vec<SavedSessionSubscription>,
 }
@@ -156,6 +177,7 @@ struct SavedRuntimeTile {
     agent_type: Option<AgentType>,
     browser: Option<SavedBrowserTile>,
     work: Option<SavedWorkTile>,
+    launch_policy: Option<LaunchPolicy>,
 }
 
 #[derive(Debug, Clone)]
@@ -321,7 +343,7 @@ fn replace_session_connections(
             && left.to_tile_id == right.to_tile_id
             && left.to_port == right.to_port
     });
-    network::replace_connections_at(db_path, &all)
+    network::replace_connections_at(db_path, &all, &NetworkChangeActor::system())
 }
 
 fn replace_session_port_settings(
@@ -343,7 +365,7 @@ fn replace_session_port_settings(
             && left.tile_id == right.tile_id
             && left.port == right.port
     });
-    network::replace_port_settings_at(db_path, &all)
+    network::replace_port_settings_at(db_path, &all, &NetworkChangeActor::system())
 }
 
 fn session_layout_entry(
@@ -397,7 +419,12 @@ fn build_saved_configuration(
     let work_items = work::list_work_at(Path::new(runtime::database_path()), work::WorkListScope::CurrentSession(session_id.to_string()))?;
     let connections = network::list_connections_at(Path::new(runtime::database_path()), session_id)?;
     let port_settings = network::list_port_settings_at(Path::new(runtime::database_path()), session_id)?;
+    let port_segments = network::list_port_segments_at(Path::new(runtime::database_path()), session_id)?;
     let subscriptions = state.list_tile_subscriptions_in_session(session_id)?;
+    let env = session_env::list_env_at(Path::new(runtime::database_path()), session_id)?
+        .iter()
+        .map(SessionEnvEntry::masked)
+        .collect::<Vec<_>>();
     let session_agents = state.list_agents_in_session(session_id)?;
     let agent_tile_id_by_agent_id = session_agents
         .iter()
@@ -473,6 +500,7 @@ fn build_saved_configuration(
                 None
             },
             work,
+            launch_policy: launch_policy::tile_policy_at(Path::new(runtime::database_path()), &record.tile_id)?,
         });
     }
 
@@ -513,6 +541,7 @@ fn build_saved_configuration(
             agent_type: tile.agent_type,
             browser: tile.browser,
             work,
+            launch_policy: tile.launch_policy,
         });
     }
 
@@ -552,6 +581,23 @@ fn build_saved_configuration(
             .then_with(|| left.port.as_str().cmp(right.port.as_str()))
     });
 
+    let mut saved_port_segments = port_segments
+        .into_iter()
+        .filter_map(|labeled| {
+            Some(SavedSessionPortSegment {
+                node_id: node_id_by_runtime_tile_id.get(&labeled.tile_id)?.clone(),
+                port: labeled.port,
+                segment: labeled.segment,
+            })
+        })
+        .collect::<Vec<_>>();
+    saved_port_segments.sort_by(|left, right| {
+        left.node_id
+            .cmp(&right.node_id)
+            .then_with(|| left.port.as_str().cmp(right.port.as_str()))
+            .then_with(|| left.segment.cmp(&right.segment))
+    });
+
     let mut saved_subscriptions = subscriptions
         .into_iter()
         .filter_map(|subscription| {
@@ -565,6 +611,7 @@ fn build_saved_configuration(
                     .clone(),
                 direction: subscription.direction,
                 action: subscription.action,
+                segment: subscription.segment,
             })
         })
         .collect::<Vec<_>>();
@@ -582,9 +629,12 @@ fn build_saved_configuration(
         session_name: session.name.clone(),
         root_cwd: session.root_cwd.clone(),
         browser_backend: session.browser_backend,
+        launch_policies: session.launch_policies.clone(),
+        env,
         tiles: saved_tiles,
         connections: saved_connections,
         port_settings: saved_port_settings,
+        port_segments: saved_port_segments,
         subscriptions: saved_subscriptions,
     })
 }
@@ -609,6 +659,7 @@ fn clear_session_for_load(
 
     replace_session_connections(Path::new(runtime::database_path()), session_id, Vec::new())?;
     replace_session_port_settings(Path::new(runtime::database_path()), session_id, Vec::new())?;
+    network::replace_session_port_segments_at(Path::new(runtime::database_path()), session_id, &[])?;
     state.clear_tile_subscriptions_in_session(session_id)?;
 
     for item in existing_work_items {
@@ -741,6 +792,15 @@ pub fn load_session_configuration(
         }
     }
     tmux_state::set_session_browser_backend(&session_id, config.browser_backend)?;
+    config.launch_policies.validate()?;
+    launch_policy::set_session_policies_at(Path::new(runtime::database_path()), &session_id, &config.launch_policies)?;
+    let missing_secrets = session_env::restore_env_at(Path::new(runtime::database_path()), &session_id, &config.env)?;
+    if !missing_secrets.is_empty() {
+        log::warn!(
+            "saved session {config_name} expects secret env values that are not set: {}",
+            missing_secrets.join(", ")
+        );
+    }
 
     let root_tile = config
         .tiles
@@ -785,6 +845,9 @@ pub fn load_session_configuration(
                     app.clone(),
                     Some(session_id.clone()),
                     tile.agent_type.unwrap_or(AgentType::Claude),
+                    false,
+                    &[],
+                    tile.launch_policy.as_ref(),
                 )?)?;
                 if let Some(title) = tile.title.as_ref() {
                     commands::set_pane_title(app.clone(), spawned.pane_id.clone(), title.clone())?;
@@ -797,7 +860,12 @@ pub fn load_session_configuration(
                 });
             }
             SavedSessionTileKind::Shell => {
-                let spawned = commands::new_shell_window_detached(app.clone(), Some(session_id.clone()))?;
+                let spawned = commands::new_shell_window_detached(
+                    app.clone(),
+                    Some(session_id.clone()),
+                    &[],
+                    tile.launch_policy.as_ref(),
+                )?;
                 if let Some(title) = tile.title.as_ref() {
                     commands::set_pane_title(app.clone(), spawned.pane_id.clone(), title.clone())?;
                 }
@@ -902,6 +970,20 @@ pub fn load_session_configuration(
         .collect::<Vec<_>>();
     replace_session_port_settings(Path::new(runtime::database_path()), &session_id, restored_port_settings)?;
 
+    let restored_port_segments = config
+        .port_segments
+        .iter()
+        .filter_map(|labeled| {
+            Some(network::PortSegment {
+                session_id: session_id.clone(),
+                tile_id: tile_id_by_node.get(&labeled.node_id)?.clone(),
+                port: labeled.port,
+                segment: labeled.segment.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    network::replace_session_port_segments_at(Path::new(runtime::database_path()), &session_id, &restored_port_segments)?;
+
     for subscription in config.subscriptions.iter().filter_map(|subscription| {
         Some(TileSubscriptionRecord {
             session_id: session_id.clone(),
@@ -910,6 +992,7 @@ pub fn load_session_configuration(
             subject_tile_id: tile_id_by_node.get(&subscription.subject_node_id)?.clone(),
             direction: subscription.direction,
             action: subscription.action.clone(),
+            segment: subscription.segment.clone(),
         })
     }) {
         state.add_tile_subscription(subscription)?;
@@ -969,6 +1052,7 @@ mod tests {
         SAVED_SESSION_VERSION,
     };
     use crate::agent::{TileSubscriptionDirection, TileSubscriptionScope};
+    use crate::launch_policy::SessionLaunchPolicies;
     use std::fs;
     use std::path::PathBuf;
 
@@ -1008,9 +1092,12 @@ mod tests {
             session_name: "Alpha".to_string(),
             root_cwd: Some("/tmp/herd".to_string()),
             browser_backend: crate::browser::BrowserBackend::LiveWebview,
+            launch_policies: SessionLaunchPolicies::default(),
+            env: Vec::new(),
             tiles: Vec::new(),
             connections: Vec::new(),
             port_settings: Vec::new(),
+            port_segments: Vec::new(),
             subscriptions: Vec::new(),
         };
         fs::write(&config_path, serde_json::to_string_pretty(&config).unwrap()).unwrap();
@@ -1049,6 +1136,36 @@ mod tests {
             config.browser_backend,
             crate::browser::BrowserBackend::LiveWebview
         );
+        assert!(config.launch_policies.is_unrestricted());
+    }
+
+    #[test]
+    fn round_trips_saved_launch_policies() {
+        let config = serde_json::from_str::<SavedSessionConfiguration>(
+            r#"{
+              "version": 1,
+              "session_name": "Sandboxed",
+              "launch_policies": {
+                "agent": {
+                  "memory_limit_mb": 2048,
+                  "sandbox": "bubblewrap",
+                  "writable_paths": ["/tmp/cache"],
+                  "no_network": true
+                }
+              },
+              "tiles": [],
+              "connections": [],
+              "port_settings": []
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.launch_policies.shell.is_unrestricted());
+        assert_eq!(config.launch_policies.agent.memory_limit_mb, Some(2048));
+        assert_eq!(config.launch_policies.agent.sandbox, crate::launch_policy::SandboxMode::Bubblewrap);
+        let encoded = serde_json::to_value(&config).unwrap();
+        assert_eq!(encoded["launch_policies"]["agent"]["writable_paths"][0], "/tmp/cache");
+        assert_eq!(encoded["launch_policies"]["shell"], serde_json::json!({}));
     }
 
     #[test]
@@ -1086,15 +1203,19 @@ mod tests {
             session_name: "Subscriptions".to_string(),
             root_cwd: None,
             browser_backend: crate::browser::BrowserBackend::LiveWebview,
+            launch_policies: SessionLaunchPolicies::default(),
+            env: Vec::new(),
             tiles: Vec::new(),
             connections: Vec::new(),
             port_settings: Vec::new(),
+            port_segments: Vec::new(),
             subscriptions: vec![SavedSessionSubscription {
                 scope: TileSubscriptionScope::Network,
                 subscriber_node_id: "agent_1".to_string(),
                 subject_node_id: "shell_1".to_string(),
                 direction: TileSubscriptionDirection::In,
                 action: "exec".to_string(),
+                segment: None,
             }],
         };
 
@@ -0,0 +1,563 @@
+use std::collections::HashSet;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{agent, db, runtime};
+
+/// Placeholder written in place of secret values anywhere they leave the
+/// store: `env_list` responses, socket traffic logs, the tile message log and
+/// saved session configurations.
+pub const SECRET_MASK: &str = "********";
+
+/// A session-scoped environment variable that tiles can opt into at creation
+/// time. Entries are stored per tmux session and handed to the pane through a
+/// [`PaneEnvFile`], so values never appear in a tmux or shell argument list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionEnvEntry {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub secret: bool,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl SessionEnvEntry {
+    pub fn masked(&self) -> Self {
+        if !self.secret {
+            return self.clone();
+        }
+        Self {
+            value: SECRET_MASK.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+pub fn validate_env_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(first) if first == '_' || first.is_ascii_alphabetic() => {
+            chars.all(|ch| ch == '_' || ch.is_ascii_alphanumeric())
+        }
+        _ => false,
+    };
+    if !valid {
+        return Err(format!(
+            "invalid environment variable name {name:?}; use letters, digits and underscores"
+        ));
+    }
+    if name.starts_with("HERD_") {
+        return Err(format!("{name} is reserved for Herd's own context variables"));
+    }
+    Ok(())
+}
+
+fn validate_env_value(name: &str, value: &str) -> Result<(), String> {
+    if value.contains('\0') {
+        return Err(format!("value for {name} must not contain NUL bytes"));
+    }
+    Ok(())
+}
+
+/// Stores a session env value. Without `secret` an existing entry keeps its
+/// flag, so rotating a secret does not expose it; new entries are not secret.
+pub fn set_env_at(
+    db_path: &Path,
+    session_id: &str,
+    name: &str,
+    value: &str,
+    secret: Option<bool>,
+) -> Result<SessionEnvEntry, String> {
+    validate_env_name(name)?;
+    validate_env_value(name, value)?;
+    let conn = db::open_at(db_path)?;
+    let secret = match secret {
+        Some(secret) => secret,
+        None => get_env_with_conn(&conn, session_id, name)?.is_some_and(|entry| entry.secret),
+    };
+    let entry = SessionEnvEntry {
+        name: name.to_string(),
+        value: value.to_string(),
+        secret,
+        updated_at: agent::now_ms(),
+    };
+    upsert_env_with_conn(&conn, session_id, &entry)?;
+    Ok(entry)
+}
+
+fn upsert_env_with_conn(conn: &Connection, session_id: &str, entry: &SessionEnvEntry) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO session_env (session_id, name, value, secret, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(session_id, name) DO UPDATE SET
+           value = excluded.value,
+           secret = excluded.secret,
+           updated_at = excluded.updated_at",
+        params![session_id, entry.name, entry.value, entry.secret, entry.updated_at],
+    )
+    .map_err(|error| format!("failed to store session env {}: {error}", entry.name))?;
+    Ok(())
+}
+
+pub fn get_env_at(db_path: &Path, session_id: &str, name: &str) -> Result<Option<SessionEnvEntry>, String> {
+    let conn = db::open_at(db_path)?;
+    get_env_with_conn(&conn, session_id, name)
+}
+
+fn get_env_with_conn(conn: &Connection, session_id: &str, name: &str) -> Result<Option<SessionEnvEntry>, String> {
+    conn.query_row(
+        "SELECT name, value, secret, updated_at FROM session_env WHERE session_id = ?1 AND name = ?2",
+        params![session_id, name],
+        |row| {
+            Ok(SessionEnvEntry {
+                name: row.get(0)?,
+                value: row.get(1)?,
+                secret: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|error| format!("failed to query session env {name}: {error}"))
+}
+
+pub fn list_env_at(db_path: &Path, session_id: &str) -> Result<Vec<SessionEnvEntry>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, value, secret, updated_at
+             FROM session_env
+             WHERE session_id = ?1
+             ORDER BY name ASC",
+        )
+        .map_err(|error| format!("failed to prepare session env query: {error}"))?;
+    let rows = stmt
+        .query_map([session_id], |row| {
+            Ok(SessionEnvEntry {
+                name: row.get(0)?,
+                value: row.get(1)?,
+                secret: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|error| format!("failed to query session env: {error}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("failed to decode session env row: {error}"))
+}
+
+pub fn unset_env_at(db_path: &Path, session_id: &str, name: &str) -> Result<bool, String> {
+    let conn = db::open_at(db_path)?;
+    let removed = conn
+        .execute(
+            "DELETE FROM session_env WHERE session_id = ?1 AND name = ?2",
+            params![session_id, name],
+        )
+        .map_err(|error| format!("failed to delete session env {name}: {error}"))?;
+    Ok(removed > 0)
+}
+
+/// Drops every entry for a session, and the env names its tiles were created
+/// with. tmux reuses session ids across server restarts, so secrets must not
+/// outlive the session they were set for.
+pub fn clear_session_env_at(db_path: &Path, session_id: &str) -> Result<usize, String> {
+    let conn = db::open_at(db_path)?;
+    conn.execute("DELETE FROM tile_env WHERE session_id = ?1", [session_id])
+        .map_err(|error| format!("failed to clear tile env names for {session_id}: {error}"))?;
+    conn.execute("DELETE FROM session_env WHERE session_id = ?1", [session_id])
+        .map_err(|error| format!("failed to clear session env for {session_id}: {error}"))
+}
+
+/// Remembers the env names a tile was created with, so a respawn of its pane
+/// injects the same entries again.
+pub fn set_tile_env_names_at(db_path: &Path, session_id: &str, tile_id: &str, names: &[String]) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    if names.is_empty() {
+        conn.execute("DELETE FROM tile_env WHERE tile_id = ?1", [tile_id])
+            .map_err(|error| format!("failed to clear tile env names: {error}"))?;
+        return Ok(());
+    }
+    let names_json =
+        serde_json::to_string(names).map_err(|error| format!("failed to encode tile env names: {error}"))?;
+    conn.execute(
+        "INSERT INTO tile_env (tile_id, session_id, names_json, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(tile_id) DO UPDATE SET
+           session_id = excluded.session_id,
+           names_json = excluded.names_json,
+           updated_at = excluded.updated_at",
+        params![tile_id, session_id, names_json, agent::now_ms()],
+    )
+    .map_err(|error| format!("failed to store tile env names: {error}"))?;
+    Ok(())
+}
+
+/// Resolves the entries a tile was created with. Names unset since then are
+/// skipped: the tile already started with them once, and a respawn should not
+/// fail over a credential the user deliberately removed.
+pub fn tile_env_at(db_path: &Path, session_id: &str, tile_id: &str) -> Result<Vec<(String, String)>, String> {
+    let conn = db::open_at(db_path)?;
+    let names_json = conn
+        .query_row("SELECT names_json FROM tile_env WHERE tile_id = ?1", [tile_id], |row| {
+            row.get::<_, String>(0)
+        })
+        .optional()
+        .map_err(|error| format!("failed to query tile env names: {error}"))?;
+    let Some(names_json) = names_json else {
+        return Ok(Vec::new());
+    };
+    let names = serde_json::from_str::<Vec<String>>(&names_json)
+        .map_err(|error| format!("failed to parse env names for {tile_id}: {error}"))?;
+    let entries = list_env_at(db_path, session_id)?;
+    Ok(names
+        .iter()
+        .filter_map(|name| entries.iter().find(|entry| &entry.name == name))
+        .map(|entry| (entry.name.clone(), entry.value.clone()))
+        .collect())
+}
+
+pub fn delete_tile_env_at(db_path: &Path, tile_ids: &HashSet<String>) -> Result<(), String> {
+    if tile_ids.is_empty() {
+        return Ok(());
+    }
+    let conn = db::open_at(db_path)?;
+    for tile_id in tile_ids {
+        conn.execute("DELETE FROM tile_env WHERE tile_id = ?1", [tile_id])
+            .map_err(|error| format!("failed to delete tile env names: {error}"))?;
+    }
+    Ok(())
+}
+
+/// A 0600 file of `export NAME='value'` lines for one pane launch. The pane
+/// sources and deletes it before it execs its program, so values stay out of
+/// `ps`, tmux argument lists and the errors that quote them.
+#[derive(Debug)]
+pub struct PaneEnvFile {
+    path: PathBuf,
+}
+
+impl PaneEnvFile {
+    pub fn write(env: &[(String, String)]) -> Result<Option<Self>, String> {
+        Self::write_in(&runtime::project_tmp_dir().join("pane-env"), env)
+    }
+
+    fn write_in(dir: &Path, env: &[(String, String)]) -> Result<Option<Self>, String> {
+        if env.is_empty() {
+            return Ok(None);
+        }
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|error| format!("failed to create pane env dir {}: {error}", dir.display()))?;
+        let path = dir.join(format!("{}.env", uuid::Uuid::new_v4().simple()));
+        let mut contents = String::new();
+        for (name, value) in env {
+            contents.push_str(&format!("export {name}={}\n", shell_single_quote(value)));
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .map_err(|error| format!("failed to create pane env file: {error}"))?;
+        let env_file = Self { path };
+        if let Err(error) = file.write_all(contents.as_bytes()) {
+            env_file.discard();
+            return Err(format!("failed to write pane env file: {error}"));
+        }
+        Ok(Some(env_file))
+    }
+
+    /// Shell line that loads and removes the file, aborting the launch if it
+    /// cannot be read instead of starting the tile without its credentials.
+    pub fn source_line(&self) -> String {
+        let quoted = shell_single_quote(&self.path.to_string_lossy());
+        format!("{{ . {quoted} && rm -f {quoted}; }} || {{ rm -f {quoted}; exit 1; }}\n")
+    }
+
+    /// Removes the file when the launch failed before the pane could read it.
+    pub fn discard(self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
+/// Replaces every secret value of the session that occurs in `text` with
+/// [`SECRET_MASK`]. Used on error messages, which can quote command lines or
+/// tool output, before they reach the tile message log or the caller.
+pub fn mask_secrets_in_text_at(db_path: &Path, session_id: &str, text: &str) -> String {
+    let Ok(entries) = list_env_at(db_path, session_id) else {
+        return text.to_string();
+    };
+    entries
+        .iter()
+        .filter(|entry| entry.secret && !entry.value.is_empty())
+        .fold(text.to_string(), |masked, entry| masked.replace(&entry.value, SECRET_MASK))
+}
+
+/// Resolves the entries a tile asked for into `(name, value)` pairs, in the
+/// order requested. Unknown names are an error rather than silently skipped so
+/// a typo does not launch a tile without the credential it expects.
+pub fn resolve_env_at(db_path: &Path, session_id: &str, names: &[String]) -> Result<Vec<(String, String)>, String> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let entries = list_env_at(db_path, session_id)?;
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    for name in names {
+        let name = name.trim();
+        if !seen.insert(name.to_string()) {
+            continue;
+        }
+        let entry = entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| format!("session env {name} is not set"))?;
+        resolved.push((entry.name.clone(), entry.value.clone()));
+    }
+    Ok(resolved)
+}
+
+/// Restores entries from a saved session configuration. Secret entries are
+/// saved without their value, so they are only kept when the session already
+/// holds a value for them; the names of secrets that still need a value are
+/// returned so the caller can report them.
+pub fn restore_env_at(db_path: &Path, session_id: &str, entries: &[SessionEnvEntry]) -> Result<Vec<String>, String> {
+    let mut missing_secrets = Vec::new();
+    for entry in entries {
+        if entry.secret {
+            if get_env_at(db_path, session_id, &entry.name)?.is_none() {
+                missing_secrets.push(entry.name.clone());
+            }
+            continue;
+        }
+        set_env_at(db_path, session_id, &entry.name, &entry.value, Some(false))?;
+    }
+    Ok(missing_secrets)
+}
+
+/// Replaces the `value` of any JSON object flagged `"secret": true` with
+/// [`SECRET_MASK`]. Used on message args before they are written to the tile
+/// message log and on raw socket lines before they reach the traffic log. An
+/// `env_set` command without a flag may be rotating a secret, so it is masked too.
+pub fn mask_secret_values(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let secret = match map.get("secret").and_then(serde_json::Value::as_bool) {
+                Some(secret) => secret,
+                None => map.get("command").and_then(serde_json::Value::as_str) == Some("env_set"),
+            };
+            if secret {
+                if let Some(secret_value) = map.get_mut("value") {
+                    *secret_value = serde_json::Value::String(SECRET_MASK.to_string());
+                }
+            }
+            for nested in map.values_mut() {
+                mask_secret_values(nested);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                mask_secret_values(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn mask_socket_line(line: &str) -> String {
+    if !line.contains("\"secret\"") && !line.contains("env_set") {
+        return line.to_string();
+    }
+    match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(mut value) => {
+            mask_secret_values(&mut value);
+            serde_json::to_string(&value).unwrap_or_else(|_| line.to_string())
+        }
+        Err(_) => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clear_session_env_at, delete_tile_env_at, list_env_at, mask_secret_values, mask_secrets_in_text_at,
+        mask_socket_line, resolve_env_at, restore_env_at, set_env_at, set_tile_env_names_at, tile_env_at,
+        unset_env_at, validate_env_name, PaneEnvFile, SessionEnvEntry, SECRET_MASK,
+    };
+    use std::collections::HashSet;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    fn temp_db(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "herd-session-env-{name}-{}-{}.sqlite",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ))
+    }
+
+    #[test]
+    fn validates_env_names() {
+        assert!(validate_env_name("API_TOKEN").is_ok());
+        assert!(validate_env_name("_private1").is_ok());
+        assert!(validate_env_name("1BAD").is_err());
+        assert!(validate_env_name("WITH-DASH").is_err());
+        assert!(validate_env_name("").is_err());
+        assert!(validate_env_name("HERD_SOCK").is_err());
+    }
+
+    #[test]
+    fn stores_resolves_and_unsets_session_env_entries() {
+        let path = temp_db("crud");
+        set_env_at(&path, "$1", "API_TOKEN", "s3cret", Some(true)).unwrap();
+        set_env_at(&path, "$1", "RUST_LOG", "info", Some(false)).unwrap();
+        set_env_at(&path, "$2", "RUST_LOG", "debug", Some(false)).unwrap();
+        set_env_at(&path, "$1", "RUST_LOG", "trace", Some(false)).unwrap();
+
+        let rotated = set_env_at(&path, "$1", "API_TOKEN", "r0tated", None).unwrap();
+        assert!(rotated.secret);
+        assert_eq!(rotated.masked().value, SECRET_MASK);
+        assert!(!set_env_at(&path, "$1", "NEW_NAME", "plain", None).unwrap().secret);
+        assert!(unset_env_at(&path, "$1", "NEW_NAME").unwrap());
+
+        let entries = list_env_at(&path, "$1").unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>(),
+            vec!["API_TOKEN", "RUST_LOG"]
+        );
+        assert_eq!(entries[0].masked().value, SECRET_MASK);
+        assert_eq!(entries[1].masked().value, "trace");
+
+        let resolved = resolve_env_at(
+            &path,
+            "$1",
+            &["RUST_LOG".to_string(), "API_TOKEN".to_string(), "RUST_LOG".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            vec![
+                ("RUST_LOG".to_string(), "trace".to_string()),
+                ("API_TOKEN".to_string(), "r0tated".to_string()),
+            ]
+        );
+        assert!(resolve_env_at(&path, "$2", &["API_TOKEN".to_string()]).is_err());
+
+        assert!(unset_env_at(&path, "$1", "API_TOKEN").unwrap());
+        assert!(!unset_env_at(&path, "$1", "API_TOKEN").unwrap());
+        assert_eq!(list_env_at(&path, "$1").unwrap().len(), 1);
+
+        let missing = restore_env_at(
+            &path,
+            "$3",
+            &[
+                SessionEnvEntry {
+                    name: "API_TOKEN".to_string(),
+                    value: SECRET_MASK.to_string(),
+                    secret: true,
+                    updated_at: 0,
+                },
+                SessionEnvEntry {
+                    name: "EDITOR".to_string(),
+                    value: "vim".to_string(),
+                    secret: false,
+                    updated_at: 0,
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(missing, vec!["API_TOKEN".to_string()]);
+        assert_eq!(list_env_at(&path, "$3").unwrap()[0].value, "vim");
+        assert_eq!(clear_session_env_at(&path, "$3").unwrap(), 1);
+        assert!(list_env_at(&path, "$3").unwrap().is_empty());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn reinjects_tile_env_by_name_and_masks_secrets_in_text() {
+        let path = temp_db("tile-env");
+        set_env_at(&path, "$1", "API_TOKEN", "s3cret-value", Some(true)).unwrap();
+        set_env_at(&path, "$1", "RUST_LOG", "info", Some(false)).unwrap();
+        set_tile_env_names_at(&path, "$1", "tile-a", &["API_TOKEN".to_string(), "RUST_LOG".to_string()]).unwrap();
+        set_tile_env_names_at(&path, "$1", "tile-b", &["RUST_LOG".to_string()]).unwrap();
+
+        unset_env_at(&path, "$1", "RUST_LOG").unwrap();
+        assert_eq!(
+            tile_env_at(&path, "$1", "tile-a").unwrap(),
+            vec![("API_TOKEN".to_string(), "s3cret-value".to_string())]
+        );
+        assert!(tile_env_at(&path, "$1", "tile-c").unwrap().is_empty());
+        delete_tile_env_at(&path, &HashSet::from(["tile-a".to_string()])).unwrap();
+        assert!(tile_env_at(&path, "$1", "tile-a").unwrap().is_empty());
+
+        assert_eq!(
+            mask_secrets_in_text_at(&path, "$1", "tmux timed out: respawn-pane -e API_TOKEN=s3cret-value"),
+            format!("tmux timed out: respawn-pane -e API_TOKEN={SECRET_MASK}")
+        );
+        assert_eq!(mask_secrets_in_text_at(&path, "$2", "s3cret-value"), "s3cret-value");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn pane_env_file_is_private_and_removed_by_the_pane() {
+        let dir = temp_db("pane-env-dir");
+        assert!(PaneEnvFile::write_in(&dir, &[]).unwrap().is_none());
+        let env_file = PaneEnvFile::write_in(&dir, &[("API_TOKEN".to_string(), "it's secret".to_string())])
+            .unwrap()
+            .unwrap();
+        assert_eq!(fs::metadata(&env_file.path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        let line = env_file.source_line();
+        assert!(!line.contains("secret"));
+        let output = std::process::Command::new("/bin/bash")
+            .arg("-c")
+            .arg(format!("{line}printf '%s' \"$API_TOKEN\""))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's secret");
+        assert!(!env_file.path.exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn masks_secret_values_in_logged_payloads() {
+        let mut args = serde_json::json!({
+            "name": "API_TOKEN",
+            "value": "s3cret",
+            "secret": true,
+        });
+        mask_secret_values(&mut args);
+        assert_eq!(args["value"], SECRET_MASK);
+
+        let mut plain = serde_json::json!({ "name": "RUST_LOG", "value": "info" });
+        mask_secret_values(&mut plain);
+        assert_eq!(plain["value"], "info");
+
+        let line = r#"{"command":"message","args":{"name":"API_TOKEN","value":"s3cret","secret":true}}"#;
+        let masked = mask_socket_line(line);
+        assert!(!masked.contains("s3cret"));
+        assert!(masked.contains(SECRET_MASK));
+
+        let unflagged = r#"{"command":"env_set","name":"API_TOKEN","value":"r0tated"}"#;
+        assert!(!mask_socket_line(unflagged).contains("r0tated"));
+    }
+}
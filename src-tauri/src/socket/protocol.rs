@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::agent::{LedControlCommand, LedPatternArgs};
-use crate::network::TileTypeFilter;
+use crate::launch_policy::LaunchPolicy;
+use crate::network::{NamedPortSpec, TileTypeFilter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestDriverKey {
@@ -158,12 +159,71 @@ pub enum SocketCommand {
         tile_id: String,
         shell_command: String,
         #[serde(default)]
+        wait: bool,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
     },
     #[serde(rename = "shell_output_read")]
     ShellOutputRead {
+        tile_id: String,
+        #[serde(default)]
+        since_offset: Option<u64>,
+        #[serde(default)]
+        max_bytes: Option<usize>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "shell_output_capture")]
+    ShellOutputCapture {
+        tile_id: String,
+        #[serde(default)]
+        start_line: Option<i64>,
+        #[serde(default)]
+        end_line: Option<i64>,
+        #[serde(default)]
+        preserve_ansi: bool,
+        #[serde(default)]
+        join_wrapped: bool,
+        #[serde(default)]
+        search: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "shell_wait_for_output")]
+    ShellWaitForOutput {
+        tile_id: String,
+        pattern: String,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        #[serde(default)]
+        since_offset: Option<u64>,
+        #[serde(default)]
+        context_lines: Option<usize>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "shell_screenshot")]
+    ShellScreenshot {
+        tile_id: String,
+        #[serde(default)]
+        format: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "shell_process_info")]
+    ShellProcessInfo {
         tile_id: String,
         #[serde(default)]
         sender_agent_id: Option<String>,
@@ -197,6 +257,46 @@ pub enum SocketCommand {
         #[serde(default)]
         sender_tile_id: Option<String>,
     },
+    #[serde(rename = "browser_console_read")]
+    BrowserConsoleRead {
+        tile_id: String,
+        #[serde(default)]
+        since: Option<u64>,
+        #[serde(default)]
+        level: Option<String>,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "browser_record_start")]
+    BrowserRecordStart {
+        tile_id: String,
+        #[serde(default)]
+        format: Option<String>,
+        #[serde(default)]
+        interval_ms: Option<u64>,
+        #[serde(default)]
+        max_frames: Option<usize>,
+        #[serde(default)]
+        max_width: Option<u32>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "browser_record_stop")]
+    BrowserRecordStop {
+        tile_id: String,
+        #[serde(default)]
+        work_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
     #[serde(rename = "browser_drive")]
     BrowserDrive {
         tile_id: String,
@@ -280,6 +380,25 @@ pub enum SocketCommand {
         sender_tile_id: Option<String>,
         #[serde(default)]
         tile_type: Option<TileTypeFilter>,
+        #[serde(default)]
+        at_ms: Option<i64>,
+        #[serde(default)]
+        segment: Option<String>,
+    },
+    #[serde(rename = "network_history")]
+    NetworkHistory {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        since_ms: Option<i64>,
+        #[serde(default)]
+        until_ms: Option<i64>,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
     },
     #[serde(rename = "network_get")]
     NetworkGet {
@@ -305,6 +424,8 @@ pub enum SocketCommand {
         tile_id: String,
         event: String,
         #[serde(default)]
+        segment: Option<String>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -314,6 +435,8 @@ pub enum SocketCommand {
         tile_id: String,
         event: String,
         #[serde(default)]
+        segment: Option<String>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -327,6 +450,46 @@ pub enum SocketCommand {
         #[serde(default)]
         sender_tile_id: Option<String>,
     },
+    #[serde(rename = "network_path")]
+    NetworkPath {
+        #[serde(default)]
+        from_tile_id: Option<String>,
+        to_tile_id: String,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "network_port_declare")]
+    NetworkPortDeclare {
+        tile_id: String,
+        #[serde(default)]
+        ports: Vec<NamedPortSpec>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "network_segment_set")]
+    NetworkSegmentSet {
+        tile_id: String,
+        port: String,
+        #[serde(default)]
+        segments: Vec<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "network_stats")]
+    NetworkStats {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
     #[serde(rename = "tile_create")]
     TileCreate {
         tile_type: TileTypeFilter,
@@ -349,6 +512,12 @@ pub enum SocketCommand {
         #[serde(default)]
         browser_path: Option<String>,
         #[serde(default)]
+        worktree: Option<bool>,
+        #[serde(default)]
+        env: Option<Vec<String>>,
+        #[serde(default)]
+        launch_policy: Option<LaunchPolicy>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -387,6 +556,107 @@ pub enum SocketCommand {
         #[serde(default)]
         sender_tile_id: Option<String>,
     },
+    #[serde(rename = "tile_record_start")]
+    TileRecordStart {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "tile_record_stop")]
+    TileRecordStop {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "tile_record_list")]
+    TileRecordList {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "resource_summary")]
+    ResourceSummary {
+        #[serde(default)]
+        tile_id: Option<String>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "tile_resource_threshold_set")]
+    TileResourceThresholdSet {
+        tile_id: String,
+        #[serde(default)]
+        cpu_percent: Option<f64>,
+        #[serde(default)]
+        rss_bytes: Option<u64>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "worktree_list")]
+    WorktreeList {
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "worktree_status")]
+    WorktreeStatus {
+        agent_id: String,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "worktree_remove")]
+    WorktreeRemove {
+        agent_id: String,
+        #[serde(default)]
+        force: bool,
+        #[serde(default)]
+        delete_branch: bool,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "env_set")]
+    EnvSet {
+        name: String,
+        value: String,
+        #[serde(default)]
+        secret: Option<bool>,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "env_list")]
+    EnvList {
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
+    #[serde(rename = "env_unset")]
+    EnvUnset {
+        name: String,
+        #[serde(default)]
+        sender_agent_id: Option<String>,
+        #[serde(default)]
+        sender_tile_id: Option<String>,
+    },
     #[serde(rename = "tile_call")]
     TileCall {
         tile_id: String,
@@ -404,6 +674,8 @@ pub enum SocketCommand {
         event: String,
         agent_id: String,
         #[serde(default)]
+        segment: Option<String>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -414,6 +686,8 @@ pub enum SocketCommand {
         event: String,
         agent_id: String,
         #[serde(default)]
+        segment: Option<String>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -510,6 +784,8 @@ pub enum SocketCommand {
     MessageNetwork {
         message: String,
         #[serde(default)]
+        segment: Option<String>,
+        #[serde(default)]
         sender_agent_id: Option<String>,
         #[serde(default)]
         sender_tile_id: Option<String>,
@@ -2,7 +2,7 @@ use std::fs::OpenOptions;
 use std::io::Write as IoWrite;
 use std::os::unix::fs::MetadataExt;
 use std::path::Path;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
@@ -38,10 +38,15 @@ use crate::agent::{
     TileSubscriptionScope,
     TileSignalState,
 };
+use crate::launch_policy::{self, LaunchPolicy};
 use crate::persist::TileState;
 use crate::state::AppState;
+use crate::network_history::{self, NetworkChangeActor, NetworkHistoryQuery};
+use crate::network_traffic::{self, TrafficSample};
 use crate::tile_message::{TileMessageChannel, TileMessageLogEntry, TileMessageLogLayer, TileMessageOutcome};
-use crate::{network, runtime, tmux, work};
+use crate::{
+    network, process_info, recording, runtime, session_env, terminal_screen, tmux, tmux_control, work, worktree,
+};
 
 use super::protocol::{SocketCommand, SocketResponse, TestDriverRequest};
 
@@ -292,6 +297,8 @@ struct TileSubscriptionView {
     subject_tile_id: String,
     direction: TileSubscriptionDirection,
     action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    segment: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -404,6 +411,7 @@ fn tile_subscription_view(state: &AppState, record: &TileSubscriptionRecord) ->
         subject_tile_id: record.subject_tile_id.clone(),
         direction: record.direction,
         action: record.action.clone(),
+        segment: record.segment.clone(),
     }
 }
 
@@ -439,6 +447,48 @@ fn related_tile_ids_for_dispatch(
     related_tile_ids.into_iter().collect()
 }
 
+fn record_network_traffic(
+    state: &AppState,
+    session_id: &str,
+    target_tile_id: &str,
+    message_name: &str,
+    sender: Option<&SenderContext>,
+    args: &serde_json::Value,
+    result: &DispatchResult,
+) {
+    let conn = match crate::db::open_at(Path::new(runtime::database_path())) {
+        Ok(conn) => conn,
+        Err(error) => {
+            log::warn!("Failed to record network traffic for {target_tile_id}: {error}");
+            return;
+        }
+    };
+    let caller_tile_id = sender.and_then(|context| context.sender_tile_id.clone());
+    let connection = caller_tile_id.as_deref().and_then(|caller_tile_id| {
+        network::list_connections_with_conn(&conn, session_id)
+            .ok()
+            .and_then(|connections| {
+                network_traffic::direct_connection_between(&connections, caller_tile_id, target_tile_id).cloned()
+            })
+    });
+    let sample = TrafficSample {
+        session_id: session_id.to_string(),
+        caller_tile_id,
+        target_tile_id: target_tile_id.to_string(),
+        action: message_name.to_string(),
+        args_bytes: serde_json::to_vec(args).map(|bytes| bytes.len() as u64).unwrap_or(0),
+        result_bytes: match result {
+            Ok(Some(data)) => serde_json::to_vec(data).map(|bytes| bytes.len() as u64).unwrap_or(0),
+            _ => 0,
+        },
+        error: result.is_err(),
+        timestamp_ms: now_ms(),
+    };
+    if let Err(error) = state.record_network_traffic(&conn, &sample, connection.as_ref()) {
+        log::warn!("Failed to record network traffic for {target_tile_id}: {error}");
+    }
+}
+
 fn dispatch_result_with_log<F>(
     state: &AppState,
     app: &AppHandle,
@@ -459,6 +509,15 @@ where
     let started = Instant::now();
     let result = dispatch();
     let duration_ms = started.elapsed().as_millis() as i64;
+    // Errors can quote tmux command lines or tool output that carry env values.
+    let result = result.map_err(|mut dispatch_error| {
+        dispatch_error.message = session_env::mask_secrets_in_text_at(
+            Path::new(runtime::database_path()),
+            &session_id,
+            &dispatch_error.message,
+        );
+        dispatch_error
+    });
     let (outcome, error) = match &result {
         Ok(_) => (TileMessageOutcome::Ok, None),
         Err(dispatch_error) => (
@@ -470,6 +529,11 @@ where
         ),
     };
     let related_tile_ids = related_tile_ids_for_dispatch(&target_kind, &target_id, sender, &args, &result);
+    if layer == TileMessageLogLayer::Message && target_kind != "network" && is_tile_target_kind(&target_kind) {
+        record_network_traffic(state, &session_id, &target_id, message_name, sender, &args, &result);
+    }
+    let mut args = args;
+    session_env::mask_secret_values(&mut args);
 
     if let Err(log_error) = append_tile_message_log_entry(
         state,
@@ -1047,6 +1111,7 @@ fn process_dead_agent(state: &AppState, app: &AppHandle, info: crate::agent::Age
         Path::new(runtime::database_path()),
         &info.session_id,
         &info.tile_id,
+        &NetworkChangeActor::system(),
     ) {
         Ok(removed_connections) => {
             for connection in &removed_connections {
@@ -1166,6 +1231,18 @@ fn ensure_root_sender(context: &SenderContext, action: &str) -> Result<(), Strin
     Ok(())
 }
 
+/// `env` copies session values into the new pane and `launch_policy` overrides
+/// the session's limits, so only Root may pass either to `tile_create`.
+fn ensure_tile_create_sender(sender: &SenderContext, args: &SessionTileCreateMessageArgs) -> Result<(), String> {
+    if args.env.as_ref().is_some_and(|names| !names.is_empty()) {
+        ensure_root_sender(sender, "tile_create with env")?;
+    }
+    if args.launch_policy.is_some() {
+        ensure_root_sender(sender, "tile_create with launch_policy")?;
+    }
+    Ok(())
+}
+
 fn ensure_root_for_sender(
     state: &AppState,
     sender_agent_id: Option<String>,
@@ -1638,11 +1715,26 @@ fn session_tile_by_id(
         .ok_or_else(|| format!("tile {tile_id} is not available from session {session_id}"))
 }
 
+fn session_agent_worktree(session_id: &str, agent_id: &str) -> Result<worktree::AgentWorktree, DispatchError> {
+    worktree::get_worktree_at(Path::new(runtime::database_path()), agent_id)
+        .map_err(DispatchError::error)?
+        .filter(|agent_worktree| agent_worktree.session_id == session_id)
+        .ok_or_else(|| DispatchError::not_found(format!("no worktree recorded for agent {agent_id}")))
+}
+
+fn worktree_status_value(agent_worktree: &worktree::AgentWorktree) -> serde_json::Value {
+    match worktree::worktree_status(agent_worktree) {
+        Ok(status) => serde_json::json!(status),
+        Err(error) => serde_json::json!({ "error": error, "worktree": agent_worktree }),
+    }
+}
+
 fn pending_agent_tile_info(
     tile: &network::SessionTileInfo,
     agent_id: &str,
     agent_type: crate::agent::AgentType,
     title: Option<&str>,
+    worktree: Option<worktree::AgentWorktree>,
 ) -> network::SessionTileInfo {
     let mut next = tile.clone();
     next.kind = network::NetworkTileKind::Agent;
@@ -1660,6 +1752,7 @@ fn pending_agent_tile_info(
         chatter_subscribed: false,
         channels: Vec::new(),
         agent_pid: None,
+        worktree,
     });
     next
 }
@@ -1715,6 +1808,7 @@ fn destroy_session_tile(
             Path::new(runtime::database_path()),
             &item.session_id,
             &tile.tile_id,
+            &NetworkChangeActor::system(),
         )
         .unwrap_or_default();
         work::delete_work_item_at(
@@ -1779,6 +1873,34 @@ fn pane_tile_details(
     }
 }
 
+/// Terminal tiles targeted by a recording or resource command: one tile, or every shell and agent tile in the session.
+fn terminal_target_tiles(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    tile_id: Option<&str>,
+) -> Result<Vec<network::SessionTileInfo>, String> {
+    let is_terminal = |tile: &network::SessionTileInfo| {
+        matches!(
+            tile.kind,
+            network::NetworkTileKind::Shell | network::NetworkTileKind::Agent | network::NetworkTileKind::RootAgent
+        )
+    };
+    match tile_id {
+        Some(tile_id) => {
+            let tile = session_tile_by_id(app, state, session_id, tile_id)?;
+            if !is_terminal(&tile) {
+                return Err(format!("tile {tile_id} is not a shell or agent tile"));
+            }
+            Ok(vec![tile])
+        }
+        None => Ok(session_network_tiles(app, state, session_id)?
+            .into_iter()
+            .filter(is_terminal)
+            .collect()),
+    }
+}
+
 fn session_network_tiles(
     app: &AppHandle,
     state: &AppState,
@@ -1794,6 +1916,7 @@ fn session_network_tiles(
         .map(|item| (item.tile_id.clone(), item.clone()))
         .collect::<std::collections::HashMap<_, _>>();
     let layout_entries = session_layout_entries(state, &snapshot, session_id, &work_items);
+    let named_ports = crate::tile_registry::list_named_ports_at(Path::new(runtime::database_path()), None)?;
     let mut tiles = Vec::new();
     for record in state.list_tile_records_in_session(session_id)? {
         let Some(window) = snapshot
@@ -1863,6 +1986,7 @@ fn session_network_tiles(
                     chatter_subscribed: agent.map(|agent| agent.chatter_subscribed).unwrap_or(false),
                     channels: agent.map(|agent| agent.channels.clone()).unwrap_or_default(),
                     agent_pid: agent.and_then(|agent| agent.agent_pid),
+                    worktree: agent.and_then(|agent| agent.worktree.clone()),
                 })
             }
             network::NetworkTileKind::Browser => network::TileDetails::Browser(network::BrowserTileDetails {
@@ -1890,6 +2014,7 @@ fn session_network_tiles(
                     current_stage: item.current_stage,
                     stages: item.stages.clone(),
                     reviews: item.reviews.clone(),
+                    attachments: item.attachments.clone(),
                     created_at: item.created_at,
                     updated_at: item.updated_at,
                 })
@@ -1929,6 +2054,11 @@ fn session_network_tiles(
             command: Some(pane.command.clone()),
             responds_to: network::responds_to(kind),
             message_api: network::message_api(kind),
+            ports: named_ports
+                .iter()
+                .filter(|named_port| named_port.tile_id == record.tile_id)
+                .cloned()
+                .collect(),
             details,
         };
         if kind == network::NetworkTileKind::Browser {
@@ -1943,9 +2073,55 @@ fn session_network_tiles(
     }
 
     tiles.sort_by(|left, right| left.tile_id.cmp(&right.tile_id));
+    apply_browser_extension_ports(session_id, &mut tiles);
     Ok(tiles)
 }
 
+/// Declares the named ports a browser extension manifest lists, once per
+/// change, so pages can shape their tile's ports without an explicit
+/// `network_port_declare`.
+fn apply_browser_extension_ports(session_id: &str, tiles: &mut [network::SessionTileInfo]) {
+    let peer_kinds = tiles
+        .iter()
+        .map(|tile| (tile.tile_id.clone(), tile.kind))
+        .collect::<std::collections::HashMap<_, _>>();
+    for tile in tiles.iter_mut() {
+        let network::TileDetails::Browser(details) = &tile.details else {
+            continue;
+        };
+        let Some(specs) = details
+            .extension
+            .as_ref()
+            .map(|extension| extension.ports.clone())
+            .filter(|specs| !specs.is_empty())
+        else {
+            continue;
+        };
+        let Ok(mut planned) = network::plan_named_ports(&tile.tile_id, tile.kind, &specs, &tile.ports) else {
+            continue;
+        };
+        planned.sort_by(|left, right| left.name.cmp(&right.name));
+        if planned == tile.ports {
+            continue;
+        }
+        match network::declare_named_ports_at(
+            Path::new(runtime::database_path()),
+            session_id,
+            &tile.tile_id,
+            tile.kind,
+            &specs,
+            &peer_kinds,
+            &NetworkChangeActor::system(),
+        ) {
+            Ok(mut ports) => {
+                ports.sort_by(|left, right| left.name.cmp(&right.name));
+                tile.ports = ports;
+            }
+            Err(error) => log::debug!("browser extension ports for {} were not applied: {error}", tile.tile_id),
+        }
+    }
+}
+
 fn component_tile_by_id(
     component: &network::NetworkComponent,
     tile_id: &str,
@@ -1994,12 +2170,47 @@ fn create_session_tile(
         parent_window_id,
         browser_incognito,
         browser_path,
+        worktree: use_worktree,
+        env,
+        launch_policy,
     } = args;
+    let use_worktree = use_worktree.unwrap_or(false);
+    if use_worktree && tile_type != network::TileTypeFilter::Agent {
+        return Err(DispatchError::error("worktree is only supported for agent tiles".to_string()));
+    }
+    let env = env.unwrap_or_default();
+    if !env.is_empty() && !matches!(tile_type, network::TileTypeFilter::Shell | network::TileTypeFilter::Agent) {
+        return Err(DispatchError::error("env is only supported for shell and agent tiles".to_string()));
+    }
+    if let Some(policy) = launch_policy.as_ref() {
+        if !matches!(tile_type, network::TileTypeFilter::Shell | network::TileTypeFilter::Agent) {
+            return Err(DispatchError::error(
+                "launch_policy is only supported for shell and agent tiles".to_string(),
+            ));
+        }
+        policy.validate().map_err(DispatchError::error)?;
+        let session_policies = launch_policy::session_policies_at(Path::new(runtime::database_path()), session_id)
+            .map_err(DispatchError::error)?;
+        let (kind, session_policy) = match tile_type {
+            network::TileTypeFilter::Agent => ("agent", &session_policies.agent),
+            _ => ("shell", &session_policies.shell),
+        };
+        policy.ensure_within(session_policy).map_err(|error| {
+            DispatchError::error(format!(
+                "launch_policy may not be less restrictive than the session {kind} policy: {error}"
+            ))
+        })?;
+    }
 
     match tile_type {
         network::TileTypeFilter::Shell => {
-            let created = crate::commands::new_shell_window_detached(app.clone(), Some(session_id.to_string()))
-                .map_err(DispatchError::error)?;
+            let created = crate::commands::new_shell_window_detached(
+                app.clone(),
+                Some(session_id.to_string()),
+                &env,
+                launch_policy.as_ref(),
+            )
+            .map_err(DispatchError::error)?;
             if let Some(parent_window_id) = parent_window_id {
                 state.set_window_parent(&created.window_id, Some(parent_window_id));
                 let _ = crate::tmux_state::emit_snapshot(app);
@@ -2033,8 +2244,19 @@ fn create_session_tile(
             apply_create_layout(app, state, &tile, x, y, width, height)
         }
         network::TileTypeFilter::Agent => {
-            let created = crate::commands::spawn_agent_window(app.clone(), Some(session_id.to_string()))
-                .map_err(DispatchError::error)?;
+            let created = crate::commands::spawn_agent_window_with_type(
+                app.clone(),
+                Some(session_id.to_string()),
+                crate::commands::default_runtime_agent_type(),
+                use_worktree,
+                &env,
+                launch_policy.as_ref(),
+            )
+            .map_err(DispatchError::error)?;
+            let agent_worktree = created
+                .get("worktree")
+                .cloned()
+                .and_then(|value| serde_json::from_value::<worktree::AgentWorktree>(value).ok());
             let pane_id = created
                 .get("pane_id")
                 .and_then(serde_json::Value::as_str)
@@ -2089,7 +2311,7 @@ fn create_session_tile(
                     if agent_type == crate::agent::AgentType::Fixture {
                         tile
                     } else {
-                        pending_agent_tile_info(&tile, &agent_id, agent_type, title.as_deref())
+                        pending_agent_tile_info(&tile, &agent_id, agent_type, title.as_deref(), agent_worktree)
                     }
                 })
                 .map_err(DispatchError::from)?;
@@ -2144,6 +2366,94 @@ fn message_not_supported(target_kind: &str, target_id: &str, message_name: &str)
     ))
 }
 
+#[derive(Deserialize)]
+struct OutputReadMessageArgs {
+    #[serde(default)]
+    since_offset: Option<u64>,
+    #[serde(default)]
+    max_bytes: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct OutputCaptureMessageArgs {
+    #[serde(default)]
+    start_line: Option<i64>,
+    #[serde(default)]
+    end_line: Option<i64>,
+    #[serde(default)]
+    preserve_ansi: bool,
+    #[serde(default)]
+    join_wrapped: bool,
+    #[serde(default)]
+    search: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WaitForOutputMessageArgs {
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    since_offset: Option<u64>,
+    #[serde(default)]
+    context_lines: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ResourceSummaryMessageArgs {
+    #[serde(default)]
+    tile_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WorktreeStatusMessageArgs {
+    agent_id: String,
+}
+
+#[derive(Deserialize)]
+struct WorktreeRemoveMessageArgs {
+    agent_id: String,
+    #[serde(default)]
+    force: bool,
+    #[serde(default)]
+    delete_branch: bool,
+}
+
+#[derive(Deserialize)]
+struct EnvSetMessageArgs {
+    name: String,
+    value: String,
+    #[serde(default)]
+    secret: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct EnvUnsetMessageArgs {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ResourceThresholdSetMessageArgs {
+    tile_id: String,
+    #[serde(default)]
+    cpu_percent: Option<f64>,
+    #[serde(default)]
+    rss_bytes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ScreenshotMessageArgs {
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExecMessageArgs {
+    #[serde(default)]
+    wait: bool,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
 #[derive(Deserialize)]
 struct SessionTileCreateMessageArgs {
     tile_type: network::TileTypeFilter,
@@ -2163,6 +2473,12 @@ struct SessionTileCreateMessageArgs {
     browser_incognito: Option<bool>,
     #[serde(default)]
     browser_path: Option<String>,
+    #[serde(default)]
+    worktree: Option<bool>,
+    #[serde(default)]
+    env: Option<Vec<String>>,
+    #[serde(default)]
+    launch_policy: Option<LaunchPolicy>,
 }
 
 #[derive(Deserialize)]
@@ -2200,6 +2516,56 @@ struct TileIdentityMessageArgs {
     tile_id: String,
 }
 
+#[derive(Deserialize)]
+struct NetworkListMessageArgs {
+    #[serde(default)]
+    tile_type: Option<network::TileTypeFilter>,
+    #[serde(default)]
+    at_ms: Option<i64>,
+    #[serde(default)]
+    segment: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NetworkHistoryMessageArgs {
+    #[serde(default)]
+    tile_id: Option<String>,
+    #[serde(default)]
+    since_ms: Option<i64>,
+    #[serde(default)]
+    until_ms: Option<i64>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct NetworkPathMessageArgs {
+    #[serde(default)]
+    from_tile_id: Option<String>,
+    to_tile_id: String,
+}
+
+#[derive(Deserialize)]
+struct NetworkPortDeclareMessageArgs {
+    tile_id: String,
+    #[serde(default)]
+    ports: Vec<network::NamedPortSpec>,
+}
+
+#[derive(Deserialize)]
+struct NetworkSegmentSetMessageArgs {
+    tile_id: String,
+    port: String,
+    #[serde(default)]
+    segments: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct NetworkStatsMessageArgs {
+    #[serde(default)]
+    tile_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct TileMoveMessageArgs {
     tile_id: String,
@@ -2220,6 +2586,20 @@ struct TileRenameMessageArgs {
     title: String,
 }
 
+#[derive(Deserialize)]
+struct TileRecordMessageArgs {
+    #[serde(default)]
+    tile_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TileRecordStopMessageArgs {
+    #[serde(default)]
+    tile_id: Option<String>,
+    #[serde(default)]
+    work_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct NetworkConnectMessageArgs {
     from_tile_id: String,
@@ -2260,6 +2640,13 @@ struct MessageTextArgs {
     message: String,
 }
 
+#[derive(Deserialize)]
+struct MessageNetworkArgs {
+    message: String,
+    #[serde(default)]
+    segment: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct ChannelSubscriptionArgs {
     agent_id: String,
@@ -2281,11 +2668,41 @@ struct BrowserDriveMessageArgs {
     args: Option<serde_json::Value>,
 }
 
+#[derive(Deserialize)]
+struct BrowserConsoleReadMessageArgs {
+    #[serde(default)]
+    since: Option<u64>,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct BrowserRecordStartMessageArgs {
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    interval_ms: Option<u64>,
+    #[serde(default)]
+    max_frames: Option<usize>,
+    #[serde(default)]
+    max_width: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct BrowserRecordStopMessageArgs {
+    #[serde(default)]
+    work_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct BrowserExtensionCallMessageArgs {
     method: String,
     #[serde(default)]
     args: Option<serde_json::Value>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -2325,7 +2742,22 @@ fn ensure_browser_tile_receiver(receiver: &TileMessageReceiver, action: &str) ->
 }
 
 fn ensure_browser_drive_action_supported(action: &str) -> Result<(), DispatchError> {
-    if matches!(action, "click" | "select" | "type" | "dom_query" | "eval" | "screenshot") {
+    if matches!(
+        action,
+        "click"
+            | "select"
+            | "type"
+            | "dom_query"
+            | "eval"
+            | "screenshot"
+            | "screenshot_diff"
+            | "press"
+            | "hover"
+            | "scroll"
+            | "wait_for"
+            | "fill_form"
+            | "check"
+    ) {
         return Ok(());
     }
     Err(DispatchError::error(format!(
@@ -2425,6 +2857,20 @@ fn subscribable_actions_for_tile(
     {
         actions.push("extension_call".to_string());
     }
+    if matches!(
+        tile.kind,
+        network::NetworkTileKind::Shell | network::NetworkTileKind::Agent | network::NetworkTileKind::RootAgent
+    ) {
+        actions.push(RESOURCE_THRESHOLD_EVENT.to_string());
+    }
+    if tile.kind == network::NetworkTileKind::Browser {
+        actions.push(BROWSER_CONSOLE_EVENT.to_string());
+    }
+    if let network::TileDetails::Browser(details) = &tile.details {
+        if let Some(extension) = details.extension.as_ref() {
+            actions.extend(extension.events.iter().map(|event| event.name.clone()));
+        }
+    }
     actions.sort();
     actions.dedup();
     actions
@@ -2456,7 +2902,9 @@ fn resolve_root_tile_subscription(
     selector: &str,
     agent_id: &str,
     scope: TileSubscriptionScope,
+    segment: Option<String>,
 ) -> Result<TileSubscriptionRecord, String> {
+    validate_subscription_segment(segment.as_deref())?;
     let subject_tile = session_tile_by_id(app, state, &sender.session_id, tile_id)?;
     let subscriber = state
         .agent_info(agent_id)?
@@ -2479,6 +2927,7 @@ fn resolve_root_tile_subscription(
         subject_tile_id: subject_tile.tile_id,
         direction,
         action,
+        segment,
     })
 }
 
@@ -2488,7 +2937,9 @@ fn resolve_worker_network_subscription(
     sender: &SenderContext,
     tile_id: &str,
     selector: &str,
+    segment: Option<String>,
 ) -> Result<TileSubscriptionRecord, String> {
+    validate_subscription_segment(segment.as_deref())?;
     let component = component_for_sender(app, state, sender)?;
     let receiver = component_tile_receiver(&component, tile_id).map_err(|error| error.message)?;
     let access = network_access_for_tile(sender, &component, &receiver.tile);
@@ -2504,9 +2955,17 @@ fn resolve_worker_network_subscription(
         subject_tile_id: receiver.target_id().to_string(),
         direction,
         action,
+        segment,
     })
 }
 
+fn validate_subscription_segment(segment: Option<&str>) -> Result<(), String> {
+    match segment {
+        Some(segment) if !network::is_valid_segment_name(segment) => Err(format!("invalid segment name: {segment}")),
+        _ => Ok(()),
+    }
+}
+
 struct TileMessageReceiver {
     tile: network::SessionTileInfo,
 }
@@ -2559,18 +3018,78 @@ impl TileMessageReceiver {
         }
 
         match message_name {
-            "get" => Ok(Some(serde_json::json!(self.tile))),
+            "get" => {
+                let mut tile = serde_json::json!(self.tile);
+                let traffic = state
+                    .tile_traffic_stat(self.session_id(), self.target_id())
+                    .map_err(DispatchError::error)?
+                    .map(|stat| stat.counters)
+                    .unwrap_or_default();
+                tile["traffic"] = serde_json::json!(traffic);
+                Ok(Some(tile))
+            }
             "output_read" => {
                 let pane_id = self
                     .tile
                     .pane_id
                     .as_deref()
                     .ok_or_else(|| DispatchError::error(format!("shell tile {} is missing a pane id", self.tile.tile_id)))?;
-                let output = state
-                    .with_control(|ctrl| ctrl.read_output(pane_id))
+                let read_args: OutputReadMessageArgs = deserialize_message_args(args, message_name)?;
+                let chunk = state
+                    .with_control(|ctrl| ctrl.read_output(pane_id, read_args.since_offset, read_args.max_bytes))
+                    .map_err(DispatchError::from)?;
+                Ok(Some(serde_json::json!(chunk)))
+            }
+            "output_capture" => {
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!("shell tile {} is missing a pane id", self.tile.tile_id)))?;
+                let capture_args: OutputCaptureMessageArgs = deserialize_message_args(args, message_name)?;
+                let options = tmux_control::OutputCaptureOptions {
+                    start_line: capture_args.start_line,
+                    end_line: capture_args.end_line,
+                    preserve_ansi: capture_args.preserve_ansi,
+                    join_wrapped: capture_args.join_wrapped,
+                    search: capture_args.search.filter(|search| !search.is_empty()),
+                };
+                let capture = tmux_control::capture_pane_output(pane_id, &options).map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!(capture)))
+            }
+            "wait_for_output" => {
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!("shell tile {} is missing a pane id", self.tile.tile_id)))?;
+                let pattern = required_string_arg(args, "pattern", message_name)?;
+                let pattern = regex::Regex::new(&pattern)
+                    .map_err(|error| DispatchError::error(format!("invalid wait_for_output pattern: {error}")))?;
+                let wait_args: WaitForOutputMessageArgs = deserialize_message_args(args, message_name)?;
+                wait_for_output(state, pane_id, &pattern, wait_args)
+                    .map(|result| Some(serde_json::json!(result)))
+                    .map_err(DispatchError::error)
+            }
+            "screenshot" => {
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!("shell tile {} is missing a pane id", self.tile.tile_id)))?;
+                let screenshot_args: ScreenshotMessageArgs = deserialize_message_args(args, message_name)?;
+                let format = terminal_screen::parse_screenshot_format(screenshot_args.format.as_deref())
+                    .map_err(DispatchError::error)?;
+                let (screens, writer) = state
+                    .with_control(|ctrl| Ok((ctrl.screens.clone(), ctrl.writer.clone())))
                     .map_err(DispatchError::from)?;
-                Ok(Some(serde_json::json!({ "output": output })))
+                let snapshot =
+                    terminal_screen::snapshot_pane(&screens, &writer, pane_id).map_err(DispatchError::error)?;
+                terminal_screen::render_screenshot(&snapshot, format)
+                    .map(|screenshot| Some(serde_json::json!(screenshot)))
+                    .map_err(DispatchError::error)
             }
+            "process_info" => tile_process_info(&self.tile).map(Some).map_err(DispatchError::error),
             "input_send" => {
                 let pane_id = self
                     .tile
@@ -2590,6 +3109,12 @@ impl TileMessageReceiver {
                     .as_deref()
                     .ok_or_else(|| DispatchError::error(format!("shell tile {} is missing a pane id", self.tile.tile_id)))?;
                 let command = required_string_arg(args, "command", message_name)?;
+                let exec_args: ExecMessageArgs = deserialize_message_args(args, message_name)?;
+                if exec_args.wait {
+                    return exec_and_wait(app, state, pane_id, &command, exec_args.timeout_ms)
+                        .map(|result| Some(serde_json::json!(result)))
+                        .map_err(DispatchError::error);
+                }
                 let mut input = command;
                 if !input.ends_with('\n') {
                     input.push('\n');
@@ -2662,15 +3187,9 @@ impl TileMessageReceiver {
                 .map_err(DispatchError::from)?;
                 Ok(Some(result))
             }
-            "extension_call" => {
-                ensure_browser_tile_receiver(self, "extension_call")?;
-                let sender = sender.ok_or_else(|| {
-                    DispatchError::error("extension_call requires a sender context".to_string())
-                })?;
-                let sender_tile_id = sender.sender_tile_id.clone().ok_or_else(|| {
-                    DispatchError::error("extension_call requires a sender tile id".to_string())
-                })?;
-                let extension_call: BrowserExtensionCallMessageArgs = deserialize_message_args(args, message_name)?;
+            "console_read" => {
+                ensure_browser_tile_receiver(self, "console_read")?;
+                let console: BrowserConsoleReadMessageArgs = deserialize_message_args(args, message_name)?;
                 let pane_id = self
                     .tile
                     .pane_id
@@ -2679,10 +3198,107 @@ impl TileMessageReceiver {
                         "browser tile {} is missing a pane id",
                         self.target_id()
                     )))?;
-                let result = crate::browser::call_browser_extension(
+                let result = crate::browser::read_browser_console(
                     app,
                     pane_id,
-                    &extension_call.method,
+                    console.since,
+                    console.level.as_deref(),
+                    console.limit,
+                )
+                .map_err(DispatchError::from)?;
+                Ok(Some(serde_json::json!(result)))
+            }
+            "record_start" => {
+                ensure_browser_tile_receiver(self, "record_start")?;
+                let record: BrowserRecordStartMessageArgs = deserialize_message_args(args, message_name)?;
+                let options = crate::browser_recording::BrowserRecordingOptions::new(
+                    record.format.as_deref(),
+                    record.interval_ms,
+                    record.max_frames,
+                    record.max_width,
+                )
+                .map_err(DispatchError::from)?;
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!(
+                        "browser tile {} is missing a pane id",
+                        self.target_id()
+                    )))?;
+                let status = crate::browser::start_browser_recording(app, pane_id, options)
+                    .map_err(DispatchError::from)?;
+                serde_json::to_value(status)
+                    .map(Some)
+                    .map_err(|error| DispatchError::error(format!("failed to serialize browser recording: {error}")))
+            }
+            "record_stop" => {
+                ensure_browser_tile_receiver(self, "record_stop")?;
+                let record: BrowserRecordStopMessageArgs = deserialize_message_args(args, message_name)?;
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!(
+                        "browser tile {} is missing a pane id",
+                        self.target_id()
+                    )))?;
+                let work_item = record
+                    .work_id
+                    .as_deref()
+                    .map(|work_id| work::get_work_item_at(Path::new(runtime::database_path()), work_id))
+                    .transpose()
+                    .map_err(DispatchError::from)?;
+                if let Some(item) = work_item.as_ref().filter(|item| item.session_id != self.tile.session_id) {
+                    return Err(DispatchError::error(format!(
+                        "work item {} belongs to session {}, not {}",
+                        item.work_id, item.session_id, self.tile.session_id
+                    )));
+                }
+                let mut result = crate::browser::stop_browser_recording(app, pane_id).map_err(DispatchError::from)?;
+                // The recording is already stopped and encoded, so an attach
+                // failure is reported next to its path rather than losing it.
+                if let Some(item) = work_item {
+                    let label = format!("Browser recording of {}", self.tile.tile_id);
+                    match work::attach_work_item_file_at(
+                        Path::new(runtime::database_path()),
+                        &item.work_id,
+                        Path::new(&result.path),
+                        &result.media_type,
+                        Some(&label),
+                    ) {
+                        Ok(item) => {
+                            emit_work_updated(app, &item);
+                            result.work_id = Some(item.work_id);
+                        }
+                        Err(error) => result.attach_error = Some(error),
+                    }
+                }
+                serde_json::to_value(result)
+                    .map(Some)
+                    .map_err(|error| DispatchError::error(format!("failed to serialize browser recording: {error}")))
+            }
+            "extension_call" => {
+                ensure_browser_tile_receiver(self, "extension_call")?;
+                let sender = sender.ok_or_else(|| {
+                    DispatchError::error("extension_call requires a sender context".to_string())
+                })?;
+                let sender_tile_id = sender.sender_tile_id.clone().ok_or_else(|| {
+                    DispatchError::error("extension_call requires a sender tile id".to_string())
+                })?;
+                let extension_call: BrowserExtensionCallMessageArgs = deserialize_message_args(args, message_name)?;
+                let pane_id = self
+                    .tile
+                    .pane_id
+                    .as_deref()
+                    .ok_or_else(|| DispatchError::error(format!(
+                        "browser tile {} is missing a pane id",
+                        self.target_id()
+                    )))?;
+                let result = crate::browser::call_browser_extension(
+                    app,
+                    pane_id,
+                    &extension_call.method,
                     &extension_call.args.unwrap_or_else(|| serde_json::json!({})),
                     &crate::browser::BrowserExtensionCallerContext {
                         sender_tile_id,
@@ -2691,6 +3307,7 @@ impl TileMessageReceiver {
                         target_tile_id: self.tile.tile_id.clone(),
                         target_pane_id: pane_id.to_string(),
                     },
+                    extension_call.timeout_ms,
                 )
                 .map_err(DispatchError::from)?;
                 Ok(Some(result))
@@ -2770,6 +3387,17 @@ impl SessionMessageReceiver {
             "tile_destroy",
             "tile_list",
             "tile_rename",
+            "tile_record_start",
+            "tile_record_stop",
+            "tile_record_list",
+            "resource_summary",
+            "tile_resource_threshold_set",
+            "worktree_list",
+            "worktree_status",
+            "worktree_remove",
+            "env_set",
+            "env_list",
+            "env_unset",
             "agent_register",
             "agent_unregister",
             "agent_ping_ack",
@@ -2777,6 +3405,11 @@ impl SessionMessageReceiver {
             "network_list",
             "network_get",
             "network_call",
+            "network_history",
+            "network_path",
+            "network_port_declare",
+            "network_segment_set",
+            "network_stats",
             "tile_move",
             "tile_resize",
             "tile_arrange_elk",
@@ -2819,6 +3452,9 @@ impl SessionMessageReceiver {
         match message_name {
             "tile_create" => {
                 let args: SessionTileCreateMessageArgs = deserialize_message_args(args, message_name)?;
+                if let Some(sender) = self.sender.as_ref() {
+                    ensure_tile_create_sender(sender, &args).map_err(DispatchError::error)?;
+                }
                 let tile = create_session_tile(app, state, &self.session_id, args)?;
                 serde_json::to_value(tile)
                     .map(Some)
@@ -2828,8 +3464,18 @@ impl SessionMessageReceiver {
                 let args: TileDestroyMessageArgs = deserialize_message_args(args, message_name)?;
                 let tile = session_tile_by_id(app, state, &self.session_id, &args.tile_id)
                     .map_err(DispatchError::not_found)?;
+                let agent_worktree = worktree::worktree_for_tile_at(Path::new(runtime::database_path()), &tile.tile_id)
+                    .ok()
+                    .flatten();
                 destroy_session_tile(app, state, &tile).map_err(DispatchError::from)?;
-                Ok(None)
+                // The worktree is kept so its branch can be reviewed; report where it
+                // stands so the caller can follow up with worktree_remove.
+                Ok(agent_worktree.map(|agent_worktree| {
+                    serde_json::json!({
+                        "tile_id": tile.tile_id,
+                        "worktree": worktree_status_value(&agent_worktree),
+                    })
+                }))
             }
             "tile_rename" => {
                 let args: TileRenameMessageArgs = deserialize_message_args(args, message_name)?;
@@ -2838,6 +3484,253 @@ impl SessionMessageReceiver {
                 let renamed = rename_session_tile(app, state, &tile, &args.title).map_err(DispatchError::from)?;
                 Ok(Some(serde_json::json!(renamed)))
             }
+            "tile_record_start" => {
+                let args: TileRecordMessageArgs = deserialize_message_args(args, message_name)?;
+                let tiles = terminal_target_tiles(app, state, &self.session_id, args.tile_id.as_deref())
+                    .map_err(DispatchError::error)?;
+                let recordings = state
+                    .with_control(|ctrl| Ok(ctrl.recordings.clone()))
+                    .map_err(DispatchError::from)?;
+                let db_path = Path::new(runtime::database_path());
+                let mut started: Vec<recording::TileRecording> = Vec::new();
+                for tile in tiles {
+                    let Some(pane_id) = tile.pane_id.as_deref() else {
+                        continue;
+                    };
+                    if args.tile_id.is_none() && recording::active_recording(&recordings, pane_id).is_some() {
+                        continue;
+                    }
+                    match recording::start_recording(
+                        &recordings,
+                        db_path,
+                        &self.session_id,
+                        &tile.tile_id,
+                        pane_id,
+                        &tile.title,
+                    ) {
+                        Ok(recording) => started.push(recording),
+                        Err(error) => {
+                            for recording in &started {
+                                if let Err(discard_error) =
+                                    recording::discard_recording(&recordings, db_path, &recording.pane_id)
+                                {
+                                    log::warn!("{discard_error}");
+                                }
+                            }
+                            return Err(DispatchError::error(error));
+                        }
+                    }
+                }
+                if args.tile_id.is_none() {
+                    recording::set_session_recording(&recordings, &self.session_id, true)
+                        .map_err(DispatchError::error)?;
+                }
+                Ok(Some(serde_json::json!({
+                    "session_id": self.session_id,
+                    "recordings": started,
+                })))
+            }
+            "tile_record_stop" => {
+                let args: TileRecordStopMessageArgs = deserialize_message_args(args, message_name)?;
+                let tiles = terminal_target_tiles(app, state, &self.session_id, args.tile_id.as_deref())
+                    .map_err(DispatchError::error)?;
+                let db_path = Path::new(runtime::database_path());
+                let work_item = args
+                    .work_id
+                    .as_deref()
+                    .map(|work_id| work::get_work_item_at(db_path, work_id))
+                    .transpose()
+                    .map_err(DispatchError::from)?;
+                if let Some(item) = work_item.as_ref().filter(|item| item.session_id != self.session_id) {
+                    return Err(DispatchError::error(format!(
+                        "work item {} belongs to session {}, not {}",
+                        item.work_id, item.session_id, self.session_id
+                    )));
+                }
+                let recordings = state
+                    .with_control(|ctrl| Ok(ctrl.recordings.clone()))
+                    .map_err(DispatchError::from)?;
+                if args.tile_id.is_none() {
+                    recording::set_session_recording(&recordings, &self.session_id, false)
+                        .map_err(DispatchError::error)?;
+                }
+                let mut stopped = Vec::new();
+                for tile in tiles {
+                    let Some(pane_id) = tile.pane_id.as_deref() else {
+                        continue;
+                    };
+                    if let Some(recording) =
+                        recording::stop_recording(&recordings, db_path, pane_id).map_err(DispatchError::error)?
+                    {
+                        stopped.push(recording);
+                    }
+                }
+                if let Some(tile_id) = args.tile_id.as_deref().filter(|_| stopped.is_empty()) {
+                    return Err(DispatchError::error(format!("tile {tile_id} is not recording")));
+                }
+                // The recordings are already stopped, so an attach failure is
+                // reported next to the recording rather than failing the stop.
+                let mut attached_item = None;
+                let stopped = stopped
+                    .into_iter()
+                    .map(|stopped| {
+                        let mut entry = serde_json::json!(stopped);
+                        if let Some(item) = work_item.as_ref() {
+                            match recording::attach_recording_to_work_at(db_path, &item.work_id, &stopped) {
+                                Ok(item) => {
+                                    entry["work_id"] = serde_json::json!(item.work_id);
+                                    attached_item = Some(item);
+                                }
+                                Err(error) => entry["attach_error"] = serde_json::json!(error),
+                            }
+                        }
+                        entry
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(item) = attached_item {
+                    emit_work_updated(app, &item);
+                }
+                Ok(Some(serde_json::json!({
+                    "session_id": self.session_id,
+                    "recordings": stopped,
+                })))
+            }
+            "tile_record_list" => {
+                let args: TileRecordMessageArgs = deserialize_message_args(args, message_name)?;
+                let recordings = state
+                    .with_control(|ctrl| Ok(ctrl.recordings.clone()))
+                    .map_err(DispatchError::from)?;
+                let listed = recording::list_recordings_at(
+                    Path::new(runtime::database_path()),
+                    &self.session_id,
+                    args.tile_id.as_deref(),
+                )
+                .map_err(DispatchError::error)?
+                .into_iter()
+                .map(|stored| {
+                    let live = recording::active_recording(&recordings, &stored.pane_id)
+                        .filter(|live| live.recording_id == stored.recording_id);
+                    let active = live.is_some();
+                    let mut entry = serde_json::json!(live.unwrap_or(stored));
+                    entry["active"] = serde_json::json!(active);
+                    entry
+                })
+                .collect::<Vec<_>>();
+                Ok(Some(serde_json::json!({
+                    "session_id": self.session_id,
+                    "recordings": listed,
+                })))
+            }
+            "resource_summary" => {
+                let args: ResourceSummaryMessageArgs = deserialize_message_args(args, message_name)?;
+                let tiles = terminal_target_tiles(app, state, &self.session_id, args.tile_id.as_deref())
+                    .map_err(DispatchError::error)?;
+                resource_summary(state, &self.session_id, &tiles)
+                    .map(Some)
+                    .map_err(DispatchError::error)
+            }
+            "tile_resource_threshold_set" => {
+                let args: ResourceThresholdSetMessageArgs = deserialize_message_args(args, message_name)?;
+                let tile = session_tile_by_id(app, state, &self.session_id, &args.tile_id)
+                    .map_err(DispatchError::not_found)?;
+                if !matches!(
+                    tile.kind,
+                    network::NetworkTileKind::Shell | network::NetworkTileKind::Agent | network::NetworkTileKind::RootAgent
+                ) {
+                    return Err(DispatchError::error(format!("tile {} is not a shell or agent tile", tile.tile_id)));
+                }
+                if args.cpu_percent.is_some_and(|cpu_percent| cpu_percent <= 0.0) {
+                    return Err(DispatchError::error("cpu_percent must be greater than 0"));
+                }
+                if args.rss_bytes == Some(0) {
+                    return Err(DispatchError::error("rss_bytes must be greater than 0"));
+                }
+                let db_path = Path::new(runtime::database_path());
+                if args.cpu_percent.is_none() && args.rss_bytes.is_none() {
+                    process_info::delete_threshold_at(db_path, &tile.tile_id).map_err(DispatchError::error)?;
+                    return Ok(Some(serde_json::json!({
+                        "tile_id": tile.tile_id,
+                        "threshold": null,
+                    })));
+                }
+                let threshold = process_info::ResourceThreshold {
+                    session_id: self.session_id.clone(),
+                    tile_id: tile.tile_id.clone(),
+                    cpu_percent: args.cpu_percent,
+                    rss_bytes: args.rss_bytes,
+                    updated_at: now_ms(),
+                };
+                process_info::upsert_threshold_at(db_path, &threshold).map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!({
+                    "tile_id": tile.tile_id,
+                    "threshold": threshold,
+                })))
+            }
+            "worktree_list" => {
+                let worktrees =
+                    worktree::list_worktrees_at(Path::new(runtime::database_path()), Some(&self.session_id))
+                        .map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!({
+                    "worktrees": worktrees.iter().map(worktree_status_value).collect::<Vec<_>>(),
+                })))
+            }
+            "worktree_status" => {
+                let args: WorktreeStatusMessageArgs = deserialize_message_args(args, message_name)?;
+                let agent_worktree = session_agent_worktree(&self.session_id, &args.agent_id)?;
+                worktree::worktree_status(&agent_worktree)
+                    .map(|status| Some(serde_json::json!(status)))
+                    .map_err(DispatchError::error)
+            }
+            "worktree_remove" => {
+                let args: WorktreeRemoveMessageArgs = deserialize_message_args(args, message_name)?;
+                let agent_worktree = session_agent_worktree(&self.session_id, &args.agent_id)?;
+                if session_tile_by_id(app, state, &self.session_id, &agent_worktree.tile_id).is_ok() {
+                    return Err(DispatchError::error(format!(
+                        "agent tile {} is still open; destroy it before removing its worktree",
+                        agent_worktree.tile_id
+                    )));
+                }
+                worktree::remove_agent_worktree(&agent_worktree, args.force, args.delete_branch)
+                    .map_err(DispatchError::error)?;
+                worktree::delete_worktree_at(Path::new(runtime::database_path()), &agent_worktree.agent_id)
+                    .map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!({
+                    "agent_id": agent_worktree.agent_id,
+                    "path": agent_worktree.path,
+                    "branch": agent_worktree.branch,
+                    "branch_deleted": args.delete_branch,
+                })))
+            }
+            "env_set" => {
+                let args: EnvSetMessageArgs = deserialize_message_args(args, message_name)?;
+                let entry = session_env::set_env_at(
+                    Path::new(runtime::database_path()),
+                    &self.session_id,
+                    args.name.trim(),
+                    &args.value,
+                    args.secret,
+                )
+                .map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!(entry.masked())))
+            }
+            "env_list" => {
+                let entries = session_env::list_env_at(Path::new(runtime::database_path()), &self.session_id)
+                    .map_err(DispatchError::error)?
+                    .iter()
+                    .map(session_env::SessionEnvEntry::masked)
+                    .collect::<Vec<_>>();
+                Ok(Some(serde_json::json!({ "entries": entries })))
+            }
+            "env_unset" => {
+                let args: EnvUnsetMessageArgs = deserialize_message_args(args, message_name)?;
+                let name = args.name.trim();
+                if !session_env::unset_env_at(Path::new(runtime::database_path()), &self.session_id, name)
+                    .map_err(DispatchError::error)?
+                {
+                    return Err(DispatchError::not_found(format!("session env {name} is not set")));
+                }
+                Ok(Some(serde_json::json!({ "name": name, "removed": true })))
+            }
             "agent_register" => {
                 let args: AgentRegisterMessageArgs = deserialize_message_args(args, message_name)?;
                 let agent_type = parse_agent_type(args.agent_type.as_deref()).map_err(DispatchError::error)?;
@@ -2901,10 +3794,11 @@ impl SessionMessageReceiver {
                 .map(|channels| Some(serde_json::json!(channels)))
                 .map_err(DispatchError::error),
             "network_list" => {
-                let args: TileListMessageArgs = deserialize_message_args(args, message_name)?;
+                let args: NetworkListMessageArgs = deserialize_message_args(args, message_name)?;
                 let sender = self.sender(message_name)?;
-                component_for_sender(app, state, sender)
+                component_for_sender_as_of(app, state, sender, args.at_ms)
                     .map(|component| network_visible_component_for_sender(sender, &component))
+                    .map(|component| network::filter_component_by_segment(component, args.segment.as_deref()))
                     .map(|component| Some(serde_json::json!(network::filter_component(component, args.tile_type))))
                     .map_err(DispatchError::error)
             }
@@ -2972,6 +3866,155 @@ impl SessionMessageReceiver {
                     "result": result,
                 })))
             }
+            "network_history" => {
+                let sender = self.sender(message_name)?;
+                let args: NetworkHistoryMessageArgs = deserialize_message_args(args, message_name)?;
+                let visible_tile_ids = if sender.sender_agent_role == Some(AgentRole::Worker) {
+                    let component = component_for_sender(app, state, sender).map_err(DispatchError::error)?;
+                    Some(component.tiles.into_iter().map(|tile| tile.tile_id).collect::<HashSet<_>>())
+                } else {
+                    None
+                };
+                let events = network_history::list_history_at(
+                    Path::new(runtime::database_path()),
+                    &self.session_id,
+                    &NetworkHistoryQuery {
+                        tile_id: args.tile_id,
+                        visible_tile_ids,
+                        since_ms: args.since_ms,
+                        until_ms: args.until_ms,
+                        limit: args.limit,
+                    },
+                )
+                .map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!({
+                    "session_id": self.session_id,
+                    "events": events,
+                })))
+            }
+            "network_path" => {
+                let sender = self.sender(message_name)?;
+                let args: NetworkPathMessageArgs = deserialize_message_args(args, message_name)?;
+                let from_tile_id = match args.from_tile_id {
+                    Some(from_tile_id) => from_tile_id,
+                    None => sender
+                        .sender_tile_id
+                        .clone()
+                        .ok_or_else(|| DispatchError::error("network_path requires from_tile_id without a sender tile"))?,
+                };
+                if sender.sender_agent_role == Some(AgentRole::Worker)
+                    && sender.sender_tile_id.as_deref() != Some(from_tile_id.as_str())
+                {
+                    return Err(DispatchError::error("workers can only query network paths from their own tile"));
+                }
+                // Workers get the same not-found error for tiles outside their
+                // visible component as for tiles that do not exist, so the
+                // query cannot probe the rest of the session.
+                let session_tiles = if sender.sender_agent_role == Some(AgentRole::Worker) {
+                    component_for_sender(app, state, sender).map_err(DispatchError::error)?.tiles
+                } else {
+                    session_network_tiles(app, state, &self.session_id).map_err(DispatchError::error)?
+                };
+                for tile_id in [&from_tile_id, &args.to_tile_id] {
+                    if !session_tiles.iter().any(|tile| &tile.tile_id == tile_id) {
+                        return Err(DispatchError::not_found(format!("tile {tile_id} not found")));
+                    }
+                }
+                let connections = network::list_connections_at(Path::new(runtime::database_path()), &self.session_id)
+                    .map_err(DispatchError::error)?;
+                let port_settings = network::list_port_settings_at(Path::new(runtime::database_path()), &self.session_id)
+                    .map_err(DispatchError::error)?;
+                Ok(Some(serde_json::json!(network::network_path_between(
+                    &self.session_id,
+                    &from_tile_id,
+                    &args.to_tile_id,
+                    &session_tiles,
+                    &connections,
+                    &port_settings,
+                ))))
+            }
+            "network_port_declare" => {
+                let sender = self.sender(message_name)?;
+                let args: NetworkPortDeclareMessageArgs = deserialize_message_args(args, message_name)?;
+                if sender.sender_agent_role == Some(AgentRole::Worker)
+                    && sender.sender_tile_id.as_deref() != Some(args.tile_id.as_str())
+                {
+                    return Err(DispatchError::error(
+                        "workers can only declare named ports on their own tile",
+                    ));
+                }
+                let descriptor = resolve_network_tile_descriptor(state, &self.session_id, &args.tile_id)
+                    .map_err(DispatchError::not_found)?;
+                let peer_kinds = session_network_tiles(app, state, &self.session_id)
+                    .map_err(DispatchError::error)?
+                    .into_iter()
+                    .map(|tile| (tile.tile_id, tile.kind))
+                    .collect();
+                let ports = network::declare_named_ports_at(
+                    Path::new(runtime::database_path()),
+                    &descriptor.session_id,
+                    &descriptor.tile_id,
+                    descriptor.kind,
+                    &args.ports,
+                    &peer_kinds,
+                    &network_change_actor(Some(sender)),
+                )
+                .map_err(DispatchError::error)?;
+                emit_agent_state(app, state);
+                Ok(Some(serde_json::json!({
+                    "tile_id": descriptor.tile_id,
+                    "ports": ports,
+                })))
+            }
+            "network_segment_set" => {
+                let sender = self.sender(message_name)?;
+                let args: NetworkSegmentSetMessageArgs = deserialize_message_args(args, message_name)?;
+                if sender.sender_agent_role == Some(AgentRole::Worker)
+                    && sender.sender_tile_id.as_deref() != Some(args.tile_id.as_str())
+                {
+                    return Err(DispatchError::error(
+                        "workers can only label ports on their own tile",
+                    ));
+                }
+                let descriptor = resolve_network_tile_descriptor(state, &self.session_id, &args.tile_id)
+                    .map_err(DispatchError::not_found)?;
+                let port = network::resolve_port_at(Path::new(runtime::database_path()), &descriptor.tile_id, &args.port)
+                    .map_err(DispatchError::error)?;
+                let segments = network::set_port_segments_at(
+                    Path::new(runtime::database_path()),
+                    &descriptor.session_id,
+                    &descriptor.tile_id,
+                    port,
+                    &args.segments,
+                )
+                .map_err(DispatchError::error)?;
+                emit_agent_state(app, state);
+                Ok(Some(serde_json::json!({
+                    "tile_id": descriptor.tile_id,
+                    "port": port,
+                    "segments": segments,
+                })))
+            }
+            "network_stats" => {
+                let sender = self.sender(message_name)?;
+                let args: NetworkStatsMessageArgs = deserialize_message_args(args, message_name)?;
+                let mut stats = state
+                    .network_traffic_stats_in_session(&self.session_id)
+                    .map_err(DispatchError::error)?;
+                if sender.sender_agent_role == Some(AgentRole::Worker) {
+                    let component = component_for_sender(app, state, sender).map_err(DispatchError::error)?;
+                    let visible_tile_ids = component
+                        .tiles
+                        .iter()
+                        .map(|tile| tile.tile_id.clone())
+                        .collect::<HashSet<_>>();
+                    stats = network_traffic::retain_visible_tiles(stats, &visible_tile_ids);
+                }
+                if let Some(tile_id) = args.tile_id.as_deref() {
+                    stats = network_traffic::retain_tile(stats, tile_id);
+                }
+                Ok(Some(serde_json::json!(stats)))
+            }
             "tile_list" => {
                 let args: TileListMessageArgs = deserialize_message_args(args, message_name)?;
                 session_component(app, state, &self.session_id)
@@ -3039,16 +4082,21 @@ impl SessionMessageReceiver {
                     .map_err(DispatchError::error)?;
                 let to_descriptor = resolve_network_tile_descriptor(state, &self.session_id, &args.to_tile_id)
                     .map_err(DispatchError::error)?;
-                let from_port = network::parse_port(&args.from_port)
-                    .map_err(|_| DispatchError::error("invalid from_port".to_string()))?;
-                let to_port = network::parse_port(&args.to_port)
-                    .map_err(|_| DispatchError::error("invalid to_port".to_string()))?;
+                let from_port = network::resolve_port_at(
+                    Path::new(runtime::database_path()),
+                    &from_descriptor.tile_id,
+                    &args.from_port,
+                )
+                .map_err(|error| DispatchError::error(format!("invalid from_port: {error}")))?;
+                let to_port = network::resolve_port_at(Path::new(runtime::database_path()), &to_descriptor.tile_id, &args.to_port)
+                    .map_err(|error| DispatchError::error(format!("invalid to_port: {error}")))?;
                 match network::connect_at(
                     Path::new(runtime::database_path()),
                     &from_descriptor,
                     from_port,
                     &to_descriptor,
                     to_port,
+                    &network_change_actor(self.sender.as_ref()),
                 ) {
                     Ok(connection) => {
                         notify_agents_about_connection_change(state, app, &connection, true);
@@ -3067,13 +4115,14 @@ impl SessionMessageReceiver {
                 let args: NetworkDisconnectMessageArgs = deserialize_message_args(args, message_name)?;
                 let descriptor = resolve_network_tile_descriptor(state, &self.session_id, &args.tile_id)
                     .map_err(DispatchError::error)?;
-                let port = network::parse_port(&args.port)
-                    .map_err(|_| DispatchError::error("invalid port".to_string()))?;
+                let port = network::resolve_port_at(Path::new(runtime::database_path()), &descriptor.tile_id, &args.port)
+                    .map_err(|error| DispatchError::error(format!("invalid port: {error}")))?;
                 match network::disconnect_at(
                     Path::new(runtime::database_path()),
                     &descriptor.session_id,
                     &descriptor.tile_id,
                     port,
+                    &network_change_actor(self.sender.as_ref()),
                 ) {
                     Ok(removed) => {
                         if let Some(connection) = removed.as_ref() {
@@ -3113,12 +4162,12 @@ impl SessionMessageReceiver {
             }
             "message_network" => {
                 let sender = self.sender(message_name)?.clone();
-                let args: MessageTextArgs = deserialize_message_args(args, message_name)?;
+                let args: MessageNetworkArgs = deserialize_message_args(args, message_name)?;
                 let Some(from_agent_id) = sender.sender_agent_id.clone() else {
                     return Err(DispatchError::error("message_network requires an agent sender".to_string()));
                 };
                 let component = component_for_sender(app, state, &sender).map_err(DispatchError::error)?;
-                let recipient_tile_ids = component
+                let recipient_tile_ids = network::filter_component_by_segment(component, args.segment.as_deref())
                     .tiles
                     .into_iter()
                     .map(|tile| tile.tile_id)
@@ -3501,10 +4550,32 @@ fn resolve_network_tile_descriptor(
     })
 }
 
+fn network_change_actor(sender: Option<&SenderContext>) -> NetworkChangeActor {
+    match sender {
+        Some(sender) if sender.sender_agent_id.is_some() => {
+            NetworkChangeActor::agent(sender.sender_agent_id.clone(), sender.sender_tile_id.clone())
+        }
+        Some(sender) => NetworkChangeActor {
+            tile_id: sender.sender_tile_id.clone(),
+            ..NetworkChangeActor::user()
+        },
+        None => NetworkChangeActor::user(),
+    }
+}
+
 fn component_for_sender(
     app: &AppHandle,
     state: &AppState,
     sender: &SenderContext,
+) -> Result<network::NetworkComponent, String> {
+    component_for_sender_as_of(app, state, sender, None)
+}
+
+fn component_for_sender_as_of(
+    app: &AppHandle,
+    state: &AppState,
+    sender: &SenderContext,
+    at_ms: Option<i64>,
 ) -> Result<network::NetworkComponent, String> {
     let Some(start_tile_id) = sender.sender_tile_id.as_deref() else {
         return Ok(network::NetworkComponent {
@@ -3513,17 +4584,35 @@ fn component_for_sender(
             tiles: Vec::new(),
             connections: Vec::new(),
             port_settings: Vec::new(),
+            segments: Vec::new(),
         });
     };
     let session_tiles = session_network_tiles(app, state, &sender.session_id)?;
-    let connections = network::list_connections_at(Path::new(runtime::database_path()), &sender.session_id)?;
-    let port_settings = network::list_port_settings_at(Path::new(runtime::database_path()), &sender.session_id)?;
-    Ok(network::sender_visible_component_for_tile(
-        &sender.session_id,
-        start_tile_id,
-        &session_tiles,
-        &connections,
-        &port_settings,
+    let (session_tiles, connections, port_settings) = match at_ms {
+        Some(at_ms) => {
+            let topology = network_history::topology_at(Path::new(runtime::database_path()), &sender.session_id, at_ms)?;
+            (
+                network::historical_session_tiles(&sender.session_id, session_tiles, &topology.tiles),
+                topology.connections,
+                topology.port_settings,
+            )
+        }
+        None => (
+            session_tiles,
+            network::list_connections_at(Path::new(runtime::database_path()), &sender.session_id)?,
+            network::list_port_settings_at(Path::new(runtime::database_path()), &sender.session_id)?,
+        ),
+    };
+    let port_segments = network::list_port_segments_at(Path::new(runtime::database_path()), &sender.session_id)?;
+    Ok(network::annotate_segments(
+        network::sender_visible_component_for_tile(
+            &sender.session_id,
+            start_tile_id,
+            &session_tiles,
+            &connections,
+            &port_settings,
+        ),
+        &port_segments,
     ))
 }
 
@@ -3532,13 +4621,18 @@ fn session_component(
     state: &AppState,
     session_id: &str,
 ) -> Result<network::NetworkComponent, String> {
-    Ok(network::NetworkComponent {
+    let component = network::NetworkComponent {
         session_id: session_id.to_string(),
         sender_tile_id: None,
         tiles: session_network_tiles(app, state, session_id)?,
         connections: network::list_connections_at(Path::new(runtime::database_path()), session_id)?,
         port_settings: network::list_port_settings_at(Path::new(runtime::database_path()), session_id)?,
-    })
+        segments: Vec::new(),
+    };
+    Ok(network::annotate_segments(
+        component,
+        &network::list_port_segments_at(Path::new(runtime::database_path()), session_id)?,
+    ))
 }
 
 fn rpc_channel_name(channel: TileMessageChannel) -> &'static str {
@@ -3627,6 +4721,7 @@ fn matching_tile_event_subscriptions(
     let sender_tile_id = sender.sender_tile_id.as_deref();
     let mut seen = BTreeSet::new();
     let mut matches = Vec::new();
+    let mut session_segments = None;
     for subscription in state.list_tile_subscriptions_in_session(&sender.session_id)? {
         if subscription.action != action {
             continue;
@@ -3645,6 +4740,22 @@ fn matching_tile_event_subscriptions(
         let Some(matched_direction) = matched_direction else {
             continue;
         };
+        if let Some(segment) = subscription.segment.as_deref() {
+            let peer_tile_id = match matched_direction {
+                TileSubscriptionDirection::Out => Some(receiver.target_id()),
+                _ => sender_tile_id,
+            };
+            if session_segments.is_none() {
+                session_segments = Some(session_component(app, state, &sender.session_id)?);
+            }
+            let in_segment = session_segments
+                .as_ref()
+                .map(|component| network::segment_tile_ids(component, segment))
+                .is_some_and(|tile_ids| peer_tile_id.is_some_and(|tile_id| tile_ids.contains(tile_id)));
+            if !in_segment {
+                continue;
+            }
+        }
         if !subscriber_can_receive_network_tile_event(app, state, &subscription)? {
             continue;
         }
@@ -3666,27 +4777,119 @@ fn matching_tile_event_subscriptions(
     Ok(matches)
 }
 
-fn emit_tile_call_events(
-    state: &AppState,
-    app: &AppHandle,
-    channel: TileMessageChannel,
-    sender: &SenderContext,
-    receiver: &TileMessageReceiver,
-    action: &str,
-    args: &serde_json::Value,
-    result: &DispatchResult,
-) {
-    let outcome = dispatch_outcome_string(result);
-    let subject_label = tile_label(&receiver.tile);
-    let target_agent = state.agent_info_by_tile(receiver.target_id()).ok().flatten();
-    let target_agent_id = target_agent.as_ref().map(|agent| agent.agent_id.clone());
-    let target_tile_id = receiver.target_id().to_string();
-    let peer_tile_id = sender.sender_tile_id.clone();
-    let compact_args = compact_tile_event_value(args);
-    let compact_result = compact_tile_event_result(result);
-
-    if sender.sender_tile_id.as_deref() != Some(receiver.target_id())
-        && matches!(
+/// A tile event Herd raises on a tile by itself, such as a resource threshold crossing, new console output, or an
+/// extension-emitted event, rather than in response to a tile message.
+struct HerdTileEvent {
+    from_display_name: String,
+    action: String,
+    message: String,
+    outcome: String,
+    args_json: Option<serde_json::Value>,
+    result_json: Option<serde_json::Value>,
+    timestamp_ms: i64,
+}
+
+impl HerdTileEvent {
+    fn to_agent_event(
+        &self,
+        receiver: &TileMessageReceiver,
+        agent: &crate::agent::AgentInfo,
+        matched: Option<&TileEventMatch>,
+    ) -> AgentChannelEvent {
+        let subject_tile_id = receiver.target_id().to_string();
+        AgentChannelEvent {
+            kind: AgentChannelEventKind::TileEvent,
+            from_agent_id: None,
+            from_display_name: self.from_display_name.clone(),
+            to_agent_id: Some(agent.agent_id.clone()),
+            to_display_name: Some(agent.display_name.clone()),
+            message: self.message.clone(),
+            channels: Vec::new(),
+            mentions: Vec::new(),
+            replay: false,
+            ping_id: None,
+            delivery_reason: matched.map(|_| TileEventDeliveryReason::Subscription),
+            subscription_scope: matched.map(|matched| matched.subscription.scope),
+            subscription_direction: matched.map(|matched| matched.matched_direction),
+            action: Some(self.action.clone()),
+            subject_tile_id: Some(subject_tile_id.clone()),
+            peer_tile_id: None,
+            caller_tile_id: Some(subject_tile_id.clone()),
+            caller_agent_id: None,
+            target_tile_id: Some(subject_tile_id),
+            target_agent_id: None,
+            rpc_channel: None,
+            outcome: Some(self.outcome.clone()),
+            args_json: self.args_json.clone(),
+            result_json: self.result_json.clone(),
+            timestamp_ms: self.timestamp_ms,
+        }
+    }
+}
+
+/// Delivers a Herd-raised event to agents subscribed to it on the receiver's tile. The tile is treated as both the
+/// caller and the target, so `in:`, `out:`, and `both:` selectors and segment filters apply as they do for tile
+/// messages. Returns the ids of the agents it reached.
+fn emit_herd_tile_event(
+    state: &AppState,
+    app: &AppHandle,
+    receiver: &TileMessageReceiver,
+    event: &HerdTileEvent,
+) -> HashSet<String> {
+    let mut delivered = HashSet::new();
+    let sender = SenderContext {
+        session_id: receiver.tile.session_id.clone(),
+        sender_agent_id: None,
+        display_name: event.from_display_name.clone(),
+        sender_agent_role: None,
+        sender_tile_id: Some(receiver.target_id().to_string()),
+        sender_window_id: receiver.tile.window_id.clone(),
+    };
+    let matches = match matching_tile_event_subscriptions(app, state, &sender, receiver, &event.action) {
+        Ok(matches) => matches,
+        Err(error) => {
+            log::warn!("Failed to resolve matching tile subscriptions for {}: {error}", event.action);
+            return delivered;
+        }
+    };
+    for matched in matches {
+        let Some(agent) = state
+            .agent_info_by_tile(&matched.subscription.subscriber_tile_id)
+            .ok()
+            .flatten()
+        else {
+            continue;
+        };
+        if !agent.alive || !delivered.insert(agent.agent_id.clone()) {
+            continue;
+        }
+        let agent_event = event.to_agent_event(receiver, &agent, Some(&matched));
+        deliver_tile_event_to_agent(state, app, &agent.agent_id, agent_event);
+    }
+    delivered
+}
+
+fn emit_tile_call_events(
+    state: &AppState,
+    app: &AppHandle,
+    channel: TileMessageChannel,
+    sender: &SenderContext,
+    receiver: &TileMessageReceiver,
+    action: &str,
+    args: &serde_json::Value,
+    result: &DispatchResult,
+) {
+    let outcome = dispatch_outcome_string(result);
+    let subject_label = tile_label(&receiver.tile);
+    let target_agent = state.agent_info_by_tile(receiver.target_id()).ok().flatten();
+    let target_agent_id = target_agent.as_ref().map(|agent| agent.agent_id.clone());
+    let target_tile_id = receiver.target_id().to_string();
+    let peer_tile_id = sender.sender_tile_id.clone();
+    let compact_args = compact_tile_event_value(args);
+    let compact_result = compact_tile_event_result(result);
+
+    if sender.sender_tile_id.as_deref() != Some(receiver.target_id())
+        && matches!(
             receiver.tile.kind,
             network::NetworkTileKind::Agent | network::NetworkTileKind::RootAgent
         )
@@ -3899,6 +5102,521 @@ fn test_driver_status(state: &AppState) -> serde_json::Value {
     })
 }
 
+const EXEC_WAIT_DEFAULT_TIMEOUT_MS: u64 = 30_000;
+const EXEC_WAIT_MAX_TIMEOUT_MS: u64 = 600_000;
+const OUTPUT_WAIT_DEFAULT_CONTEXT_LINES: usize = 2;
+
+fn exec_and_wait(
+    app: &AppHandle,
+    state: &AppState,
+    pane_id: &str,
+    command: &str,
+    timeout_ms: Option<u64>,
+) -> Result<tmux_control::ExecWaitResult, String> {
+    let timeout = Duration::from_millis(
+        timeout_ms
+            .unwrap_or(EXEC_WAIT_DEFAULT_TIMEOUT_MS)
+            .min(EXEC_WAIT_MAX_TIMEOUT_MS),
+    );
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let input = tmux_control::exec_wait_wrapped_command(&token, command);
+    let since_offset = state.with_control(|ctrl| ctrl.output_end_offset(pane_id))?;
+    let started = Instant::now();
+    state.with_control(|ctrl| ctrl.writer.send_input_by_id(pane_id, input.as_bytes()))?;
+    let _ = crate::tmux_state::emit_snapshot(app);
+    loop {
+        let buffer = state
+            .with_control(|ctrl| ctrl.read_output(pane_id, Some(since_offset), None))?
+            .output;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        if let Some((exit_code, output)) = tmux_control::parse_exec_wait_output(&buffer, &token) {
+            return Ok(tmux_control::ExecWaitResult {
+                completed: true,
+                exit_code: Some(exit_code),
+                duration_ms,
+                output,
+            });
+        }
+        if started.elapsed() >= timeout {
+            return Ok(tmux_control::ExecWaitResult {
+                completed: false,
+                exit_code: None,
+                duration_ms,
+                output: tmux_control::partial_exec_wait_output(&buffer, &token),
+            });
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn wait_for_output(
+    state: &AppState,
+    pane_id: &str,
+    pattern: &regex::Regex,
+    args: WaitForOutputMessageArgs,
+) -> Result<tmux_control::OutputWaitResult, String> {
+    let timeout = Duration::from_millis(
+        args.timeout_ms
+            .unwrap_or(EXEC_WAIT_DEFAULT_TIMEOUT_MS)
+            .min(EXEC_WAIT_MAX_TIMEOUT_MS),
+    );
+    let context_lines = args.context_lines.unwrap_or(OUTPUT_WAIT_DEFAULT_CONTEXT_LINES);
+    let since_offset = match args.since_offset {
+        Some(since_offset) => since_offset,
+        None => state.with_control(|ctrl| ctrl.output_end_offset(pane_id))?,
+    };
+    let started = Instant::now();
+    loop {
+        let chunk = state.with_control(|ctrl| ctrl.read_output_raw(pane_id, Some(since_offset), None))?;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        if let Some(found) = tmux_control::find_output_match(&chunk.bytes, chunk.offset, pattern, context_lines) {
+            return Ok(tmux_control::OutputWaitResult {
+                matched: true,
+                duration_ms,
+                next_offset: found.end_offset,
+                found: Some(found),
+            });
+        }
+        if started.elapsed() >= timeout {
+            return Ok(tmux_control::OutputWaitResult {
+                matched: false,
+                duration_ms,
+                next_offset: chunk.next_offset,
+                found: None,
+            });
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+const RESOURCE_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+const RESOURCE_THRESHOLD_EVENT: &str = "resource_threshold";
+const BROWSER_CONSOLE_EVENT: &str = crate::browser_console::BROWSER_CONSOLE_EVENT;
+const BROWSER_CONSOLE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const BROWSER_EXTENSION_EVENT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn tile_pane_pid(tile: &network::SessionTileInfo) -> Result<u32, String> {
+    let pane_id = tile
+        .pane_id
+        .as_deref()
+        .ok_or_else(|| format!("tile {} is missing a pane id", tile.tile_id))?;
+    crate::tmux_state::pane_pid(pane_id)?.ok_or_else(|| format!("pane {pane_id} has no running process"))
+}
+
+fn tile_process_tree(tile: &network::SessionTileInfo) -> Result<process_info::ProcessTree, String> {
+    process_info::process_tree(tile_pane_pid(tile)?)
+}
+
+fn tile_process_info(tile: &network::SessionTileInfo) -> Result<serde_json::Value, String> {
+    let tree = tile_process_tree(tile)?;
+    let mut info = serde_json::json!({
+        "tile_id": tile.tile_id,
+        "pane_id": tile.pane_id,
+        "pane_pid": tree.root_pid,
+    });
+    if let (Some(info), serde_json::Value::Object(tree)) = (info.as_object_mut(), serde_json::json!(tree)) {
+        info.extend(tree);
+    }
+    Ok(info)
+}
+
+/// Reports each tile's latest sample from the background resource monitor, which only runs while some tile has a
+/// threshold. Any other tile is sampled on the spot from one `/proc` scan, without CPU usage.
+fn resource_summary(
+    state: &AppState,
+    session_id: &str,
+    tiles: &[network::SessionTileInfo],
+) -> Result<serde_json::Value, String> {
+    let thresholds = process_info::list_thresholds_at(Path::new(runtime::database_path()), Some(session_id))?;
+    let samples = state.resource_samples()?;
+    let mut entries = Vec::new();
+    let mut process_count = 0;
+    let mut total_cpu_percent: f64 = 0.0;
+    let mut total_rss_bytes = 0;
+    let mut table: Option<Result<process_info::ProcessTable, String>> = None;
+    for tile in tiles {
+        let threshold = thresholds.iter().find(|threshold| threshold.tile_id == tile.tile_id);
+        let mut entry = serde_json::json!({
+            "tile_id": tile.tile_id,
+            "kind": tile.kind,
+            "title": tile.title,
+            "threshold": threshold,
+        });
+        let sample = match samples.get(&tile.tile_id) {
+            Some(sample) => Ok(sample.clone()),
+            None => table
+                .get_or_insert_with(process_info::ProcessTable::scan)
+                .as_ref()
+                .map_err(String::clone)
+                .and_then(|table| tile_pane_pid(tile).and_then(|pane_pid| table.tree(pane_pid)))
+                .map(|tree| process_info::ResourceSample::from_tree(&tree, None, now_ms())),
+        };
+        match sample {
+            Ok(sample) => {
+                let exceeded = threshold
+                    .map(|threshold| threshold.exceeded(sample.cpu_percent, sample.rss_bytes))
+                    .unwrap_or_default();
+                process_count += sample.process_count;
+                total_cpu_percent += sample.cpu_percent.unwrap_or_default();
+                total_rss_bytes += sample.rss_bytes;
+                entry["pane_pid"] = serde_json::json!(sample.pane_pid);
+                entry["process_count"] = serde_json::json!(sample.process_count);
+                entry["cpu_percent"] = serde_json::json!(sample.cpu_percent);
+                entry["cpu_time_ms"] = serde_json::json!(sample.cpu_time_ms);
+                entry["rss_bytes"] = serde_json::json!(sample.rss_bytes);
+                entry["busiest"] = serde_json::json!(sample.busiest);
+                entry["sampled_at"] = serde_json::json!(sample.sampled_at);
+                entry["exceeded"] = serde_json::json!(exceeded);
+            }
+            Err(error) => entry["error"] = serde_json::json!(error),
+        }
+        entries.push(entry);
+    }
+    Ok(serde_json::json!({
+        "session_id": session_id,
+        "sample_interval_ms": RESOURCE_MONITOR_INTERVAL.as_millis() as u64,
+        "process_count": process_count,
+        "cpu_percent": (total_cpu_percent * 10.0).round() / 10.0,
+        "rss_bytes": total_rss_bytes,
+        "tiles": entries,
+    }))
+}
+
+/// Samples every shell and agent tile's process tree in the background while any tile has a threshold, caching the
+/// samples for `resource_summary`, and emits a tile event whenever a tile with a threshold crosses into or back out
+/// of its limits. Each tick reads `/proc` once for all panes, and pane pids are cached until their process exits.
+fn resource_threshold_loop(state: AppState, app: AppHandle) {
+    let mut exceeded_tiles: HashSet<String> = HashSet::new();
+    let mut pane_pids: HashMap<String, u32> = HashMap::new();
+    loop {
+        std::thread::sleep(RESOURCE_MONITOR_INTERVAL);
+        if state.is_shutting_down() {
+            return;
+        }
+        let thresholds = match process_info::list_thresholds_at(Path::new(runtime::database_path()), None) {
+            Ok(thresholds) => thresholds,
+            Err(error) => {
+                log::warn!("resource threshold check failed: {error}");
+                continue;
+            }
+        };
+        exceeded_tiles.retain(|tile_id| thresholds.iter().any(|threshold| &threshold.tile_id == tile_id));
+        if thresholds.is_empty() {
+            // Drop the cached samples so `resource_summary` samples on demand rather than reporting stale usage.
+            if !pane_pids.is_empty() {
+                pane_pids.clear();
+                if let Err(error) = state.replace_resource_samples(HashMap::new()) {
+                    log::warn!("resource monitor could not store samples: {error}");
+                }
+            }
+            continue;
+        }
+        let previous = state.resource_samples().unwrap_or_default();
+        let records = match state.tile_records_snapshot() {
+            Ok(records) => records,
+            Err(error) => {
+                log::warn!("resource monitor could not list tiles: {error}");
+                continue;
+            }
+        };
+        let table = match process_info::ProcessTable::scan() {
+            Ok(table) => table,
+            Err(error) => {
+                log::warn!("resource monitor could not read processes: {error}");
+                continue;
+            }
+        };
+        let terminal_records = records
+            .iter()
+            .filter(|record| {
+                matches!(
+                    record.kind,
+                    crate::tile_registry::TileRecordKind::Shell | crate::tile_registry::TileRecordKind::Agent
+                )
+            })
+            .collect::<Vec<_>>();
+        pane_pids.retain(|pane_id, _| terminal_records.iter().any(|record| &record.pane_id == pane_id));
+        let mut samples = HashMap::new();
+        for record in terminal_records {
+            // A cached pid whose process is gone means the pane was respawned or is exiting, so ask tmux again.
+            let tree = match pane_pids.get(&record.pane_id).map(|pane_pid| table.tree(*pane_pid)) {
+                Some(Ok(tree)) => tree,
+                _ => {
+                    pane_pids.remove(&record.pane_id);
+                    let Ok(Some(pane_pid)) = crate::tmux_state::pane_pid(&record.pane_id) else {
+                        continue;
+                    };
+                    let Ok(tree) = table.tree(pane_pid) else {
+                        continue;
+                    };
+                    pane_pids.insert(record.pane_id.clone(), pane_pid);
+                    tree
+                }
+            };
+            let sample = process_info::ResourceSample::from_tree(&tree, previous.get(&record.tile_id), now_ms());
+            samples.insert(record.tile_id.clone(), sample);
+        }
+        if let Err(error) = state.replace_resource_samples(samples.clone()) {
+            log::warn!("resource monitor could not store samples: {error}");
+        }
+
+        for threshold in thresholds {
+            let Some(sample) = samples.get(&threshold.tile_id) else {
+                exceeded_tiles.remove(&threshold.tile_id);
+                continue;
+            };
+            let exceeded = threshold.exceeded(sample.cpu_percent, sample.rss_bytes);
+            let was_exceeded = if exceeded.is_empty() {
+                exceeded_tiles.remove(&threshold.tile_id)
+            } else {
+                !exceeded_tiles.insert(threshold.tile_id.clone())
+            };
+            if exceeded.is_empty() != was_exceeded {
+                continue;
+            }
+            let Ok(receiver) = session_tile_receiver(&app, &state, &threshold.session_id, &threshold.tile_id) else {
+                continue;
+            };
+            emit_resource_threshold_event(&state, &app, &receiver, &threshold, &exceeded, sample);
+        }
+    }
+}
+
+/// Delivers a `resource_threshold` tile event to agents subscribed to the tile and to the session's Root agent.
+fn emit_resource_threshold_event(
+    state: &AppState,
+    app: &AppHandle,
+    receiver: &TileMessageReceiver,
+    threshold: &process_info::ResourceThreshold,
+    exceeded: &[&str],
+    sample: &process_info::ResourceSample,
+) {
+    let subject_label = tile_label(&receiver.tile);
+    let event = HerdTileEvent {
+        from_display_name: "HERD".to_string(),
+        action: RESOURCE_THRESHOLD_EVENT.to_string(),
+        message: if exceeded.is_empty() {
+            format!("Resource usage on {subject_label} is back under its thresholds")
+        } else {
+            format!("Resource threshold exceeded on {subject_label}: {}", exceeded.join(", "))
+        },
+        outcome: if exceeded.is_empty() { "recovered" } else { "exceeded" }.to_string(),
+        args_json: Some(serde_json::json!(threshold)),
+        result_json: Some(serde_json::json!({
+            "pane_pid": sample.pane_pid,
+            "process_count": sample.process_count,
+            "cpu_percent": sample.cpu_percent,
+            "rss_bytes": sample.rss_bytes,
+            "busiest": sample.busiest,
+            "exceeded": exceeded,
+        })),
+        timestamp_ms: now_ms(),
+    };
+    let delivered = emit_herd_tile_event(state, app, receiver, &event);
+    if let Ok(Some(root)) = state.root_agent_in_session(&receiver.tile.session_id) {
+        if root.alive && !delivered.contains(&root.agent_id) {
+            let root_event = event.to_agent_event(receiver, &root, None);
+            deliver_tile_event_to_agent(state, app, &root.agent_id, root_event);
+        }
+    }
+}
+
+/// Drains console output from browser tiles that have `console` subscribers and delivers anything new as a tile
+/// event.
+fn browser_console_loop(state: AppState, app: AppHandle) {
+    let mut cursors: HashMap<String, u64> = HashMap::new();
+    loop {
+        std::thread::sleep(BROWSER_CONSOLE_POLL_INTERVAL);
+        if state.is_shutting_down() {
+            return;
+        }
+        let subscriptions = match state.list_tile_subscriptions_for_action(BROWSER_CONSOLE_EVENT) {
+            Ok(subscriptions) => subscriptions,
+            Err(error) => {
+                log::warn!("browser console subscription lookup failed: {error}");
+                continue;
+            }
+        };
+        cursors.retain(|tile_id, _| {
+            subscriptions
+                .iter()
+                .any(|subscription| &subscription.subject_tile_id == tile_id)
+        });
+        let mut watched = subscriptions
+            .iter()
+            .map(|subscription| (subscription.session_id.clone(), subscription.subject_tile_id.clone()))
+            .collect::<Vec<_>>();
+        watched.sort();
+        watched.dedup();
+        for (session_id, tile_id) in watched {
+            let Ok(receiver) = session_tile_receiver(&app, &state, &session_id, &tile_id) else {
+                continue;
+            };
+            let Some(pane_id) = receiver.tile.pane_id.clone() else {
+                continue;
+            };
+            let since = match cursors.get(&tile_id) {
+                Some(cursor) => *cursor,
+                None => match state.with_browser_console(&pane_id, |log| log.next_cursor()) {
+                    Ok(cursor) => cursor,
+                    Err(_) => continue,
+                },
+            };
+            let result = match crate::browser::read_browser_console(&app, &pane_id, Some(since), None, Some(usize::MAX)) {
+                Ok(result) => result,
+                Err(error) => {
+                    log::debug!("browser console poll failed for {tile_id}: {error}");
+                    continue;
+                }
+            };
+            cursors.insert(tile_id.clone(), result.next_cursor);
+            if !result.entries.is_empty() {
+                emit_browser_console_event(&state, &app, &receiver, &result.entries);
+            }
+        }
+    }
+}
+
+/// Delivers a `console` tile event carrying new console entries to agents subscribed to the browser tile.
+fn emit_browser_console_event(
+    state: &AppState,
+    app: &AppHandle,
+    receiver: &TileMessageReceiver,
+    entries: &[crate::browser_console::BrowserConsoleEntry],
+) {
+    let Some(latest) = entries.last() else {
+        return;
+    };
+    let outcome = ["error", "warn", "info", "log", "debug"]
+        .into_iter()
+        .find(|level| entries.iter().any(|entry| entry.level == *level))
+        .unwrap_or("log");
+    let subject_label = tile_label(&receiver.tile);
+    let message = if entries.len() == 1 {
+        format!("Console {} on {subject_label}: {}", latest.level, latest.message)
+    } else {
+        format!(
+            "{} console entries on {subject_label}; latest {}: {}",
+            entries.len(),
+            latest.level,
+            latest.message
+        )
+    };
+    let event = HerdTileEvent {
+        from_display_name: "HERD".to_string(),
+        action: BROWSER_CONSOLE_EVENT.to_string(),
+        message,
+        outcome: outcome.to_string(),
+        args_json: None,
+        result_json: Some(serde_json::json!({ "entries": entries })),
+        timestamp_ms: now_ms(),
+    };
+    emit_herd_tile_event(state, app, receiver, &event);
+}
+
+/// Whether a subscription action on a browser tile can only be satisfied by an
+/// extension-emitted event, as opposed to a tile message or a built-in event.
+fn is_browser_extension_event_action(action: &str) -> bool {
+    action != "get"
+        && action != "extension_call"
+        && action != BROWSER_CONSOLE_EVENT
+        && !network::dispatchable_messages(network::NetworkTileKind::Browser).contains(&action)
+}
+
+/// Drains events emitted by extension pages on browser tiles that have subscribers for non-built-in actions and
+/// routes each declared event as a tile event.
+fn browser_extension_event_loop(state: AppState, app: AppHandle) {
+    loop {
+        std::thread::sleep(BROWSER_EXTENSION_EVENT_POLL_INTERVAL);
+        if state.is_shutting_down() {
+            return;
+        }
+        let subscriptions = match state.list_tile_subscriptions() {
+            Ok(subscriptions) => subscriptions,
+            Err(error) => {
+                log::warn!("browser extension event subscription lookup failed: {error}");
+                continue;
+            }
+        };
+        let mut watched = subscriptions
+            .iter()
+            .filter(|subscription| is_browser_extension_event_action(&subscription.action))
+            .map(|subscription| (subscription.session_id.clone(), subscription.subject_tile_id.clone()))
+            .collect::<Vec<_>>();
+        watched.sort();
+        watched.dedup();
+        for (session_id, tile_id) in watched {
+            let Ok(Some(record)) = state.tile_record(&tile_id) else {
+                continue;
+            };
+            if record.kind != crate::tile_registry::TileRecordKind::Browser {
+                continue;
+            }
+            let drain = match crate::browser::drain_browser_extension_events(&app, &record.pane_id) {
+                Ok(drain) => drain,
+                Err(error) => {
+                    log::debug!("browser extension event poll failed for {tile_id}: {error}");
+                    continue;
+                }
+            };
+            if drain.dropped > 0 {
+                log::warn!("browser tile {tile_id} dropped {} extension events before Herd drained them", drain.dropped);
+            }
+            if drain.events.is_empty() {
+                continue;
+            }
+            let receiver = match session_tile_receiver(&app, &state, &session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    log::debug!("browser extension events from {tile_id} were not routed: {}", error.message);
+                    continue;
+                }
+            };
+            for emitted in drain.events {
+                emit_browser_extension_event(&state, &app, &receiver, emitted);
+            }
+        }
+    }
+}
+
+/// Delivers one declared `HerdBrowserExtension.emit` event to agents subscribed to it on the emitting tile.
+fn emit_browser_extension_event(
+    state: &AppState,
+    app: &AppHandle,
+    receiver: &TileMessageReceiver,
+    emitted: crate::browser::BrowserExtensionEmittedEvent,
+) {
+    let network::TileDetails::Browser(details) = &receiver.tile.details else {
+        return;
+    };
+    let Some(extension) = details.extension.as_ref() else {
+        return;
+    };
+    if !extension.events.iter().any(|event| event.name == emitted.event) {
+        log::debug!(
+            "ignoring undeclared extension event {} from {}",
+            emitted.event,
+            receiver.target_id()
+        );
+        return;
+    }
+    let event = HerdTileEvent {
+        from_display_name: extension.label.clone(),
+        action: emitted.event.clone(),
+        message: format!(
+            "{} emitted {} on {}",
+            extension.label,
+            emitted.event,
+            tile_label(&receiver.tile)
+        ),
+        outcome: "emitted".to_string(),
+        args_json: Some(compact_tile_event_value(&emitted.payload)),
+        result_json: None,
+        timestamp_ms: if emitted.timestamp_ms > 0 { emitted.timestamp_ms } else { now_ms() },
+    };
+    emit_herd_tile_event(state, app, receiver, &event);
+}
+
 fn wait_for<F>(timeout_ms: u64, mut predicate: F, description: &str) -> Result<(), String>
 where
     F: FnMut() -> bool,
@@ -4330,7 +6048,22 @@ pub async fn start(state: AppState, app_handle: AppHandle) {
     let logger: SharedLogger = Arc::new(Mutex::new(SocketLogger::open()));
     log::info!("Socket server listening on {}", runtime::socket_path());
     tokio::spawn(agent_ping_loop(state.clone(), app_handle.clone()));
-
+    {
+        let state = state.clone();
+        let app = app_handle.clone();
+        std::thread::spawn(move || resource_threshold_loop(state, app));
+    }
+    {
+        let state = state.clone();
+        let app = app_handle.clone();
+        std::thread::spawn(move || browser_console_loop(state, app));
+    }
+    {
+        let state = state.clone();
+        let app = app_handle.clone();
+        std::thread::spawn(move || browser_extension_event_loop(state, app));
+    }
+
     loop {
         match listener.accept().await {
             Ok((stream, _)) => {
@@ -4360,7 +6093,7 @@ async fn handle_connection(
     while let Ok(Some(line)) = lines.next_line().await {
         if let Ok(mut guard) = logger.lock() {
             if let Some(ref mut l) = *guard {
-                l.log(">>>", &line);
+                l.log(">>>", &session_env::mask_socket_line(&line));
             }
         }
 
@@ -4386,7 +6119,7 @@ async fn handle_connection(
                         handle_agent_event_subscription(agent_id, channel, lines, writer, state, app, logger).await;
                         return;
                     }
-                    Ok(cmd) => handle_command(cmd, channel, &state, &app),
+                    Ok(cmd) => dispatch_command_blocking(cmd, channel, &state, &app).await,
                     Err(e) => SocketResponse::error(format!("Parse error: {e}")),
                 }
             }
@@ -4405,79 +6138,552 @@ async fn handle_connection(
         if writer.write_all(resp_json.as_bytes()).await.is_err() {
             break;
         }
-    }
-}
+    }
+}
+
+/// Runs a command on the blocking pool. Commands such as `exec` with `wait`
+/// hold their caller for seconds or minutes, and must not stall the async
+/// runtime every other socket connection shares.
+async fn dispatch_command_blocking(
+    cmd: SocketCommand,
+    channel: TileMessageChannel,
+    state: &AppState,
+    app: &AppHandle,
+) -> SocketResponse {
+    let state = state.clone();
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || handle_command(cmd, channel, &state, &app))
+        .await
+        .unwrap_or_else(|error| SocketResponse::error(format!("socket command task failed: {error}")))
+}
+
+fn handle_command(
+    cmd: SocketCommand,
+    channel: TileMessageChannel,
+    state: &AppState,
+    app: &AppHandle,
+) -> SocketResponse {
+    match cmd {
+        SocketCommand::TileCreate {
+            tile_type,
+            title,
+            x,
+            y,
+            width,
+            height,
+            parent_session_id,
+            parent_tile_id,
+            browser_incognito,
+            browser_path,
+            worktree,
+            env,
+            launch_policy,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender = match sender_agent_id
+                .clone()
+                .or(sender_tile_id.clone())
+                .map(|_| resolve_sender_context(state, sender_agent_id.clone(), sender_tile_id.clone()))
+            {
+                Some(Ok(sender)) => Some(sender),
+                Some(Err(error)) => return SocketResponse::error(error),
+                None => None,
+            };
+            let before = match crate::tmux_state::snapshot(state) {
+                Ok(snapshot) => snapshot,
+                Err(e) => return SocketResponse::error(e),
+            };
+            let (target_session_id, parent_window_id) =
+                resolve_create_target(
+                    state,
+                    &before,
+                    sender.as_ref().map(|context| context.session_id.clone()),
+                    parent_session_id,
+                    parent_tile_id,
+                );
+            let target_session_id =
+                target_session_id.unwrap_or_else(|| before.active_session_id.unwrap_or_default());
+            let args = serde_json::json!({
+                "tile_type": tile_type,
+                "title": title,
+                "x": x,
+                "y": y,
+                "width": width,
+                "height": height,
+                "parent_window_id": parent_window_id,
+                "browser_incognito": browser_incognito,
+                "browser_path": browser_path,
+                "worktree": worktree,
+                "env": env,
+                "launch_policy": launch_policy,
+            });
+            let receiver = SessionMessageReceiver::new(target_session_id, sender.clone());
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_create",
+                "tile_create",
+                sender.as_ref(),
+                args,
+            )
+        }
+
+        SocketCommand::TileDestroy { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_destroy") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_destroy",
+                "tile_destroy",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::ShellInputSend { tile_id, input, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_input_send") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({ "input": input });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_input_send",
+                        "input_send",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(state, app, channel, &receiver, "shell_input_send", "input_send", Some(&sender), args)
+        }
+
+        SocketCommand::ShellExec { tile_id, shell_command, wait, timeout_ms, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_exec") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({ "command": shell_command, "wait": wait, "timeout_ms": timeout_ms });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_exec",
+                        "exec",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(state, app, channel, &receiver, "shell_exec", "exec", Some(&sender), args)
+        }
+
+        SocketCommand::ShellOutputRead { tile_id, since_offset, max_bytes, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_output_read") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({ "since_offset": since_offset, "max_bytes": max_bytes });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_output_read",
+                        "output_read",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "shell_output_read",
+                "output_read",
+                Some(&sender),
+                args,
+            )
+        }
+
+        SocketCommand::ShellOutputCapture {
+            tile_id,
+            start_line,
+            end_line,
+            preserve_ansi,
+            join_wrapped,
+            search,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_output_capture") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({
+                "start_line": start_line,
+                "end_line": end_line,
+                "preserve_ansi": preserve_ansi,
+                "join_wrapped": join_wrapped,
+                "search": search,
+            });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_output_capture",
+                        "output_capture",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "shell_output_capture",
+                "output_capture",
+                Some(&sender),
+                args,
+            )
+        }
+
+        SocketCommand::ShellWaitForOutput {
+            tile_id,
+            pattern,
+            timeout_ms,
+            since_offset,
+            context_lines,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_wait_for_output") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({
+                "pattern": pattern,
+                "timeout_ms": timeout_ms,
+                "since_offset": since_offset,
+                "context_lines": context_lines,
+            });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_wait_for_output",
+                        "wait_for_output",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "shell_wait_for_output",
+                "wait_for_output",
+                Some(&sender),
+                args,
+            )
+        }
+
+        SocketCommand::ShellScreenshot {
+            tile_id,
+            format,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_screenshot") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({
+                "format": format,
+            });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "shell_screenshot",
+                        "screenshot",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "shell_screenshot",
+                "screenshot",
+                Some(&sender),
+                args,
+            )
+        }
+
+        SocketCommand::TileRename { tile_id, title, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_rename") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_rename",
+                "tile_rename",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id, "title": title }),
+            )
+        }
+
+        SocketCommand::TileRecordStart { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_record_start") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_record_start",
+                "tile_record_start",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::TileRecordStop { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_record_stop") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_record_stop",
+                "tile_record_stop",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::TileRecordList { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_record_list") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_record_list",
+                "tile_record_list",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::ResourceSummary { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "resource_summary") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "resource_summary",
+                "resource_summary",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::TileResourceThresholdSet {
+            tile_id,
+            cpu_percent,
+            rss_bytes,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender =
+                match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_resource_threshold_set") {
+                    Ok(sender) => sender,
+                    Err(error) => return SocketResponse::error(error),
+                };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "tile_resource_threshold_set",
+                "tile_resource_threshold_set",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id, "cpu_percent": cpu_percent, "rss_bytes": rss_bytes }),
+            )
+        }
+
+        SocketCommand::WorktreeList { sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "worktree_list") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "worktree_list",
+                "worktree_list",
+                Some(&sender),
+                serde_json::json!({}),
+            )
+        }
+
+        SocketCommand::WorktreeStatus { agent_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "worktree_status") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "worktree_status",
+                "worktree_status",
+                Some(&sender),
+                serde_json::json!({ "agent_id": agent_id }),
+            )
+        }
 
-fn handle_command(
-    cmd: SocketCommand,
-    channel: TileMessageChannel,
-    state: &AppState,
-    app: &AppHandle,
-) -> SocketResponse {
-    match cmd {
-        SocketCommand::TileCreate {
-            tile_type,
-            title,
-            x,
-            y,
-            width,
-            height,
-            parent_session_id,
-            parent_tile_id,
-            browser_incognito,
-            browser_path,
+        SocketCommand::WorktreeRemove {
+            agent_id,
+            force,
+            delete_branch,
             sender_agent_id,
             sender_tile_id,
         } => {
-            let sender = match sender_agent_id
-                .clone()
-                .or(sender_tile_id.clone())
-                .map(|_| resolve_sender_context(state, sender_agent_id.clone(), sender_tile_id.clone()))
-            {
-                Some(Ok(sender)) => Some(sender),
-                Some(Err(error)) => return SocketResponse::error(error),
-                None => None,
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "worktree_remove") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
             };
-            let before = match crate::tmux_state::snapshot(state) {
-                Ok(snapshot) => snapshot,
-                Err(e) => return SocketResponse::error(e),
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "worktree_remove",
+                "worktree_remove",
+                Some(&sender),
+                serde_json::json!({ "agent_id": agent_id, "force": force, "delete_branch": delete_branch }),
+            )
+        }
+
+        SocketCommand::EnvSet { name, value, secret, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "env_set") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
             };
-            let (target_session_id, parent_window_id) =
-                resolve_create_target(
-                    state,
-                    &before,
-                    sender.as_ref().map(|context| context.session_id.clone()),
-                    parent_session_id,
-                    parent_tile_id,
-                );
-            let target_session_id =
-                target_session_id.unwrap_or_else(|| before.active_session_id.unwrap_or_default());
-            let args = serde_json::json!({
-                "tile_type": tile_type,
-                "title": title,
-                "x": x,
-                "y": y,
-                "width": width,
-                "height": height,
-                "parent_window_id": parent_window_id,
-                "browser_incognito": browser_incognito,
-                "browser_path": browser_path,
+            // Resolve an omitted flag up front so the message log masks a rotated secret.
+            let secret = secret.unwrap_or_else(|| {
+                session_env::get_env_at(Path::new(runtime::database_path()), &sender.session_id, name.trim())
+                    .ok()
+                    .flatten()
+                    .is_some_and(|entry| entry.secret)
             });
-            let receiver = SessionMessageReceiver::new(target_session_id, sender.clone());
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
             dispatch_session_message(
                 state,
                 app,
                 channel,
                 &receiver,
-                "tile_create",
-                "tile_create",
-                sender.as_ref(),
-                args,
+                "env_set",
+                "env_set",
+                Some(&sender),
+                serde_json::json!({ "name": name, "value": value, "secret": secret }),
             )
         }
 
-        SocketCommand::TileDestroy { tile_id, sender_agent_id, sender_tile_id } => {
-            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_destroy") {
+        SocketCommand::EnvList { sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "env_list") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
@@ -4487,19 +6693,37 @@ fn handle_command(
                 app,
                 channel,
                 &receiver,
-                "tile_destroy",
-                "tile_destroy",
+                "env_list",
+                "env_list",
                 Some(&sender),
-                serde_json::json!({ "tile_id": tile_id }),
+                serde_json::json!({}),
             )
         }
 
-        SocketCommand::ShellInputSend { tile_id, input, sender_agent_id, sender_tile_id } => {
-            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_input_send") {
+        SocketCommand::EnvUnset { name, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "env_unset") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let args = serde_json::json!({ "input": input });
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "env_unset",
+                "env_unset",
+                Some(&sender),
+                serde_json::json!({ "name": name }),
+            )
+        }
+
+        SocketCommand::ShellProcessInfo { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_process_info") {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let args = serde_json::json!({});
             let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
                 Ok(receiver) => receiver,
                 Err(error) => {
@@ -4510,23 +6734,32 @@ fn handle_command(
                         sender.session_id.clone(),
                         tile_id,
                         "tile".to_string(),
-                        "shell_input_send",
-                        "input_send",
+                        "shell_process_info",
+                        "process_info",
                         Some(&sender),
                         args,
                         || Err(error),
                     )
                 }
             };
-            dispatch_tile_message(state, app, channel, &receiver, "shell_input_send", "input_send", Some(&sender), args)
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "shell_process_info",
+                "process_info",
+                Some(&sender),
+                args,
+            )
         }
 
-        SocketCommand::ShellExec { tile_id, shell_command, sender_agent_id, sender_tile_id } => {
-            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_exec") {
+        SocketCommand::BrowserConsoleRead { tile_id, since, level, limit, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "browser_console_read") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let args = serde_json::json!({ "command": shell_command });
+            let args = serde_json::json!({ "since": since, "level": level, "limit": limit });
             let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
                 Ok(receiver) => receiver,
                 Err(error) => {
@@ -4537,23 +6770,37 @@ fn handle_command(
                         sender.session_id.clone(),
                         tile_id,
                         "tile".to_string(),
-                        "shell_exec",
-                        "exec",
+                        "browser_console_read",
+                        "console_read",
                         Some(&sender),
                         args,
                         || Err(error),
                     )
                 }
             };
-            dispatch_tile_message(state, app, channel, &receiver, "shell_exec", "exec", Some(&sender), args)
+            dispatch_tile_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "browser_console_read",
+                "console_read",
+                Some(&sender),
+                args,
+            )
         }
 
-        SocketCommand::ShellOutputRead { tile_id, sender_agent_id, sender_tile_id } => {
-            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "shell_output_read") {
+        SocketCommand::BrowserRecordStart { tile_id, format, interval_ms, max_frames, max_width, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "browser_record_start") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let args = serde_json::json!({});
+            let args = serde_json::json!({
+                "format": format,
+                "interval_ms": interval_ms,
+                "max_frames": max_frames,
+                "max_width": max_width,
+            });
             let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
                 Ok(receiver) => receiver,
                 Err(error) => {
@@ -4564,8 +6811,8 @@ fn handle_command(
                         sender.session_id.clone(),
                         tile_id,
                         "tile".to_string(),
-                        "shell_output_read",
-                        "output_read",
+                        "browser_record_start",
+                        "record_start",
                         Some(&sender),
                         args,
                         || Err(error),
@@ -4577,28 +6824,46 @@ fn handle_command(
                 app,
                 channel,
                 &receiver,
-                "shell_output_read",
-                "output_read",
+                "browser_record_start",
+                "record_start",
                 Some(&sender),
-                serde_json::json!({}),
+                args,
             )
         }
 
-        SocketCommand::TileRename { tile_id, title, sender_agent_id, sender_tile_id } => {
-            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_rename") {
+        SocketCommand::BrowserRecordStop { tile_id, work_id, sender_agent_id, sender_tile_id } => {
+            let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "browser_record_stop") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
-            dispatch_session_message(
+            let args = serde_json::json!({ "work_id": work_id });
+            let receiver = match session_tile_receiver(app, state, &sender.session_id, &tile_id) {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    return dispatch_with_log(
+                        state,
+                        app,
+                        channel,
+                        sender.session_id.clone(),
+                        tile_id,
+                        "tile".to_string(),
+                        "browser_record_stop",
+                        "record_stop",
+                        Some(&sender),
+                        args,
+                        || Err(error),
+                    )
+                }
+            };
+            dispatch_tile_message(
                 state,
                 app,
                 channel,
                 &receiver,
-                "tile_rename",
-                "tile_rename",
+                "browser_record_stop",
+                "record_stop",
                 Some(&sender),
-                serde_json::json!({ "tile_id": tile_id, "title": title }),
+                args,
             )
         }
 
@@ -4947,7 +7212,7 @@ fn handle_command(
             )
         }
 
-        SocketCommand::ListNetwork { sender_agent_id, sender_tile_id, tile_type } => {
+        SocketCommand::ListNetwork { sender_agent_id, sender_tile_id, tile_type, at_ms, segment } => {
             let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
@@ -4961,7 +7226,37 @@ fn handle_command(
                 "network_list",
                 "network_list",
                 Some(&sender),
-                serde_json::json!({ "tile_type": tile_type }),
+                serde_json::json!({ "tile_type": tile_type, "at_ms": at_ms, "segment": segment }),
+            )
+        }
+
+        SocketCommand::NetworkHistory {
+            tile_id,
+            since_ms,
+            until_ms,
+            limit,
+            sender_agent_id,
+            sender_tile_id,
+        } => {
+            let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "network_history",
+                "network_history",
+                Some(&sender),
+                serde_json::json!({
+                    "tile_id": tile_id,
+                    "since_ms": since_ms,
+                    "until_ms": until_ms,
+                    "limit": limit,
+                }),
             )
         }
 
@@ -5005,12 +7300,84 @@ fn handle_command(
             )
         }
 
-        SocketCommand::NetworkSubscribe { tile_id, event, sender_agent_id, sender_tile_id } => {
+        SocketCommand::NetworkPath { from_tile_id, to_tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "network_path",
+                "network_path",
+                Some(&sender),
+                serde_json::json!({ "from_tile_id": from_tile_id, "to_tile_id": to_tile_id }),
+            )
+        }
+
+        SocketCommand::NetworkPortDeclare { tile_id, ports, sender_agent_id, sender_tile_id } => {
+            let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "network_port_declare",
+                "network_port_declare",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id, "ports": ports }),
+            )
+        }
+
+        SocketCommand::NetworkSegmentSet { tile_id, port, segments, sender_agent_id, sender_tile_id } => {
+            let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "network_segment_set",
+                "network_segment_set",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id, "port": port, "segments": segments }),
+            )
+        }
+
+        SocketCommand::NetworkStats { tile_id, sender_agent_id, sender_tile_id } => {
+            let sender = match resolve_sender_context(state, sender_agent_id, sender_tile_id) {
+                Ok(sender) => sender,
+                Err(error) => return SocketResponse::error(error),
+            };
+            let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
+            dispatch_session_message(
+                state,
+                app,
+                channel,
+                &receiver,
+                "network_stats",
+                "network_stats",
+                Some(&sender),
+                serde_json::json!({ "tile_id": tile_id }),
+            )
+        }
+
+        SocketCommand::NetworkSubscribe { tile_id, event, segment, sender_agent_id, sender_tile_id } => {
             let sender = match ensure_worker_for_sender(state, sender_agent_id, sender_tile_id, "network_subscribe") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            match resolve_worker_network_subscription(app, state, &sender, &tile_id, &event)
+            match resolve_worker_network_subscription(app, state, &sender, &tile_id, &event, segment)
                 .and_then(|record| state.add_tile_subscription(record.clone()).map(|_| record))
             {
                 Ok(record) => SocketResponse::success(Some(serde_json::json!(tile_subscription_view(state, &record)))),
@@ -5018,23 +7385,16 @@ fn handle_command(
             }
         }
 
-        SocketCommand::NetworkUnsubscribe { tile_id, event, sender_agent_id, sender_tile_id } => {
+        SocketCommand::NetworkUnsubscribe { tile_id, event, segment, sender_agent_id, sender_tile_id } => {
             let sender = match ensure_worker_for_sender(state, sender_agent_id, sender_tile_id, "network_unsubscribe") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let record = match resolve_worker_network_subscription(app, state, &sender, &tile_id, &event) {
+            let record = match resolve_worker_network_subscription(app, state, &sender, &tile_id, &event, segment) {
                 Ok(record) => record,
                 Err(error) => return SocketResponse::error(error),
             };
-            match state.remove_tile_subscription(
-                &record.session_id,
-                record.scope,
-                &record.subscriber_tile_id,
-                &record.subject_tile_id,
-                record.direction,
-                &record.action,
-            ) {
+            match state.remove_tile_subscription(&record) {
                 Ok(removed) => SocketResponse::success(Some(serde_json::json!({ "removed": removed }))),
                 Err(error) => SocketResponse::error(error),
             }
@@ -5188,7 +7548,7 @@ fn handle_command(
             }
         }
 
-        SocketCommand::TileSubscribe { tile_id, event, agent_id, sender_agent_id, sender_tile_id } => {
+        SocketCommand::TileSubscribe { tile_id, event, agent_id, segment, sender_agent_id, sender_tile_id } => {
             let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_subscribe") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
@@ -5201,6 +7561,7 @@ fn handle_command(
                 &event,
                 &agent_id,
                 TileSubscriptionScope::Tile,
+                segment,
             )
             .and_then(|record| state.add_tile_subscription(record.clone()).map(|_| record))
             {
@@ -5209,7 +7570,7 @@ fn handle_command(
             }
         }
 
-        SocketCommand::TileUnsubscribe { tile_id, event, agent_id, sender_agent_id, sender_tile_id } => {
+        SocketCommand::TileUnsubscribe { tile_id, event, agent_id, segment, sender_agent_id, sender_tile_id } => {
             let sender = match ensure_root_for_sender(state, sender_agent_id, sender_tile_id, "tile_unsubscribe") {
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
@@ -5222,18 +7583,12 @@ fn handle_command(
                 &event,
                 &agent_id,
                 TileSubscriptionScope::Tile,
+                segment,
             ) {
                 Ok(record) => record,
                 Err(error) => return SocketResponse::error(error),
             };
-            match state.remove_tile_subscription(
-                &record.session_id,
-                record.scope,
-                &record.subscriber_tile_id,
-                &record.subject_tile_id,
-                record.direction,
-                &record.action,
-            ) {
+            match state.remove_tile_subscription(&record) {
                 Ok(removed) => SocketResponse::success(Some(serde_json::json!({ "removed": removed }))),
                 Err(error) => SocketResponse::error(error),
             }
@@ -5479,6 +7834,7 @@ fn handle_command(
 
         SocketCommand::MessageNetwork {
             message,
+            segment,
             sender_agent_id,
             sender_tile_id,
         } => {
@@ -5486,7 +7842,7 @@ fn handle_command(
                 Ok(sender) => sender,
                 Err(error) => return SocketResponse::error(error),
             };
-            let args = serde_json::json!({ "message": message });
+            let args = serde_json::json!({ "message": message, "segment": segment });
             let receiver = SessionMessageReceiver::new(sender.session_id.clone(), Some(sender.clone()));
             dispatch_session_message(
                 state,
@@ -5835,4 +8191,75 @@ mod tests {
         let receiver = SessionMessageReceiver::new("$1", None);
         assert!(receiver.responds_to().contains(&"tile_arrange_elk"));
     }
+
+    #[test]
+    fn declared_extension_events_are_subscribable_on_browser_tiles() {
+        use crate::network::{BrowserTileDetails, NetworkTileKind, SessionTileInfo, TileDetails, TileRpcAccess};
+
+        let mut tile = SessionTileInfo::placeholder("MnOpQr", "$1");
+        tile.kind = NetworkTileKind::Browser;
+        tile.details = TileDetails::Browser(BrowserTileDetails {
+            window_name: "Browser".to_string(),
+            window_index: 0,
+            pane_index: 0,
+            cols: 80,
+            rows: 24,
+            active: false,
+            dead: false,
+            current_url: None,
+            extension: Some(
+                serde_json::from_value(serde_json::json!({
+                    "extension_id": "checkers",
+                    "label": "Checkers",
+                    "events": [{ "name": "turn_started" }],
+                }))
+                .unwrap(),
+            ),
+        });
+
+        let actions = super::subscribable_actions_for_tile(&tile, TileRpcAccess::Read);
+        assert!(actions.iter().any(|action| action == "turn_started"));
+        assert!(super::parse_validated_subscription_event(&tile, TileRpcAccess::Read, "in:turn_started").is_ok());
+        assert!(super::parse_validated_subscription_event(&tile, TileRpcAccess::Read, "in:game_over").is_err());
+
+        assert!(super::is_browser_extension_event_action("turn_started"));
+        assert!(!super::is_browser_extension_event_action("navigate"));
+        assert!(!super::is_browser_extension_event_action("console"));
+        assert!(!super::is_browser_extension_event_action("extension_call"));
+    }
+
+    #[test]
+    fn only_root_may_create_tiles_with_env_or_a_launch_policy() {
+        use crate::agent::AgentRole;
+
+        let sender = |role: AgentRole| super::SenderContext {
+            session_id: "$1".to_string(),
+            sender_agent_id: Some("agent-1".to_string()),
+            display_name: "Agent 1".to_string(),
+            sender_agent_role: Some(role),
+            sender_tile_id: Some("tile-1".to_string()),
+            sender_window_id: None,
+        };
+        let args = |payload: serde_json::Value| -> super::SessionTileCreateMessageArgs {
+            serde_json::from_value(payload).unwrap()
+        };
+        let plain = args(serde_json::json!({ "tile_type": "shell" }));
+        let with_env = args(serde_json::json!({ "tile_type": "shell", "env": ["API_KEY"] }));
+        let with_policy = args(serde_json::json!({ "tile_type": "agent", "launch_policy": {} }));
+
+        let worker = sender(AgentRole::Worker);
+        assert!(super::ensure_tile_create_sender(&worker, &plain).is_ok());
+        assert_eq!(
+            super::ensure_tile_create_sender(&worker, &with_env).unwrap_err(),
+            "non-root agents may not call tile_create with env; send a message to Root instead"
+        );
+        assert_eq!(
+            super::ensure_tile_create_sender(&worker, &with_policy).unwrap_err(),
+            "non-root agents may not call tile_create with launch_policy; send a message to Root instead"
+        );
+
+        let root = sender(AgentRole::Root);
+        assert!(super::ensure_tile_create_sender(&root, &with_env).is_ok());
+        assert!(super::ensure_tile_create_sender(&root, &with_policy).is_ok());
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
@@ -21,19 +21,27 @@ use crate::agent::{
     AgentType,
     ChannelInfo,
     ChatterEntry,
-    TileSubscriptionDirection,
     TileSubscriptionRecord,
-    TileSubscriptionScope,
     TileSignalLed,
     TileSignalState,
     TILE_SIGNAL_LED_COUNT,
 };
+use crate::browser_capture::BrowserCaptureStore;
+use crate::browser_console::BrowserConsoleLog;
+use crate::browser_recording::BrowserRecording;
 use crate::db::{self, PersistedChannelRecord};
+use crate::launch_policy;
 use crate::network;
+use crate::network_traffic::{self, NetworkTrafficStats, TrafficSample, TrafficScope, TrafficStat, TrafficStatMap};
 use crate::persist::{self, HerdState, TileState};
-use crate::tile_registry::{self, TileRecord};
+use crate::process_info::{self, ResourceSample};
+use crate::recording;
+use crate::session_env;
+use crate::terminal_screen;
+use crate::tile_registry::{self, TileRecord, TileRecordKind};
 use crate::tile_message::TileMessageLogEntry;
 use crate::tmux_control::{TmuxControl, TmuxWriter, OutputBuffers};
+use crate::worktree::AgentWorktree;
 
 type PendingTestDriverRequests = HashMap<String, Sender<Result<Value, String>>>;
 type AgentSubscribers = HashMap<u64, UnboundedSender<AgentStreamEnvelope>>;
@@ -72,6 +80,7 @@ struct AgentRecord {
     last_ping_ack_at: Option<Instant>,
     ping_deadline: Option<Instant>,
     agent_pid: Option<u32>,
+    worktree: Option<AgentWorktree>,
     subscribers: AgentSubscribers,
 }
 
@@ -91,6 +100,7 @@ impl AgentRecord {
             chatter_subscribed: self.chatter_subscribed,
             channels: self.channels.iter().cloned().collect(),
             agent_pid: self.agent_pid,
+            worktree: self.worktree.clone(),
         }
     }
 }
@@ -132,12 +142,17 @@ pub struct AppState {
     pub test_driver_request_counter: Arc<AtomicU64>,
     pub claude_command_cache: Arc<Mutex<HashMap<String, crate::commands::ClaudeMenuData>>>,
     browser_page_zoom_by_pane: Arc<Mutex<HashMap<String, f64>>>,
+    browser_console_by_pane: Arc<Mutex<HashMap<String, BrowserConsoleLog>>>,
+    browser_captures_by_pane: Arc<Mutex<HashMap<String, BrowserCaptureStore>>>,
+    browser_recordings_by_pane: Arc<Mutex<HashMap<String, BrowserRecording>>>,
     agent_records: Arc<Mutex<HashMap<String, AgentRecord>>>,
     channel_records: Arc<Mutex<HashMap<String, ChannelRecord>>>,
     tile_subscription_records: Arc<Mutex<HashMap<String, TileSubscriptionRecord>>>,
     chatter_entries: Arc<Mutex<Vec<ChatterEntry>>>,
     agent_log_entries: Arc<Mutex<Vec<AgentLogEntry>>>,
     tile_message_log_entries: Arc<Mutex<Vec<TileMessageLogEntry>>>,
+    network_traffic_stats: Arc<Mutex<TrafficStatMap>>,
+    resource_samples: Arc<Mutex<HashMap<String, ResourceSample>>>,
     agent_display_frames: Arc<Mutex<HashMap<String, AgentDisplayFrame>>>,
     tile_signal_states: Arc<Mutex<HashMap<String, TileSignalState>>>,
     tile_signal_program_generations: Arc<Mutex<HashMap<String, u64>>>,
@@ -170,6 +185,9 @@ impl AppState {
         let chatter_entries = persist::load_chatter_entries();
         let agent_log_entries = persist::load_agent_log_entries();
         let tile_message_log_entries = persist::load_tile_message_log_entries();
+        let network_traffic_stats =
+            network_traffic::load_traffic_stats_at(std::path::Path::new(crate::runtime::database_path()))
+                .unwrap_or_default();
         let agent_display_counter = persisted_agents
             .iter()
             .filter_map(|agent| parse_agent_display_index(&agent.display_name))
@@ -191,6 +209,9 @@ impl AppState {
             test_driver_request_counter: Arc::new(AtomicU64::new(0)),
             claude_command_cache: Arc::new(Mutex::new(HashMap::new())),
             browser_page_zoom_by_pane: Arc::new(Mutex::new(HashMap::new())),
+            browser_console_by_pane: Arc::new(Mutex::new(HashMap::new())),
+            browser_captures_by_pane: Arc::new(Mutex::new(HashMap::new())),
+            browser_recordings_by_pane: Arc::new(Mutex::new(HashMap::new())),
             agent_records: Arc::new(Mutex::new(build_agent_record_map(persisted_agents))),
             channel_records: Arc::new(Mutex::new(build_channel_record_map(persisted_channels))),
             tile_subscription_records: Arc::new(Mutex::new(build_tile_subscription_record_map(
@@ -199,6 +220,8 @@ impl AppState {
             chatter_entries: Arc::new(Mutex::new(chatter_entries)),
             agent_log_entries: Arc::new(Mutex::new(agent_log_entries)),
             tile_message_log_entries: Arc::new(Mutex::new(tile_message_log_entries)),
+            network_traffic_stats: Arc::new(Mutex::new(network_traffic_stats)),
+            resource_samples: Arc::new(Mutex::new(HashMap::new())),
             agent_display_frames: Arc::new(Mutex::new(HashMap::new())),
             tile_signal_states: Arc::new(Mutex::new(HashMap::new())),
             tile_signal_program_generations: Arc::new(Mutex::new(HashMap::new())),
@@ -254,17 +277,12 @@ impl AppState {
         self.shutting_down.load(Ordering::SeqCst)
     }
 
-    pub fn read_output(&self, session_id: &str) -> Result<String, String> {
+    /// Offset just past the newest buffered byte for a pane, or 0 before any output.
+    pub fn output_end_offset(&self, session_id: &str) -> Result<u64, String> {
         let guard = self.output_buffers.lock().map_err(|e| e.to_string())?;
         let bufs_arc = guard.as_ref().ok_or("output buffers not initialized")?;
-        let mut bufs = bufs_arc.lock().map_err(|e| e.to_string())?;
-        match bufs.get_mut(session_id) {
-            Some(b) => {
-                let bytes: Vec<u8> = b.drain(..).collect();
-                Ok(String::from_utf8_lossy(&bytes).to_string())
-            }
-            None => Ok(String::new()),
-        }
+        let bufs = bufs_arc.lock().map_err(|e| e.to_string())?;
+        Ok(bufs.get(session_id).map(|buffer| buffer.end_offset()).unwrap_or(0))
     }
 
     pub fn with_control<F, R>(&self, f: F) -> Result<R, String>
@@ -316,6 +334,70 @@ impl AppState {
         }
     }
 
+    pub fn with_browser_console<R>(
+        &self,
+        pane_id: &str,
+        f: impl FnOnce(&mut BrowserConsoleLog) -> R,
+    ) -> Result<R, String> {
+        let mut logs = self.browser_console_by_pane.lock().map_err(|e| e.to_string())?;
+        Ok(f(logs.entry(pane_id.to_string()).or_default()))
+    }
+
+    pub fn remove_browser_console(&self, pane_id: &str) {
+        if let Ok(mut logs) = self.browser_console_by_pane.lock() {
+            logs.remove(pane_id);
+        }
+    }
+
+    pub fn with_browser_captures<R>(
+        &self,
+        pane_id: &str,
+        f: impl FnOnce(&mut BrowserCaptureStore) -> R,
+    ) -> Result<R, String> {
+        let mut stores = self.browser_captures_by_pane.lock().map_err(|e| e.to_string())?;
+        Ok(f(stores.entry(pane_id.to_string()).or_default()))
+    }
+
+    pub fn remove_browser_captures(&self, pane_id: &str) {
+        if let Ok(mut stores) = self.browser_captures_by_pane.lock() {
+            stores.remove(pane_id);
+        }
+    }
+
+    /// Registers the pane's active recording. Fails if the pane is already recording.
+    pub fn insert_browser_recording(&self, pane_id: &str, recording: BrowserRecording) -> Result<(), String> {
+        let mut recordings = self.browser_recordings_by_pane.lock().map_err(|e| e.to_string())?;
+        if let Some(active) = recordings.get(pane_id) {
+            return Err(format!(
+                "browser pane {pane_id} is already recording ({})",
+                active.recording_id()
+            ));
+        }
+        recordings.insert(pane_id.to_string(), recording);
+        Ok(())
+    }
+
+    /// Runs `f` against the pane's recording if `recording_id` is still the active one.
+    pub fn with_browser_recording<R>(
+        &self,
+        pane_id: &str,
+        recording_id: &str,
+        f: impl FnOnce(&mut BrowserRecording) -> R,
+    ) -> Option<R> {
+        let mut recordings = self.browser_recordings_by_pane.lock().ok()?;
+        recordings
+            .get_mut(pane_id)
+            .filter(|recording| recording.recording_id() == recording_id)
+            .map(f)
+    }
+
+    pub fn remove_browser_recording(&self, pane_id: &str) -> Option<BrowserRecording> {
+        self.browser_recordings_by_pane
+            .lock()
+            .ok()
+            .and_then(|mut recordings| recordings.remove(pane_id))
+    }
+
     pub fn start_tile_signal_program(&self, tile_id: &str) -> u64 {
         let generation = self.tile_signal_counter.fetch_add(1, Ordering::SeqCst) + 1;
         if let Ok(mut generations) = self.tile_signal_program_generations.lock() {
@@ -582,6 +664,9 @@ impl AppState {
         agent_pid: Option<u32>,
     ) -> Result<AgentInfo, String> {
         let now_ms = crate::agent::now_ms();
+        let worktree = crate::worktree::get_worktree_at(std::path::Path::new(crate::runtime::database_path()), &agent_id)
+            .ok()
+            .flatten();
         let mut agents = self.agent_records.lock().map_err(|e| e.to_string())?;
         let record = agents.entry(agent_id.clone()).or_insert_with(|| AgentRecord {
             agent_id: agent_id.clone(),
@@ -607,6 +692,7 @@ impl AppState {
             last_ping_ack_at: None,
             ping_deadline: None,
             agent_pid,
+            worktree: None,
             subscribers: HashMap::new(),
         });
         record.tile_id = tile_id;
@@ -623,6 +709,9 @@ impl AppState {
         if agent_pid.is_some() {
             record.agent_pid = agent_pid;
         }
+        if worktree.is_some() {
+            record.worktree = worktree;
+        }
         let info = record.to_info();
         drop(agents);
         self.persist_agent_and_channel_state()?;
@@ -739,9 +828,20 @@ impl AppState {
 
     pub fn replace_tile_records(&self, records: Vec<TileRecord>) -> Result<(), String> {
         let mut tiles = self.tile_records.lock().map_err(|e| e.to_string())?;
-        *tiles = build_tile_record_map(records);
+        let previous_tiles = std::mem::replace(&mut *tiles, build_tile_record_map(records));
         let valid_tile_ids = tiles.keys().cloned().collect::<BTreeSet<_>>();
+        let created_tiles = tiles
+            .values()
+            .filter(|record| !previous_tiles.contains_key(&record.tile_id))
+            .cloned()
+            .collect::<Vec<_>>();
         drop(tiles);
+        let destroyed_tiles = previous_tiles
+            .into_values()
+            .filter(|record| !valid_tile_ids.contains(&record.tile_id))
+            .collect::<Vec<_>>();
+        self.forget_destroyed_tiles(&destroyed_tiles);
+        self.record_created_tiles(&created_tiles);
         if let Ok(mut signals) = self.tile_signal_states.lock() {
             signals.retain(|tile_id, _| valid_tile_ids.contains(tile_id));
         }
@@ -758,11 +858,86 @@ impl AppState {
         self.persist_tile_registry_state()
     }
 
+    /// Drops per-tile bookkeeping that outlives the tile itself: traffic
+    /// rollups in memory and SQLite, resource samples and thresholds,
+    /// terminal recordings, and the pane's screen model.
+    fn forget_destroyed_tiles(&self, destroyed: &[TileRecord]) {
+        if destroyed.is_empty() {
+            return;
+        }
+        let tile_ids = destroyed
+            .iter()
+            .map(|record| record.tile_id.clone())
+            .collect::<HashSet<_>>();
+        let db_path = std::path::Path::new(crate::runtime::database_path());
+        if let Ok(mut stats) = self.network_traffic_stats.lock() {
+            let removed = network_traffic::remove_tiles(&mut stats, &tile_ids);
+            if let Err(error) = network_traffic::delete_traffic_stats_at(db_path, &removed) {
+                log::warn!("Failed to delete traffic stats for destroyed tiles: {error}");
+            }
+        }
+        if let Ok(mut samples) = self.resource_samples.lock() {
+            samples.retain(|tile_id, _| !tile_ids.contains(tile_id));
+        }
+        if let Err(error) = process_info::delete_tile_thresholds_at(db_path, &tile_ids) {
+            log::warn!("Failed to delete resource thresholds for destroyed tiles: {error}");
+        }
+        if let Err(error) = launch_policy::delete_tile_policies_at(db_path, &tile_ids) {
+            log::warn!("Failed to delete launch policies for destroyed tiles: {error}");
+        }
+        if let Err(error) = session_env::delete_tile_env_at(db_path, &tile_ids) {
+            log::warn!("Failed to delete env names for destroyed tiles: {error}");
+        }
+        let Ok((recordings, screens)) = self.with_control(|ctrl| Ok((ctrl.recordings.clone(), ctrl.screens.clone())))
+        else {
+            return;
+        };
+        if let Err(error) = recording::stop_tile_recordings(&recordings, db_path, &tile_ids) {
+            log::warn!("Failed to stop recordings for destroyed tiles: {error}");
+        }
+        terminal_screen::forget_panes(&screens, destroyed.iter().map(|record| record.pane_id.as_str()));
+    }
+
+    /// Starts recording new shell and agent tiles in sessions that are recorded as a whole.
+    fn record_created_tiles(&self, records: &[TileRecord]) {
+        let terminals = records
+            .iter()
+            .filter(|record| matches!(record.kind, TileRecordKind::Shell | TileRecordKind::Agent))
+            .collect::<Vec<_>>();
+        if terminals.is_empty() {
+            return;
+        }
+        let Ok(recordings) = self.with_control(|ctrl| Ok(ctrl.recordings.clone())) else {
+            return;
+        };
+        let db_path = std::path::Path::new(crate::runtime::database_path());
+        for record in terminals {
+            if !recording::is_session_recording(&recordings, &record.session_id)
+                || recording::active_recording(&recordings, &record.pane_id).is_some()
+            {
+                continue;
+            }
+            if let Err(error) = recording::start_recording(
+                &recordings,
+                db_path,
+                &record.session_id,
+                &record.tile_id,
+                &record.pane_id,
+                &record.tile_id,
+            ) {
+                log::warn!("Failed to start recording for new tile {}: {error}", record.tile_id);
+            }
+        }
+    }
+
     pub fn upsert_tile_record(&self, record: TileRecord) -> Result<TileRecord, String> {
         let mut tiles = self.tile_records.lock().map_err(|e| e.to_string())?;
-        tiles.insert(record.tile_id.clone(), record.clone());
+        let created = tiles.insert(record.tile_id.clone(), record.clone()).is_none();
         drop(tiles);
         self.persist_tile_registry_state()?;
+        if created {
+            self.record_created_tiles(std::slice::from_ref(&record));
+        }
         Ok(record)
     }
 
@@ -770,7 +945,8 @@ impl AppState {
         let mut tiles = self.tile_records.lock().map_err(|e| e.to_string())?;
         let removed = tiles.remove(tile_id);
         drop(tiles);
-        if removed.is_some() {
+        if let Some(removed_record) = removed.as_ref() {
+            self.forget_destroyed_tiles(std::slice::from_ref(removed_record));
             self.remove_tile_signal_state(tile_id);
             if let Ok(mut subscriptions) = self.tile_subscription_records.lock() {
                 subscriptions.retain(|_, record| {
@@ -987,6 +1163,7 @@ impl AppState {
                 .then_with(|| left.scope.cmp(&right.scope))
                 .then_with(|| left.direction.cmp(&right.direction))
                 .then_with(|| left.action.cmp(&right.action))
+                .then_with(|| left.segment.cmp(&right.segment))
         });
         Ok(list)
     }
@@ -1004,34 +1181,33 @@ impl AppState {
         Ok(record)
     }
 
-    pub fn remove_tile_subscription(
-        &self,
-        session_id: &str,
-        scope: TileSubscriptionScope,
-        subscriber_tile_id: &str,
-        subject_tile_id: &str,
-        direction: TileSubscriptionDirection,
-        action: &str,
-    ) -> Result<bool, String> {
-        let removed = self
-            .tile_subscription_records
-            .lock()
-            .map_err(|e| e.to_string())?
-            .remove(&tile_subscription_key_parts(
-                session_id,
-                scope,
-                subscriber_tile_id,
-                subject_tile_id,
-                direction,
-                action,
-            ))
-            .is_some();
+    /// Removes the subscription with the given `segment`, or every segment
+    /// variant of it when `segment` is `None`.
+    pub fn remove_tile_subscription(&self, record: &TileSubscriptionRecord) -> Result<bool, String> {
+        let removed = remove_tile_subscription_records(
+            &mut self.tile_subscription_records.lock().map_err(|e| e.to_string())?,
+            record,
+        );
         if removed {
             self.persist_agent_and_channel_state()?;
         }
         Ok(removed)
     }
 
+    pub fn list_tile_subscriptions(&self) -> Result<Vec<TileSubscriptionRecord>, String> {
+        let records = self.tile_subscription_records.lock().map_err(|e| e.to_string())?;
+        Ok(records.values().cloned().collect())
+    }
+
+    pub fn list_tile_subscriptions_for_action(&self, action: &str) -> Result<Vec<TileSubscriptionRecord>, String> {
+        let records = self.tile_subscription_records.lock().map_err(|e| e.to_string())?;
+        Ok(records
+            .values()
+            .filter(|record| record.action == action)
+            .cloned()
+            .collect())
+    }
+
     pub fn clear_tile_subscriptions_in_session(&self, session_id: &str) -> Result<(), String> {
         let mut records = self.tile_subscription_records.lock().map_err(|e| e.to_string())?;
         let before = records.len();
@@ -1130,6 +1306,42 @@ impl AppState {
         Ok(())
     }
 
+    pub fn record_network_traffic(
+        &self,
+        conn: &rusqlite::Connection,
+        sample: &TrafficSample,
+        connection: Option<&network::NetworkConnection>,
+    ) -> Result<(), String> {
+        // The upsert stays under the lock so concurrent samples reach SQLite
+        // in the order they were applied, and cannot revive rows that
+        // `forget_destroyed_tiles` deleted in between.
+        let mut stats = self.network_traffic_stats.lock().map_err(|e| e.to_string())?;
+        let touched = network_traffic::apply_sample(&mut stats, sample, connection);
+        network_traffic::upsert_traffic_stats_with_conn(conn, &touched)
+    }
+
+    /// Latest background resource samples, keyed by tile id.
+    pub fn resource_samples(&self) -> Result<HashMap<String, ResourceSample>, String> {
+        Ok(self.resource_samples.lock().map_err(|e| e.to_string())?.clone())
+    }
+
+    pub fn replace_resource_samples(&self, samples: HashMap<String, ResourceSample>) -> Result<(), String> {
+        *self.resource_samples.lock().map_err(|e| e.to_string())? = samples;
+        Ok(())
+    }
+
+    pub fn network_traffic_stats_in_session(&self, session_id: &str) -> Result<NetworkTrafficStats, String> {
+        let stats = self.network_traffic_stats.lock().map_err(|e| e.to_string())?;
+        Ok(network_traffic::stats_in_session(&stats, session_id))
+    }
+
+    pub fn tile_traffic_stat(&self, session_id: &str, tile_id: &str) -> Result<Option<TrafficStat>, String> {
+        let stats = self.network_traffic_stats.lock().map_err(|e| e.to_string())?;
+        Ok(stats
+            .get(&network_traffic::stat_key(session_id, TrafficScope::Tile, tile_id))
+            .cloned())
+    }
+
     pub fn clear_debug_logs(&self) -> Result<(), String> {
         persist::clear_log_entries()?;
         self.chatter_entries
@@ -1493,6 +1705,7 @@ fn build_agent_record_map(agents: Vec<AgentInfo>) -> HashMap<String, AgentRecord
                 last_ping_ack_at: None,
                 ping_deadline: None,
                 agent_pid: agent.agent_pid,
+                worktree: agent.worktree.clone(),
                 subscribers: HashMap::new(),
             };
             (record.agent_id.clone(), record)
@@ -1549,27 +1762,33 @@ fn channel_key(session_id: &str, channel_name: &str) -> String {
 }
 
 fn tile_subscription_key(record: &TileSubscriptionRecord) -> String {
-    tile_subscription_key_parts(
-        &record.session_id,
+    format!(
+        "{}::{:?}::{}::{}::{:?}::{}::{}",
+        record.session_id,
         record.scope,
-        &record.subscriber_tile_id,
-        &record.subject_tile_id,
+        record.subscriber_tile_id,
+        record.subject_tile_id,
         record.direction,
-        &record.action,
+        record.action,
+        record.segment.as_deref().unwrap_or_default(),
     )
 }
 
-fn tile_subscription_key_parts(
-    session_id: &str,
-    scope: TileSubscriptionScope,
-    subscriber_tile_id: &str,
-    subject_tile_id: &str,
-    direction: TileSubscriptionDirection,
-    action: &str,
-) -> String {
-    format!(
-        "{session_id}::{scope:?}::{subscriber_tile_id}::{subject_tile_id}::{direction:?}::{action}"
-    )
+fn remove_tile_subscription_records(
+    records: &mut HashMap<String, TileSubscriptionRecord>,
+    target: &TileSubscriptionRecord,
+) -> bool {
+    let before = records.len();
+    records.retain(|_, record| {
+        !(record.session_id == target.session_id
+            && record.scope == target.scope
+            && record.subscriber_tile_id == target.subscriber_tile_id
+            && record.subject_tile_id == target.subject_tile_id
+            && record.direction == target.direction
+            && record.action == target.action
+            && (target.segment.is_none() || record.segment == target.segment))
+    });
+    records.len() != before
 }
 
 fn parse_agent_display_index(display_name: &str) -> Option<u64> {
@@ -1598,8 +1817,12 @@ fn resolve_root_parent_from_map(
 
 #[cfg(test)]
 mod tests {
-    use super::{preferred_agent_record, AgentRecord};
-    use crate::agent::{AgentRole, AgentType};
+    use super::{
+        build_tile_subscription_record_map, preferred_agent_record, remove_tile_subscription_records, AgentRecord,
+    };
+    use crate::agent::{
+        AgentRole, AgentType, TileSubscriptionDirection, TileSubscriptionRecord, TileSubscriptionScope,
+    };
     use std::collections::{BTreeSet, HashMap};
 
     fn record(agent_id: &str, tile_id: &str, alive: bool, last_seen_ts_ms: i64) -> AgentRecord {
@@ -1623,6 +1846,7 @@ mod tests {
             last_ping_ack_at: None,
             ping_deadline: None,
             agent_pid: None,
+            worktree: None,
             subscribers: HashMap::new(),
         }
     }
@@ -1642,4 +1866,29 @@ mod tests {
         let selected = preferred_agent_record([&older, &newer]).unwrap();
         assert_eq!(selected.agent_id, "agent-newer");
     }
+
+    #[test]
+    fn keeps_tile_subscriptions_that_differ_only_by_segment() {
+        let subscription = |segment: Option<&str>| TileSubscriptionRecord {
+            session_id: "$1".to_string(),
+            scope: TileSubscriptionScope::Network,
+            subscriber_tile_id: "%worker".to_string(),
+            subject_tile_id: "%browser".to_string(),
+            direction: TileSubscriptionDirection::In,
+            action: "console".to_string(),
+            segment: segment.map(str::to_string),
+        };
+        let mut records = build_tile_subscription_record_map(vec![
+            subscription(Some("team-a")),
+            subscription(Some("team-b")),
+            subscription(None),
+        ]);
+        assert_eq!(records.len(), 3);
+
+        assert!(remove_tile_subscription_records(&mut records, &subscription(Some("team-a"))));
+        assert_eq!(records.len(), 2);
+        assert!(!remove_tile_subscription_records(&mut records, &subscription(Some("team-a"))));
+        assert!(remove_tile_subscription_records(&mut records, &subscription(None)));
+        assert!(records.is_empty());
+    }
 }
@@ -0,0 +1,962 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use image::{ImageFormat, Rgb, RgbImage};
+use serde::Serialize;
+
+use crate::tmux;
+use crate::tmux_control::TmuxWriter;
+
+/// Per-pane terminal state models, fed from the decoded `%output` stream.
+pub type TerminalScreens = Arc<Mutex<HashMap<String, vt100::Parser>>>;
+
+const CELL_WIDTH: u32 = 6;
+const CELL_HEIGHT: u32 = 10;
+const PIXEL_SCALE: u32 = 2;
+const GLYPH_TOP: u32 = 1;
+
+const PANE_METRICS_FORMAT: &str = "#{pane_height} #{pane_width} #{cursor_y} #{cursor_x} #{cursor_flag} #{alternate_on} #{alternate_saved_y} #{alternate_saved_x}";
+const SEED_MARKER: &str = "herd-screen-seed";
+const SEED_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalScreenshotFormat {
+    Text,
+    Ansi,
+    Png,
+}
+
+impl TerminalScreenshotFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Ansi => "ansi",
+            Self::Png => "png",
+        }
+    }
+}
+
+pub fn parse_screenshot_format(raw: Option<&str>) -> Result<TerminalScreenshotFormat, String> {
+    match raw.map(str::trim).filter(|value| !value.is_empty()) {
+        None | Some("text") => Ok(TerminalScreenshotFormat::Text),
+        Some("ansi") => Ok(TerminalScreenshotFormat::Ansi),
+        Some("png" | "image") => Ok(TerminalScreenshotFormat::Png),
+        Some(other) => Err(format!(
+            "screenshot requires `format` to be one of `text`, `ansi`, or `png`, got `{other}`"
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellColor {
+    Default,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl From<vt100::Color> for CellColor {
+    fn from(color: vt100::Color) -> Self {
+        match color {
+            vt100::Color::Default => Self::Default,
+            vt100::Color::Idx(index) => Self::Indexed(index),
+            vt100::Color::Rgb(red, green, blue) => Self::Rgb(red, green, blue),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenCell {
+    pub text: String,
+    pub fg: CellColor,
+    pub bg: CellColor,
+    pub bold: bool,
+    pub underline: bool,
+    pub inverse: bool,
+    pub wide: bool,
+    pub wide_continuation: bool,
+}
+
+impl Default for ScreenCell {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            fg: CellColor::Default,
+            bg: CellColor::Default,
+            bold: false,
+            underline: false,
+            inverse: false,
+            wide: false,
+            wide_continuation: false,
+        }
+    }
+}
+
+impl ScreenCell {
+    fn has_default_style(&self) -> bool {
+        self.fg == CellColor::Default && self.bg == CellColor::Default && !self.bold && !self.underline && !self.inverse
+    }
+
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty() && self.has_default_style()
+    }
+}
+
+/// The visible grid of a terminal pane, independent of the emulator that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenSnapshot {
+    pub rows: u16,
+    pub columns: u16,
+    pub cursor_row: u16,
+    pub cursor_column: u16,
+    pub cursor_visible: bool,
+    pub alternate_screen: bool,
+    pub cells: Vec<ScreenCell>,
+}
+
+impl ScreenSnapshot {
+    pub fn from_screen(screen: &vt100::Screen) -> Self {
+        let (rows, columns) = screen.size();
+        let (cursor_row, cursor_column) = screen.cursor_position();
+        let mut cells = Vec::with_capacity(rows as usize * columns as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                cells.push(
+                    screen
+                        .cell(row, column)
+                        .map(|cell| ScreenCell {
+                            text: cell.contents().to_string(),
+                            fg: cell.fgcolor().into(),
+                            bg: cell.bgcolor().into(),
+                            bold: cell.bold(),
+                            underline: cell.underline(),
+                            inverse: cell.inverse(),
+                            wide: cell.is_wide(),
+                            wide_continuation: cell.is_wide_continuation(),
+                        })
+                        .unwrap_or_default(),
+                );
+            }
+        }
+        Self {
+            rows,
+            columns,
+            cursor_row,
+            cursor_column,
+            cursor_visible: !screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+            cells,
+        }
+    }
+
+    fn row(&self, row: u16) -> &[ScreenCell] {
+        let start = row as usize * self.columns as usize;
+        &self.cells[start..start + self.columns as usize]
+    }
+
+    pub fn to_text(&self) -> String {
+        (0..self.rows)
+            .map(|row| {
+                let mut line = String::new();
+                for cell in self.row(row).iter().filter(|cell| !cell.wide_continuation) {
+                    line.push_str(if cell.text.is_empty() { " " } else { &cell.text });
+                }
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn to_ansi(&self) -> String {
+        (0..self.rows)
+            .map(|row| {
+                let cells = self.row(row);
+                let visible = cells.len() - cells.iter().rev().take_while(|cell| cell.is_blank()).count();
+                let mut line = String::new();
+                let mut current: Option<String> = None;
+                for cell in cells[..visible].iter().filter(|cell| !cell.wide_continuation) {
+                    let sgr = sgr_for_cell(cell);
+                    if current.as_deref() != Some(sgr.as_str()) {
+                        line.push_str(&sgr);
+                        current = Some(sgr);
+                    }
+                    line.push_str(if cell.text.is_empty() { " " } else { &cell.text });
+                }
+                if current.is_some() {
+                    line.push_str("\x1b[0m");
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the grid with a built-in 5x7 bitmap font using the terminal tile palette.
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let cell_width = CELL_WIDTH * PIXEL_SCALE;
+        let cell_height = CELL_HEIGHT * PIXEL_SCALE;
+        let mut image = RgbImage::from_pixel(
+            u32::from(self.columns.max(1)) * cell_width,
+            u32::from(self.rows.max(1)) * cell_height,
+            Rgb(DEFAULT_BACKGROUND),
+        );
+        for row in 0..self.rows {
+            for (column, cell) in self.row(row).iter().enumerate() {
+                if cell.wide_continuation {
+                    continue;
+                }
+                let cursor = self.cursor_visible && row == self.cursor_row && column as u16 == self.cursor_column;
+                let (mut fg, mut bg) = cell_colors(cell);
+                if cursor {
+                    std::mem::swap(&mut fg, &mut bg);
+                }
+                let span = if cell.wide { 2 } else { 1 };
+                let origin_x = column as u32 * cell_width;
+                let origin_y = u32::from(row) * cell_height;
+                fill_rect(&mut image, origin_x, origin_y, cell_width * span, cell_height, bg);
+                draw_cell_text(&mut image, origin_x, origin_y, span, &cell.text, fg);
+                if cell.underline {
+                    fill_rect(
+                        &mut image,
+                        origin_x,
+                        origin_y + (CELL_HEIGHT - 1) * PIXEL_SCALE,
+                        cell_width * span,
+                        PIXEL_SCALE,
+                        fg,
+                    );
+                }
+            }
+        }
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .map_err(|error| format!("failed to encode terminal screenshot PNG: {error}"))?;
+        Ok(bytes)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TerminalCursor {
+    pub row: u16,
+    pub column: u16,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TerminalScreenshot {
+    pub format: &'static str,
+    pub rows: u16,
+    pub columns: u16,
+    pub cursor: TerminalCursor,
+    pub alternate_screen: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+}
+
+pub fn render_screenshot(
+    snapshot: &ScreenSnapshot,
+    format: TerminalScreenshotFormat,
+) -> Result<TerminalScreenshot, String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+
+    let mut screenshot = TerminalScreenshot {
+        format: format.as_str(),
+        rows: snapshot.rows,
+        columns: snapshot.columns,
+        cursor: TerminalCursor {
+            row: snapshot.cursor_row,
+            column: snapshot.cursor_column,
+            visible: snapshot.cursor_visible,
+        },
+        alternate_screen: snapshot.alternate_screen,
+        text: None,
+        mime_type: None,
+        data_base64: None,
+    };
+    match format {
+        TerminalScreenshotFormat::Text => screenshot.text = Some(snapshot.to_text()),
+        TerminalScreenshotFormat::Ansi => screenshot.text = Some(snapshot.to_ansi()),
+        TerminalScreenshotFormat::Png => {
+            screenshot.mime_type = Some("image/png".to_string());
+            screenshot.data_base64 = Some(STANDARD.encode(snapshot.to_png()?));
+        }
+    }
+    Ok(screenshot)
+}
+
+pub fn new_terminal_screens() -> TerminalScreens {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Feeds pane output into its model. Panes are only tracked once a screenshot seeded them,
+/// so the model always starts from tmux's own view of the pane.
+pub fn feed(screens: &TerminalScreens, pane_id: &str, bytes: &[u8]) {
+    if let Ok(mut screens) = screens.lock() {
+        if let Some(parser) = screens.get_mut(pane_id) {
+            parser.process(bytes);
+        }
+    }
+}
+
+/// Drops the models of panes that no longer exist.
+pub fn forget_panes<'a>(screens: &TerminalScreens, pane_ids: impl IntoIterator<Item = &'a str>) {
+    if let Ok(mut screens) = screens.lock() {
+        for pane_id in pane_ids {
+            screens.remove(pane_id);
+        }
+    }
+}
+
+/// Snapshot of a pane's visible screen. The model is (re)seeded when the pane is first seen
+/// or its size changed, then follows the live output stream. Seeding goes through the control
+/// connection so the capture lands in the `%output` stream exactly where it was taken: output
+/// queued before it is already on the captured screen and is not applied again.
+pub fn snapshot_pane(screens: &TerminalScreens, writer: &TmuxWriter, pane_id: &str) -> Result<ScreenSnapshot, String> {
+    let metrics = pane_metrics(pane_id)?;
+    let in_sync = screens
+        .lock()
+        .map_err(|error| error.to_string())?
+        .get(pane_id)
+        .is_some_and(|parser| parser.screen().size() == (metrics.rows, metrics.columns));
+    if !in_sync {
+        screens.lock().map_err(|error| error.to_string())?.remove(pane_id);
+        writer.send_raw(&seed_command(pane_id))?;
+        let deadline = Instant::now() + SEED_TIMEOUT;
+        while !screens.lock().map_err(|error| error.to_string())?.contains_key(pane_id) {
+            if Instant::now() >= deadline {
+                return Err(format!("timed out seeding the terminal model for pane {pane_id}"));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    let screens = screens.lock().map_err(|error| error.to_string())?;
+    let parser = screens
+        .get(pane_id)
+        .ok_or_else(|| format!("terminal model for pane {pane_id} is missing"))?;
+    Ok(ScreenSnapshot::from_screen(parser.screen()))
+}
+
+/// Control-mode command line that reports a pane's metrics and captures its primary and
+/// visible screens in one go. Each command answers in its own `%begin`/`%end` block.
+fn seed_command(pane_id: &str) -> String {
+    format!(
+        "display-message -p -t {pane_id} '{SEED_MARKER} {pane_id} {PANE_METRICS_FORMAT}' ; \
+         capture-pane -p -e -a -q -t {pane_id} ; capture-pane -p -e -t {pane_id}\n"
+    )
+}
+
+fn parse_seed_marker(line: &str) -> Option<(String, PaneMetrics)> {
+    let rest = line.strip_prefix(SEED_MARKER)?.strip_prefix(' ')?;
+    let (pane_id, metrics) = rest.split_once(' ')?;
+    Some((pane_id.to_string(), parse_pane_metrics(metrics)?))
+}
+
+/// Follows reply blocks on the control stream and installs a pane model once the replies to
+/// [`seed_command`] have arrived.
+#[derive(Default)]
+pub struct SeedReplies {
+    block: Option<ReplyBlock>,
+    pending: Option<PendingSeed>,
+}
+
+struct ReplyBlock {
+    guard: String,
+    lines: Vec<String>,
+}
+
+struct PendingSeed {
+    pane_id: String,
+    metrics: PaneMetrics,
+    captures: Vec<String>,
+}
+
+impl SeedReplies {
+    /// Consumes a control line that is part of a `%begin`/`%end` reply block. Returns false
+    /// for anything else, which the caller handles as a notification.
+    pub fn handle_line(&mut self, line: &str, screens: &TerminalScreens) -> bool {
+        let Some(mut block) = self.block.take() else {
+            let Some(guard) = line.strip_prefix("%begin ") else {
+                return false;
+            };
+            self.block = Some(ReplyBlock {
+                guard: guard.to_string(),
+                lines: Vec::new(),
+            });
+            return true;
+        };
+        if line.strip_prefix("%end ") == Some(block.guard.as_str()) {
+            self.finish_block(block.lines, screens);
+        } else if line.strip_prefix("%error ") == Some(block.guard.as_str()) {
+            self.pending = None;
+        } else {
+            block.lines.push(line.to_string());
+            self.block = Some(block);
+        }
+        true
+    }
+
+    fn finish_block(&mut self, lines: Vec<String>, screens: &TerminalScreens) {
+        let Some(mut pending) = self.pending.take() else {
+            if let [line] = lines.as_slice() {
+                self.pending = parse_seed_marker(line).map(|(pane_id, metrics)| PendingSeed {
+                    pane_id,
+                    metrics,
+                    captures: Vec::new(),
+                });
+            }
+            return;
+        };
+        pending.captures.push(lines.join("\n"));
+        let [primary, visible] = pending.captures.as_slice() else {
+            self.pending = Some(pending);
+            return;
+        };
+        let mut parser = vt100::Parser::new(pending.metrics.rows, pending.metrics.columns, 0);
+        parser.process(&seed_bytes(visible, Some(primary), &pending.metrics));
+        if let Ok(mut screens) = screens.lock() {
+            screens.insert(pending.pane_id, parser);
+        }
+    }
+}
+
+/// Bytes that redraw a pane's visible screen, as `capture-pane -e` sees it, on an
+/// empty terminal and restore the cursor. A pane on its alternate screen gets the primary
+/// screen drawn first, so leaving the alternate screen later shows what tmux would.
+pub fn capture_seed(pane_id: &str) -> Result<Vec<u8>, String> {
+    let metrics = pane_metrics(pane_id)?;
+    let primary = if metrics.alternate_on {
+        Some(capture_pane(pane_id, &["-a", "-q"])?)
+    } else {
+        None
+    };
+    let visible = capture_pane(pane_id, &[])?;
+    Ok(seed_bytes(&visible, primary.as_deref(), &metrics))
+}
+
+fn capture_pane(pane_id: &str, extra: &[&str]) -> Result<String, String> {
+    let mut args = vec!["capture-pane", "-p", "-e"];
+    args.extend_from_slice(extra);
+    args.extend_from_slice(&["-t", pane_id]);
+    let captured = tmux::output(&args)?;
+    if !captured.status.success() {
+        return Err(format!(
+            "failed to capture pane {pane_id}: {}",
+            String::from_utf8_lossy(&captured.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&captured.stdout).to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PaneMetrics {
+    rows: u16,
+    columns: u16,
+    cursor_row: u16,
+    cursor_column: u16,
+    cursor_visible: bool,
+    alternate_on: bool,
+    saved_cursor_row: u16,
+    saved_cursor_column: u16,
+}
+
+fn pane_metrics(pane_id: &str) -> Result<PaneMetrics, String> {
+    let output = tmux::output(&["display-message", "-p", "-t", pane_id, PANE_METRICS_FORMAT])?;
+    if !output.status.success() {
+        return Err(format!(
+            "failed to read pane metrics for {pane_id}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let raw = String::from_utf8_lossy(&output.stdout).to_string();
+    parse_pane_metrics(&raw).ok_or_else(|| format!("failed to parse pane metrics for {pane_id}: {}", raw.trim()))
+}
+
+/// Parses [`PANE_METRICS_FORMAT`]. tmux reports the saved cursor as `u32::MAX` when the pane
+/// has no alternate screen, so it is only read while the alternate screen is on.
+fn parse_pane_metrics(raw: &str) -> Option<PaneMetrics> {
+    let values = raw
+        .split_whitespace()
+        .map(|value| value.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let cell = |value: u32| u16::try_from(value).ok();
+    match values.as_slice() {
+        [rows, columns, cursor_row, cursor_column, cursor_flag, alternate_on, saved_row, saved_column] => {
+            let alternate_on = *alternate_on != 0;
+            Some(PaneMetrics {
+                rows: cell(*rows)?,
+                columns: cell(*columns)?,
+                cursor_row: cell(*cursor_row)?,
+                cursor_column: cell(*cursor_column)?,
+                cursor_visible: *cursor_flag != 0,
+                alternate_on,
+                saved_cursor_row: if alternate_on { cell(*saved_row)? } else { 0 },
+                saved_cursor_column: if alternate_on { cell(*saved_column)? } else { 0 },
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replays `capture-pane -e` dumps onto an empty screen and restores the cursor. With the
+/// alternate screen on, the primary dump is drawn first and `?1049h` switches over, saving
+/// the primary cursor for when the application leaves the alternate screen.
+fn seed_bytes(visible: &str, primary: Option<&str>, metrics: &PaneMetrics) -> Vec<u8> {
+    let mut seed = String::new();
+    if let Some(primary) = primary.filter(|_| metrics.alternate_on) {
+        seed.push_str(&redraw(primary, metrics.rows, metrics.saved_cursor_row, metrics.saved_cursor_column));
+        seed.push_str("\x1b[?1049h\x1b[H");
+    }
+    seed.push_str(&redraw(visible, metrics.rows, metrics.cursor_row, metrics.cursor_column));
+    if !metrics.cursor_visible {
+        seed.push_str("\x1b[?25l");
+    }
+    seed.into_bytes()
+}
+
+fn redraw(captured: &str, rows: u16, cursor_row: u16, cursor_column: u16) -> String {
+    let lines = captured
+        .strip_suffix('\n')
+        .unwrap_or(captured)
+        .split('\n')
+        .take(rows as usize)
+        .collect::<Vec<_>>();
+    let mut redraw = lines.join("\x1b[0m\r\n");
+    redraw.push_str("\x1b[0m");
+    redraw.push_str(&format!("\x1b[{};{}H", cursor_row + 1, cursor_column + 1));
+    redraw
+}
+
+fn sgr_for_cell(cell: &ScreenCell) -> String {
+    let mut codes = vec!["0".to_string()];
+    if cell.bold {
+        codes.push("1".to_string());
+    }
+    if cell.underline {
+        codes.push("4".to_string());
+    }
+    if cell.inverse {
+        codes.push("7".to_string());
+    }
+    match cell.fg {
+        CellColor::Default => {}
+        CellColor::Indexed(index @ 0..=7) => codes.push((30 + index).to_string()),
+        CellColor::Indexed(index @ 8..=15) => codes.push((90 + index - 8).to_string()),
+        CellColor::Indexed(index) => codes.push(format!("38;5;{index}")),
+        CellColor::Rgb(red, green, blue) => codes.push(format!("38;2;{red};{green};{blue}")),
+    }
+    match cell.bg {
+        CellColor::Default => {}
+        CellColor::Indexed(index @ 0..=7) => codes.push((40 + index).to_string()),
+        CellColor::Indexed(index @ 8..=15) => codes.push((100 + index - 8).to_string()),
+        CellColor::Indexed(index) => codes.push(format!("48;5;{index}")),
+        CellColor::Rgb(red, green, blue) => codes.push(format!("48;2;{red};{green};{blue}")),
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+const DEFAULT_FOREGROUND: [u8; 3] = [0x33, 0xff, 0x33];
+const DEFAULT_BACKGROUND: [u8; 3] = [0x06, 0x0d, 0x04];
+
+/// Matches the xterm.js theme used by terminal tiles.
+const ANSI_PALETTE: [[u8; 3]; 16] = [
+    [0x0a, 0x0e, 0x08],
+    [0xff, 0x33, 0x33],
+    [0x33, 0xff, 0x33],
+    [0xff, 0xaa, 0x00],
+    [0x33, 0x88, 0xff],
+    [0xcc, 0x33, 0xff],
+    [0x33, 0xcc, 0xcc],
+    [0xc0, 0xc8, 0xb8],
+    [0x2a, 0x3a, 0x20],
+    [0xff, 0x55, 0x55],
+    [0x55, 0xff, 0x55],
+    [0xff, 0xcc, 0x33],
+    [0x55, 0x99, 0xff],
+    [0xdd, 0x55, 0xff],
+    [0x55, 0xdd, 0xdd],
+    [0xe0, 0xe8, 0xd8],
+];
+
+fn indexed_color(index: u8) -> [u8; 3] {
+    match index {
+        0..=15 => ANSI_PALETTE[index as usize],
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let offset = index - 16;
+            [level(offset / 36), level((offset / 6) % 6), level(offset % 6)]
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
+fn cell_colors(cell: &ScreenCell) -> (Rgb<u8>, Rgb<u8>) {
+    let fg = match cell.fg {
+        CellColor::Default => DEFAULT_FOREGROUND,
+        CellColor::Indexed(index) if cell.bold && index < 8 => indexed_color(index + 8),
+        CellColor::Indexed(index) => indexed_color(index),
+        CellColor::Rgb(red, green, blue) => [red, green, blue],
+    };
+    let bg = match cell.bg {
+        CellColor::Default => DEFAULT_BACKGROUND,
+        CellColor::Indexed(index) => indexed_color(index),
+        CellColor::Rgb(red, green, blue) => [red, green, blue],
+    };
+    if cell.inverse {
+        (Rgb(bg), Rgb(fg))
+    } else {
+        (Rgb(fg), Rgb(bg))
+    }
+}
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for pixel_y in y..(y + height).min(image.height()) {
+        for pixel_x in x..(x + width).min(image.width()) {
+            image.put_pixel(pixel_x, pixel_y, color);
+        }
+    }
+}
+
+/// Fills one unscaled font pixel of the cell at `origin`.
+fn plot(image: &mut RgbImage, origin_x: u32, origin_y: u32, x: u32, y: u32, color: Rgb<u8>) {
+    fill_rect(
+        image,
+        origin_x + x * PIXEL_SCALE,
+        origin_y + y * PIXEL_SCALE,
+        PIXEL_SCALE,
+        PIXEL_SCALE,
+        color,
+    );
+}
+
+fn draw_cell_text(image: &mut RgbImage, origin_x: u32, origin_y: u32, span: u32, text: &str, color: Rgb<u8>) {
+    let Some(ch) = text.chars().next() else {
+        return;
+    };
+    if ch == ' ' {
+        return;
+    }
+    if draw_box_char(image, origin_x, origin_y, span, ch, color) {
+        return;
+    }
+    match glyph_for(ch) {
+        Some(rows) => {
+            for (y, bits) in rows.iter().enumerate() {
+                for x in 0..5 {
+                    if bits & (0x10 >> x) != 0 {
+                        plot(image, origin_x, origin_y, x, GLYPH_TOP + y as u32, color);
+                    }
+                }
+            }
+        }
+        None => {
+            let right = CELL_WIDTH * span - 2;
+            for x in 0..=right {
+                plot(image, origin_x, origin_y, x, GLYPH_TOP, color);
+                plot(image, origin_x, origin_y, x, GLYPH_TOP + 6, color);
+            }
+            for y in GLYPH_TOP..=GLYPH_TOP + 6 {
+                plot(image, origin_x, origin_y, 0, y, color);
+                plot(image, origin_x, origin_y, right, y, color);
+            }
+        }
+    }
+}
+
+/// Box-drawing and block characters are common in TUIs and are drawn geometrically.
+fn draw_box_char(image: &mut RgbImage, origin_x: u32, origin_y: u32, span: u32, ch: char, color: Rgb<u8>) -> bool {
+    let width = CELL_WIDTH * span;
+    let (mid_x, mid_y) = (width / 2, CELL_HEIGHT / 2);
+    let arms = match ch {
+        '─' | '━' | '═' => Some((true, true, false, false)),
+        '│' | '┃' | '║' => Some((false, false, true, true)),
+        '┌' | '╭' | '╔' | '┏' => Some((false, true, false, true)),
+        '┐' | '╮' | '╗' | '┓' => Some((true, false, false, true)),
+        '└' | '╰' | '╚' | '┗' => Some((false, true, true, false)),
+        '┘' | '╯' | '╝' | '┛' => Some((true, false, true, false)),
+        '├' | '╠' | '┣' => Some((false, true, true, true)),
+        '┤' | '╣' | '┫' => Some((true, false, true, true)),
+        '┬' | '╦' | '┳' => Some((true, true, false, true)),
+        '┴' | '╩' | '┻' => Some((true, true, true, false)),
+        '┼' | '╬' | '╋' => Some((true, true, true, true)),
+        _ => None,
+    };
+    if let Some((left, right, up, down)) = arms {
+        let x_range = (if left { 0 } else { mid_x })..=(if right { width - 1 } else { mid_x });
+        for x in x_range {
+            plot(image, origin_x, origin_y, x, mid_y, color);
+        }
+        let y_range = (if up { 0 } else { mid_y })..=(if down { CELL_HEIGHT - 1 } else { mid_y });
+        for y in y_range {
+            plot(image, origin_x, origin_y, mid_x, y, color);
+        }
+        return true;
+    }
+    let block = match ch {
+        '█' => Some((0, 0, width, CELL_HEIGHT)),
+        '▀' => Some((0, 0, width, mid_y)),
+        '▄' => Some((0, mid_y, width, CELL_HEIGHT - mid_y)),
+        '▌' => Some((0, 0, mid_x, CELL_HEIGHT)),
+        '▐' => Some((mid_x, 0, width - mid_x, CELL_HEIGHT)),
+        _ => None,
+    };
+    if let Some((x, y, block_width, block_height)) = block {
+        fill_rect(
+            image,
+            origin_x + x * PIXEL_SCALE,
+            origin_y + y * PIXEL_SCALE,
+            block_width * PIXEL_SCALE,
+            block_height * PIXEL_SCALE,
+            color,
+        );
+        return true;
+    }
+    false
+}
+
+fn glyph_for(ch: char) -> Option<&'static [u8; 7]> {
+    let ch = match ch {
+        '•' | '●' | '·' | '∙' => '*',
+        '❯' | '›' | '→' | '▶' | '▸' | '»' => '>',
+        '‹' | '←' | '◀' | '«' => '<',
+        '✓' | '✔' => 'v',
+        '✗' | '✘' | '×' => 'x',
+        '…' => '.',
+        '“' | '”' => '"',
+        '‘' | '’' => '\'',
+        '–' | '—' => '-',
+        other => other,
+    };
+    let code = ch as u32;
+    (0x20..0x7f).contains(&code).then(|| &FONT_5X7[(code - 0x20) as usize])
+}
+
+/// 5x7 glyphs for printable ASCII, one byte per row with bit 4 as the leftmost column.
+const FONT_5X7: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // '#'
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // '&'
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // '0'
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // '1'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // '2'
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // '3'
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // '4'
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // '5'
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // '6'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // '8'
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // '9'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // ':'
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // '@'
+    [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'A'
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // 'B'
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // 'C'
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // 'D'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // 'E'
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // 'F'
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // 'G'
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'H'
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // 'L'
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'O'
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // 'P'
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // 'Q'
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // 'R'
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // 'S'
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // 'W'
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04], // 'Y'
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // 'Z'
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\\'
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ']'
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // 'b'
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // 'c'
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // 'd'
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // 'e'
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'l'
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // 'o'
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // 's'
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // 'w'
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'y'
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        new_terminal_screens, parse_pane_metrics, parse_screenshot_format, render_screenshot, seed_bytes,
+        seed_command, CellColor, ScreenSnapshot, SeedReplies, TerminalScreenshotFormat,
+    };
+
+    fn snapshot_of(rows: u16, columns: u16, bytes: &[u8]) -> ScreenSnapshot {
+        let mut parser = vt100::Parser::new(rows, columns, 0);
+        parser.process(bytes);
+        ScreenSnapshot::from_screen(parser.screen())
+    }
+
+    #[test]
+    fn models_full_screen_redraws_as_text_with_cursor() {
+        let snapshot = snapshot_of(
+            4,
+            20,
+            b"old output\r\n\x1b[2J\x1b[H\x1b[1;32mready\x1b[0m\r\n\x1b[3;5H> prompt",
+        );
+        assert_eq!(snapshot.to_text(), "ready\n\n    > prompt\n");
+        assert_eq!((snapshot.cursor_row, snapshot.cursor_column), (2, 12));
+        assert!(snapshot.cursor_visible);
+        assert_eq!(snapshot.cells[0].fg, CellColor::Indexed(2));
+        assert!(snapshot.cells[0].bold);
+
+        let alternate = snapshot_of(2, 10, b"shell\x1b[?1049h\x1b[?25lTUI");
+        assert_eq!(alternate.to_text(), "TUI\n");
+        assert!(alternate.alternate_screen);
+        assert!(!alternate.cursor_visible);
+    }
+
+    #[test]
+    fn renders_ansi_rows_with_sgr_runs() {
+        let snapshot = snapshot_of(2, 10, b"\x1b[31mab\x1b[0m c\r\n\x1b[48;5;200mx");
+        assert_eq!(
+            snapshot.to_ansi(),
+            "\x1b[0;31mab\x1b[0m c\x1b[0m\n\x1b[0;48;5;200mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn renders_png_screenshots_with_cell_dimensions() {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine as _;
+
+        let snapshot = snapshot_of(3, 7, "hi ─█?é".as_bytes());
+        let screenshot = render_screenshot(&snapshot, TerminalScreenshotFormat::Png).unwrap();
+        assert_eq!(screenshot.mime_type.as_deref(), Some("image/png"));
+        assert_eq!((screenshot.rows, screenshot.columns), (3, 7));
+        let bytes = STANDARD.decode(screenshot.data_base64.unwrap()).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (7 * 12, 3 * 20));
+
+        let text = render_screenshot(&snapshot, TerminalScreenshotFormat::Text).unwrap();
+        assert_eq!(text.text.as_deref(), Some("hi ─█?é\n\n"));
+        assert!(text.data_base64.is_none());
+    }
+
+    #[test]
+    fn parses_formats_metrics_and_seeds_captured_screens() {
+        assert_eq!(parse_screenshot_format(None), Ok(TerminalScreenshotFormat::Text));
+        assert_eq!(
+            parse_screenshot_format(Some("image")),
+            Ok(TerminalScreenshotFormat::Png)
+        );
+        assert!(parse_screenshot_format(Some("braille")).is_err());
+
+        let metrics = parse_pane_metrics("3 12 1 4 1 0 4294967295 4294967295\n").unwrap();
+        assert_eq!(
+            (metrics.rows, metrics.columns, metrics.cursor_row, metrics.cursor_column),
+            (3, 12, 1, 4)
+        );
+        assert!(!metrics.alternate_on);
+        assert!(parse_pane_metrics("3 12 1 4 1").is_none());
+
+        let seed = seed_bytes("\x1b[31m$ ls\x1b[39m\nfile\n\n", None, &metrics);
+        let snapshot = snapshot_of(3, 12, &seed);
+        assert_eq!(snapshot.to_text(), "$ ls\nfile\n");
+        assert_eq!((snapshot.cursor_row, snapshot.cursor_column), (1, 4));
+        assert_eq!(snapshot.cells[0].fg, CellColor::Indexed(1));
+    }
+
+    #[test]
+    fn seeds_the_primary_screen_under_an_active_alternate_screen() {
+        let metrics = parse_pane_metrics("3 12 0 3 1 1 2 1").unwrap();
+        let mut parser = vt100::Parser::new(3, 12, 0);
+        parser.process(&seed_bytes("top\n", Some("$ vim\n\n$"), &metrics));
+        assert_eq!(ScreenSnapshot::from_screen(parser.screen()).to_text(), "top\n\n");
+        parser.process(b"\x1b[?1049l");
+        let restored = ScreenSnapshot::from_screen(parser.screen());
+        assert_eq!(restored.to_text(), "$ vim\n\n$");
+        assert_eq!((restored.cursor_row, restored.cursor_column), (2, 1));
+    }
+
+    #[test]
+    fn installs_seeded_models_from_control_mode_replies_only() {
+        assert!(seed_command("%3").contains("capture-pane -p -e -a -q -t %3 ; capture-pane -p -e -t %3\n"));
+        let screens = new_terminal_screens();
+        let mut replies = SeedReplies::default();
+        assert!(!replies.handle_line("%output %3 hi", &screens));
+        for line in [
+            "%begin 1 10 1",
+            "%end 1 10 1",
+            "%begin 1 11 1",
+            "herd-screen-seed %3 2 12 1 2 1 0 4294967295 4294967295",
+            "%end 1 11 1",
+            "%begin 1 12 1",
+            "%end 1 12 1",
+            "%begin 1 13 1",
+            "%end 1 0 1",
+            "$ ls",
+            "%end 1 13 1",
+        ] {
+            assert!(replies.handle_line(line, &screens), "{line}");
+        }
+        let screens = screens.lock().unwrap();
+        let snapshot = ScreenSnapshot::from_screen(screens["%3"].screen());
+        assert_eq!(snapshot.to_text(), "%end 1 0 1\n$ ls");
+        assert_eq!((snapshot.cursor_row, snapshot.cursor_column), (1, 2));
+    }
+}
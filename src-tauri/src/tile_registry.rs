@@ -3,7 +3,12 @@ use std::path::Path;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
-use crate::{db, runtime};
+use crate::{
+    db,
+    network::{self, NamedPort, NetworkTileKind},
+    network_history::{self, NetworkChangeActor, NetworkHistoryEventKind},
+    runtime,
+};
 
 const TILE_ID_ALPHABET: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const TILE_ID_LENGTH: usize = 6;
@@ -110,7 +115,10 @@ pub fn replace_at(path: &Path, records: &[TileRecord]) -> Result<(), String> {
     Ok(())
 }
 
+/// Also appends `tile_created`/`tile_destroyed` network history events for the
+/// difference, so topology rewinds know which tiles existed at a given time.
 pub fn replace_with_conn(conn: &Connection, records: &[TileRecord]) -> Result<(), String> {
+    let previous = load_with_conn(conn)?;
     conn.execute("DELETE FROM tile_registry", [])
         .map_err(|error| format!("failed to clear tile registry rows: {error}"))?;
     for record in records {
@@ -130,6 +138,85 @@ pub fn replace_with_conn(conn: &Connection, records: &[TileRecord]) -> Result<()
         )
         .map_err(|error| format!("failed to insert tile registry row {}: {error}", record.tile_id))?;
     }
+    let actor = NetworkChangeActor::system();
+    for record in previous
+        .iter()
+        .filter(|record| !records.iter().any(|next| next.tile_id == record.tile_id))
+    {
+        network_history::record_tile_with_conn(conn, NetworkHistoryEventKind::TileDestroyed, record, &actor)?;
+    }
+    for record in records
+        .iter()
+        .filter(|record| !previous.iter().any(|prior| prior.tile_id == record.tile_id))
+    {
+        network_history::record_tile_with_conn(conn, NetworkHistoryEventKind::TileCreated, record, &actor)?;
+    }
+    conn.execute(
+        "DELETE FROM tile_named_port
+         WHERE tile_id NOT IN (SELECT tile_id FROM tile_registry)
+           AND tile_id NOT IN (SELECT tile_id FROM work_item)",
+        [],
+    )
+    .map_err(|error| format!("failed to prune named ports: {error}"))?;
+    conn.execute(
+        "DELETE FROM tile_port_segment
+         WHERE tile_id NOT IN (SELECT tile_id FROM tile_registry)
+           AND tile_id NOT IN (SELECT tile_id FROM work_item)",
+        [],
+    )
+    .map_err(|error| format!("failed to prune port segments: {error}"))?;
+    Ok(())
+}
+
+pub fn list_named_ports_at(path: &Path, tile_id: Option<&str>) -> Result<Vec<NamedPort>, String> {
+    let conn = db::open_at(path)?;
+    list_named_ports_with_conn(&conn, tile_id)
+}
+
+pub fn list_named_ports_with_conn(conn: &Connection, tile_id: Option<&str>) -> Result<Vec<NamedPort>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tile_id, name, port, access_mode, accepts_json
+             FROM tile_named_port
+             WHERE ?1 IS NULL OR tile_id = ?1
+             ORDER BY tile_id ASC, name ASC",
+        )
+        .map_err(|error| format!("failed to prepare named port query: {error}"))?;
+    let rows = stmt
+        .query_map([tile_id], |row| {
+            let accepts_json = row.get::<_, String>(4)?;
+            Ok(NamedPort {
+                tile_id: row.get(0)?,
+                name: row.get(1)?,
+                port: network::parse_port(&row.get::<_, String>(2)?)?,
+                access_mode: network::parse_port_mode(&row.get::<_, String>(3)?)?,
+                accepts: serde_json::from_str::<Vec<NetworkTileKind>>(&accepts_json).unwrap_or_default(),
+            })
+        })
+        .map_err(|error| format!("failed to query named ports: {error}"))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("failed to decode named port rows: {error}"))
+}
+
+pub fn replace_named_ports_with_conn(conn: &Connection, tile_id: &str, ports: &[NamedPort]) -> Result<(), String> {
+    conn.execute("DELETE FROM tile_named_port WHERE tile_id = ?1", [tile_id])
+        .map_err(|error| format!("failed to clear named ports for {tile_id}: {error}"))?;
+    for port in ports {
+        let accepts_json = serde_json::to_string(&port.accepts)
+            .map_err(|error| format!("failed to encode accepted kinds for port {}: {error}", port.name))?;
+        conn.execute(
+            "INSERT INTO tile_named_port (tile_id, name, port, access_mode, accepts_json)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tile_id,
+                port.name,
+                port.port.as_str(),
+                port.access_mode.as_str(),
+                accepts_json,
+            ],
+        )
+        .map_err(|error| format!("failed to insert named port {} for {tile_id}: {error}", port.name))?;
+    }
     Ok(())
 }
 
@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Write};
 use std::os::fd::FromRawFd;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use regex::Regex;
+use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::recording::{self, ActiveRecordings};
+use crate::terminal_screen::{self, TerminalScreens};
 use crate::{runtime, tmux};
 
 /// Thread-safe writer + id map for sending input without contending with the reader.
@@ -27,17 +31,163 @@ impl TmuxWriter {
     }
 }
 
+/// Per-pane ring buffer addressed by monotonically increasing byte offsets.
+/// Reads never consume data, so several readers can follow the same pane.
+#[derive(Debug, Clone)]
+pub struct PaneOutputBuffer {
+    data: VecDeque<u8>,
+    start_offset: u64,
+    capacity: usize,
+}
+
+/// A slice of pane output plus the offset to pass as `since_offset` on the next read.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PaneOutputChunk {
+    pub output: String,
+    pub offset: u64,
+    pub next_offset: u64,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaneOutputBytes {
+    pub bytes: Vec<u8>,
+    pub offset: u64,
+    pub next_offset: u64,
+    pub truncated: bool,
+}
+
+impl PaneOutputBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::new(),
+            start_offset: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.start_offset + self.data.len() as u64
+    }
+
+    /// Appends a chunk and returns the stream offset of its first byte.
+    pub fn push(&mut self, bytes: &[u8]) -> u64 {
+        let offset = self.end_offset();
+        self.data.extend(bytes);
+        if self.data.len() > self.capacity {
+            let overflow = self.data.len() - self.capacity;
+            self.data.drain(..overflow);
+            self.start_offset += overflow as u64;
+        }
+        offset
+    }
+
+    /// Reads from `since_offset` (default: oldest retained byte). `truncated` is set when
+    /// the requested offset has already been evicted from the ring.
+    pub fn read(&self, since_offset: Option<u64>, max_bytes: Option<usize>) -> PaneOutputChunk {
+        let raw = self.read_raw(since_offset, max_bytes);
+        PaneOutputChunk {
+            output: String::from_utf8_lossy(&raw.bytes).to_string(),
+            offset: raw.offset,
+            next_offset: raw.next_offset,
+            truncated: raw.truncated,
+        }
+    }
+
+    /// Like `read`, but keeps the bytes as stored so positions in them are stream offsets.
+    pub fn read_raw(&self, since_offset: Option<u64>, max_bytes: Option<usize>) -> PaneOutputBytes {
+        let requested = since_offset.unwrap_or(self.start_offset);
+        let offset = requested.clamp(self.start_offset, self.end_offset());
+        let skip = (offset - self.start_offset) as usize;
+        let available = self.data.len() - skip;
+        let limit = max_bytes.map_or(available, |max_bytes| max_bytes.min(available));
+        let mut take = limit;
+        while take > 0 && take < available && is_utf8_continuation(self.data[skip + take]) {
+            take -= 1;
+        }
+        if take == 0 {
+            take = limit;
+        }
+        PaneOutputBytes {
+            bytes: self.data.range(skip..skip + take).copied().collect(),
+            offset,
+            next_offset: offset + take as u64,
+            truncated: requested < self.start_offset,
+        }
+    }
+}
+
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
 /// Thread-safe output buffers — separate from TmuxControl to avoid lock contention.
-pub type OutputBuffers = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+pub type OutputBuffers = Arc<Mutex<HashMap<String, PaneOutputBuffer>>>;
 
 /// Manages a tmux control mode (-CC) connection for per-pane I/O.
 pub struct TmuxControl {
     child_pid: libc::pid_t,
     pub writer: Arc<TmuxWriter>,
     pub output_buffers: OutputBuffers,
+    pub recordings: ActiveRecordings,
+    pub screens: TerminalScreens,
 }
 
-const OUTPUT_BUFFER_CAP: usize = 65536;
+/// Outcome of an `exec` sent with `wait`, parsed from the sentinel-wrapped pane output.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExecWaitResult {
+    pub completed: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutputCaptureOptions {
+    pub start_line: Option<i64>,
+    pub end_line: Option<i64>,
+    pub preserve_ansi: bool,
+    pub join_wrapped: bool,
+    pub search: Option<String>,
+}
+
+/// Scrollback captured with `tmux capture-pane`. Line 0 is the first visible row;
+/// negative lines reach into history.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OutputCapture {
+    pub output: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub line_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<OutputCaptureMatch>>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OutputCaptureMatch {
+    pub line: i64,
+    pub text: String,
+}
+
+/// Outcome of `wait_for_output`. `next_offset` is the end of the match, or the end of
+/// the output seen so far when the wait timed out.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OutputWaitResult {
+    pub matched: bool,
+    pub duration_ms: u64,
+    pub next_offset: u64,
+    #[serde(rename = "match")]
+    pub found: Option<OutputMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OutputMatch {
+    pub text: String,
+    pub groups: Vec<Option<String>>,
+    pub context: String,
+    pub offset: u64,
+    pub end_offset: u64,
+}
 
 impl TmuxControl {
     pub fn child_pid(&self) -> libc::pid_t {
@@ -141,11 +291,15 @@ impl TmuxControl {
         });
 
         let output_buffers: OutputBuffers = Arc::new(Mutex::new(HashMap::new()));
+        let recordings = recording::new_active_recordings();
+        let screens = terminal_screen::new_terminal_screens();
 
         let control = Arc::new(Mutex::new(TmuxControl {
             child_pid,
             writer: tmux_writer.clone(),
             output_buffers: output_buffers.clone(),
+            recordings: recordings.clone(),
+            screens: screens.clone(),
         }));
 
         // Reader thread: parse control mode output
@@ -164,6 +318,7 @@ impl TmuxControl {
 
             let mut reader = BufReader::new(reader);
             let mut raw_line = Vec::new();
+            let mut seed_replies = terminal_screen::SeedReplies::default();
             loop {
                 raw_line.clear();
                 let read = match reader.read_until(b'\n', &mut raw_line) {
@@ -206,25 +361,33 @@ impl TmuxControl {
                     }
                 }
 
+                if seed_replies.handle_line(&line, &screens) {
+                    continue;
+                }
+
                 if line.starts_with("%output ") {
                     // %output %<pane_id> <escaped_data>
                     if let Some((pane_id, data)) = parse_output_line(&line) {
                         let decoded = decode_tmux_output(&data);
 
                         // Buffer for read_output API (separate lock, no contention)
-                        if let Ok(mut bufs) = bufs_clone.lock() {
-                            let buf = bufs.entry(pane_id.clone()).or_insert_with(Vec::new);
-                            buf.extend_from_slice(&decoded);
-                            if buf.len() > OUTPUT_BUFFER_CAP {
-                                let drain = buf.len() - OUTPUT_BUFFER_CAP;
-                                buf.drain(..drain);
-                            }
+                        let offset = bufs_clone.lock().ok().map(|mut bufs| {
+                            bufs.entry(pane_id.clone())
+                                .or_insert_with(|| PaneOutputBuffer::new(runtime::output_buffer_bytes()))
+                                .push(&decoded)
+                        });
+
+                        terminal_screen::feed(&screens, &pane_id, &decoded);
+
+                        if let Err(error) = recording::write_output(&recordings, &pane_id, &decoded) {
+                            log::warn!("{error}");
                         }
 
                         let text = String::from_utf8_lossy(&decoded).to_string();
                         let payload = serde_json::json!({
                             "pane_id": pane_id,
                             "data": text,
+                            "offset": offset,
                         });
                         let emit_result = app.emit("pty-output", &payload);
 
@@ -280,12 +443,19 @@ impl TmuxControl {
         if let (Ok(old_bufs), Ok(mut new_bufs)) = (old.output_buffers.lock(), self.output_buffers.lock()) {
             *new_bufs = old_bufs.clone();
         }
+        if let (Ok(mut old_recordings), Ok(mut new_recordings)) = (old.recordings.lock(), self.recordings.lock()) {
+            new_recordings.inherit(&mut old_recordings);
+        }
+        if let (Ok(mut old_screens), Ok(mut new_screens)) = (old.screens.lock(), self.screens.lock()) {
+            new_screens.extend(old_screens.drain());
+        }
         log::info!("Inherited tmux output buffers from previous control");
     }
 
     /// Clear all tracked state (for restart).
     pub fn clear_all(&mut self) {
         if let Ok(mut bufs) = self.output_buffers.lock() { bufs.clear(); }
+        if let Ok(mut screens) = self.screens.lock() { screens.clear(); }
     }
 
     /// Create a new window with a shell in the session.
@@ -315,16 +485,37 @@ impl TmuxControl {
         self.writer.send_raw(&format!("resize-pane -t {} -x {} -y {}\n", pane_id, cols, rows))
     }
 
-    /// Read buffered output for a pane. Drains the buffer.
-    pub fn read_output(&self, pane_id: &str) -> Result<String, String> {
-        let mut bufs = self.output_buffers.lock().map_err(|e| e.to_string())?;
-        match bufs.get_mut(pane_id) {
-            Some(b) => {
-                let bytes: Vec<u8> = b.drain(..).collect();
-                Ok(String::from_utf8_lossy(&bytes).to_string())
-            }
-            None => Ok(String::new()),
-        }
+    /// Read buffered output for a pane from `since_offset` without draining it.
+    pub fn read_output(
+        &self,
+        pane_id: &str,
+        since_offset: Option<u64>,
+        max_bytes: Option<usize>,
+    ) -> Result<PaneOutputChunk, String> {
+        let bufs = self.output_buffers.lock().map_err(|e| e.to_string())?;
+        Ok(bufs
+            .get(pane_id)
+            .map(|buffer| buffer.read(since_offset, max_bytes))
+            .unwrap_or_default())
+    }
+
+    pub fn read_output_raw(
+        &self,
+        pane_id: &str,
+        since_offset: Option<u64>,
+        max_bytes: Option<usize>,
+    ) -> Result<PaneOutputBytes, String> {
+        let bufs = self.output_buffers.lock().map_err(|e| e.to_string())?;
+        Ok(bufs
+            .get(pane_id)
+            .map(|buffer| buffer.read_raw(since_offset, max_bytes))
+            .unwrap_or_default())
+    }
+
+    /// Offset just past the newest buffered byte for a pane.
+    pub fn output_end_offset(&self, pane_id: &str) -> Result<u64, String> {
+        let bufs = self.output_buffers.lock().map_err(|e| e.to_string())?;
+        Ok(bufs.get(pane_id).map(PaneOutputBuffer::end_offset).unwrap_or(0))
     }
 }
 
@@ -413,6 +604,266 @@ fn log_child_exit_status(child_pid: libc::pid_t) {
     }
 }
 
+fn exec_start_marker(token: &str) -> String {
+    format!("__HERD_EXEC_{token}_START__")
+}
+
+fn exec_end_marker_prefix(token: &str) -> String {
+    format!("__HERD_EXEC_{token}_END_")
+}
+
+/// Wrap a command so its output is bracketed by sentinels carrying the exit status.
+/// The markers are assembled by printf, so the echoed command line never matches them.
+pub fn exec_wait_wrapped_command(token: &str, command: &str) -> String {
+    let quoted = format!("'{}'", command.trim_end_matches('\n').replace('\'', r"'\''"));
+    let mut wrapped = format!(
+        r#"printf '\n__HERD_EXEC_%s_START__\n' {token}; eval {quoted}; printf '\n__HERD_EXEC_%s_END_%s__\n' {token} "$?""#
+    );
+    wrapped.push('\n');
+    wrapped
+}
+
+/// Returns the exit code and the output between the sentinels once the end marker is present.
+pub fn parse_exec_wait_output(buffer: &str, token: &str) -> Option<(i32, String)> {
+    let output_start = exec_output_start(buffer, token).unwrap_or(0);
+    let end_prefix = exec_end_marker_prefix(token);
+    let end_index = output_start + buffer[output_start..].find(&end_prefix)?;
+    let status_start = end_index + end_prefix.len();
+    let status_len = buffer[status_start..].find("__")?;
+    let exit_code = buffer[status_start..status_start + status_len].parse::<i32>().ok()?;
+    Some((exit_code, trim_exec_output(&buffer[output_start..end_index])))
+}
+
+/// Output seen after the start marker so far, for commands that have not finished.
+pub fn partial_exec_wait_output(buffer: &str, token: &str) -> String {
+    exec_output_start(buffer, token)
+        .map(|output_start| trim_exec_output(&buffer[output_start..]))
+        .unwrap_or_default()
+}
+
+fn exec_output_start(buffer: &str, token: &str) -> Option<usize> {
+    let start_marker = exec_start_marker(token);
+    buffer.find(&start_marker).map(|index| index + start_marker.len())
+}
+
+fn trim_exec_output(output: &str) -> String {
+    let output = output.strip_prefix("\r\n").or_else(|| output.strip_prefix('\n')).unwrap_or(output);
+    let output = output.strip_suffix("\r\n").or_else(|| output.strip_suffix('\n')).unwrap_or(output);
+    output.to_string()
+}
+
+/// Capture a pane's scrollback through tmux history rather than the live output ring.
+/// Without an explicit start, searches cover the whole history and plain captures the visible screen.
+pub fn capture_pane_output(pane_id: &str, options: &OutputCaptureOptions) -> Result<OutputCapture, String> {
+    let metrics = tmux::output(&[
+        "display-message",
+        "-p",
+        "-t",
+        pane_id,
+        "#{history_size} #{pane_height} #{pane_width}",
+    ])?;
+    if !metrics.status.success() {
+        return Err(format!(
+            "failed to read pane history size for {pane_id}: {}",
+            String::from_utf8_lossy(&metrics.stderr).trim()
+        ));
+    }
+    let metrics = String::from_utf8_lossy(&metrics.stdout).to_string();
+    let mut metrics = metrics.split_whitespace().map(|value| value.parse::<i64>().unwrap_or(0));
+    let history_size = metrics.next().unwrap_or(0);
+    let pane_height = metrics.next().unwrap_or(0).max(1);
+    let pane_width = metrics.next().unwrap_or(0).clamp(1, i64::from(u16::MAX)) as u16;
+
+    let (start_line, end_line) = resolve_capture_range(options, history_size, pane_height)?;
+    let start_arg = start_line.to_string();
+    let end_arg = end_line.to_string();
+    let mut args = vec!["capture-pane", "-p", "-t", pane_id, "-S", start_arg.as_str(), "-E", end_arg.as_str()];
+    if options.preserve_ansi {
+        args.push("-e");
+    }
+    if options.join_wrapped {
+        args.push("-J");
+    }
+    let output = tmux::output(&args)?;
+    if !output.status.success() {
+        return Err(format!(
+            "failed to capture pane output for {pane_id}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let captured = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(build_output_capture(
+        &captured,
+        start_line,
+        end_line,
+        options.search.as_deref(),
+        options.join_wrapped.then_some(pane_width),
+    ))
+}
+
+fn resolve_capture_range(
+    options: &OutputCaptureOptions,
+    history_size: i64,
+    pane_height: i64,
+) -> Result<(i64, i64), String> {
+    let default_start = if options.search.is_some() { -history_size } else { 0 };
+    let start_line = options.start_line.unwrap_or(default_start).clamp(-history_size, pane_height - 1);
+    let end_line = options.end_line.unwrap_or(pane_height - 1).clamp(-history_size, pane_height - 1);
+    if end_line < start_line {
+        return Err(format!("end_line {end_line} is before start_line {start_line}"));
+    }
+    Ok((start_line, end_line))
+}
+
+/// `wrap_width` is the pane width when `captured` holds joined (`-J`) lines, so
+/// match line numbers can count the pane rows each joined line covered.
+fn build_output_capture(
+    captured: &str,
+    start_line: i64,
+    end_line: i64,
+    search: Option<&str>,
+    wrap_width: Option<u16>,
+) -> OutputCapture {
+    let output = captured.strip_suffix('\n').unwrap_or(captured);
+    let lines: Vec<&str> = if output.is_empty() { Vec::new() } else { output.split('\n').collect() };
+    let matches = search.map(|needle| {
+        let mut row = start_line;
+        let mut matches = Vec::new();
+        for line in &lines {
+            let text = strip_ansi(line);
+            let rows = wrap_width.map_or(1, |width| wrapped_row_count(&text, width));
+            if text.contains(needle) {
+                matches.push(OutputCaptureMatch { line: row, text });
+            }
+            row += rows;
+        }
+        matches
+    });
+    OutputCapture {
+        output: output.to_string(),
+        start_line,
+        end_line,
+        line_count: lines.len(),
+        matches,
+    }
+}
+
+/// Pane rows a soft-wrapped line occupied, measured by laying it out on a
+/// terminal `width` columns wide so double-width characters count correctly.
+fn wrapped_row_count(text: &str, width: u16) -> i64 {
+    let text = text.trim_end_matches(' ');
+    let bound = (text.chars().count() * 2 / usize::from(width) + 2).min(usize::from(u16::MAX)) as u16;
+    let mut parser = vt100::Parser::new(bound, width, 0);
+    parser.process(text.as_bytes());
+    i64::from(parser.screen().cursor_position().0) + 1
+}
+
+/// Remove CSI, OSC and two-byte escape sequences from terminal text.
+fn strip_ansi(text: &str) -> String {
+    strip_ansi_indexed(text).0
+}
+
+/// Like `strip_ansi`, plus the source byte index of every stripped byte (and one past the end).
+fn strip_ansi_indexed(text: &str) -> (String, Vec<usize>) {
+    let mut result = String::with_capacity(text.len());
+    let mut source_index = Vec::with_capacity(text.len() + 1);
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, ch)) = chars.next() {
+        if ch != '\x1b' {
+            result.push(ch);
+            source_index.extend(position..position + ch.len_utf8());
+            continue;
+        }
+        match chars.next().map(|(_, next)| next) {
+            Some('[') => {
+                for (_, next) in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&next) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\x07' {
+                        break;
+                    }
+                    if next == '\x1b' && chars.peek().map(|(_, peeked)| *peeked) == Some('\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    source_index.push(text.len());
+    (result, source_index)
+}
+
+/// Decodes bytes like `String::from_utf8_lossy`, plus the source byte index of every
+/// decoded byte (and one past the end); a replacement character maps to the invalid byte.
+fn decode_utf8_indexed(bytes: &[u8]) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut source_index = Vec::with_capacity(bytes.len() + 1);
+    let mut position = 0;
+    while position < bytes.len() {
+        match std::str::from_utf8(&bytes[position..]) {
+            Ok(valid) => {
+                text.push_str(valid);
+                source_index.extend(position..bytes.len());
+                position = bytes.len();
+            }
+            Err(error) => {
+                let valid_end = position + error.valid_up_to();
+                text.push_str(std::str::from_utf8(&bytes[position..valid_end]).unwrap_or_default());
+                source_index.extend(position..valid_end);
+                let invalid_end = valid_end + error.error_len().unwrap_or(bytes.len() - valid_end);
+                // U+FFFD is three bytes; its last byte maps to the last invalid byte so
+                // a match ending on it ends after the whole invalid sequence.
+                text.push(char::REPLACEMENT_CHARACTER);
+                source_index.extend([valid_end, valid_end, invalid_end - 1]);
+                position = invalid_end;
+            }
+        }
+    }
+    source_index.push(bytes.len());
+    (text, source_index)
+}
+
+/// Find the first match of `pattern` in ANSI-stripped pane output read from `base_offset`.
+/// Offsets in the result address the raw pane stream, so `end_offset` can seed the next read.
+pub fn find_output_match(output: &[u8], base_offset: u64, pattern: &Regex, context_lines: usize) -> Option<OutputMatch> {
+    let (decoded, decoded_index) = decode_utf8_indexed(output);
+    let (text, stripped_index) = strip_ansi_indexed(&decoded);
+    let source_index = stripped_index
+        .iter()
+        .map(|index| decoded_index[*index])
+        .collect::<Vec<_>>();
+    let captures = pattern.captures(&text)?;
+    let whole = captures.get(0)?;
+    let first_line = text[..whole.start()].matches('\n').count();
+    let last_line = first_line + whole.as_str().matches('\n').count();
+    let lines: Vec<&str> = text.split('\n').map(|line| line.trim_end_matches('\r')).collect();
+    let context_start = first_line.saturating_sub(context_lines);
+    let context_end = (last_line + context_lines).min(lines.len() - 1);
+    let match_end = if whole.is_empty() {
+        source_index[whole.start()]
+    } else {
+        source_index[whole.end() - 1] + 1
+    };
+    Some(OutputMatch {
+        text: whole.as_str().to_string(),
+        groups: captures
+            .iter()
+            .skip(1)
+            .map(|group| group.map(|group| group.as_str().to_string()))
+            .collect(),
+        context: lines[context_start..=context_end].join("\n"),
+        offset: base_offset + source_index[whole.start()] as u64,
+        end_offset: base_offset + match_end as u64,
+    })
+}
+
 /// Parse a %output line: "%output %<pane_id> <data>"
 fn parse_output_line(line: &str) -> Option<(String, String)> {
     // Format: "%output %N <data>"
@@ -461,7 +912,12 @@ fn decode_tmux_output(data: &str) -> Vec<u8> {
 
 #[cfg(test)]
 mod tests {
-    use super::{control_line_from_bytes, parse_session_changed_id};
+    use super::{
+        build_output_capture, control_line_from_bytes, exec_wait_wrapped_command, find_output_match,
+        parse_exec_wait_output, parse_session_changed_id, partial_exec_wait_output, resolve_capture_range, strip_ansi,
+        OutputCaptureMatch, OutputCaptureOptions, PaneOutputBuffer,
+    };
+    use regex::Regex;
 
     #[test]
     fn parses_session_changed_events() {
@@ -481,4 +937,133 @@ mod tests {
         assert_eq!(control_line_from_bytes(b"%layout-change @1\r\n"), "%layout-change @1");
         assert!(control_line_from_bytes(&[b'%', 0xff, b'\n']).starts_with('%'));
     }
+
+    #[test]
+    fn pane_output_ring_reads_by_offset_without_draining() {
+        let mut buffer = PaneOutputBuffer::new(8);
+        buffer.push(b"hello");
+        let first = buffer.read(None, None);
+        assert_eq!((first.output.as_str(), first.offset, first.next_offset, first.truncated), ("hello", 0, 5, false));
+        assert_eq!(buffer.read(None, None).output, "hello");
+
+        buffer.push(b" world");
+        assert_eq!(buffer.end_offset(), 11);
+        let resumed = buffer.read(Some(first.next_offset), Some(3));
+        assert_eq!((resumed.output.as_str(), resumed.offset, resumed.next_offset), (" wo", 5, 8));
+
+        let evicted = buffer.read(Some(0), None);
+        assert!(evicted.truncated);
+        assert_eq!((evicted.output.as_str(), evicted.offset), ("lo world", 3));
+        assert_eq!(buffer.read(Some(99), None).next_offset, 11);
+    }
+
+    #[test]
+    fn pane_output_reads_do_not_split_utf8_sequences() {
+        let mut buffer = PaneOutputBuffer::new(64);
+        buffer.push("aé!".as_bytes());
+        let chunk = buffer.read(None, Some(2));
+        assert_eq!((chunk.output.as_str(), chunk.next_offset), ("a", 1));
+        let rest = buffer.read(Some(chunk.next_offset), None);
+        assert_eq!(rest.output, "é!");
+    }
+
+    #[test]
+    fn wraps_exec_commands_with_sentinels_and_parses_completion() {
+        let wrapped = exec_wait_wrapped_command("abc123", "echo 'hi'\n");
+        assert_eq!(
+            wrapped,
+            concat!(
+                r#"printf '\n__HERD_EXEC_%s_START__\n' abc123; eval 'echo '\''hi'\'''; "#,
+                r#"printf '\n__HERD_EXEC_%s_END_%s__\n' abc123 "$?""#,
+                "\n"
+            )
+        );
+        assert!(!wrapped.contains("__HERD_EXEC_abc123_START__"));
+
+        let running = "$ printf ... abc123\r\n\r\n__HERD_EXEC_abc123_START__\r\nhi\r\n";
+        assert_eq!(parse_exec_wait_output(running, "abc123"), None);
+        assert_eq!(partial_exec_wait_output(running, "abc123"), "hi");
+
+        let finished = format!("{running}there\r\n\r\n__HERD_EXEC_abc123_END_2__\r\n$ ");
+        assert_eq!(
+            parse_exec_wait_output(&finished, "abc123"),
+            Some((2, "hi\r\nthere\r\n".to_string()))
+        );
+        assert_eq!(parse_exec_wait_output(&finished, "other"), None);
+        assert_eq!(partial_exec_wait_output(&finished, "other"), "");
+    }
+
+    #[test]
+    fn strips_ansi_sequences_from_captured_text() {
+        assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m done"), "ok done");
+        assert_eq!(strip_ansi("\x1b]0;title\x07prompt\x1b]2;t\x1b\\ $"), "prompt $");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn resolves_capture_ranges_and_search_matches() {
+        let plain = OutputCaptureOptions::default();
+        assert_eq!(resolve_capture_range(&plain, 100, 24), Ok((0, 23)));
+        let search = OutputCaptureOptions {
+            search: Some("error".to_string()),
+            ..OutputCaptureOptions::default()
+        };
+        assert_eq!(resolve_capture_range(&search, 100, 24), Ok((-100, 23)));
+        let clamped = OutputCaptureOptions {
+            start_line: Some(-500),
+            end_line: Some(5),
+            ..OutputCaptureOptions::default()
+        };
+        assert_eq!(resolve_capture_range(&clamped, 100, 24), Ok((-100, 5)));
+        let inverted = OutputCaptureOptions {
+            start_line: Some(4),
+            end_line: Some(2),
+            ..OutputCaptureOptions::default()
+        };
+        assert!(resolve_capture_range(&inverted, 100, 24).is_err());
+
+        let capture = build_output_capture("ok\n\x1b[31merror: one\x1b[0m\nfine\nerror: two\n", -2, 1, Some("error"), None);
+        assert_eq!(capture.line_count, 4);
+        assert_eq!(capture.output, "ok\n\x1b[31merror: one\x1b[0m\nfine\nerror: two");
+        assert_eq!(
+            capture.matches,
+            Some(vec![
+                OutputCaptureMatch { line: -1, text: "error: one".to_string() },
+                OutputCaptureMatch { line: 1, text: "error: two".to_string() },
+            ])
+        );
+        assert_eq!(build_output_capture("", 0, 0, None, None).line_count, 0);
+
+        let joined = build_output_capture("0123456789abcdef\n界界界界界\nerror: three\n", 0, 5, Some("error"), Some(8));
+        assert_eq!(
+            joined.matches,
+            Some(vec![OutputCaptureMatch { line: 4, text: "error: three".to_string() }])
+        );
+    }
+
+    #[test]
+    fn finds_output_matches_with_raw_offsets_and_context() {
+        let output = "make test\r\nrunning 3 tests\r\n\x1b[32mTests passed: 3\x1b[0m\r\n$ ";
+        let pattern = Regex::new(r"Tests passed: (\d+)").unwrap();
+        let found = find_output_match(output.as_bytes(), 100, &pattern, 1).unwrap();
+        assert_eq!(found.text, "Tests passed: 3");
+        assert_eq!(found.groups, vec![Some("3".to_string())]);
+        assert_eq!(found.context, "running 3 tests\nTests passed: 3\n$ ");
+        let raw_start = output.find("Tests").unwrap() as u64;
+        assert_eq!(found.offset, 100 + raw_start);
+        assert_eq!(found.end_offset, 100 + raw_start + "Tests passed: 3".len() as u64);
+
+        let prompt = find_output_match(output.as_bytes(), 0, &Regex::new(r"\$ $").unwrap(), 0).unwrap();
+        assert_eq!(prompt.context, "$ ");
+        assert_eq!(prompt.end_offset, output.len() as u64);
+        assert!(find_output_match(output.as_bytes(), 0, &Regex::new("FAILED").unwrap(), 2).is_none());
+
+        let invalid = b"\xff\xfe bad \xe2\x82 ok: 7";
+        let found = find_output_match(invalid, 10, &Regex::new(r"ok: (\d)").unwrap(), 0).unwrap();
+        assert_eq!(found.offset, 10 + 10);
+        assert_eq!(found.end_offset, 10 + invalid.len() as u64);
+        let replaced = find_output_match(invalid, 0, &Regex::new("bad \u{FFFD}").unwrap(), 0).unwrap();
+        assert_eq!(replaced.offset, 3);
+        assert_eq!(replaced.end_offset, 9);
+    }
 }
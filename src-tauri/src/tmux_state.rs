@@ -1,13 +1,16 @@
 use serde::Serialize;
 use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
 use std::process::Output;
 use tauri::{AppHandle, Emitter, Manager};
 
 use crate::{
     agent::AgentRole,
     browser::BrowserBackend,
+    launch_policy::{self, SessionLaunchPolicies},
     network::{self, NetworkTileKind},
     runtime,
+    session_env::PaneEnvFile,
     state::{AppState, WindowParentSource},
     tile_registry::TileRecord,
     tmux,
@@ -35,6 +38,7 @@ pub struct TmuxSession {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub root_cwd: Option<String>,
     pub browser_backend: BrowserBackend,
+    pub launch_policies: SessionLaunchPolicies,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -140,6 +144,7 @@ fn parse_snapshot(
             active_window_id: None,
             root_cwd: None,
             browser_backend: BrowserBackend::default(),
+            launch_policies: SessionLaunchPolicies::default(),
         });
     }
 
@@ -284,6 +289,7 @@ fn parse_snapshot(
             active_window_id: None,
             root_cwd: None,
             browser_backend: BrowserBackend::default(),
+            launch_policies: SessionLaunchPolicies::default(),
         });
     }
 
@@ -698,39 +704,54 @@ pub fn respawn_window(window_id: &str) -> Result<(), String> {
     Ok(())
 }
 
-fn respawn_pane_shell_command_args(pane_id: &str, command: &str, tile_id: Option<&str>) -> Vec<String> {
-    let herd_sock = format!("HERD_SOCK={}", runtime::socket_path());
+fn respawn_pane_shell_command_args(
+    pane_id: &str,
+    command: &str,
+    tile_id: Option<&str>,
+    env_file: Option<&PaneEnvFile>,
+) -> Vec<String> {
     let mut args = vec![
         "respawn-pane".to_string(),
         "-k".to_string(),
         "-t".to_string(),
         pane_id.to_string(),
         "-e".to_string(),
-        herd_sock,
-        "/bin/bash".to_string(),
-        "-lc".to_string(),
-        command.to_string(),
+        format!("HERD_SOCK={}", runtime::socket_path()),
     ];
     if let Some(tile_id) = tile_id {
-        args.splice(
-            6..6,
-            [
-                "-e".to_string(),
-                format!("HERD_TILE_ID={tile_id}"),
-            ],
-        );
+        args.push("-e".to_string());
+        args.push(format!("HERD_TILE_ID={tile_id}"));
     }
+    let command = match env_file {
+        Some(env_file) => format!("{}{command}", env_file.source_line()),
+        None => command.to_string(),
+    };
+    args.extend(["/bin/bash".to_string(), "-lc".to_string(), command]);
     args
 }
 
 pub fn respawn_pane_shell_command(pane_id: &str, command: &str, tile_id: Option<&str>) -> Result<(), String> {
-    let args = respawn_pane_shell_command_args(pane_id, command, tile_id);
+    respawn_pane_shell_command_with_env(pane_id, command, tile_id, &[])
+}
+
+/// Same as [`respawn_pane_shell_command`] but also exports the selected
+/// session env entries in the pane through a [`PaneEnvFile`].
+pub fn respawn_pane_shell_command_with_env(
+    pane_id: &str,
+    command: &str,
+    tile_id: Option<&str>,
+    env: &[(String, String)],
+) -> Result<(), String> {
+    let env_file = PaneEnvFile::write(env)?;
+    let args = respawn_pane_shell_command_args(pane_id, command, tile_id, env_file.as_ref());
     let arg_refs = args.iter().map(String::as_str).collect::<Vec<_>>();
-    ensure_success(
-        run_tmux(&arg_refs)?,
-        "tmux respawn-pane failed",
-    )?;
-    Ok(())
+    let result = run_tmux(&arg_refs).and_then(|output| ensure_success(output, "tmux respawn-pane failed"));
+    if result.is_err() {
+        if let Some(env_file) = env_file {
+            env_file.discard();
+        }
+    }
+    result.map(|_| ())
 }
 
 pub fn kill_session(session_id: &str) -> Result<(), String> {
@@ -869,6 +890,8 @@ pub fn snapshot(state: &AppState) -> Result<TmuxSnapshot, String> {
         window.parent_window_source = window_parent_sources.get(&window.id).copied();
     }
 
+    let mut launch_policies =
+        launch_policy::list_session_policies_at(Path::new(runtime::database_path())).unwrap_or_default();
     for session in &mut snapshot.sessions {
         session.root_cwd = session_root_cwd(&session.id)
             .ok()
@@ -878,6 +901,7 @@ pub fn snapshot(state: &AppState) -> Result<TmuxSnapshot, String> {
             .ok()
             .flatten()
             .unwrap_or_else(default_session_browser_backend);
+        session.launch_policies = launch_policies.remove(&session.id).unwrap_or_default();
     }
 
     Ok(snapshot)
@@ -938,6 +962,7 @@ mod tests {
         PaneNormalizationRow,
     };
     use crate::runtime;
+    use crate::session_env::PaneEnvFile;
 
     #[test]
     fn parse_snapshot_keeps_sessions_windows_and_panes() {
@@ -1029,10 +1054,25 @@ mod tests {
 
     #[test]
     fn shell_respawn_args_include_socket_and_tile_env() {
-        let args = respawn_pane_shell_command_args("%42", "printf ready\\n", Some("tile-abc123"));
+        let args = respawn_pane_shell_command_args(
+            "%42",
+            "printf ready\\n",
+            Some("tile-abc123"),
+            None,
+        );
         assert_eq!(args[0], "respawn-pane");
         assert!(args.contains(&format!("HERD_SOCK={}", runtime::socket_path())));
         assert!(args.contains(&"HERD_TILE_ID=tile-abc123".to_string()));
         assert!(args.ends_with(&["/bin/bash".to_string(), "-lc".to_string(), "printf ready\\n".to_string()]));
+
+        let env_file = PaneEnvFile::write(&[("API_TOKEN".to_string(), "s3cret".to_string())])
+            .unwrap()
+            .unwrap();
+        let args = respawn_pane_shell_command_args("%42", "printf ready\\n", None, Some(&env_file));
+        assert!(args.iter().all(|arg| !arg.contains("s3cret")));
+        let command = args.last().unwrap();
+        assert!(command.starts_with(&env_file.source_line()));
+        assert!(command.ends_with("printf ready\\n"));
+        env_file.discard();
     }
 }
@@ -4,7 +4,7 @@ use std::path::Path;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
-use crate::{agent::now_ms, db, network};
+use crate::{agent::now_ms, db, network, network_history::NetworkChangeActor};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
@@ -85,6 +85,16 @@ pub struct WorkReviewEntry {
     pub created_at: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkAttachment {
+    pub stage: WorkStage,
+    pub path: String,
+    pub media_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WorkItem {
     pub work_id: String,
@@ -97,6 +107,8 @@ pub struct WorkItem {
     pub current_stage: WorkStage,
     pub stages: Vec<WorkStageState>,
     pub reviews: Vec<WorkReviewEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<WorkAttachment>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -431,6 +443,8 @@ pub fn delete_work_item_at(
 
     tx.execute("DELETE FROM work_review WHERE work_id = ?1", [work_id])
         .map_err(|error| format!("failed to delete work reviews for {work_id}: {error}"))?;
+    tx.execute("DELETE FROM work_attachment WHERE work_id = ?1", [work_id])
+        .map_err(|error| format!("failed to delete work attachments for {work_id}: {error}"))?;
     tx.execute("DELETE FROM work_stage WHERE work_id = ?1", [work_id])
         .map_err(|error| format!("failed to delete work stages for {work_id}: {error}"))?;
     tx.execute("DELETE FROM work_item WHERE work_id = ?1", [work_id])
@@ -438,7 +452,12 @@ pub fn delete_work_item_at(
 
     tx.commit()
         .map_err(|error| format!("failed to commit work delete transaction: {error}"))?;
-    let _ = network::disconnect_all_for_tile_at(db_path, &item.session_id, &item.tile_id);
+    let _ = network::disconnect_all_for_tile_at(
+        db_path,
+        &item.session_id,
+        &item.tile_id,
+        &NetworkChangeActor::system(),
+    );
 
     Ok(())
 }
@@ -576,6 +595,40 @@ pub fn improve_work_stage_at(
     get_work_item_at(db_path, work_id)
 }
 
+pub fn attach_work_item_file_at(
+    db_path: &Path,
+    work_id: &str,
+    path: &Path,
+    media_type: &str,
+    label: Option<&str>,
+) -> Result<WorkItem, String> {
+    if !path.is_file() {
+        return Err(format!("attachment {} is not a file", path.display()));
+    }
+    let label = label.map(str::trim).filter(|value| !value.is_empty());
+    let mut conn = db::open_at(db_path)?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("failed to begin work attachment transaction: {error}"))?;
+    let item = load_work_item_with_conn(&tx, work_id)?;
+    tx.execute(
+        "INSERT INTO work_attachment (work_id, stage_name, path, media_type, label, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            work_id,
+            item.current_stage.as_str(),
+            path.to_string_lossy(),
+            media_type,
+            label,
+            now_ms(),
+        ],
+    )
+    .map_err(|error| format!("failed to insert attachment for {work_id}: {error}"))?;
+    touch_work_item(&tx, work_id)?;
+    tx.commit()
+        .map_err(|error| format!("failed to commit work attachment transaction: {error}"))?;
+    get_work_item_at(db_path, work_id)
+}
+
 pub fn remove_legacy_work_directory(project_root: &Path) -> Result<(), String> {
     let work_root = project_root.join("work");
     if !work_root.exists() {
@@ -808,6 +861,35 @@ fn load_work_item_with_conn(conn: &Connection, work_id: &str) -> Result<WorkItem
         }
     }
 
+    let mut attachments = Vec::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT stage_name, path, media_type, label, created_at FROM work_attachment WHERE work_id = ?1 ORDER BY created_at ASC, id ASC")
+            .map_err(|error| format!("failed to prepare attachment query: {error}"))?;
+        let rows = stmt
+            .query_map([work_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .map_err(|error| format!("failed to query work attachments: {error}"))?;
+        for row in rows {
+            let (stage_name, path, media_type, label, created_at) =
+                row.map_err(|error| format!("failed to read attachment row: {error}"))?;
+            attachments.push(WorkAttachment {
+                stage: parse_stage(&stage_name)?,
+                path,
+                media_type,
+                label,
+                created_at,
+            });
+        }
+    }
+
     Ok(WorkItem {
         work_id: work_id.to_string(),
         tile_id,
@@ -818,6 +900,7 @@ fn load_work_item_with_conn(conn: &Connection, work_id: &str) -> Result<WorkItem
         current_stage,
         stages,
         reviews,
+        attachments,
         created_at: row.6,
         updated_at: row.7,
     })
@@ -866,7 +949,7 @@ fn parse_review_decision(value: &str) -> Result<WorkReviewDecision, String> {
 #[cfg(test)]
 mod tests {
     use super::{
-        approve_work_stage_at, complete_work_stage_at, create_work_item_at, delete_work_item_at,
+        approve_work_stage_at, attach_work_item_file_at, complete_work_stage_at, create_work_item_at, delete_work_item_at,
         get_work_item_at, improve_work_stage_at, list_work_at, read_current_stage_preview_at,
         start_work_stage_at, WorkListScope, WorkStage, WorkStageStatus,
     };
@@ -874,6 +957,7 @@ mod tests {
         agent::{AgentInfo, AgentRole, AgentType},
         db,
         network::{self, NetworkTileDescriptor, NetworkTileKind, TilePort},
+        network_history::NetworkChangeActor,
     };
     use std::fs;
     use std::path::PathBuf;
@@ -904,6 +988,7 @@ mod tests {
             chatter_subscribed: true,
             channels: Vec::new(),
             agent_pid: None,
+            worktree: None,
         }
     }
 
@@ -928,6 +1013,7 @@ mod tests {
                 kind: NetworkTileKind::Work,
             },
             TilePort::Left,
+            &NetworkChangeActor::user(),
         )
         .unwrap();
     }
@@ -991,6 +1077,7 @@ mod tests {
             &owner.session_id,
             &item.tile_id,
             TilePort::Left,
+            &NetworkChangeActor::user(),
         )
         .unwrap();
         let unowned = get_work_item_at(&db_path, &item.work_id).unwrap();
@@ -1084,7 +1171,8 @@ mod tests {
         let item = get_work_item_at(&db_path, &work_id).unwrap();
         assert_eq!(item.owner_agent_id.as_deref(), Some(owner.agent_id.as_str()));
 
-        network::disconnect_all_for_tile_at(&db_path, &owner.session_id, &owner.tile_id).unwrap();
+        network::disconnect_all_for_tile_at(&db_path, &owner.session_id, &owner.tile_id, &NetworkChangeActor::user())
+            .unwrap();
         let item = get_work_item_at(&db_path, &work_id).unwrap();
         assert_eq!(item.owner_agent_id, None);
     }
@@ -1105,6 +1193,46 @@ mod tests {
         assert!(lookup_error.contains("unknown work item"));
     }
 
+    #[test]
+    fn attaches_files_to_the_current_stage() {
+        let db_path = temp_db_path("attach");
+        let project_root = temp_root("attach-project");
+        db::open_at(&db_path).unwrap();
+
+        let item = create_work_item_at(&db_path, "$5", "Record a demo").unwrap();
+        let missing_error = attach_work_item_file_at(
+            &db_path,
+            &item.work_id,
+            &project_root.join("missing.gif"),
+            "image/gif",
+            None,
+        )
+        .unwrap_err();
+        assert!(missing_error.contains("is not a file"));
+
+        let recording = project_root.join("demo.gif");
+        fs::write(&recording, b"GIF89a").unwrap();
+        let attached = attach_work_item_file_at(
+            &db_path,
+            &item.work_id,
+            &recording,
+            "image/gif",
+            Some(" demo recording "),
+        )
+        .unwrap();
+        assert_eq!(attached.attachments.len(), 1);
+        assert_eq!(attached.attachments[0].stage, WorkStage::Plan);
+        assert_eq!(attached.attachments[0].path, recording.to_string_lossy());
+        assert_eq!(attached.attachments[0].label.as_deref(), Some("demo recording"));
+
+        delete_work_item_at(&db_path, &item.work_id).unwrap();
+        let remaining = db::open_at(&db_path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM work_attachment", [], |row| row.get::<_, i64>(0))
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
     #[test]
     fn lists_completed_review_items_before_newer_in_progress_items() {
         let db_path = temp_db_path("ordering");
@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+
+/// A git worktree created for a single worker agent so that concurrent agents
+/// do not edit the same checkout. The record outlives the agent tile so the
+/// branch can still be inspected and cleaned up after the tile is destroyed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AgentWorktree {
+    pub agent_id: String,
+    pub session_id: String,
+    pub tile_id: String,
+    pub repo_root: String,
+    pub path: String,
+    pub cwd: String,
+    pub branch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_branch: Option<String>,
+    pub base_commit: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct WorktreeStatus {
+    #[serde(flatten)]
+    pub worktree: AgentWorktree,
+    pub exists: bool,
+    pub branch_exists: bool,
+    pub ahead: u64,
+    pub behind: u64,
+    pub dirty_files: usize,
+    pub merged: bool,
+}
+
+/// Worktrees live next to the repository they branch from rather than inside
+/// it or inside Herd's own checkout, so they never show up as untracked files.
+pub fn managed_worktree_dir(repo_root: &Path) -> PathBuf {
+    repo_root.parent().unwrap_or(repo_root).join(".herd-worktrees")
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|error| format!("failed to run git {}: {error}", args.join(" ")))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+fn git_succeeds(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn worktree_name(agent_id: &str) -> String {
+    let short = agent_id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    format!("agent-{short}")
+}
+
+/// Creates `herd/agent-<id>` from the current HEAD of the repository that
+/// contains `cwd`, checked out under [`managed_worktree_dir`]. The returned `cwd`
+/// keeps the same relative position inside the worktree as `cwd` had inside the repo.
+pub fn create_agent_worktree(
+    cwd: &Path,
+    agent_id: &str,
+    session_id: &str,
+    tile_id: &str,
+) -> Result<AgentWorktree, String> {
+    let repo_root = PathBuf::from(
+        git(cwd, &["rev-parse", "--show-toplevel"])
+            .map_err(|_| format!("spawn directory {} is not inside a git repository", cwd.display()))?,
+    );
+    let prefix = git(cwd, &["rev-parse", "--show-prefix"])?;
+    let base_commit = git(&repo_root, &["rev-parse", "--verify", "HEAD"])
+        .map_err(|_| format!("repository {} has no commits to branch from", repo_root.display()))?;
+    let base_branch = git(&repo_root, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+
+    let name = worktree_name(agent_id);
+    let repo_name = repo_root
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    let managed_dir = managed_worktree_dir(&repo_root);
+    let path = managed_dir.join(format!("{repo_name}-{name}"));
+    let branch = format!("herd/{name}");
+    fs::create_dir_all(&managed_dir)
+        .map_err(|error| format!("failed to create worktree directory {}: {error}", managed_dir.display()))?;
+    let path_arg = path.to_string_lossy().to_string();
+    git(&repo_root, &["worktree", "add", "-b", &branch, &path_arg, &base_commit])?;
+
+    Ok(AgentWorktree {
+        agent_id: agent_id.to_string(),
+        session_id: session_id.to_string(),
+        tile_id: tile_id.to_string(),
+        repo_root: repo_root.to_string_lossy().to_string(),
+        path: path_arg,
+        cwd: path.join(prefix).to_string_lossy().trim_end_matches('/').to_string(),
+        branch,
+        base_branch,
+        base_commit,
+        created_at: crate::agent::now_ms(),
+    })
+}
+
+/// Compares the agent branch with the branch it was created from, falling back
+/// to the original commit when the repository was on a detached HEAD.
+pub fn worktree_status(worktree: &AgentWorktree) -> Result<WorktreeStatus, String> {
+    let repo_root = Path::new(&worktree.repo_root);
+    let branch_ref = format!("refs/heads/{}", worktree.branch);
+    let branch_exists = git_succeeds(repo_root, &["rev-parse", "--verify", "--quiet", &branch_ref]);
+    let exists = Path::new(&worktree.path).is_dir();
+    let base = worktree.base_branch.as_deref().unwrap_or(&worktree.base_commit);
+
+    let (mut ahead, mut behind, mut merged) = (0, 0, false);
+    if branch_exists {
+        let counts = git(
+            repo_root,
+            &["rev-list", "--left-right", "--count", &format!("{base}...{}", worktree.branch)],
+        )?;
+        (behind, ahead) = parse_left_right_counts(&counts)?;
+        merged = git_succeeds(repo_root, &["merge-base", "--is-ancestor", &worktree.branch, base]);
+    }
+    let dirty_files = if exists {
+        git(Path::new(&worktree.path), &["status", "--porcelain"])?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count()
+    } else {
+        0
+    };
+
+    Ok(WorktreeStatus {
+        worktree: worktree.clone(),
+        exists,
+        branch_exists,
+        ahead,
+        behind,
+        dirty_files,
+        merged,
+    })
+}
+
+fn parse_left_right_counts(value: &str) -> Result<(u64, u64), String> {
+    let mut parts = value.split_whitespace().map(str::parse::<u64>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(left)), Some(Ok(right))) => Ok((left, right)),
+        _ => Err(format!("unexpected git rev-list output: {value}")),
+    }
+}
+
+/// Removes the worktree checkout and optionally its branch. Without `force`
+/// nothing is touched when the checkout has uncommitted changes or the branch
+/// to delete has commits that are not in its base.
+pub fn remove_agent_worktree(worktree: &AgentWorktree, force: bool, delete_branch: bool) -> Result<(), String> {
+    let repo_root = Path::new(&worktree.repo_root);
+    if !force {
+        let status = worktree_status(worktree)?;
+        if status.dirty_files > 0 {
+            return Err(format!(
+                "worktree {} has {} uncommitted change(s); pass force to discard them",
+                worktree.path, status.dirty_files
+            ));
+        }
+        if delete_branch && status.branch_exists && !status.merged {
+            return Err(format!(
+                "branch {} has {} commit(s) not merged into {}; pass force to delete it",
+                worktree.branch,
+                status.ahead,
+                worktree.base_branch.as_deref().unwrap_or(&worktree.base_commit)
+            ));
+        }
+    }
+    if Path::new(&worktree.path).exists() {
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(&worktree.path);
+        git(repo_root, &args)?;
+    } else {
+        git(repo_root, &["worktree", "prune"])?;
+    }
+    if delete_branch {
+        let branch_ref = format!("refs/heads/{}", worktree.branch);
+        if git_succeeds(repo_root, &["rev-parse", "--verify", "--quiet", &branch_ref]) {
+            // Merged status was checked against the base above; `branch -d` would
+            // compare with whatever the repository has checked out instead.
+            git(repo_root, &["branch", "-D", &worktree.branch])?;
+        }
+    }
+    Ok(())
+}
+
+/// Rolls back the worktree of an agent that never started: nothing in it is the
+/// agent's work yet, so the checkout and branch are force-removed along with the
+/// stored row.
+pub fn discard_agent_worktree_at(db_path: &Path, worktree: &AgentWorktree) -> Result<(), String> {
+    remove_agent_worktree(worktree, true, true)?;
+    delete_worktree_at(db_path, &worktree.agent_id)?;
+    Ok(())
+}
+
+pub fn upsert_worktree_at(db_path: &Path, worktree: &AgentWorktree) -> Result<(), String> {
+    let conn = db::open_at(db_path)?;
+    upsert_worktree_with_conn(&conn, worktree)
+}
+
+fn upsert_worktree_with_conn(conn: &Connection, worktree: &AgentWorktree) -> Result<(), String> {
+    let data_json =
+        serde_json::to_string(worktree).map_err(|error| format!("failed to encode agent worktree: {error}"))?;
+    conn.execute(
+        "INSERT INTO agent_worktree (agent_id, session_id, tile_id, data_json, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(agent_id) DO UPDATE SET
+           session_id = excluded.session_id,
+           tile_id = excluded.tile_id,
+           data_json = excluded.data_json",
+        params![worktree.agent_id, worktree.session_id, worktree.tile_id, data_json, worktree.created_at],
+    )
+    .map_err(|error| format!("failed to store agent worktree: {error}"))?;
+    Ok(())
+}
+
+pub fn get_worktree_at(db_path: &Path, agent_id: &str) -> Result<Option<AgentWorktree>, String> {
+    let conn = db::open_at(db_path)?;
+    let data_json = conn
+        .query_row(
+            "SELECT data_json FROM agent_worktree WHERE agent_id = ?1",
+            [agent_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|error| format!("failed to query agent worktree: {error}"))?;
+    data_json
+        .map(|data_json| {
+            serde_json::from_str(&data_json)
+                .map_err(|error| format!("failed to parse agent worktree for {agent_id}: {error}"))
+        })
+        .transpose()
+}
+
+pub fn worktree_for_tile_at(db_path: &Path, tile_id: &str) -> Result<Option<AgentWorktree>, String> {
+    Ok(list_worktrees_at(db_path, None)?
+        .into_iter()
+        .find(|worktree| worktree.tile_id == tile_id))
+}
+
+pub fn delete_worktree_at(db_path: &Path, agent_id: &str) -> Result<bool, String> {
+    let conn = db::open_at(db_path)?;
+    let removed = conn
+        .execute("DELETE FROM agent_worktree WHERE agent_id = ?1", [agent_id])
+        .map_err(|error| format!("failed to delete agent worktree: {error}"))?;
+    Ok(removed > 0)
+}
+
+pub fn list_worktrees_at(db_path: &Path, session_id: Option<&str>) -> Result<Vec<AgentWorktree>, String> {
+    let conn = db::open_at(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT agent_id, data_json
+             FROM agent_worktree
+             WHERE (?1 IS NULL OR session_id = ?1)
+             ORDER BY created_at ASC, agent_id ASC",
+        )
+        .map_err(|error| format!("failed to prepare agent worktree query: {error}"))?;
+    let rows = stmt
+        .query_map(params![session_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|error| format!("failed to query agent worktrees: {error}"))?;
+    let mut worktrees = Vec::new();
+    for row in rows {
+        let (agent_id, data_json) = row.map_err(|error| format!("failed to decode agent worktree row: {error}"))?;
+        let worktree = serde_json::from_str(&data_json)
+            .map_err(|error| format!("failed to parse agent worktree for {agent_id}: {error}"))?;
+        worktrees.push(worktree);
+    }
+    Ok(worktrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        create_agent_worktree, delete_worktree_at, discard_agent_worktree_at, get_worktree_at, list_worktrees_at,
+        parse_left_right_counts, remove_agent_worktree, upsert_worktree_at, worktree_for_tile_at, worktree_name,
+        worktree_status,
+    };
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "herd-worktree-test-{name}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Herd", "-c", "user.email=herd@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn names_worktrees_from_the_agent_id() {
+        assert_eq!(worktree_name("3F2A-91bc-77d0"), "agent-3f2a91bc");
+        assert_eq!(parse_left_right_counts("2\t5").unwrap(), (2, 5));
+        assert!(parse_left_right_counts("nope").is_err());
+    }
+
+    #[test]
+    fn creates_reports_and_removes_an_agent_worktree() {
+        let dir = temp_dir("lifecycle");
+        let repo = dir.join("project");
+        fs::create_dir_all(repo.join("app")).unwrap();
+        run_git(&repo, &["init", "-q", "-b", "main"]);
+        fs::write(repo.join("app/README.md"), "hello\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        let worktree = create_agent_worktree(&repo.join("app"), "abcd1234-ef", "$1", "tile-9").unwrap();
+        assert_eq!(worktree.branch, "herd/agent-abcd1234");
+        assert_eq!(worktree.base_branch.as_deref(), Some("main"));
+        assert_eq!(Path::new(&worktree.path), dir.join(".herd-worktrees/project-agent-abcd1234"));
+        assert!(worktree.cwd.ends_with("project-agent-abcd1234/app"));
+        assert!(Path::new(&worktree.cwd).join("README.md").is_file());
+
+        let worktree_path = PathBuf::from(&worktree.path);
+        fs::write(worktree_path.join("app/notes.md"), "wip\n").unwrap();
+        let status = worktree_status(&worktree).unwrap();
+        assert!(status.exists && status.branch_exists);
+        assert_eq!((status.ahead, status.behind, status.dirty_files), (0, 0, 1));
+        assert!(status.merged);
+        assert!(remove_agent_worktree(&worktree, false, true)
+            .unwrap_err()
+            .contains("1 uncommitted change(s)"));
+        assert!(worktree_path.join("app/notes.md").is_file());
+
+        run_git(&worktree_path, &["add", "."]);
+        run_git(&worktree_path, &["commit", "-q", "-m", "agent work"]);
+        let status = worktree_status(&worktree).unwrap();
+        assert_eq!((status.ahead, status.dirty_files), (1, 0));
+        assert!(!status.merged);
+
+        assert_eq!(
+            remove_agent_worktree(&worktree, false, true).unwrap_err(),
+            "branch herd/agent-abcd1234 has 1 commit(s) not merged into main; pass force to delete it"
+        );
+        assert!(worktree_path.join("app/notes.md").is_file());
+        remove_agent_worktree(&worktree, true, true).unwrap();
+        let status = worktree_status(&worktree).unwrap();
+        assert!(!status.exists && !status.branch_exists);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn discards_the_worktree_of_an_agent_that_never_started() {
+        let dir = temp_dir("discard");
+        let repo = dir.join("project");
+        fs::create_dir_all(&repo).unwrap();
+        run_git(&repo, &["init", "-q", "-b", "main"]);
+        fs::write(repo.join("README.md"), "hello\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        let db_path = dir.join("herd.sqlite");
+        let worktree = create_agent_worktree(&repo, "0badf00d-1", "$1", "tile-1").unwrap();
+        upsert_worktree_at(&db_path, &worktree).unwrap();
+
+        discard_agent_worktree_at(&db_path, &worktree).unwrap();
+        let status = worktree_status(&worktree).unwrap();
+        assert!(!status.exists && !status.branch_exists);
+        assert_eq!(get_worktree_at(&db_path, "0badf00d-1").unwrap(), None);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn stores_agent_worktrees_by_agent_and_tile() {
+        let dir = temp_dir("db");
+        let db_path = dir.join("herd.sqlite");
+        let worktree = super::AgentWorktree {
+            agent_id: "agent-1".to_string(),
+            session_id: "$1".to_string(),
+            tile_id: "tile-1".to_string(),
+            repo_root: "/repo".to_string(),
+            path: "/worktrees/repo-agent-agent1".to_string(),
+            cwd: "/worktrees/repo-agent-agent1".to_string(),
+            branch: "herd/agent-agent1".to_string(),
+            base_branch: None,
+            base_commit: "abc123".to_string(),
+            created_at: 10,
+        };
+        upsert_worktree_at(&db_path, &worktree).unwrap();
+
+        assert_eq!(get_worktree_at(&db_path, "agent-1").unwrap(), Some(worktree.clone()));
+        assert_eq!(worktree_for_tile_at(&db_path, "tile-1").unwrap(), Some(worktree.clone()));
+        assert_eq!(list_worktrees_at(&db_path, Some("$2")).unwrap(), Vec::new());
+        assert!(delete_worktree_at(&db_path, "agent-1").unwrap());
+        assert_eq!(get_worktree_at(&db_path, "agent-1").unwrap(), None);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -2,14 +2,18 @@ import { invoke } from '@tauri-apps/api/core';
 import type {
   AgentBrowserInstallStatus,
   AgentDebugState,
+  AgentWorktree,
   BrowserBackend,
   BrowserExtensionPage,
   ClaudeMenuData,
   LayoutStateMap,
   LoadedSessionConfiguration,
   NetworkConnection,
+  PaneOutputSeed,
   PortMode,
   PortNetworkingMode,
+  SessionEnvEntry,
+  SessionLaunchPolicies,
   SavedSessionConfigurationSummary,
   TilePortSetting,
   TilePort,
@@ -170,6 +174,30 @@ export async function setSessionBrowserBackend(sessionId: string, backend: Brows
   return invoke<string>('set_session_browser_backend', { sessionId, backend });
 }
 
+export async function setSessionLaunchPolicies(
+  sessionId: string,
+  policies: SessionLaunchPolicies,
+): Promise<SessionLaunchPolicies> {
+  return invoke<SessionLaunchPolicies>('set_session_launch_policies', { sessionId, policies });
+}
+
+export async function listSessionEnv(sessionId: string): Promise<SessionEnvEntry[]> {
+  return invoke<SessionEnvEntry[]>('list_session_env', { sessionId });
+}
+
+export async function setSessionEnvEntry(
+  sessionId: string,
+  name: string,
+  value: string,
+  secret = false,
+): Promise<SessionEnvEntry> {
+  return invoke<SessionEnvEntry>('set_session_env_entry', { sessionId, name, value, secret });
+}
+
+export async function unsetSessionEnvEntry(sessionId: string, name: string): Promise<boolean> {
+  return invoke<boolean>('unset_session_env_entry', { sessionId, name });
+}
+
 export async function getAgentBrowserInstallStatus(): Promise<AgentBrowserInstallStatus> {
   return invoke<AgentBrowserInstallStatus>('get_agent_browser_install_status');
 }
@@ -186,7 +214,11 @@ export async function newWindow(targetSessionId?: string | null): Promise<string
   return invoke<string>('new_window', { targetSessionId: targetSessionId ?? null });
 }
 
-export async function spawnAgentWindow(targetSessionId?: string | null): Promise<{
+export async function spawnAgentWindow(
+  targetSessionId?: string | null,
+  worktree?: boolean,
+  env?: string[],
+): Promise<{
   agent_id: string;
   agent_type: 'claude' | 'fixture';
   agent_role: 'root' | 'worker';
@@ -194,8 +226,13 @@ export async function spawnAgentWindow(targetSessionId?: string | null): Promise
   window_id: string;
   session_id: string;
   cwd: string;
+  worktree: AgentWorktree | null;
 }> {
-  return invoke('spawn_agent_window', { targetSessionId: targetSessionId ?? null });
+  return invoke('spawn_agent_window', {
+    targetSessionId: targetSessionId ?? null,
+    worktree: worktree ?? null,
+    env: env ?? null,
+  });
 }
 
 export async function spawnBrowserWindow(
@@ -300,8 +337,8 @@ export async function writePane(paneId: string, data: string): Promise<void> {
   return invoke('write_pty', { sessionId: paneId, data });
 }
 
-export async function readPaneOutput(paneId: string): Promise<string> {
-  return invoke<string>('read_pty_output', { sessionId: paneId });
+export async function readPaneOutput(paneId: string): Promise<PaneOutputSeed> {
+  return invoke<PaneOutputSeed>('read_pty_output', { sessionId: paneId });
 }
 
 export async function saveLayoutState(
@@ -68,6 +68,20 @@ export interface AgentInfo {
   chatter_subscribed: boolean;
   channels: string[];
   agent_pid?: number | null;
+  worktree?: AgentWorktree | null;
+}
+
+export interface AgentWorktree {
+  agent_id: string;
+  session_id: string;
+  tile_id: string;
+  repo_root: string;
+  path: string;
+  cwd: string;
+  branch: string;
+  base_branch?: string | null;
+  base_commit: string;
+  created_at: number;
 }
 
 export interface ChannelInfo {
@@ -127,6 +141,14 @@ export interface WorkReviewEntry {
   created_at: number;
 }
 
+export interface WorkAttachment {
+  stage: WorkStage;
+  path: string;
+  media_type: string;
+  label?: string | null;
+  created_at: number;
+}
+
 export interface WorkItem {
   work_id: string;
   tile_id: string;
@@ -137,6 +159,7 @@ export interface WorkItem {
   current_stage: WorkStage;
   stages: WorkStageState[];
   reviews: WorkReviewEntry[];
+  attachments?: WorkAttachment[];
   created_at: number;
   updated_at: number;
 }
@@ -157,6 +180,13 @@ export interface TilePortSetting {
   networking_mode: PortNetworkingMode;
 }
 
+export interface NamedPortSpec {
+  name: string;
+  port?: TilePort | null;
+  access_mode?: PortMode | null;
+  accepts?: NetworkTileKind[];
+}
+
 export type TileTypeFilter = 'agent' | 'shell' | 'browser' | 'work';
 
 export interface AgentTileDetails {
@@ -168,6 +198,7 @@ export interface AgentTileDetails {
   chatter_subscribed: boolean;
   channels: string[];
   agent_pid?: number | null;
+  worktree?: AgentWorktree | null;
 }
 
 export interface PaneTileDetails {
@@ -194,6 +225,15 @@ export interface BrowserExtensionMethod {
   name: string;
   description?: string | null;
   args?: TileMessageArgSpec[];
+  async?: boolean;
+  timeout_ms?: number | null;
+  args_schema?: Record<string, unknown> | null;
+  result_schema?: Record<string, unknown> | null;
+}
+
+export interface BrowserExtensionEvent {
+  name: string;
+  description?: string | null;
 }
 
 export interface BrowserExtensionInfo {
@@ -201,6 +241,8 @@ export interface BrowserExtensionInfo {
   label: string;
   source_path?: string | null;
   methods: BrowserExtensionMethod[];
+  events?: BrowserExtensionEvent[];
+  ports?: NamedPortSpec[];
 }
 
 export interface WorkTileDetails {
@@ -210,6 +252,7 @@ export interface WorkTileDetails {
   current_stage: WorkStage;
   stages: WorkStageState[];
   reviews: WorkReviewEntry[];
+  attachments?: WorkAttachment[];
   created_at: number;
   updated_at: number;
 }
@@ -228,6 +271,10 @@ export interface TileMessageSubcommandSpec {
   name: string;
   description?: string | null;
   args?: TileMessageArgSpec[];
+  async?: boolean;
+  timeout_ms?: number | null;
+  args_schema?: Record<string, unknown> | null;
+  result_schema?: Record<string, unknown> | null;
 }
 
 export interface TileMessageSpec {
@@ -503,6 +550,31 @@ export interface TmuxSession {
   active_window_id: string | null;
   root_cwd?: string | null;
   browser_backend: BrowserBackend;
+  launch_policies?: SessionLaunchPolicies;
+}
+
+export type SandboxMode = 'none' | 'bubblewrap' | 'unshare';
+
+export interface LaunchPolicy {
+  memory_limit_mb?: number;
+  cpu_time_limit_secs?: number;
+  max_processes?: number;
+  sandbox?: SandboxMode;
+  read_only_paths?: string[];
+  writable_paths?: string[];
+  no_network?: boolean;
+}
+
+export interface SessionLaunchPolicies {
+  shell: LaunchPolicy;
+  agent: LaunchPolicy;
+}
+
+export interface SessionEnvEntry {
+  name: string;
+  value: string;
+  secret: boolean;
+  updated_at: number;
 }
 
 export interface TmuxWindow {
@@ -638,6 +710,12 @@ export interface AppStateTree {
 export interface PtyOutputEvent {
   pane_id: string;
   data: string;
+  offset: number | null;
+}
+
+export interface PaneOutputSeed {
+  data: string;
+  offset: number;
 }
 
 export interface LayoutStateMap {
@@ -24,6 +24,21 @@ interface SocketResponse<T = unknown> {
   error?: string;
 }
 
+export type BrowserDriveAction =
+  | 'click'
+  | 'select'
+  | 'type'
+  | 'dom_query'
+  | 'eval'
+  | 'screenshot'
+  | 'screenshot_diff'
+  | 'press'
+  | 'hover'
+  | 'scroll'
+  | 'wait_for'
+  | 'fill_form'
+  | 'check';
+
 export class HerdTestClient {
   constructor(private readonly socketPath: string) {}
 
@@ -443,7 +458,7 @@ export class HerdTestClient {
 
   async browserDrive<T = unknown>(
     tileId: string,
-    action: 'click' | 'select' | 'type' | 'dom_query' | 'eval' | 'screenshot',
+    action: BrowserDriveAction,
     args?: Record<string, unknown>,
     senderTileId?: string | null,
     senderAgentId?: string | null,
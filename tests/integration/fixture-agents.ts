@@ -3,7 +3,7 @@ import readline from 'node:readline';
 
 import type { AgentInfo, SessionTileInfo, TileGraph, TileTypeFilter } from '../../src/lib/types';
 import { waitFor } from './helpers';
-import { HerdTestClient } from './client';
+import { HerdTestClient, type BrowserDriveAction } from './client';
 import type { HerdIntegrationRuntime } from './runtime';
 
 interface SocketResponse<T = unknown> {
@@ -360,7 +360,7 @@ abstract class BaseFixtureAgentContext {
 export class FixtureRootContext extends BaseFixtureAgentContext {
   async browserDrive<T = unknown>(
     tileId: string,
-    action: 'click' | 'select' | 'type' | 'dom_query' | 'eval' | 'screenshot',
+    action: BrowserDriveAction,
     args?: Record<string, unknown>,
     timeoutMs = 20_000,
   ): Promise<T> {
@@ -529,7 +529,7 @@ export class FixtureWorkerContext extends BaseFixtureAgentContext {
 
   async browserDrive<T = unknown>(
     browserTitle: string,
-    action: 'click' | 'select' | 'type' | 'dom_query' | 'eval' | 'screenshot',
+    action: BrowserDriveAction,
     args?: Record<string, unknown>,
   ): Promise<T> {
     const browser = await this.findConnectedBrowser(browserTitle);
@@ -662,6 +662,10 @@ describe.sequential('worker/root mcp and permissions', () => {
       'get',
       'call',
       'output_read',
+      'output_capture',
+      'wait_for_output',
+      'screenshot',
+      'process_info',
       'input_send',
       'exec',
       'role_set',
@@ -672,6 +676,9 @@ describe.sequential('worker/root mcp and permissions', () => {
       'navigate',
       'load',
       'drive',
+      'console_read',
+      'record_start',
+      'record_stop',
     ]);
 
     const shellTile = await client.networkGet(shellPaneId, worker.paneId, worker.agentId);
@@ -680,6 +687,10 @@ describe.sequential('worker/root mcp and permissions', () => {
       'get',
       'call',
       'output_read',
+      'output_capture',
+      'wait_for_output',
+      'screenshot',
+      'process_info',
       'input_send',
       'exec',
       'role_set',
@@ -719,7 +730,21 @@ describe.sequential('worker/root mcp and permissions', () => {
               type: 'string',
               required: true,
               description: 'Browser drive subcommand to execute.',
-              enum_values: ['click', 'select', 'type', 'dom_query', 'eval', 'screenshot'],
+              enum_values: [
+                'click',
+                'select',
+                'type',
+                'dom_query',
+                'eval',
+                'screenshot',
+                'screenshot_diff',
+                'press',
+                'hover',
+                'scroll',
+                'wait_for',
+                'fill_form',
+                'check',
+              ],
             },
             {
               name: 'args',
@@ -735,8 +760,14 @@ describe.sequential('worker/root mcp and permissions', () => {
                 {
                   name: 'selector',
                   type: 'string',
-                  required: true,
-                  description: 'CSS selector for the target element.',
+                  required: false,
+                  description: 'CSS selector for the target element. Required unless ref is provided.',
+                },
+                {
+                  name: 'ref',
+                  type: 'string',
+                  required: false,
+                  description: 'Element ref from the latest accessibility screenshot, used in place of selector.',
                 },
               ],
             }),
@@ -746,8 +777,14 @@ describe.sequential('worker/root mcp and permissions', () => {
                 {
                   name: 'selector',
                   type: 'string',
-                  required: true,
-                  description: 'CSS selector for the target element.',
+                  required: false,
+                  description: 'CSS selector for the target element. Required unless ref is provided.',
+                },
+                {
+                  name: 'ref',
+                  type: 'string',
+                  required: false,
+                  description: 'Element ref from the latest accessibility screenshot, used in place of selector.',
                 },
                 {
                   name: 'text',
@@ -810,13 +847,27 @@ describe.sequential('worker/root mcp and permissions', () => {
                   type: 'string',
                   required: false,
                   description: 'Screenshot output format. Defaults to image.',
-                  enum_values: ['image', 'braille', 'ascii', 'ansi', 'text'],
+                  enum_values: ['image', 'braille', 'ascii', 'ansi', 'halfblock', 'sixel', 'kitty', 'text', 'accessibility'],
                 },
                 {
                   name: 'columns',
                   type: 'number',
                   required: false,
-                  description: 'Requested text width in characters when format is braille, ascii, ansi, or text.',
+                  description:
+                    'Requested text width in characters, or terminal cells for sixel and kitty, when format is not image or accessibility.',
+                },
+                {
+                  name: 'selector',
+                  type: 'string',
+                  required: false,
+                  description: 'CSS selector of an element to scroll into view and capture instead of the whole viewport.',
+                },
+                {
+                  name: 'clip',
+                  type: 'object',
+                  required: false,
+                  description:
+                    'Viewport region to capture as {x, y, width, height} in CSS pixels. Cannot be combined with selector.',
                 },
               ],
             }),
@@ -837,11 +888,18 @@ describe.sequential('worker/root mcp and permissions', () => {
       'navigate',
       'load',
       'drive',
+      'console_read',
+      'record_start',
+      'record_stop',
     ]);
     expect(observerVisibleNetwork.tiles.find((tile) => tile.tile_id === shellPaneId)?.responds_to).toEqual([
       'get',
       'call',
       'output_read',
+      'output_capture',
+      'wait_for_output',
+      'screenshot',
+      'process_info',
     ]);
 
     const observerBrowserTile = await client.networkGet(browserPaneId, observer.paneId, observer.agentId);
@@ -853,6 +911,10 @@ describe.sequential('worker/root mcp and permissions', () => {
       'get',
       'call',
       'output_read',
+      'output_capture',
+      'wait_for_output',
+      'screenshot',
+      'process_info',
     ]);
 
     await expect(client.networkGet(foreignPaneId, worker.paneId, worker.agentId)).rejects.toThrow(/sender network/i);
@@ -1438,19 +1500,19 @@ describe.sequential('worker/root mcp and permissions', () => {
     const workerBTile = visibleNetwork.tiles.find((tile) => tile.tile_id === workerB.paneId);
     const rootTile = visibleNetwork.tiles.find((tile) => tile.tile_id === rootAgent.tile_id);
 
-    expect(workerBTile?.responds_to).toEqual(['get', 'call', 'output_read']);
-    expect(rootTile?.responds_to).toEqual(['get', 'call', 'output_read']);
+    expect(workerBTile?.responds_to).toEqual(['get', 'call', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info']);
+    expect(rootTile?.responds_to).toEqual(['get', 'call', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info']);
 
     const workerBGet = await client.networkGet(workerB.paneId, workerA.paneId, workerA.agentId);
-    expect(workerBGet.responds_to).toEqual(['get', 'call', 'output_read']);
-    expect(workerBGet.message_api.map((message) => message.name)).toEqual(['get', 'call', 'output_read']);
+    expect(workerBGet.responds_to).toEqual(['get', 'call', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info']);
+    expect(workerBGet.message_api.map((message) => message.name)).toEqual(['get', 'call', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info']);
     expect(workerBGet.message_api.find((message) => message.name === 'call')?.args).toEqual([
       {
         name: 'action',
         type: 'string',
         required: true,
         description: 'Message name to invoke on this tile.',
-        enum_values: ['get', 'output_read'],
+        enum_values: ['get', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info'],
       },
       {
         name: 'args',
@@ -1461,7 +1523,7 @@ describe.sequential('worker/root mcp and permissions', () => {
     ]);
 
     const rootGet = await client.networkGet(rootAgent.tile_id, workerA.paneId, workerA.agentId);
-    expect(rootGet.responds_to).toEqual(['get', 'call', 'output_read']);
+    expect(rootGet.responds_to).toEqual(['get', 'call', 'output_read', 'output_capture', 'wait_for_output', 'screenshot', 'process_info']);
 
     await expect(
       client.networkCall(workerB.paneId, 'input_send', { input: "printf 'should-not-run\\n'\n" }, workerA.paneId, workerA.agentId),
@@ -2013,6 +2075,7 @@ return document.body.dataset.driven;
     const screenshot = await client.browserDrive<BrowserScreenshotResult>(
       browserPaneId,
       'screenshot',
+      'process_info',
       {},
       worker.paneId,
       worker.agentId,
@@ -2022,6 +2085,7 @@ return document.body.dataset.driven;
     const brailleScreenshot = await client.browserDrive<BrowserTextScreenshotResult>(
       browserPaneId,
       'screenshot',
+      'process_info',
       { format: 'braille', columns: 24 },
       worker.paneId,
       worker.agentId,
@@ -2031,6 +2095,7 @@ return document.body.dataset.driven;
     const asciiScreenshot = await client.browserDrive<BrowserTextScreenshotResult>(
       browserPaneId,
       'screenshot',
+      'process_info',
       { format: 'ascii', columns: 24 },
       worker.paneId,
       worker.agentId,
@@ -2040,6 +2105,7 @@ return document.body.dataset.driven;
     const ansiScreenshot = await client.browserDrive<BrowserTextScreenshotResult>(
       browserPaneId,
       'screenshot',
+      'process_info',
       { format: 'ansi', columns: 24 },
       worker.paneId,
       worker.agentId,
@@ -2070,6 +2136,7 @@ return document.body.dataset.driven;
     const textScreenshot = await client.browserDrive<BrowserTextScreenshotResult>(
       browserPaneId,
       'screenshot',
+      'process_info',
       { format: 'text', columns: 80 },
       worker.paneId,
       worker.agentId,
@@ -2256,6 +2323,7 @@ return document.body.dataset.driven;
         'state',
         'load_bundled_rom',
         'screenshot',
+        'process_info',
         'set_button',
         'button_combo',
         'release_all_buttons',
@@ -2526,6 +2594,7 @@ return document.body.dataset.driven;
         'claim_player',
         'load_rom_base64',
         'screenshot',
+        'process_info',
         'set_button',
         'button_combo',
         'release_all_buttons',